@@ -1,15 +1,56 @@
 use eframe::{self, egui, App};
 use dmxt_ui::pages::*;
 use dmxt_ui::windows::about_window::about_window;
+use dmxt_ui::windows::fixture::FixtureWindow;
+use dmxt_ui::windows::monitor::MonitorWindow;
+use dmxt_ui::windows::interfaces::InterfaceWindow;
+use dmxt_ui::windows::file_prompt::FilePrompt;
+use dmxt_ui::windows::missing_files::MissingFilesWindow;
+use dmxt_ui::windows::import::ImportWindow;
+use dmxt_ui::windows::recorder::RecorderWindow;
+use dmxt_ui::windows::preferences::{shortcut_pressed, PreferencesWindow};
+use dmxt_ui::windows::SubWindow;
+use dmxt_lib::interfaces::InterfaceMapping;
+use dmxt_lib::output::OutputEngine;
+use dmxt_lib::show::{Show, ShowCommand, ShowState};
+use dmxt_lib::history::History;
+use dmxt_lib::patch::sheet;
+use dmxt_lib::preferences::{Action, Preferences, Theme};
+use dmxt_lib::threads::shared::Lock;
 
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileAction {
+    Open,
+    SaveAs,
+    ExportSheet,
+}
 
 #[derive(Debug, Default)]
 struct  DMXTApp {
     open_page: Page,
-    // file: String,
+    show_path: Option<PathBuf>,
+    file_prompt: FilePrompt,
+    file_action: Option<FileAction>,
+    status: Option<String>,
+    patch_page: PatchPage,
+    timeline_page: TimelinePage,
+    visualizer_page: VisualizerPage,
     about_window: bool,
+    fixture_window: FixtureWindow,
+    monitor_window: MonitorWindow,
+    interface_window: InterfaceWindow,
+    preferences_window: PreferencesWindow,
+    missing_files_window: MissingFilesWindow,
+    import_window: ImportWindow,
+    recorder_window: RecorderWindow,
+    output: OutputEngine,
+    state: ShowState,
+    history: Lock<History<ShowCommand>>,
+    preferences: Lock<Preferences>,
+    preferences_path: Option<PathBuf>,
     // universes: Vec<Universe>,
-    // interfaces: Vec<Interface>,
     
     // fixtures: Vec<Fixture>,
     // scene_groups: Vec<SceneGroup>,
@@ -17,13 +58,180 @@ struct  DMXTApp {
     // mixer: Mixer,
 }
 
+impl DMXTApp {
+    fn apply_show(&mut self, show: Show) {
+        self.state.load(show);
+        self.history.write().unwrap().clear();
+    }
+
+    fn new_show(&mut self) {
+        let interfaces = self.preferences.read().unwrap().default_interface.clone()
+            .map(|interface| InterfaceMapping { interface, universe: 0 })
+            .into_iter().collect();
+        self.apply_show(Show { interfaces, ..Show::default() });
+        self.show_path = None;
+        self.status = None;
+    }
+
+    fn open_show(&mut self, path: PathBuf) {
+        match Show::load(&path) {
+            Ok(show) => {
+                self.apply_show(show);
+                self.status = Some(match self.missing_files_window.check() {
+                    0 => format!("Opened {}", path.display()),
+                    missing => format!("Opened {}, {} files are missing", path.display(), missing),
+                });
+                self.add_recent(&path);
+                self.show_path = Some(path);
+            },
+            Err(error) => self.status = Some(format!("Could not open {}: {}", path.display(), error)),
+        }
+    }
+
+    fn save_show(&mut self, path: PathBuf) {
+        match self.state.show().save(&path) {
+            Ok(()) => {
+                self.history.write().unwrap().mark_saved();
+                self.status = Some(format!("Saved {}", path.display()));
+                self.add_recent(&path);
+                self.show_path = Some(path);
+            },
+            Err(error) => self.status = Some(format!("Could not save {}: {}", path.display(), error)),
+        }
+    }
+
+    fn add_recent(&mut self, path: &std::path::Path) {
+        self.preferences.write().unwrap().add_recent(&path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+        self.save_preferences();
+    }
+
+    fn save_preferences(&mut self) {
+        let preferences = self.preferences.read().unwrap();
+        if let Some(error) = self.preferences_path.as_ref().and_then(|config| preferences.save(config).err()) {
+            self.status = Some(format!("Could not save the preferences: {}", error));
+        }
+    }
+
+    fn apply_preferences(&mut self, ctx: &egui::Context) {
+        let preferences = self.preferences.read().unwrap();
+        self.output.set_frame_rate(preferences.frame_rate);
+        ctx.set_visuals(match preferences.theme {
+            Theme::Dark => egui::Visuals::dark(),
+            Theme::Light => egui::Visuals::light(),
+        });
+        self.patch_page.set_fixture_library(&preferences.fixture_library);
+    }
+
+    fn run_action(&mut self, action: Action) {
+        match action {
+            Action::New => self.new_show(),
+            Action::Open => self.ask_path(FileAction::Open),
+            Action::Save => match self.show_path.clone() {
+                Some(path) => self.save_show(path),
+                None => self.ask_path(FileAction::SaveAs),
+            },
+            Action::SaveAs => self.ask_path(FileAction::SaveAs),
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+        }
+    }
+
+    fn export_sheet(&mut self, path: PathBuf) {
+        let result = sheet::export(&self.state.patch.read().unwrap())
+            .map_err(|error| error.to_string())
+            .and_then(|csv| std::fs::write(&path, csv).map_err(|error| error.to_string()));
+        self.status = Some(match result {
+            Ok(()) => format!("Exported the patch to {}", path.display()),
+            Err(error) => format!("Could not export the patch to {}: {}", path.display(), error),
+        });
+    }
+
+    fn undo(&mut self) {
+        if let Some(description) = self.history.write().unwrap().undo(&mut self.state) {
+            self.status = Some(format!("Undid {}", description));
+        }
+    }
+
+    fn redo(&mut self) {
+        self.status = match self.history.write().unwrap().redo(&mut self.state) {
+            Ok(Some(description)) => Some(format!("Redid {}", description)),
+            Ok(None) => return,
+            Err(error) => Some(format!("Could not redo: {}", error)),
+        };
+    }
+
+    fn ask_path(&mut self, action: FileAction) {
+        let title = match action {
+            FileAction::Open => "Open Show",
+            FileAction::SaveAs => "Save Show As",
+            FileAction::ExportSheet => "Export Patch Sheet",
+        };
+        let path = match action {
+            FileAction::ExportSheet => self.show_path.as_ref().map(|path| path.with_extension("csv")),
+            _ => self.show_path.clone(),
+        };
+        self.file_prompt.ask(title, path.as_deref());
+        self.file_action = Some(action);
+    }
+}
+
 impl App for DMXTApp {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.state.interfaces.write().unwrap().poll(&mut self.output);
+        self.timeline_page.tick(ctx);
+        self.recorder_window.tick(ctx);
+        if std::mem::take(&mut self.preferences_window.applied) {
+            self.apply_preferences(ctx);
+        }
+        let pressed = {
+            let preferences = self.preferences.read().unwrap();
+            let input = ctx.input();
+            Action::ALL.into_iter().find(|action| preferences.key_binding(*action).is_some_and(|binding| shortcut_pressed(&input, binding)))
+        };
+        // Text fields handle these themselves
+        if let Some(action) = pressed.filter(|_| !ctx.wants_keyboard_input()) {
+            self.run_action(action);
+        }
+        let (can_undo, can_redo, dirty) = {
+            let history = self.history.read().unwrap();
+            (history.undo_description(), history.redo_description(), history.is_dirty())
+        };
         if self.about_window {
             if !about_window(ctx).hovered() && ctx.input().pointer.any_pressed() {
                 self.about_window = false;
             };
         }
+        if self.fixture_window.open {
+            self.fixture_window.ui(ctx);
+        }
+        if self.monitor_window.open {
+            self.monitor_window.ui(ctx);
+        }
+        if self.interface_window.open {
+            self.interface_window.ui(ctx);
+        }
+        if self.preferences_window.open {
+            self.preferences_window.ui(ctx);
+        }
+        if self.missing_files_window.open {
+            self.missing_files_window.ui(ctx);
+        }
+        if self.import_window.open {
+            self.import_window.ui(ctx);
+        }
+        if self.recorder_window.open {
+            self.recorder_window.ui(ctx);
+        }
+        if self.file_prompt.open {
+            if let Some(path) = self.file_prompt.ui(ctx) {
+                match self.file_action.take() {
+                    Some(FileAction::Open) => self.open_show(path),
+                    Some(FileAction::SaveAs) => self.save_show(path),
+                    Some(FileAction::ExportSheet) => self.export_sheet(path),
+                    None => {},
+                }
+            }
+        }
 
         egui::TopBottomPanel::top("wrap_app_top_bar").show(ctx, |ui| {
             egui::trace!(ui); 
@@ -43,30 +251,83 @@ impl App for DMXTApp {
                             }
                         });
                         ui.menu_button("File", |ui| {
-                            let _ = ui.button("New");
-                            let _ = ui.button("Open...");
-                            let _ = ui.menu_button("Open recent", |ui| {
-                                let _ = ui.button("File 1");
-                                let _ = ui.button("File 2");
-                                let _ = ui.button("File 3");
+                            if ui.button("New").clicked() {
+                                self.new_show();
+                            }
+                            if ui.button("Open...").clicked() {
+                                self.ask_path(FileAction::Open);
+                            }
+                            ui.menu_button("Open recent", |ui| {
+                                let recent_files = self.preferences.read().unwrap().recent_files.clone();
+                                if recent_files.is_empty() {
+                                    ui.label("No recent shows");
+                                }
+                                for path in recent_files {
+                                    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                                    let button = ui.add_enabled(path.is_file(), egui::Button::new(name));
+                                    let button = button.on_hover_text(path.display().to_string())
+                                        .on_disabled_hover_text(format!("{} does not exist anymore", path.display()));
+                                    if button.clicked() {
+                                        self.open_show(path);
+                                        ui.close_menu();
+                                    }
+                                }
+                                ui.separator();
+                                if ui.button("Clear recent").clicked() {
+                                    self.preferences.write().unwrap().recent_files.clear();
+                                    self.save_preferences();
+                                    ui.close_menu();
+                                }
                             });
-                            let _ = ui.button("Import Patch...");
-                            let _ = ui.button("Import Scenes...");
+                            if ui.button("Import Patch...").clicked() {
+                                self.import_window.open = true;
+                            }
+                            let _ = ui.add_enabled(false, egui::Button::new("Import Scenes..."))
+                                .on_disabled_hover_text("Shows have no scenes yet");
+                            if ui.button("Export Patch Sheet...").clicked() {
+                                self.ask_path(FileAction::ExportSheet);
+                            }
                             let _ = ui.separator();
-                            let _ = ui.button("Check for missing files");
+                            if ui.button("Check for missing files").clicked() {
+                                self.missing_files_window.check();
+                                self.missing_files_window.open = true;
+                            }
                             let _ = ui.separator();
-                            let _ = ui.button("Save");
-                            let _ = ui.button("Save As...");
+                            if ui.add_enabled(dirty || self.show_path.is_none(), egui::Button::new("Save")).clicked() {
+                                self.run_action(Action::Save);
+                            }
+                            if ui.button("Save As...").clicked() {
+                                self.ask_path(FileAction::SaveAs);
+                            }
                         });
                         ui.menu_button("Edit", |ui| {
-                            let _ = ui.button("Undo");
-                            let _ = ui.button("Redo");
+                            let undo = ui.add_enabled(can_undo.is_some(), egui::Button::new("Undo"));
+                            if undo.on_hover_text(can_undo.unwrap_or_default()).clicked() {
+                                self.undo();
+                            }
+                            let redo = ui.add_enabled(can_redo.is_some(), egui::Button::new("Redo"));
+                            if redo.on_hover_text(can_redo.unwrap_or_default()).clicked() {
+                                self.redo();
+                            }
                             let _ = ui.separator();
-                            let _ = ui.button("Preferences");
+                            if ui.button("Preferences").clicked() {
+                                self.preferences_window.show();
+                            }
                         });
                         ui.menu_button("Tools", |ui| {
-                            let _ = ui.button("Interface Manager");
-                            let _ = ui.button("DMX Monitor");
+                            if ui.button("Interface Manager").clicked() {
+                                self.interface_window.open = true;
+                            }
+                            if ui.button("DMX Monitor").clicked() {
+                                self.monitor_window.open = true;
+                            }
+                            if ui.button("Recorder").clicked() {
+                                self.recorder_window.open = true;
+                            }
+                            if ui.button("Fixture Editor").clicked() {
+                                self.fixture_window.set_output(self.output.universes());
+                                self.fixture_window.open = true;
+                            }
                         });
                         ui.menu_button("Mappings" , |ui| {
                             let _ = ui.button("Map Keyboard");
@@ -86,17 +347,31 @@ impl App for DMXTApp {
 
                 ui.selectable_value(&mut self.open_page, Page::Patch, "Patch");
                 ui.selectable_value(&mut self.open_page, Page::Scenes, "Scenes");
+                ui.selectable_value(&mut self.open_page, Page::Timeline, "Timeline");
+                ui.selectable_value(&mut self.open_page, Page::Visualizer, "Visualizer");
             });
         });
 
+        if let Some(status) = &self.status {
+            egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+                ui.label(status);
+            });
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             match self.open_page {
                 Page::Patch => {
-                    PatchPage::default().ui(ui);
+                    self.patch_page.ui(ui);
                 }
                 Page::Scenes => {
                     ScenePage::default().ui(ui);
                 }
+                Page::Timeline => {
+                    self.timeline_page.ui(ui);
+                }
+                Page::Visualizer => {
+                    self.visualizer_page.ui(ui);
+                }
             }
         });
     }
@@ -105,10 +380,37 @@ impl App for DMXTApp {
 fn main() {
     let mut options = eframe::NativeOptions::default();
     options.maximized = true;
-    let app = DMXTApp::default();
+    let mut app = DMXTApp {
+        preferences_path: Preferences::path(),
+        ..DMXTApp::default()
+    };
+    app.state = ShowState::new(app.output.universes());
+    let loaded = app.preferences_path.as_deref().map(Preferences::load_or_default);
+    if let Some(Ok(preferences)) = &loaded {
+        *app.preferences.write().unwrap() = preferences.clone();
+    }
+    app.new_show();
+    if let Some(Err(error)) = loaded {
+        app.status = Some(format!("Could not load the preferences, using the defaults: {}", error));
+    }
+    app.missing_files_window = MissingFilesWindow::new(app.state.clone(), app.history.clone());
+    app.import_window = ImportWindow::new(app.state.clone(), app.history.clone(), app.preferences.clone());
+    app.preferences_window = PreferencesWindow::new(app.preferences.clone(), app.preferences_path.clone(), app.state.interfaces.clone());
+    app.patch_page = PatchPage::new(app.state.clone(), app.history.clone());
+    app.timeline_page = TimelinePage::new(app.state.clone(), app.history.clone());
+    app.visualizer_page = VisualizerPage::new(app.state.clone(), app.history.clone());
+    app.monitor_window = MonitorWindow::new(app.output.universes(), app.state.patch.clone());
+    app.recorder_window = RecorderWindow::new(app.output.recorder(), app.output.universes());
+    app.interface_window = InterfaceWindow::new(app.state.clone(), app.history.clone());
+    if let Err(error) = app.output.start() {
+        app.status = Some(format!("Could not start the output: {}", error));
+    }
     eframe::run_native(
         "DMXT",
         options,
-        Box::new(|_cc| Box::new(app)),
+        Box::new(|cc| {
+            app.apply_preferences(&cc.egui_ctx);
+            Box::new(app)
+        }),
     );
 }
\ No newline at end of file
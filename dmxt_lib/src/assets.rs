@@ -0,0 +1,175 @@
+use crate::builders::fixture::FixtureModel;
+use crate::components::Fixture;
+use crate::patch::Patch;
+
+use serde_json::Value;
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+// How deep the relink search goes into a directory
+const SEARCH_DEPTH: usize = 8;
+
+// A file outside of the show file that a patched fixture refers to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Asset {
+    FixtureConfig(PathBuf),
+    // Icon of a `FixtureName`, as written in the model
+    Icon(PathBuf),
+}
+
+impl std::fmt::Display for Asset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Asset::FixtureConfig(path) => write!(f, "Fixture config {}", path.display()),
+            Asset::Icon(path) => write!(f, "Icon {}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingAsset {
+    // Index in the patch
+    pub fixture: usize,
+    pub asset: Asset,
+}
+
+// Icons of all names in the model, e.g. of the fixture, its color wheel slots and operations
+pub fn model_icons(model: &FixtureModel) -> Vec<PathBuf> {
+    let mut icons = Vec::new();
+    if let Ok(value) = serde_json::to_value(model) {
+        visit_icons(&value, &mut |icon| {
+            let icon = PathBuf::from(icon);
+            if !icons.contains(&icon) {
+                icons.push(icon);
+            }
+        });
+    }
+    icons
+}
+
+fn visit_icons(value: &Value, visit: &mut impl FnMut(&str)) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::String(icon)) = object.get("icon") {
+                visit(icon);
+            }
+            object.values().for_each(|value| visit_icons(value, visit));
+        },
+        Value::Array(values) => values.iter().for_each(|value| visit_icons(value, visit)),
+        _ => {},
+    }
+}
+
+// Files that patched fixtures refer to but that don't exist. Fixtures without a config file only
+// have missing icons if their model has any
+pub fn missing_assets(patch: &Patch) -> Vec<MissingAsset> {
+    let mut missing = Vec::new();
+    for (index, fixture) in patch.fixtures().iter().enumerate() {
+        if let Some(source) = fixture.source.as_ref().filter(|source| !source.is_file()) {
+            missing.push(MissingAsset { fixture: index, asset: Asset::FixtureConfig(source.clone()) });
+        }
+        for icon in model_icons(fixture.model()) {
+            if fixture.icon_file(&icon).is_none() {
+                missing.push(MissingAsset { fixture: index, asset: Asset::Icon(icon) });
+            }
+        }
+    }
+    missing
+}
+
+// All files called `file_name` in `directory` and its subdirectories
+pub fn find_files(directory: &Path, file_name: &OsStr) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    search(directory, file_name, SEARCH_DEPTH, &mut found);
+    found.sort();
+    found
+}
+
+fn search(directory: &Path, file_name: &OsStr, depth: usize, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let hidden = path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if path.is_dir() && !hidden && depth > 0 {
+            search(&path, file_name, depth - 1, found);
+        } else if path.file_name() == Some(file_name) && path.is_file() {
+            found.push(path);
+        }
+    }
+}
+
+// The fixture with `asset` pointing to a file of the same name found in `directory`.
+// Fixture configs are usually all called the same, so the one in a directory of the old name or
+// with the same model is taken
+pub fn relink(fixture: &Fixture, asset: &Asset, directory: &Path) -> Option<Fixture> {
+    let mut relinked = fixture.clone();
+    match asset {
+        Asset::FixtureConfig(path) => {
+            let candidates = find_files(directory, path.file_name()?);
+            let parent_name = |path: &Path| path.parent().and_then(Path::file_name).map(OsStr::to_os_string);
+            let found = candidates.iter().find(|candidate| parent_name(candidate) == parent_name(path))
+                .or_else(|| candidates.iter().find(|candidate| same_model(candidate, fixture.model())))?;
+            relinked.source = Some(found.clone());
+        },
+        Asset::Icon(icon) => {
+            let found = find_files(directory, icon.file_name()?).into_iter().next()?;
+            let mut value = serde_json::to_value(fixture.model()).ok()?;
+            replace_icon(&mut value, icon, &found);
+            *relinked.model_mut() = serde_json::from_value(value).ok()?;
+        },
+    }
+    Some(relinked)
+}
+
+fn same_model(path: &Path, model: &FixtureModel) -> bool {
+    std::fs::read_to_string(path).ok()
+        .and_then(|json| serde_json::from_str::<FixtureModel>(&json).ok())
+        .is_some_and(|candidate| candidate.name == model.name && candidate.manufacturer == model.manufacturer)
+}
+
+fn replace_icon(value: &mut Value, icon: &Path, found: &Path) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::String(path)) = object.get_mut("icon") {
+                if Path::new(path.as_str()) == icon {
+                    *path = found.display().to_string();
+                }
+            }
+            object.values_mut().for_each(|value| replace_icon(value, icon, found));
+        },
+        Value::Array(values) => values.iter_mut().for_each(|value| replace_icon(value, icon, found)),
+        _ => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::fixture::FixtureName;
+    use crate::test_support::test_fixture;
+
+    #[test]
+    fn moved_fixture_folders_are_found_again() {
+        let directory = std::env::temp_dir().join(format!("dmxt_assets_{}", std::process::id()));
+        let moved = directory.join("library").join("Par");
+        std::fs::create_dir_all(moved.join("img")).unwrap();
+
+        let mut fixture = test_fixture(1, 1);
+        fixture.model_mut().name = FixtureName::new_with_icon("Par".into(), PathBuf::from("par.png").into_boxed_path());
+        std::fs::write(moved.join("fixture_config.json"), serde_json::to_string(fixture.model()).unwrap()).unwrap();
+        std::fs::write(moved.join("img").join("par.png"), []).unwrap();
+        fixture.source = Some(directory.join("old").join("Par").join("fixture_config.json"));
+        let mut patch = Patch::new();
+        patch.add(fixture).unwrap();
+
+        let missing = missing_assets(&patch);
+        assert_eq!(missing.len(), 2);
+        assert!(matches!(missing[1].asset, Asset::Icon(_)));
+        let relinked = relink(&patch.fixtures()[0], &missing[0].asset, &directory).unwrap();
+        patch.update(0, relinked).unwrap();
+        assert!(missing_assets(&patch).is_empty());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
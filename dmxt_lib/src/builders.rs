@@ -1,3 +1,5 @@
 // Paths
 pub mod fixture;
-pub mod error;
\ No newline at end of file
+pub mod error;
+pub mod capability;
+pub mod validation;
\ No newline at end of file
@@ -0,0 +1,202 @@
+use crate::builders::error::BuildError;
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Unit {
+    Hertz,
+    Rpm,
+    Degrees,
+    Percent,
+    Seconds,
+    Index,
+    Custom(String),
+}
+
+impl Unit {
+    pub fn symbol(&self) -> &str {
+        match self {
+            Unit::Hertz => "Hz",
+            Unit::Rpm => "rpm",
+            Unit::Degrees => "°",
+            Unit::Percent => "%",
+            Unit::Seconds => "s",
+            Unit::Index => "#",
+            Unit::Custom(symbol) => symbol,
+        }
+    }
+
+    // Discrete units snap to the nearest calibration point instead of interpolating
+    pub fn is_discrete(&self) -> bool {
+        matches!(self, Unit::Index)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationPoint {
+    pub value: f64,
+    pub dmx: u8,
+}
+
+// Maps physical values onto DMX values through calibration points sorted by DMX value.
+// The physical values may decrease with the DMX value, e.g. a strobe going from fast to slow.
+// Loaded ranges go through the builder, which sorts the points
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "PhysicalRangeBuilder")]
+pub struct PhysicalRange {
+    pub unit: Unit,
+    pub points: Vec<CalibrationPoint>,
+}
+
+impl PhysicalRange {
+    pub fn builder() -> PhysicalRangeBuilder {
+        PhysicalRangeBuilder::default()
+    }
+
+    // Smallest and largest physical value
+    pub fn bounds(&self) -> Option<(f64, f64)> {
+        let values = self.points.iter().map(|point| point.value);
+        let min = values.clone().reduce(f64::min)?;
+        let max = values.reduce(f64::max)?;
+        Some((min, max))
+    }
+
+    pub fn to_dmx(&self, value: f64) -> Option<u8> {
+        let (min, max) = self.bounds()?;
+        let value = value.clamp(min, max);
+        if self.unit.is_discrete() {
+            return self.points.iter()
+                .min_by(|a, b| (a.value - value).abs().total_cmp(&(b.value - value).abs()))
+                .map(|point| point.dmx);
+        }
+        if self.points.len() == 1 {
+            return Some(self.points[0].dmx);
+        }
+        self.points.windows(2).find_map(|segment| {
+            let (a, b) = (segment[0], segment[1]);
+            let (low, high) = (a.value.min(b.value), a.value.max(b.value));
+            if value < low || value > high {
+                return None;
+            }
+            if a.value == b.value {
+                return Some(a.dmx);
+            }
+            let t = (value - a.value) / (b.value - a.value);
+            Some((a.dmx as f64 + (b.dmx as f64 - a.dmx as f64) * t).round() as u8)
+        })
+    }
+
+    pub fn from_dmx(&self, dmx: u8) -> Option<f64> {
+        let first = self.points.first()?;
+        let min = self.points.iter().map(|point| point.dmx).min()?;
+        let max = self.points.iter().map(|point| point.dmx).max()?;
+        let dmx = dmx.clamp(min, max);
+        if self.unit.is_discrete() {
+            return self.points.iter().rev().find(|point| point.dmx <= dmx).map(|point| point.value);
+        }
+        if self.points.len() == 1 {
+            return Some(first.value);
+        }
+        self.points.windows(2).find_map(|segment| {
+            let (a, b) = (segment[0], segment[1]);
+            if dmx < a.dmx || dmx > b.dmx {
+                return None;
+            }
+            if a.dmx == b.dmx {
+                return Some(a.value);
+            }
+            let t = (dmx - a.dmx) as f64 / (b.dmx - a.dmx) as f64;
+            Some(a.value + (b.value - a.value) * t)
+        })
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PhysicalRangeBuilder {
+    unit: Option<Unit>,
+    points: Vec<CalibrationPoint>,
+}
+
+impl PhysicalRangeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn unit(&mut self, unit: Unit) -> &mut Self {
+        self.unit = Some(unit);
+        self
+    }
+    pub fn point(&mut self, value: f64, dmx: u8) -> &mut Self {
+        self.points.push(CalibrationPoint { value, dmx });
+        self
+    }
+    pub fn build(&self) -> Result<PhysicalRange, BuildError> {
+        if self.unit.is_none() {
+            return Err(BuildError::MissingField("unit"));
+        }
+        if self.points.is_empty() {
+            return Err(BuildError::EmptyField("points"));
+        }
+        let mut points = self.points.clone();
+        points.sort_by_key(|point| point.dmx);
+        Ok(PhysicalRange {
+            unit: self.unit.clone().unwrap(),
+            points,
+        })
+    }
+}
+
+impl TryFrom<PhysicalRangeBuilder> for PhysicalRange {
+    type Error = BuildError;
+
+    fn try_from(builder: PhysicalRangeBuilder) -> Result<Self, Self::Error> {
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_inverted_strobe() {
+        let strobe = PhysicalRange::builder()
+            .unit(Unit::Hertz)
+            .point(20.0, 10)
+            .point(1.0, 255)
+            .build().unwrap();
+        assert_eq!(strobe.to_dmx(20.0), Some(10));
+        assert_eq!(strobe.to_dmx(1.0), Some(255));
+        assert_eq!(strobe.to_dmx(10.5), Some(133));
+        assert_eq!(strobe.to_dmx(50.0), Some(10));
+        assert_eq!(strobe.from_dmx(255), Some(1.0));
+    }
+
+    #[test]
+    fn discrete_units_snap_to_points() {
+        let gobo = PhysicalRange::builder()
+            .unit(Unit::Index)
+            .point(1.0, 0)
+            .point(2.0, 16)
+            .point(3.0, 32)
+            .build().unwrap();
+        assert_eq!(gobo.to_dmx(2.4), Some(16));
+        assert_eq!(gobo.from_dmx(20), Some(2.0));
+    }
+
+    #[test]
+    fn loaded_points_are_sorted_by_dmx() {
+        let json = r#"{"unit": "Degrees", "points": [{"value": 40.0, "dmx": 255}, {"value": 5.0, "dmx": 0}, {"value": 50.0, "dmx": 128}]}"#;
+        let zoom: PhysicalRange = serde_json::from_str(json).unwrap();
+        assert_eq!(zoom.points.iter().map(|point| point.dmx).collect::<Vec<_>>(), [0, 128, 255]);
+        assert_eq!(zoom.from_dmx(255), Some(40.0));
+        // The largest value is in the middle of the range
+        assert_eq!(zoom.bounds(), Some((5.0, 50.0)));
+        assert!(serde_json::from_str::<PhysicalRange>(r#"{"unit": "Degrees", "points": []}"#).is_err());
+
+        // Points edited out of order don't resolve, but don't panic either
+        let mut reversed = zoom.clone();
+        reversed.points.reverse();
+        assert_eq!(reversed.from_dmx(0), None);
+        assert_eq!(reversed.bounds(), Some((5.0, 50.0)));
+    }
+}
@@ -2,4 +2,14 @@
 pub enum BuildError {
     MissingField(&'static str),
     EmptyField(&'static str),
-}
\ No newline at end of file
+}
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::MissingField(field) => write!(f, "missing field {}", field),
+            BuildError::EmptyField(field) => write!(f, "field {} is empty", field),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
@@ -1,4 +1,5 @@
 use crate::builders::error::BuildError;
+use crate::builders::capability::PhysicalRange;
 use crate::dmx::{DMXRange, DMXAddress, Color, Channel};
 
 use std::path::Path;
@@ -250,6 +251,8 @@ impl FixtureMatrixBuilder {
 pub struct FixtureLights {
     pub color_mode: FixtureColorMode,
     pub dimmer: Option<DMXRange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimmer_fine: Option<Channel>,
 }
 
 impl FixtureLights {
@@ -257,8 +260,14 @@ impl FixtureLights {
         Self {
             color_mode,
             dimmer,
+            dimmer_fine: None,
         }
     }
+
+    pub fn dimmer_fine(&mut self, fine: Channel) -> &mut Self {
+        self.dimmer_fine = Some(fine);
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -317,6 +326,10 @@ impl FixtureMovementBuilder {
 pub struct MovementAxis {
     pub range: DMXRange,
     pub reset: Option<DMXAddress>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fine: Option<Channel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub degrees: Option<(f64, f64)>,
 }
 
 impl MovementAxis {
@@ -324,7 +337,32 @@ impl MovementAxis {
         Self {
             range,
             reset,
+            fine: None,
+            degrees: None,
+        }
+    }
+
+    pub fn fine(&mut self, fine: Channel) -> &mut Self {
+        self.fine = Some(fine);
+        self
+    }
+
+    pub fn degrees(&mut self, start: f64, end: f64) -> &mut Self {
+        self.degrees = Some((start, end));
+        self
+    }
+
+    // Position of an angle within the axis (0.0 - 1.0), None if the axis has no degree range
+    pub fn normalize(&self, degrees: f64) -> Option<f64> {
+        let (start, end) = self.degrees?;
+        if start == end {
+            return Some(0.0);
         }
+        Some(((degrees - start) / (end - start)).clamp(0.0, 1.0))
+    }
+
+    pub fn values(&self, position: f64) -> Vec<(Channel, u8)> {
+        self.range.values(position, self.fine)
     }
 }
 
@@ -332,6 +370,10 @@ impl MovementAxis {
 pub struct FixtureZoom {
     pub range: DMXRange,
     pub reset: Option<DMXAddress>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fine: Option<Channel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub physical: Option<PhysicalRange>,
 }
 
 impl FixtureZoom {
@@ -339,8 +381,33 @@ impl FixtureZoom {
         Self {
             range,
             reset,
+            fine: None,
+            physical: None,
         }
     }
+
+    pub fn fine(&mut self, fine: Channel) -> &mut Self {
+        self.fine = Some(fine);
+        self
+    }
+
+    pub fn physical(&mut self, physical: PhysicalRange) -> &mut Self {
+        self.physical = Some(physical);
+        self
+    }
+
+    pub fn values(&self, position: f64) -> Vec<(Channel, u8)> {
+        self.range.values(position, self.fine)
+    }
+
+    // Values for a beam angle, None without a physical range. The calibration is on the coarse
+    // channel, the fine channel is reset so it doesn't keep what the zoom slider set
+    pub fn physical_values(&self, value: f64) -> Option<Vec<(Channel, u8)>> {
+        let dmx = self.physical.as_ref()?.to_dmx(value)?;
+        let mut values = vec![(self.range.channel(), dmx)];
+        values.extend(self.fine.map(|fine| (fine, 0)));
+        Some(values)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -348,6 +415,55 @@ pub enum FixtureCustomOperation {
     Slider(FixtureName, DMXRange),
     Button(FixtureName, DMXAddress),
     Stepped(FixtureName, Vec<(FixtureName, DMXAddress)>),
+    // Slider with a coarse range and a fine (LSB) channel
+    FineSlider(FixtureName, DMXRange, Channel),
+    // Slider set in physical units (e.g. strobe in Hz), calibrated on the channel of the range
+    Physical(FixtureName, DMXRange, PhysicalRange),
+}
+
+impl FixtureCustomOperation {
+    pub fn name(&self) -> &FixtureName {
+        match self {
+            FixtureCustomOperation::Slider(name, _) => name,
+            FixtureCustomOperation::Button(name, _) => name,
+            FixtureCustomOperation::Stepped(name, _) => name,
+            FixtureCustomOperation::FineSlider(name, _, _) => name,
+            FixtureCustomOperation::Physical(name, _, _) => name,
+        }
+    }
+
+    pub fn physical_range(&self) -> Option<&PhysicalRange> {
+        match self {
+            FixtureCustomOperation::Physical(_, _, physical) => Some(physical),
+            _ => None,
+        }
+    }
+
+    // Channel values for a physical value, None if the operation has no physical range
+    pub fn physical_values(&self, value: f64) -> Option<Vec<(Channel, u8)>> {
+        match self {
+            FixtureCustomOperation::Physical(_, range, physical) => Some(vec![(range.channel(), physical.to_dmx(value)?)]),
+            _ => None,
+        }
+    }
+
+    // Channel values for a normalized position, steps are spread evenly over the range
+    pub fn values(&self, position: f64) -> Vec<(Channel, u8)> {
+        match self {
+            FixtureCustomOperation::Slider(_, range) => range.values(position, None),
+            FixtureCustomOperation::FineSlider(_, range, fine) => range.values(position, Some(*fine)),
+            FixtureCustomOperation::Physical(_, range, _) => range.values(position, None),
+            FixtureCustomOperation::Button(_, address) => vec![(address.channel, address.value)],
+            FixtureCustomOperation::Stepped(_, steps) => {
+                if steps.is_empty() {
+                    return vec![];
+                }
+                let index = ((position.clamp(0.0, 1.0) * steps.len() as f64) as usize).min(steps.len() - 1);
+                let address = steps[index].1;
+                vec![(address.channel, address.value)]
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -375,6 +491,20 @@ impl FixtureName {
         self.icon = Some(icon.to_path_buf().into_boxed_path());
         self
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn icon_path(&self) -> Option<&Path> {
+        self.icon.as_deref()
+    }
+}
+
+impl std::fmt::Display for FixtureName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
 }
 
 impl From<String> for FixtureName {
@@ -393,4 +523,78 @@ pub enum OperationModeType {
     Auto,
     SoundToLight,
     DMX(FixtureName),
-}
\ No newline at end of file
+}
+
+impl std::fmt::Display for OperationModeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationModeType::Off => write!(f, "Off"),
+            OperationModeType::On => write!(f, "On"),
+            OperationModeType::Auto => write!(f, "Auto"),
+            OperationModeType::SoundToLight => write!(f, "Sound to light"),
+            OperationModeType::DMX(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::capability::Unit;
+    use crate::dmx::DMXAddress;
+
+    fn fixture_config(name: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../data/fixtures").join(name).join("fixture_config.json");
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn existing_fixture_configs_load() {
+        for name in ["Laserworld_El230RGB_MKII", "Stairville_LedBar2408RGBDMX30"] {
+            let config = fixture_config(name);
+            let model: FixtureModel = serde_json::from_str(&config).unwrap();
+            assert!(!model.channel_modes.is_empty());
+            // Optional fields they don't use aren't written back as nulls
+            let saved = serde_json::to_value(&model).unwrap();
+            assert_eq!(saved, serde_json::from_str::<serde_json::Value>(&config).unwrap());
+        }
+    }
+
+    #[test]
+    fn fine_axis_splits_into_msb_and_lsb() {
+        let range = DMXRange::new(
+            DMXAddress::new(Channel::new(1).unwrap(), 0),
+            DMXAddress::new(Channel::new(1).unwrap(), 255),
+        );
+        let mut axis = MovementAxis::new(range, None);
+        axis.fine(Channel::new(2).unwrap());
+        assert_eq!(axis.values(0.0), vec![(Channel::new(1).unwrap(), 0), (Channel::new(2).unwrap(), 0)]);
+        assert_eq!(axis.values(0.5), vec![(Channel::new(1).unwrap(), 128), (Channel::new(2).unwrap(), 0)]);
+        assert_eq!(axis.values(1.0), vec![(Channel::new(1).unwrap(), 255), (Channel::new(2).unwrap(), 255)]);
+    }
+
+    #[test]
+    fn degrees_normalize_within_the_axis() {
+        let mut axis = MovementAxis::new(Channel::new(1).unwrap().into(), None);
+        assert_eq!(axis.normalize(90.0), None);
+        axis.degrees(0.0, 540.0);
+        assert_eq!(axis.normalize(270.0), Some(0.5));
+        assert_eq!(axis.normalize(-10.0), Some(0.0));
+        assert_eq!(axis.normalize(600.0), Some(1.0));
+        // Reversed ranges count down from their start
+        axis.degrees(135.0, -135.0);
+        assert_eq!(axis.normalize(67.5), Some(0.25));
+        axis.degrees(10.0, 10.0);
+        assert_eq!(axis.normalize(10.0), Some(0.0));
+    }
+
+    #[test]
+    fn physical_zoom_resets_the_fine_channel() {
+        let mut zoom = FixtureZoom::new(Channel::new(1).unwrap().into(), None);
+        zoom.physical(PhysicalRange::builder().unit(Unit::Degrees).point(5.0, 0).point(45.0, 200).build().unwrap());
+        assert_eq!(zoom.physical_values(25.0), Some(vec![(Channel::new(1).unwrap(), 100)]));
+        zoom.fine(Channel::new(2).unwrap());
+        assert_ne!(zoom.values(0.3)[1].1, 0);
+        assert_eq!(zoom.physical_values(25.0), Some(vec![(Channel::new(1).unwrap(), 100), (Channel::new(2).unwrap(), 0)]));
+    }
+}
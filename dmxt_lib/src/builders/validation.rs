@@ -0,0 +1,287 @@
+use crate::builders::fixture::{FixtureChannelMode, FixtureColorMode, FixtureCustomOperation, FixtureModel};
+use crate::dmx::{Channel, DMXAddress, DMXRange};
+
+use std::collections::HashSet;
+
+// A range of values on a channel that controls one function of a channel mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelUsage {
+    pub channel: Channel,
+    pub start: u8,
+    pub end: u8,
+    pub function: String,
+    // Submodes only apply while their operation mode (index into `operation_modes`) is active
+    pub operation_mode: Option<usize>,
+}
+
+impl ChannelUsage {
+    fn overlaps(&self, other: &ChannelUsage) -> bool {
+        self.channel == other.channel && self.start <= other.end && other.start <= self.end
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub severity: Severity,
+    // Index into `channel_modes`, None for issues of the whole model
+    pub mode: Option<usize>,
+    pub message: String,
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        match self.mode {
+            Some(mode) => write!(f, "{} (mode {}): {}", severity, mode + 1, self.message),
+            None => write!(f, "{}: {}", severity, self.message),
+        }
+    }
+}
+
+// Every channel value a channel mode uses, sorted by channel and value
+pub fn channel_usages(mode: &FixtureChannelMode) -> Vec<ChannelUsage> {
+    let mut usages = Vec::new();
+
+    let operation_steps: Vec<(String, DMXAddress)> = mode.operation_modes.iter()
+        .filter_map(|operation_mode| Some((format!("Operation mode: {}", operation_mode.mode_type), operation_mode.address?)))
+        .collect();
+    push_steps(&mut usages, &operation_steps, None);
+    for (index, operation_mode) in mode.operation_modes.iter().enumerate() {
+        for submode in &operation_mode.submodes {
+            push_custom(&mut usages, submode, &format!("{}: ", operation_mode.mode_type), Some(index));
+        }
+    }
+
+    if let Some(movement) = &mode.movement {
+        for (name, axis) in [("Pan", &movement.pan), ("Tilt", &movement.tilt)] {
+            if let Some(axis) = axis {
+                push_range(&mut usages, name, &axis.range);
+                if let Some(reset) = axis.reset {
+                    push_address(&mut usages, &format!("{} reset", name), reset);
+                }
+                if let Some(fine) = axis.fine {
+                    push_fine(&mut usages, name, fine);
+                }
+            }
+        }
+    }
+
+    if let Some(lights) = &mode.lights {
+        let single = lights.matrix.iter().flatten().count() == 1;
+        for (row, cells) in lights.matrix.iter().enumerate() {
+            for (column, cell) in cells.iter().enumerate() {
+                let prefix = if single { String::new() } else { format!("Cell {}/{} ", row + 1, column + 1) };
+                let components: Vec<(&str, DMXRange)> = match &cell.color_mode {
+                    FixtureColorMode::RGB(r, g, b) => vec![("Red", *r), ("Green", *g), ("Blue", *b)],
+                    FixtureColorMode::RGBW(r, g, b, w) => vec![("Red", *r), ("Green", *g), ("Blue", *b), ("White", *w)],
+                    FixtureColorMode::CMY(c, m, y) => vec![("Cyan", *c), ("Magenta", *m), ("Yellow", *y)],
+                    FixtureColorMode::CMYW(c, m, y, w) => vec![("Cyan", *c), ("Magenta", *m), ("Yellow", *y), ("White", *w)],
+                    FixtureColorMode::RgbTrailingChannels(range) => vec![("Red", *range), ("Green", range.shifted(1)), ("Blue", range.shifted(2))],
+                    FixtureColorMode::RgbwTrailingChannels(range) => vec![("Red", *range), ("Green", range.shifted(1)), ("Blue", range.shifted(2)), ("White", range.shifted(3))],
+                    FixtureColorMode::CmyTrailingChannels(range) => vec![("Cyan", *range), ("Magenta", range.shifted(1)), ("Yellow", range.shifted(2))],
+                    FixtureColorMode::CmywTrailingChannels(range) => vec![("Cyan", *range), ("Magenta", range.shifted(1)), ("Yellow", range.shifted(2)), ("White", range.shifted(3))],
+                    FixtureColorMode::Presets(presets) => {
+                        let steps: Vec<(String, DMXAddress)> = presets.iter()
+                            .map(|(color, address)| (format!("{}Color: {:?}", prefix, color), *address))
+                            .collect();
+                        push_steps(&mut usages, &steps, None);
+                        vec![]
+                    },
+                    FixtureColorMode::Custom(name, ranges) => ranges.iter().map(|range| (name.as_str(), *range)).collect(),
+                };
+                for (component, range) in components {
+                    push_range(&mut usages, &format!("{}{}", prefix, component), &range);
+                }
+                if let Some(dimmer) = &cell.dimmer {
+                    push_range(&mut usages, &format!("{}Dimmer", prefix), dimmer);
+                    if let Some(fine) = cell.dimmer_fine {
+                        push_fine(&mut usages, &format!("{}Dimmer", prefix), fine);
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(zoom) = &mode.zoom {
+        push_range(&mut usages, "Zoom", &zoom.range);
+        if let Some(reset) = zoom.reset {
+            push_address(&mut usages, "Zoom reset", reset);
+        }
+        if let Some(fine) = zoom.fine {
+            push_fine(&mut usages, "Zoom", fine);
+        }
+    }
+
+    for operation in mode.custom.iter().flatten() {
+        push_custom(&mut usages, operation, "", None);
+    }
+
+    usages.sort_by_key(|usage| (usage.channel, usage.start, usage.end));
+    usages
+}
+
+fn push_range(usages: &mut Vec<ChannelUsage>, function: &str, range: &DMXRange) {
+    usages.push(ChannelUsage {
+        channel: range.channel(),
+        start: range.start.value.min(range.end.value),
+        end: range.start.value.max(range.end.value),
+        function: function.into(),
+        operation_mode: None,
+    });
+}
+
+fn push_address(usages: &mut Vec<ChannelUsage>, function: &str, address: DMXAddress) {
+    usages.push(ChannelUsage {
+        channel: address.channel,
+        start: address.value,
+        end: address.value,
+        function: function.into(),
+        operation_mode: None,
+    });
+}
+
+fn push_fine(usages: &mut Vec<ChannelUsage>, function: &str, fine: Channel) {
+    usages.push(ChannelUsage {
+        channel: fine,
+        start: 0,
+        end: 255,
+        function: format!("{} fine", function),
+        operation_mode: None,
+    });
+}
+
+// Steps hold their value up to the next step on the same channel
+fn push_steps(usages: &mut Vec<ChannelUsage>, steps: &[(String, DMXAddress)], operation_mode: Option<usize>) {
+    for (function, address) in steps {
+        let end = steps.iter()
+            .map(|(_, other)| other)
+            .filter(|other| other.channel == address.channel && other.value > address.value)
+            .map(|other| other.value - 1)
+            .min()
+            .unwrap_or(255);
+        usages.push(ChannelUsage {
+            channel: address.channel,
+            start: address.value,
+            end,
+            function: function.clone(),
+            operation_mode,
+        });
+    }
+}
+
+fn push_custom(usages: &mut Vec<ChannelUsage>, operation: &FixtureCustomOperation, prefix: &str, operation_mode: Option<usize>) {
+    let name = format!("{}{}", prefix, operation.name());
+    let start = usages.len();
+    match operation {
+        FixtureCustomOperation::Slider(_, range) => push_range(usages, &name, range),
+        FixtureCustomOperation::FineSlider(_, range, fine) => {
+            push_range(usages, &name, range);
+            push_fine(usages, &name, *fine);
+        },
+        FixtureCustomOperation::Physical(_, range, physical) => push_range(usages, &format!("{} ({})", name, physical.unit.symbol()), range),
+        FixtureCustomOperation::Button(_, address) => push_address(usages, &name, *address),
+        FixtureCustomOperation::Stepped(_, steps) => {
+            let steps: Vec<(String, DMXAddress)> = steps.iter().map(|(step, address)| (format!("{}: {}", name, step), *address)).collect();
+            push_steps(usages, &steps, operation_mode);
+        },
+    }
+    for usage in &mut usages[start..] {
+        usage.operation_mode = operation_mode;
+    }
+}
+
+pub fn validate(model: &FixtureModel) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut issue = |severity, mode, message: String| issues.push(Issue { severity, mode, message });
+    if model.name.name().trim().is_empty() {
+        issue(Severity::Error, None, "the model has no name".into());
+    }
+    if model.manufacturer.trim().is_empty() {
+        issue(Severity::Warning, None, "the manufacturer is empty".into());
+    }
+    if model.channel_modes.is_empty() {
+        issue(Severity::Error, None, "the model has no channel modes".into());
+    }
+
+    let mut names = HashSet::new();
+    for (index, mode) in model.channel_modes.iter().enumerate() {
+        let mode_index = Some(index);
+        if let Some(name) = &mode.name {
+            if !names.insert(name.name()) {
+                issue(Severity::Warning, mode_index, format!("the mode name {} is used twice", name));
+            }
+        }
+
+        let usages = channel_usages(mode);
+        if usages.is_empty() {
+            issue(Severity::Warning, mode_index, "the mode has no functions".into());
+        }
+        let total = mode.total_channels.id();
+        for usage in usages.iter().filter(|usage| usage.channel.id() > total) {
+            issue(Severity::Error, mode_index, format!("{} uses channel {} but the mode has {} channels", usage.function, usage.channel.id(), total));
+        }
+        // One warning per channel, shared channels like the ones of operation modes overlap a lot
+        let mut overlaps: Vec<(&ChannelUsage, &ChannelUsage)> = Vec::new();
+        for (i, usage) in usages.iter().enumerate() {
+            for other in &usages[i + 1..] {
+                let exclusive = matches!((usage.operation_mode, other.operation_mode), (Some(a), Some(b)) if a != b);
+                if !exclusive && usage.overlaps(other) {
+                    overlaps.push((usage, other));
+                }
+            }
+        }
+        let mut channels: Vec<Channel> = overlaps.iter().map(|(usage, _)| usage.channel).collect();
+        channels.dedup();
+        for channel in channels {
+            let on_channel: Vec<_> = overlaps.iter().filter(|(usage, _)| usage.channel == channel).collect();
+            let (usage, other) = on_channel[0];
+            let more = match on_channel.len() {
+                1 => String::new(),
+                count => format!(" ({} overlaps in total)", count),
+            };
+            issue(Severity::Warning, mode_index, format!(
+                "channel {}: {} ({}-{}) overlaps {} ({}-{}){}",
+                channel.id(), usage.function, usage.start, usage.end, other.function, other.start, other.end, more,
+            ));
+        }
+
+        let empty_steps = mode.custom.iter().flatten()
+            .chain(mode.operation_modes.iter().flat_map(|operation_mode| &operation_mode.submodes))
+            .filter(|operation| matches!(operation, FixtureCustomOperation::Stepped(_, steps) if steps.is_empty()));
+        for operation in empty_steps {
+            issue(Severity::Error, mode_index, format!("{} has no steps", operation.name()));
+        }
+        let empty_presets = mode.lights.iter()
+            .flat_map(|lights| lights.matrix.iter().flatten())
+            .any(|cell| matches!(&cell.color_mode, FixtureColorMode::Presets(presets) if presets.is_empty()));
+        if empty_presets {
+            issue(Severity::Error, mode_index, "color presets without any color".into());
+        }
+    }
+    issues
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn existing_fixtures_have_no_errors() {
+        for name in ["Laserworld_El230RGB_MKII", "Stairville_LedBar2408RGBDMX30"] {
+            let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../data/fixtures").join(name).join("fixture_config.json");
+            let model: FixtureModel = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+            let errors: Vec<Issue> = validate(&model).into_iter().filter(|issue| issue.severity == Severity::Error).collect();
+            assert!(errors.is_empty(), "{}: {:?}", name, errors);
+        }
+    }
+}
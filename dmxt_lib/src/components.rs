@@ -1,3 +1,4 @@
 mod fixture;
+pub mod color;
 
-pub use fixture::Fixture;
\ No newline at end of file
+pub use fixture::Fixture;
@@ -0,0 +1,258 @@
+use crate::builders::fixture::{FixtureColorMode, FixtureLights};
+use crate::dmx::{Channel, DMXRange};
+
+use serde::{Serialize, Deserialize};
+
+// Normalized color, every component is in the range 0.0 - 1.0
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RGB {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+}
+
+impl RGB {
+    pub const BLACK: RGB = RGB { r: 0.0, g: 0.0, b: 0.0 };
+    pub const WHITE: RGB = RGB { r: 1.0, g: 1.0, b: 1.0 };
+
+    pub fn new(r: f64, g: f64, b: f64) -> RGB {
+        RGB {
+            r: r.clamp(0.0, 1.0),
+            g: g.clamp(0.0, 1.0),
+            b: b.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn from_u8(r: u8, g: u8, b: u8) -> RGB {
+        RGB::new(r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0)
+    }
+
+    pub fn to_u8(&self) -> (u8, u8, u8) {
+        (
+            (self.r * 255.0).round() as u8,
+            (self.g * 255.0).round() as u8,
+            (self.b * 255.0).round() as u8,
+        )
+    }
+
+    // Hue in turns (0.0 - 1.0)
+    pub fn from_hsv(hue: f64, saturation: f64, value: f64) -> RGB {
+        let hue = hue.rem_euclid(1.0) * 6.0;
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let (r, g, b) = match hue as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = value - chroma;
+        RGB::new(r + m, g + m, b + m)
+    }
+
+    pub fn lerp(&self, other: RGB, t: f64) -> RGB {
+        let t = t.clamp(0.0, 1.0);
+        RGB::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+        )
+    }
+
+    pub fn scale(&self, factor: f64) -> RGB {
+        RGB::new(self.r * factor, self.g * factor, self.b * factor)
+    }
+
+    pub fn max(&self) -> f64 {
+        self.r.max(self.g).max(self.b)
+    }
+
+    pub fn min(&self) -> f64 {
+        self.r.min(self.g).min(self.b)
+    }
+
+    fn distance(&self, other: &RGB) -> f64 {
+        (self.r - other.r).powi(2) + (self.g - other.g).powi(2) + (self.b - other.b).powi(2)
+    }
+}
+
+// Translates a color into fixture relative channel values for the given lights
+pub fn color_values(lights: &FixtureLights, color: RGB) -> Vec<(Channel, u8)> {
+    let mut values = Vec::new();
+    // With a dedicated dimmer the brightness goes to the dimmer and the color channels stay saturated
+    let color = match &lights.dimmer {
+        Some(dimmer) => {
+            let intensity = color.max();
+            values.extend(dimmer.values(intensity, lights.dimmer_fine));
+            if intensity > 0.0 { color.scale(1.0 / intensity) } else { color }
+        },
+        None => color,
+    };
+    let mut push = |range: &DMXRange, value: f64| values.push((range.channel(), range.value_at(value)));
+    match &lights.color_mode {
+        FixtureColorMode::RGB(r, g, b) => {
+            push(r, color.r);
+            push(g, color.g);
+            push(b, color.b);
+        },
+        FixtureColorMode::RGBW(r, g, b, w) => {
+            let white = color.min();
+            push(r, color.r - white);
+            push(g, color.g - white);
+            push(b, color.b - white);
+            push(w, white);
+        },
+        FixtureColorMode::CMY(c, m, y) => {
+            push(c, 1.0 - color.r);
+            push(m, 1.0 - color.g);
+            push(y, 1.0 - color.b);
+        },
+        FixtureColorMode::CMYW(c, m, y, w) => {
+            let white = color.min();
+            push(c, 1.0 - (color.r - white));
+            push(m, 1.0 - (color.g - white));
+            push(y, 1.0 - (color.b - white));
+            push(w, white);
+        },
+        FixtureColorMode::RgbTrailingChannels(range) => {
+            push(range, color.r);
+            push(&range.shifted(1), color.g);
+            push(&range.shifted(2), color.b);
+        },
+        FixtureColorMode::RgbwTrailingChannels(range) => {
+            let white = color.min();
+            push(range, color.r - white);
+            push(&range.shifted(1), color.g - white);
+            push(&range.shifted(2), color.b - white);
+            push(&range.shifted(3), white);
+        },
+        FixtureColorMode::CmyTrailingChannels(range) => {
+            push(range, 1.0 - color.r);
+            push(&range.shifted(1), 1.0 - color.g);
+            push(&range.shifted(2), 1.0 - color.b);
+        },
+        FixtureColorMode::CmywTrailingChannels(range) => {
+            let white = color.min();
+            push(range, 1.0 - (color.r - white));
+            push(&range.shifted(1), 1.0 - (color.g - white));
+            push(&range.shifted(2), 1.0 - (color.b - white));
+            push(&range.shifted(3), white);
+        },
+        FixtureColorMode::Presets(presets) => {
+            let nearest = presets.iter()
+                .filter_map(|(preset, address)| preset.rgb().map(|(r, g, b)| (RGB::from_u8(r, g, b), address)))
+                .min_by(|(a, _), (b, _)| a.distance(&color).total_cmp(&b.distance(&color)));
+            if let Some((_, address)) = nearest {
+                values.push((address.channel, address.value));
+            }
+        },
+        FixtureColorMode::Custom(_, _) => {},
+    }
+    values
+}
+
+// Inverse of `color_values`, the color the lights show for the fixture relative channel values `read` gives
+pub fn read_color(lights: &FixtureLights, read: impl Fn(Channel) -> u8) -> RGB {
+    let get = |range: &DMXRange| range.position_of(read(range.channel()));
+    let color = match &lights.color_mode {
+        FixtureColorMode::RGB(r, g, b) => RGB::new(get(r), get(g), get(b)),
+        FixtureColorMode::RGBW(r, g, b, w) => {
+            let white = get(w);
+            RGB::new(get(r) + white, get(g) + white, get(b) + white)
+        },
+        FixtureColorMode::CMY(c, m, y) => RGB::new(1.0 - get(c), 1.0 - get(m), 1.0 - get(y)),
+        FixtureColorMode::CMYW(c, m, y, w) => {
+            let white = get(w);
+            RGB::new(1.0 - get(c) + white, 1.0 - get(m) + white, 1.0 - get(y) + white)
+        },
+        FixtureColorMode::RgbTrailingChannels(range) => RGB::new(get(range), get(&range.shifted(1)), get(&range.shifted(2))),
+        FixtureColorMode::RgbwTrailingChannels(range) => {
+            let white = get(&range.shifted(3));
+            RGB::new(get(range) + white, get(&range.shifted(1)) + white, get(&range.shifted(2)) + white)
+        },
+        FixtureColorMode::CmyTrailingChannels(range) => {
+            RGB::new(1.0 - get(range), 1.0 - get(&range.shifted(1)), 1.0 - get(&range.shifted(2)))
+        },
+        FixtureColorMode::CmywTrailingChannels(range) => {
+            let white = get(&range.shifted(3));
+            RGB::new(1.0 - get(range) + white, 1.0 - get(&range.shifted(1)) + white, 1.0 - get(&range.shifted(2)) + white)
+        },
+        // A color wheel without a matching slot is taken as open white
+        FixtureColorMode::Presets(presets) => presets.iter()
+            .find(|(_, address)| read(address.channel) == address.value)
+            .and_then(|(preset, _)| preset.rgb())
+            .map_or(RGB::WHITE, |(r, g, b)| RGB::from_u8(r, g, b)),
+        FixtureColorMode::Custom(_, _) => RGB::WHITE,
+    };
+    match &lights.dimmer {
+        Some(dimmer) => color.scale(dimmer.read(lights.dimmer_fine, &read)),
+        None => color,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dmx::Color;
+
+    fn channel(id: u16) -> Channel {
+        Channel::new(id).unwrap()
+    }
+
+    fn range(id: u16) -> DMXRange {
+        channel(id).into()
+    }
+
+    fn values(values: &[(u16, u8)]) -> Vec<(Channel, u8)> {
+        values.iter().map(|(id, value)| (channel(*id), *value)).collect()
+    }
+
+    #[test]
+    fn colors_translate_into_every_color_mode() {
+        let rgb = FixtureLights::new(FixtureColorMode::RGB(range(1), range(2), range(3)), None);
+        assert_eq!(color_values(&rgb, RGB::new(1.0, 0.5, 0.0)), values(&[(1, 255), (2, 128), (3, 0)]));
+
+        // The white channel takes what all colors have in common
+        let rgbw = FixtureLights::new(FixtureColorMode::RGBW(range(1), range(2), range(3), range(4)), None);
+        assert_eq!(color_values(&rgbw, RGB::new(1.0, 0.5, 0.5)), values(&[(1, 128), (2, 0), (3, 0), (4, 128)]));
+
+        let cmy = FixtureLights::new(FixtureColorMode::CMY(range(1), range(2), range(3)), None);
+        assert_eq!(color_values(&cmy, RGB::new(1.0, 0.5, 0.0)), values(&[(1, 0), (2, 128), (3, 255)]));
+
+        let trailing = FixtureLights::new(FixtureColorMode::RgbTrailingChannels(range(5)), None);
+        assert_eq!(color_values(&trailing, RGB::new(0.0, 1.0, 0.0)), values(&[(5, 0), (6, 255), (7, 0)]));
+    }
+
+    #[test]
+    fn dimmers_take_the_brightness() {
+        let mut lights = FixtureLights::new(FixtureColorMode::RGB(range(2), range(3), range(4)), Some(range(1)));
+        let color = RGB::new(0.5, 0.25, 0.0);
+        let dimmed = color_values(&lights, color);
+        assert_eq!(dimmed, values(&[(1, 128), (2, 255), (3, 128), (4, 0)]));
+        let read = read_color(&lights, |channel| dimmed.iter().find(|(c, _)| *c == channel).map_or(0, |(_, value)| *value));
+        assert!((read.r - 0.5).abs() < 0.01 && (read.g - 0.25).abs() < 0.01 && read.b == 0.0);
+
+        lights.dimmer_fine(channel(5));
+        assert_eq!(color_values(&lights, RGB::WHITE)[..2], values(&[(1, 255), (5, 255)])[..]);
+        assert_eq!(color_values(&lights, RGB::BLACK), values(&[(1, 0), (5, 0), (2, 0), (3, 0), (4, 0)]));
+    }
+
+    #[test]
+    fn presets_pick_the_nearest_slot() {
+        let slots = vec![
+            (Color::Red, (1, 10).into()),
+            (Color::Blue, (1, 20).into()),
+            (Color::UV, (1, 30).into()),
+        ];
+        let wheel = FixtureLights::new(FixtureColorMode::Presets(slots.clone()), None);
+        assert_eq!(color_values(&wheel, RGB::new(0.9, 0.1, 0.0)), values(&[(1, 10)]));
+        assert_eq!(color_values(&wheel, RGB::new(0.2, 0.0, 0.8)), values(&[(1, 20)]));
+
+        // The dimmer dims, the wheel picks by hue
+        let dimmed = FixtureLights::new(FixtureColorMode::Presets(slots), Some(range(2)));
+        assert_eq!(color_values(&dimmed, RGB::new(0.0, 0.0, 0.5)), values(&[(2, 128), (1, 20)]));
+        assert_eq!(read_color(&dimmed, |read| if read == channel(1) { 30 } else { 255 }), RGB::WHITE);
+    }
+}
@@ -1,17 +1,49 @@
 
-use crate::builders::fixture::FixtureModel;
-use crate::dmx::{DMXAddress, DMXDevice};
+use crate::builders::fixture::{FixtureModel, FixtureChannelMode, FixtureLights, FixtureCustomOperation, MovementAxis};
+use crate::components::color::{self, RGB};
+use crate::effects::movement::PanTilt;
+use crate::stage::Placement;
+use crate::dmx::{Channel, DMXAddress, DMXDevice, DMXUniverse};
 use open_dmx::error::DMXError;
-use crate::{address};
 
+use serde::{Serialize, Deserialize};
+
+use std::path::{Path, PathBuf};
+
+// Assumed for axes without a degree range, common for moving heads
+pub const DEFAULT_PAN_RANGE: (f64, f64) = (-270.0, 270.0);
+pub const DEFAULT_TILT_RANGE: (f64, f64) = (-135.0, 135.0);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fixture {
     pub name: String,
     pub address: DMXAddress,
+    pub universe: usize,
+    pub mode: usize,
+    #[serde(default)]
+    pub movement: MovementOptions,
+    // Where the fixture is rigged, e.g. "FOH truss" or "LX1"
+    #[serde(default)]
+    pub position: String,
+    // Where the fixture is on stage, None until it has been placed
+    #[serde(default)]
+    pub placement: Option<Placement>,
+    // Fixture config the model was loaded from, icons are relative to its directory
+    #[serde(default)]
+    pub source: Option<PathBuf>,
     model: FixtureModel,
 }
 
+// Per fixture corrections for how a moving head is mounted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MovementOptions {
+    pub invert_pan: bool,
+    pub invert_tilt: bool,
+    pub swap_axes: bool,
+}
+
 impl DMXDevice for Fixture {
-    fn write_channels(&mut self, channels: &mut [u8]) -> Result<(), DMXError> {
+    fn write_channels(&mut self, _channels: &mut [u8]) -> Result<(), DMXError> {
         Ok(())
     }
 }
@@ -21,7 +53,255 @@ impl Fixture {
         Ok(Fixture {
             name,
             address,
+            universe: 0,
+            mode: 0,
+            movement: MovementOptions::default(),
+            position: String::new(),
+            placement: None,
+            source: None,
             model,
         })
     }
-}
\ No newline at end of file
+
+    pub fn model(&self) -> &FixtureModel {
+        &self.model
+    }
+
+    pub(crate) fn model_mut(&mut self) -> &mut FixtureModel {
+        &mut self.model
+    }
+
+    // Existing file of an icon of the model, next to the fixture config or in its `img` directory
+    pub fn icon_file(&self, icon: &Path) -> Option<PathBuf> {
+        if icon.is_absolute() {
+            return Some(icon.to_path_buf()).filter(|path| path.is_file());
+        }
+        let directory = self.source.as_deref()?.parent()?;
+        [directory.join(icon), directory.join("img").join(icon)].into_iter().find(|path| path.is_file())
+    }
+
+    pub fn channel_mode(&self) -> Option<&FixtureChannelMode> {
+        self.model.channel_modes.get(self.mode)
+    }
+
+    // Number of channels the fixture occupies in its current mode
+    pub fn channel_count(&self) -> u16 {
+        self.channel_mode().map_or(0, |mode| mode.total_channels.id())
+    }
+
+    // First and last absolute channel (inclusive)
+    pub fn channels(&self) -> (u16, u16) {
+        let start = self.address.channel.id();
+        (start, start + self.channel_count().max(1) - 1)
+    }
+
+    pub fn lights(&self, row: usize, column: usize) -> Option<&FixtureLights> {
+        self.channel_mode()?.lights.as_ref()?.matrix.get(row)?.get(column)
+    }
+
+    // Writes a value to a fixture relative channel
+    pub fn write(&self, universe: &mut DMXUniverse, channel: Channel, value: u8) -> Result<(), DMXError> {
+        universe.set(channel.absolute(self.address.channel)?, value)
+    }
+
+    // Reads a fixture relative channel, channels past the end of the universe read as 0
+    pub fn read(&self, universe: &DMXUniverse, channel: Channel) -> u8 {
+        channel.absolute(self.address.channel).and_then(|channel| universe.get(channel)).unwrap_or(0)
+    }
+
+    // Color of a matrix cell as the universe shows it, scaled by the dimmer
+    pub fn read_color(&self, universe: &DMXUniverse, row: usize, column: usize) -> Option<RGB> {
+        let lights = self.lights(row, column)?;
+        Some(color::read_color(lights, |channel| self.read(universe, channel)))
+    }
+
+    // Inverse of `write_position_normalized`, axes the fixture doesn't have read as centered
+    pub fn read_position_normalized(&self, universe: &DMXUniverse) -> Option<(f64, f64)> {
+        let movement = self.channel_mode()?.movement.as_ref()?;
+        let read = |axis: &Option<MovementAxis>| axis.as_ref().map_or(0.5, |axis| {
+            axis.range.read(axis.fine, |channel| self.read(universe, channel))
+        });
+        let (pan, tilt) = (read(&movement.pan), read(&movement.tilt));
+        let (pan, tilt) = if self.movement.swap_axes { (tilt, pan) } else { (pan, tilt) };
+        let pan = if self.movement.invert_pan { 1.0 - pan } else { pan };
+        let tilt = if self.movement.invert_tilt { 1.0 - tilt } else { tilt };
+        Some((pan, tilt))
+    }
+
+    // Degree ranges of the pan and tilt the fixture is moved with, after swapping the axes
+    fn degree_ranges(&self) -> Option<((f64, f64), (f64, f64))> {
+        let movement = self.channel_mode()?.movement.as_ref()?;
+        let (pan, tilt) = if self.movement.swap_axes { (&movement.tilt, &movement.pan) } else { (&movement.pan, &movement.tilt) };
+        let range = |axis: &Option<MovementAxis>, default| axis.as_ref().and_then(|axis| axis.degrees).unwrap_or(default);
+        Some((range(pan, DEFAULT_PAN_RANGE), range(tilt, DEFAULT_TILT_RANGE)))
+    }
+
+    // Positions are in degrees from the middle of both axes, 0/0 points the beam away from the base.
+    // Axes without a degree range assume `DEFAULT_PAN_RANGE` and `DEFAULT_TILT_RANGE`
+    pub fn read_position(&self, universe: &DMXUniverse) -> Option<PanTilt> {
+        let (pan_range, tilt_range) = self.degree_ranges()?;
+        let (pan, tilt) = self.read_position_normalized(universe)?;
+        let degrees = |(start, end): (f64, f64), position: f64| (end - start) * (position - 0.5);
+        Some(PanTilt::new(degrees(pan_range, pan), degrees(tilt_range, tilt)))
+    }
+
+    // Moves the fixture to a position as `read_position` returns it, angles past the end of an axis stop there
+    pub fn write_position(&self, universe: &mut DMXUniverse, position: PanTilt) -> Result<(), DMXError> {
+        let Some((pan_range, tilt_range)) = self.degree_ranges() else {
+            return Ok(());
+        };
+        let normalize = |(start, end): (f64, f64), degrees: f64| if start == end { 0.5 } else { degrees / (end - start) + 0.5 };
+        self.write_position_normalized(universe, normalize(pan_range, position.pan), normalize(tilt_range, position.tilt))
+    }
+
+    pub fn write_values(&self, universe: &mut DMXUniverse, values: &[(Channel, u8)]) -> Result<(), DMXError> {
+        for (channel, value) in values {
+            self.write(universe, *channel, *value)?;
+        }
+        Ok(())
+    }
+
+    // Selects an operation mode by its index, modes without an address can't be selected
+    pub fn write_operation_mode(&self, universe: &mut DMXUniverse, index: usize) -> Result<(), DMXError> {
+        match self.channel_mode().and_then(|mode| mode.operation_modes.get(index)).and_then(|mode| mode.address) {
+            Some(address) => self.write(universe, address.channel, address.value),
+            None => Ok(()),
+        }
+    }
+
+    pub fn write_address(&self, universe: &mut DMXUniverse, address: DMXAddress) -> Result<(), DMXError> {
+        self.write(universe, address.channel, address.value)
+    }
+
+    pub fn write_zoom(&self, universe: &mut DMXUniverse, position: f64) -> Result<(), DMXError> {
+        match self.channel_mode().and_then(|mode| mode.zoom.as_ref()) {
+            Some(zoom) => self.write_values(universe, &zoom.values(position)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn write_custom(&self, universe: &mut DMXUniverse, operation: &FixtureCustomOperation, position: f64) -> Result<(), DMXError> {
+        self.write_values(universe, &operation.values(position))
+    }
+
+    // Writes a capability in physical units, operations without calibration are ignored
+    pub fn write_physical(&self, universe: &mut DMXUniverse, operation: &FixtureCustomOperation, value: f64) -> Result<(), DMXError> {
+        match operation.physical_values(value) {
+            Some(values) => self.write_values(universe, &values),
+            None => Ok(()),
+        }
+    }
+
+    pub fn write_zoom_degrees(&self, universe: &mut DMXUniverse, degrees: f64) -> Result<(), DMXError> {
+        match self.channel_mode().and_then(|mode| mode.zoom.as_ref()).and_then(|zoom| zoom.physical_values(degrees)) {
+            Some(values) => self.write_values(universe, &values),
+            None => Ok(()),
+        }
+    }
+
+    pub fn write_color(&self, universe: &mut DMXUniverse, row: usize, column: usize, color: RGB) -> Result<(), DMXError> {
+        if let Some(lights) = self.lights(row, column) {
+            self.write_values(universe, &color::color_values(lights, color))?;
+        }
+        Ok(())
+    }
+
+    // Writes the color to every cell of the matrix
+    pub fn fill_color(&self, universe: &mut DMXUniverse, color: RGB) -> Result<(), DMXError> {
+        let cells = self.channel_mode().and_then(|mode| mode.lights.as_ref()).map_or(&[][..], |lights| &lights.matrix[..]);
+        for lights in cells.iter().flatten() {
+            self.write_values(universe, &color::color_values(lights, color))?;
+        }
+        Ok(())
+    }
+
+    // Moves the fixture to a normalized position (0.0 - 1.0 on both axes)
+    pub fn write_position_normalized(&self, universe: &mut DMXUniverse, pan: f64, tilt: f64) -> Result<(), DMXError> {
+        let movement = match self.channel_mode().and_then(|mode| mode.movement.as_ref()) {
+            Some(movement) => movement,
+            None => return Ok(()),
+        };
+        let pan = if self.movement.invert_pan { 1.0 - pan } else { pan };
+        let tilt = if self.movement.invert_tilt { 1.0 - tilt } else { tilt };
+        let (pan, tilt) = if self.movement.swap_axes { (tilt, pan) } else { (pan, tilt) };
+        for (axis, position) in [(&movement.pan, pan), (&movement.tilt, tilt)] {
+            if let Some(axis) = axis {
+                self.write_values(universe, &axis.values(position))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::fixture::{FixtureColorMode, FixtureMatrix, FixtureMovement};
+    use crate::dmx::DMXRange;
+    use crate::test_support::test_fixture_with;
+
+    fn channel(id: u16) -> Channel {
+        Channel::new(id).unwrap()
+    }
+
+    // Pan on channel 1 over 540 degrees, tilt on channel 2 over 270 degrees
+    fn head() -> Fixture {
+        let mut pan = MovementAxis::new(channel(1).into(), None);
+        pan.degrees(0.0, 540.0);
+        let mut tilt = MovementAxis::new(channel(2).into(), None);
+        tilt.degrees(0.0, 270.0);
+        let mut mode = FixtureChannelMode::builder();
+        mode.total_channels(channel(2)).movement(FixtureMovement::builder().pan(pan).tilt(tilt).build().unwrap());
+        test_fixture_with(mode.build().unwrap(), 1)
+    }
+
+    #[test]
+    fn colors_are_written_relative_to_the_address() {
+        let lights = FixtureLights::new(FixtureColorMode::RGB(channel(2).into(), channel(3).into(), channel(4).into()), Some(DMXRange::from(channel(1))));
+        let mut mode = FixtureChannelMode::builder();
+        mode.total_channels(channel(4)).matrix(FixtureMatrix::new(vec![vec![lights]]));
+        let fixture = test_fixture_with(mode.build().unwrap(), 10);
+        let mut universe = DMXUniverse::new();
+        fixture.write_color(&mut universe, 0, 0, RGB::new(0.0, 0.25, 0.5)).unwrap();
+        assert_eq!(universe.channels[9..13], [128, 0, 128, 255]);
+        // Cells outside the matrix are ignored
+        fixture.write_color(&mut universe, 0, 1, RGB::WHITE).unwrap();
+        assert_eq!(universe.channels[13], 0);
+    }
+
+    #[test]
+    fn positions_convert_degrees_and_follow_the_mounting() {
+        let mut fixture = head();
+        let mut universe = DMXUniverse::new();
+        // Degrees are measured from the middle of the axis
+        fixture.write_position(&mut universe, PanTilt::new(-135.0, -67.5)).unwrap();
+        assert_eq!(universe.channels[..2], [64, 64]);
+        let position = fixture.read_position(&universe).unwrap();
+        assert!((position.pan + 135.0).abs() < 1.0 && (position.tilt + 67.5).abs() < 1.0);
+        // Angles outside the range stop at its ends
+        fixture.write_position(&mut universe, PanTilt::new(-400.0, 400.0)).unwrap();
+        assert_eq!(universe.channels[..2], [0, 255]);
+
+        fixture.movement.invert_pan = true;
+        fixture.write_position(&mut universe, PanTilt::new(-135.0, -67.5)).unwrap();
+        assert_eq!(universe.channels[..2], [191, 64]);
+
+        // Swapped axes move the pan angle with the tilt motor and convert it with the tilt range
+        fixture.movement = MovementOptions { swap_axes: true, ..MovementOptions::default() };
+        fixture.write_position(&mut universe, PanTilt::new(-67.5, 135.0)).unwrap();
+        assert_eq!(universe.channels[..2], [191, 64]);
+
+        fixture.movement.invert_pan = true;
+        fixture.write_position_normalized(&mut universe, 0.25, 1.0).unwrap();
+        assert_eq!(universe.channels[..2], [255, 191]);
+        let (pan, tilt) = fixture.read_position_normalized(&universe).unwrap();
+        assert!((pan - 0.25).abs() < 0.01 && (tilt - 1.0).abs() < 0.01);
+
+        // Without a degree range the axis assumes the default one
+        let mut mode = fixture.channel_mode().unwrap().clone();
+        mode.movement.as_mut().unwrap().tilt.as_mut().unwrap().degrees = None;
+        let fixture = test_fixture_with(mode, 1);
+        fixture.write_position(&mut universe, PanTilt::new(270.0, DEFAULT_TILT_RANGE.0)).unwrap();
+        assert_eq!(universe.channels[..2], [255, 0]);
+    }
+}
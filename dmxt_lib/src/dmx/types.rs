@@ -17,6 +17,31 @@ pub struct DMXUniverse {
     pub channels: [u8; DMX_CHANNELS],
 }
 
+impl DMXUniverse {
+    pub fn new() -> DMXUniverse {
+        DMXUniverse { channels: [0; DMX_CHANNELS] }
+    }
+
+    pub fn get(&self, channel: Channel) -> Result<u8, DMXError> {
+        Ok(self.channels[channel.index()?])
+    }
+
+    pub fn set(&mut self, channel: Channel, value: u8) -> Result<(), DMXError> {
+        self.channels[channel.index()?] = value;
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.channels = [0; DMX_CHANNELS];
+    }
+}
+
+impl Default for DMXUniverse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct DMXRange {
     pub start: DMXAddress,
@@ -31,6 +56,70 @@ impl DMXRange {
     pub fn from_tuple(range: (DMXAddress, DMXAddress)) -> DMXRange {
         DMXRange { start: range.0, end: range.1 }
     }
+
+    pub fn channel(&self) -> Channel {
+        self.start.channel
+    }
+
+    // Maps a normalized value (0.0 - 1.0) onto the range, `end` may be lower than `start`
+    pub fn value_at(&self, value: f64) -> u8 {
+        let value = value.clamp(0.0, 1.0);
+        let start = self.start.value as f64;
+        let end = self.end.value as f64;
+        (start + (end - start) * value).round() as u8
+    }
+
+    // Coarse/fine values for a normalized value, without a fine channel this is `value_at`.
+    // With a fine channel the range spans from `start.value` MSB/0 LSB to `end.value` MSB/255 LSB
+    pub fn values(&self, value: f64, fine: Option<Channel>) -> Vec<(Channel, u8)> {
+        match fine {
+            Some(fine) => {
+                let (msb, lsb) = split_16bit(self.value_at_16bit(value));
+                vec![(self.channel(), msb), (fine, lsb)]
+            },
+            None => vec![(self.channel(), self.value_at(value))],
+        }
+    }
+
+    pub fn value_at_16bit(&self, value: f64) -> u16 {
+        let value = value.clamp(0.0, 1.0);
+        let start = self.start.value as f64 * 256.0;
+        let end = self.end.value as f64 * 256.0 + 255.0;
+        let (start, end) = if self.start.value <= self.end.value { (start, end) } else { (start + 255.0, end - 255.0) };
+        (start + (end - start) * value).round() as u16
+    }
+
+    // Inverse of `value_at`, values outside of the range are clamped
+    pub fn position_of(&self, value: u8) -> f64 {
+        let start = self.start.value as f64;
+        let end = self.end.value as f64;
+        if start == end {
+            return 0.0;
+        }
+        ((value as f64 - start) / (end - start)).clamp(0.0, 1.0)
+    }
+
+    // Inverse of `values`, `read` gives the value of a channel
+    pub fn read(&self, fine: Option<Channel>, read: impl Fn(Channel) -> u8) -> f64 {
+        let Some(fine) = fine else {
+            return self.position_of(read(self.channel()));
+        };
+        let value = join_16bit(read(self.channel()), read(fine)) as f64;
+        let start = self.value_at_16bit(0.0) as f64;
+        let end = self.value_at_16bit(1.0) as f64;
+        if start == end {
+            return 0.0;
+        }
+        ((value - start) / (end - start)).clamp(0.0, 1.0)
+    }
+
+    // Channel of the range shifted by `offset` channels, used for trailing channel color modes
+    pub fn shifted(&self, offset: u16) -> DMXRange {
+        DMXRange {
+            start: DMXAddress::new(self.start.channel.offset(offset), self.start.value),
+            end: DMXAddress::new(self.end.channel.offset(offset), self.end.value),
+        }
+    }
 }
 
 impl From<Channel> for DMXRange {
@@ -88,6 +177,14 @@ impl From<(u16, u8)> for DMXAddress {
     }
 }
 
+pub fn split_16bit(value: u16) -> (u8, u8) {
+    ((value >> 8) as u8, (value & 0xFF) as u8)
+}
+
+pub fn join_16bit(msb: u8, lsb: u8) -> u16 {
+    (msb as u16) << 8 | lsb as u16
+}
+
 #[derive(Debug, Clone, Copy, Add, Sub, From, Into, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Channel{
     id: u16
@@ -98,6 +195,29 @@ impl Channel {
         check_valid_channel(channel.into())?;
         Ok(Channel { id: channel })
     }
+
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    // Zero based index into `DMXUniverse::channels`. Channels that didn't go through `new`,
+    // e.g. deserialized or offset ones, can be outside of the universe
+    pub fn index(&self) -> Result<usize, DMXError> {
+        check_valid_channel(self.id.into())?;
+        Ok(self.id as usize - 1)
+    }
+
+    // May go past the last channel, `index` and `absolute` check it
+    pub fn offset(&self, offset: u16) -> Channel {
+        Channel { id: self.id.saturating_add(offset) }
+    }
+
+    // Resolves a fixture relative channel (starting at 1) against the fixture start channel
+    pub fn absolute(&self, start: Channel) -> Result<Channel, DMXError> {
+        let id = (start.id as usize + self.id as usize).saturating_sub(1);
+        check_valid_channel(id)?;
+        Ok(Channel { id: id as u16 })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -118,6 +238,55 @@ pub enum Color {
     All,
 }
 
+impl Color {
+    pub fn rgb(&self) -> Option<(u8, u8, u8)> {
+        match self {
+            Color::Red => Some((255, 0, 0)),
+            Color::Green => Some((0, 255, 0)),
+            Color::Blue => Some((0, 0, 255)),
+            Color::Cyan => Some((0, 255, 255)),
+            Color::Magenta => Some((255, 0, 255)),
+            Color::Yellow => Some((255, 255, 0)),
+            Color::White => Some((255, 255, 255)),
+            Color::Black => Some((0, 0, 0)),
+            Color::CustomRGB(_, rgb) => Some(*rgb),
+            _ => None,
+        }
+    }
+}
+
 pub trait DMXDevice {
     fn write_channels(&mut self, channels: &mut [u8]) -> Result<(), DMXError>;
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn universes_and_channels_resolve_fixture_addresses() {
+        let last = Channel::new(512).unwrap();
+        let mut universe = DMXUniverse::new();
+        universe.set(last, 7).unwrap();
+        assert_eq!(universe.get(last).unwrap(), 7);
+        assert_eq!(universe.channels[511], 7);
+        universe.clear();
+        assert_eq!(universe, DMXUniverse::new());
+
+        let start = Channel::new(10).unwrap();
+        assert_eq!(Channel::new(3).unwrap().offset(2).id(), 5);
+        assert_eq!(Channel::new(1).unwrap().absolute(start).unwrap(), start);
+        assert_eq!(Channel::new(4).unwrap().absolute(start).unwrap().id(), 13);
+        assert!(Channel::new(4).unwrap().absolute(Channel::new(510).unwrap()).is_err());
+
+        // Channels made without `Channel::new` are checked when they are used
+        let past = last.offset(1);
+        assert!(universe.set(past, 1).is_err() && universe.get(past).is_err());
+        assert!(universe.get(Channel::from(0)).is_err());
+        assert_eq!(Channel::from(u16::MAX).offset(2).id(), u16::MAX);
+        assert!(Channel::from(0).absolute(Channel::from(0)).is_err());
+        assert!(Channel::from(u16::MAX).absolute(start).is_err());
+        let trailing = DMXRange::from(Channel::from(u16::MAX - 1));
+        assert_eq!(trailing.shifted(3).channel().id(), u16::MAX);
+    }
+}
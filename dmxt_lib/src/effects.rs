@@ -0,0 +1,4 @@
+// Paths
+pub mod pixel_map;
+pub mod movement;
+pub mod spatial;
\ No newline at end of file
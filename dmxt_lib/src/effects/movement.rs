@@ -0,0 +1,163 @@
+use crate::components::Fixture;
+use crate::dmx::DMXUniverse;
+
+use std::f64::consts::TAU;
+
+use open_dmx::error::DMXError;
+
+use serde::{Serialize, Deserialize};
+
+// Position of a moving head in degrees from the middle of both axes, see `Fixture::read_position`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PanTilt {
+    pub pan: f64,
+    pub tilt: f64,
+}
+
+impl PanTilt {
+    pub fn new(pan: f64, tilt: f64) -> PanTilt {
+        PanTilt { pan, tilt }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Shape {
+    Circle,
+    Figure8,
+    // Back and forth along a line, the angle is measured from the pan axis in degrees
+    Line(f64),
+    // Smoothly interpolated random waypoints, one per cycle
+    Random(u32),
+}
+
+impl Shape {
+    // Unit offset (-1.0 - 1.0 on both axes) for a phase in cycles
+    pub fn offset(&self, phase: f64) -> (f64, f64) {
+        let angle = phase.rem_euclid(1.0) * TAU;
+        match self {
+            Shape::Circle => (angle.cos(), angle.sin()),
+            Shape::Figure8 => (angle.sin(), (2.0 * angle).sin()),
+            Shape::Line(direction) => {
+                let t = angle.sin();
+                let direction = direction.to_radians();
+                (t * direction.cos(), t * direction.sin())
+            },
+            Shape::Random(seed) => {
+                let cycle = phase.floor() as i64;
+                let t = phase - phase.floor();
+                let t = t * t * (3.0 - 2.0 * t);
+                let (x0, y0) = waypoint(cycle, *seed);
+                let (x1, y1) = waypoint(cycle + 1, *seed);
+                (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t)
+            },
+        }
+    }
+}
+
+fn waypoint(cycle: i64, seed: u32) -> (f64, f64) {
+    let hash = |salt: u64| {
+        let mut h = (cycle as u64).wrapping_mul(0x9E3779B97F4A7C15) ^ (seed as u64) ^ salt;
+        h = (h ^ (h >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94D049BB133111EB);
+        h ^= h >> 31;
+        (h & 0xFFFF) as f64 / 32767.5 - 1.0
+    };
+    (hash(0x51), hash(0xA7))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShapeGenerator {
+    pub shape: Shape,
+    pub center: PanTilt,
+    // Amplitude in degrees on each axis
+    pub size: PanTilt,
+    // Phase offset spread over the group in cycles, 1.0 distributes the fixtures over the whole shape
+    pub spread: f64,
+}
+
+impl ShapeGenerator {
+    pub fn new(shape: Shape, center: PanTilt, size: PanTilt) -> Self {
+        Self {
+            shape,
+            center,
+            size,
+            spread: 0.0,
+        }
+    }
+
+    pub fn position(&self, phase: f64, index: usize, count: usize) -> PanTilt {
+        let offset = if count > 0 { self.spread * index as f64 / count as f64 } else { 0.0 };
+        let (x, y) = self.shape.offset(phase + offset);
+        PanTilt::new(self.center.pan + x * self.size.pan, self.center.tilt + y * self.size.tilt)
+    }
+
+    // Writes the shape position for every fixture of the group, `group` indexes into `fixtures`
+    pub fn render(&self, phase: f64, fixtures: &[Fixture], group: &[usize], universes: &mut [DMXUniverse]) -> Result<(), DMXError> {
+        for (index, fixture) in group.iter().enumerate() {
+            let fixture = match fixtures.get(*fixture) {
+                Some(fixture) => fixture,
+                None => continue,
+            };
+            if let Some(universe) = universes.get_mut(fixture.universe) {
+                fixture.write_position(universe, self.position(phase, index, group.len()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::fixture::{FixtureChannelMode, FixtureMovement, MovementAxis};
+    use crate::dmx::Channel;
+    use crate::test_support::test_fixture_with;
+
+    fn close((x, y): (f64, f64), (ex, ey): (f64, f64)) -> bool {
+        (x - ex).abs() < 1e-9 && (y - ey).abs() < 1e-9
+    }
+
+    #[test]
+    fn shapes_trace_unit_offsets() {
+        assert!(close(Shape::Circle.offset(0.0), (1.0, 0.0)));
+        assert!(close(Shape::Circle.offset(1.25), (0.0, 1.0)));
+        assert!(close(Shape::Figure8.offset(0.25), (1.0, 0.0)));
+        assert!(close(Shape::Figure8.offset(0.125), (0.5f64.sqrt(), 1.0)));
+        assert!(close(Shape::Line(90.0).offset(0.25), (0.0, 1.0)));
+        assert!(close(Shape::Line(0.0).offset(0.75), (-1.0, 0.0)));
+
+        // Random shapes pass through one waypoint per cycle without jumps
+        let random = Shape::Random(3);
+        assert!(close(random.offset(2.0), waypoint(2, 3)));
+        assert!(close(random.offset(3.0 - 1e-12), random.offset(3.0)));
+        assert_eq!(random.offset(2.5), Shape::Random(3).offset(2.5));
+        assert_ne!(random.offset(2.0), Shape::Random(4).offset(2.0));
+        for step in 0..100 {
+            let (x, y) = random.offset(step as f64 * 0.37);
+            assert!((-1.0..=1.0).contains(&x) && (-1.0..=1.0).contains(&y));
+        }
+    }
+
+    #[test]
+    fn groups_spread_over_the_shape() {
+        let mut generator = ShapeGenerator::new(Shape::Circle, PanTilt::new(10.0, 20.0), PanTilt::new(5.0, 2.0));
+        assert_eq!(generator.position(0.0, 3, 4), PanTilt::new(15.0, 20.0));
+        generator.spread = 1.0;
+        let second = generator.position(0.0, 1, 4);
+        assert!(close((second.pan, second.tilt), (10.0, 22.0)));
+        let third = generator.position(0.0, 2, 4);
+        assert!(close((third.pan, third.tilt), (5.0, 20.0)));
+
+        let mut pan = MovementAxis::new(Channel::new(1).unwrap().into(), None);
+        pan.degrees(-90.0, 90.0);
+        let mut mode = FixtureChannelMode::builder();
+        mode.total_channels(Channel::new(1).unwrap()).movement(FixtureMovement::builder().pan(pan).build().unwrap());
+        let mode = mode.build().unwrap();
+        let fixtures = vec![test_fixture_with(mode.clone(), 1), test_fixture_with(mode, 2)];
+        let generator = ShapeGenerator { spread: 0.5, ..ShapeGenerator::new(Shape::Line(0.0), PanTilt::default(), PanTilt::new(90.0, 0.0)) };
+        let mut universes = vec![DMXUniverse::new()];
+        generator.render(0.25, &fixtures, &[1, 0], &mut universes).unwrap();
+        // The first of the group swings out to the end of the pan range, the second is a quarter cycle behind in the middle
+        assert_eq!(universes[0].channels[..2], [128, 255]);
+    }
+}
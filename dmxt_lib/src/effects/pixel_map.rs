@@ -0,0 +1,469 @@
+use crate::components::Fixture;
+use crate::components::color::RGB;
+use crate::dmx::DMXUniverse;
+use crate::patch::Patch;
+use crate::stage::Stage;
+
+use open_dmx::error::DMXError;
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PixelCanvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<RGB>,
+}
+
+impl PixelCanvas {
+    pub fn new(width: usize, height: usize) -> PixelCanvas {
+        PixelCanvas {
+            width,
+            height,
+            pixels: vec![RGB::BLACK; width * height],
+        }
+    }
+
+    // Builds a canvas from a packed 8-bit RGB image or video frame
+    pub fn from_rgb8(width: usize, height: usize, data: &[u8]) -> Option<PixelCanvas> {
+        if data.len() != width * height * 3 {
+            return None;
+        }
+        Some(PixelCanvas {
+            width,
+            height,
+            pixels: data.chunks_exact(3).map(|p| RGB::from_u8(p[0], p[1], p[2])).collect(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<RGB> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.pixels[y * self.width + x])
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: RGB) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    pub fn fill(&mut self, color: RGB) {
+        self.pixels.iter_mut().for_each(|p| *p = color);
+    }
+
+    // Average color of a `size` x `size` block, pixels outside the canvas are skipped
+    pub fn sample(&self, x: usize, y: usize, size: usize) -> RGB {
+        let mut sum = (0.0, 0.0, 0.0);
+        let mut count = 0;
+        for sy in y..y + size.max(1) {
+            for sx in x..x + size.max(1) {
+                if let Some(p) = self.get(sx, sy) {
+                    sum = (sum.0 + p.r, sum.1 + p.g, sum.2 + p.b);
+                    count += 1;
+                }
+            }
+        }
+        if count == 0 {
+            return RGB::BLACK;
+        }
+        let count = count as f64;
+        RGB::new(sum.0 / count, sum.1 / count, sum.2 / count)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+// Procedural content, `phase` is a running position in beats or seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Pattern {
+    Solid(RGB),
+    Gradient { from: RGB, to: RGB, axis: Axis },
+    Rainbow { axis: Axis },
+    Chase { color: RGB, background: RGB, width: usize, axis: Axis },
+    Noise { color: RGB, scale: f64, seed: u32 },
+    TextScroll { text: String, color: RGB, background: RGB },
+}
+
+impl Pattern {
+    pub fn render(&self, canvas: &mut PixelCanvas, phase: f64) {
+        let (width, height) = (canvas.width(), canvas.height());
+        match self {
+            Pattern::Solid(color) => canvas.fill(*color),
+            Pattern::Gradient { from, to, axis } => {
+                for y in 0..height {
+                    for x in 0..width {
+                        let t = position(*axis, x, y, width, height) + phase;
+                        // Ping pong so the gradient can scroll without a hard edge
+                        let t = 1.0 - (t.rem_euclid(2.0) - 1.0).abs();
+                        canvas.set(x, y, from.lerp(*to, t));
+                    }
+                }
+            },
+            Pattern::Rainbow { axis } => {
+                for y in 0..height {
+                    for x in 0..width {
+                        let hue = position(*axis, x, y, width, height) + phase;
+                        canvas.set(x, y, RGB::from_hsv(hue, 1.0, 1.0));
+                    }
+                }
+            },
+            Pattern::Chase { color, background, width: chase_width, axis } => {
+                let length = match axis {
+                    Axis::Horizontal => width,
+                    Axis::Vertical => height,
+                };
+                if length == 0 {
+                    return;
+                }
+                let head = (phase.rem_euclid(1.0) * length as f64) as usize;
+                for y in 0..height {
+                    for x in 0..width {
+                        let i = if *axis == Axis::Horizontal { x } else { y };
+                        let lit = (i + length - head) % length < (*chase_width).max(1);
+                        canvas.set(x, y, if lit { *color } else { *background });
+                    }
+                }
+            },
+            Pattern::Noise { color, scale, seed } => {
+                let scale = if *scale > 0.0 { *scale } else { 1.0 };
+                for y in 0..height {
+                    for x in 0..width {
+                        let v = value_noise(x as f64 / scale, y as f64 / scale, phase, *seed);
+                        canvas.set(x, y, color.scale(v));
+                    }
+                }
+            },
+            Pattern::TextScroll { text, color, background } => {
+                canvas.fill(*background);
+                let columns = text_columns(text);
+                if columns.is_empty() {
+                    return;
+                }
+                // Text enters from the right edge and scrolls one column per phase step
+                let total = columns.len() + width;
+                let offset = (phase.max(0.0) as usize) % total;
+                let top = height.saturating_sub(GLYPH_HEIGHT) / 2;
+                for x in 0..width {
+                    let column = (x + offset).checked_sub(width).and_then(|c| columns.get(c));
+                    if let Some(bits) = column {
+                        for row in 0..GLYPH_HEIGHT {
+                            if bits & (1 << row) != 0 {
+                                canvas.set(x, top + row, *color);
+                            }
+                        }
+                    }
+                }
+            },
+        }
+    }
+}
+
+fn position(axis: Axis, x: usize, y: usize, width: usize, height: usize) -> f64 {
+    match axis {
+        Axis::Horizontal => x as f64 / width.max(1) as f64,
+        Axis::Vertical => y as f64 / height.max(1) as f64,
+    }
+}
+
+fn hash(x: i64, y: i64, z: i64, seed: u32) -> f64 {
+    let mut h = (x.wrapping_mul(374761393) ^ y.wrapping_mul(668265263) ^ z.wrapping_mul(2147483647)) as u64;
+    h ^= seed as u64;
+    h = (h ^ (h >> 13)).wrapping_mul(1274126177);
+    h ^= h >> 16;
+    (h & 0xFFFF) as f64 / 65535.0
+}
+
+fn smooth(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Trilinear value noise, time is the third dimension so the pattern evolves smoothly
+fn value_noise(x: f64, y: f64, z: f64, seed: u32) -> f64 {
+    let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+    let (tx, ty, tz) = (smooth(x - x0), smooth(y - y0), smooth(z - z0));
+    let (xi, yi, zi) = (x0 as i64, y0 as i64, z0 as i64);
+    let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+    let layer = |z: i64| {
+        let top = lerp(hash(xi, yi, z, seed), hash(xi + 1, yi, z, seed), tx);
+        let bottom = lerp(hash(xi, yi + 1, z, seed), hash(xi + 1, yi + 1, z, seed), tx);
+        lerp(top, bottom, ty)
+    };
+    lerp(layer(zi), layer(zi + 1), tz)
+}
+
+const GLYPH_HEIGHT: usize = 5;
+
+// 3x5 glyphs, one byte per column with the top row in the lowest bit
+fn glyph(c: char) -> [u8; 3] {
+    match c.to_ascii_uppercase() {
+        'A' => [0x1E, 0x05, 0x1E],
+        'B' => [0x1F, 0x15, 0x0A],
+        'C' => [0x0E, 0x11, 0x11],
+        'D' => [0x1F, 0x11, 0x0E],
+        'E' => [0x1F, 0x15, 0x11],
+        'F' => [0x1F, 0x05, 0x01],
+        'G' => [0x0E, 0x11, 0x1D],
+        'H' => [0x1F, 0x04, 0x1F],
+        'I' => [0x11, 0x1F, 0x11],
+        'J' => [0x08, 0x10, 0x0F],
+        'K' => [0x1F, 0x04, 0x1B],
+        'L' => [0x1F, 0x10, 0x10],
+        'M' => [0x1F, 0x02, 0x1F],
+        'N' => [0x1F, 0x0E, 0x1F],
+        'O' => [0x0E, 0x11, 0x0E],
+        'P' => [0x1F, 0x05, 0x02],
+        'Q' => [0x0E, 0x19, 0x1E],
+        'R' => [0x1F, 0x0D, 0x16],
+        'S' => [0x12, 0x15, 0x09],
+        'T' => [0x01, 0x1F, 0x01],
+        'U' => [0x0F, 0x10, 0x1F],
+        'V' => [0x07, 0x18, 0x07],
+        'W' => [0x1F, 0x08, 0x1F],
+        'X' => [0x1B, 0x04, 0x1B],
+        'Y' => [0x03, 0x1C, 0x03],
+        'Z' => [0x19, 0x15, 0x13],
+        '0' => [0x1F, 0x11, 0x1F],
+        '1' => [0x12, 0x1F, 0x10],
+        '2' => [0x1D, 0x15, 0x17],
+        '3' => [0x15, 0x15, 0x1F],
+        '4' => [0x07, 0x04, 0x1F],
+        '5' => [0x17, 0x15, 0x1D],
+        '6' => [0x1F, 0x15, 0x1D],
+        '7' => [0x01, 0x01, 0x1F],
+        '8' => [0x1F, 0x15, 0x1F],
+        '9' => [0x17, 0x15, 0x1F],
+        '!' => [0x00, 0x17, 0x00],
+        '.' => [0x00, 0x10, 0x00],
+        '-' => [0x04, 0x04, 0x04],
+        _ => [0x00, 0x00, 0x00],
+    }
+}
+
+fn text_columns(text: &str) -> Vec<u8> {
+    let mut columns = Vec::new();
+    for c in text.chars() {
+        columns.extend_from_slice(&glyph(c));
+        columns.push(0);
+    }
+    columns
+}
+
+// Places a fixture matrix on the canvas, every matrix cell covers `scale` x `scale` pixels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PixelMapPlacement {
+    pub fixture: usize,
+    pub x: usize,
+    pub y: usize,
+    pub scale: usize,
+    pub vertical: bool,
+}
+
+impl PixelMapPlacement {
+    pub fn new(fixture: usize, x: usize, y: usize) -> Self {
+        Self {
+            fixture,
+            x,
+            y,
+            scale: 1,
+            vertical: false,
+        }
+    }
+
+    fn pixel(&self, row: usize, column: usize) -> (usize, usize) {
+        let (dx, dy) = if self.vertical { (row, column) } else { (column, row) };
+        (self.x + dx * self.scale, self.y + dy * self.scale)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PixelMap {
+    pub width: usize,
+    pub height: usize,
+    pub placements: Vec<PixelMapPlacement>,
+}
+
+impl PixelMap {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            placements: Vec::new(),
+        }
+    }
+
+    // Maps the matrices of the placed fixtures by where they are on the plan, one pixel per cell
+    // with upstage at the top. Fixtures turned sideways map their rows vertically
+    pub fn from_stage(stage: &Stage, patch: &Patch, pixels_per_meter: f64) -> Self {
+        let mut map = Self::new((stage.width * pixels_per_meter).ceil() as usize, (stage.depth * pixels_per_meter).ceil() as usize);
+        for (index, (fixture, placement)) in patch.fixtures().iter().zip(stage.placements(patch)).enumerate() {
+            let (Some(placement), Some(lights)) = (placement, fixture.channel_mode().and_then(|mode| mode.lights.as_ref())) else {
+                continue;
+            };
+            let rows = lights.matrix.len() as f64;
+            let columns = lights.matrix.iter().map(Vec::len).max().unwrap_or(0) as f64;
+            let vertical = (placement.rotation.rem_euclid(180.0) - 90.0).abs() < 45.0;
+            let (width, height) = if vertical { (rows, columns) } else { (columns, rows) };
+            let x = (placement.position.x + stage.width / 2.0) * pixels_per_meter - width / 2.0;
+            let y = (stage.depth - placement.position.y) * pixels_per_meter - height / 2.0;
+            map.place(PixelMapPlacement {
+                fixture: index,
+                x: x.round().max(0.0) as usize,
+                y: y.round().max(0.0) as usize,
+                scale: 1,
+                vertical,
+            });
+        }
+        map
+    }
+
+    pub fn place(&mut self, placement: PixelMapPlacement) -> &mut Self {
+        self.placements.push(placement);
+        self
+    }
+
+    pub fn canvas(&self) -> PixelCanvas {
+        PixelCanvas::new(self.width, self.height)
+    }
+
+    pub fn render_pattern(&self, pattern: &Pattern, phase: f64, fixtures: &[Fixture], universes: &mut [DMXUniverse]) -> Result<(), DMXError> {
+        let mut canvas = self.canvas();
+        pattern.render(&mut canvas, phase);
+        self.render(&canvas, fixtures, universes)
+    }
+
+    // Samples the canvas for every placed matrix cell and writes it through the color engine
+    pub fn render(&self, canvas: &PixelCanvas, fixtures: &[Fixture], universes: &mut [DMXUniverse]) -> Result<(), DMXError> {
+        for placement in &self.placements {
+            let fixture = match fixtures.get(placement.fixture) {
+                Some(fixture) => fixture,
+                None => continue,
+            };
+            let matrix = match fixture.channel_mode().and_then(|mode| mode.lights.as_ref()) {
+                Some(lights) => &lights.matrix,
+                None => continue,
+            };
+            let universe = match universes.get_mut(fixture.universe) {
+                Some(universe) => universe,
+                None => continue,
+            };
+            for (row, cells) in matrix.iter().enumerate() {
+                for column in 0..cells.len() {
+                    let (x, y) = placement.pixel(row, column);
+                    let color = canvas.sample(x, y, placement.scale);
+                    fixture.write_color(universe, row, column, color)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::fixture::{FixtureChannelMode, FixtureColorMode, FixtureLights, FixtureMatrix};
+    use crate::dmx::Channel;
+    use crate::test_support::test_fixture_with;
+
+    fn close(a: RGB, b: RGB) -> bool {
+        (a.r - b.r).abs() < 1e-9 && (a.g - b.g).abs() < 1e-9 && (a.b - b.b).abs() < 1e-9
+    }
+
+    fn rgb(start: u16) -> FixtureLights {
+        let channel = |offset: u16| Channel::new(start + offset).unwrap().into();
+        FixtureLights::new(FixtureColorMode::RGB(channel(0), channel(1), channel(2)), None)
+    }
+
+    fn row(pattern: &Pattern, phase: f64) -> Vec<RGB> {
+        let mut canvas = PixelCanvas::new(4, 1);
+        pattern.render(&mut canvas, phase);
+        (0..4).map(|x| canvas.get(x, 0).unwrap()).collect()
+    }
+
+    #[test]
+    fn canvases_average_blocks_and_skip_outside_pixels() {
+        let mut canvas = PixelCanvas::new(2, 2);
+        canvas.set(0, 0, RGB::new(1.0, 0.0, 0.0));
+        canvas.set(1, 1, RGB::new(0.0, 0.0, 1.0));
+        canvas.set(2, 2, RGB::WHITE);
+        assert!(close(canvas.sample(0, 0, 2), RGB::new(0.25, 0.0, 0.25)));
+        assert!(close(canvas.sample(1, 1, 2), RGB::new(0.0, 0.0, 1.0)));
+        assert_eq!(canvas.sample(5, 5, 1), RGB::BLACK);
+        assert_eq!(canvas.get(2, 0), None);
+
+        assert!(PixelCanvas::from_rgb8(2, 1, &[255, 0, 0]).is_none());
+        let image = PixelCanvas::from_rgb8(2, 1, &[255, 0, 0, 0, 0, 255]).unwrap();
+        assert_eq!(image.get(1, 0), Some(RGB::new(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn patterns_render_onto_the_canvas() {
+        let red = RGB::new(1.0, 0.0, 0.0);
+        assert!(row(&Pattern::Solid(red), 0.0).iter().all(|pixel| *pixel == red));
+
+        let gradient = Pattern::Gradient { from: RGB::BLACK, to: RGB::WHITE, axis: Axis::Horizontal };
+        let pixels = row(&gradient, 0.0);
+        assert!(close(pixels[0], RGB::BLACK) && close(pixels[2], RGB::new(0.5, 0.5, 0.5)));
+        // Scrolled by a whole gradient it runs back from white
+        let pixels = row(&gradient, 1.0);
+        assert!(close(pixels[0], RGB::WHITE) && close(pixels[2], RGB::new(0.5, 0.5, 0.5)));
+
+        let pixels = row(&Pattern::Rainbow { axis: Axis::Horizontal }, 0.0);
+        assert!(close(pixels[0], red) && close(pixels[1], RGB::new(0.5, 1.0, 0.0)) && close(pixels[2], RGB::new(0.0, 1.0, 1.0)));
+
+        let chase = Pattern::Chase { color: red, background: RGB::BLACK, width: 1, axis: Axis::Horizontal };
+        assert_eq!(row(&chase, 0.5), vec![RGB::BLACK, RGB::BLACK, red, RGB::BLACK]);
+        assert_eq!(row(&chase, 1.0), vec![red, RGB::BLACK, RGB::BLACK, RGB::BLACK]);
+
+        let noise = Pattern::Noise { color: red, scale: 2.0, seed: 7 };
+        let pixels = row(&noise, 0.3);
+        assert_eq!(pixels, row(&noise, 0.3));
+        assert!(pixels.iter().all(|pixel| pixel.g == 0.0 && pixel.b == 0.0));
+
+        // "I" is three columns wide, fully on screen once it has scrolled in by its width
+        let text = Pattern::TextScroll { text: "I".into(), color: RGB::WHITE, background: red };
+        let mut canvas = PixelCanvas::new(3, 5);
+        text.render(&mut canvas, 0.0);
+        assert!((0..5).all(|y| canvas.get(1, y) == Some(red)));
+        text.render(&mut canvas, 3.0);
+        assert!((0..5).all(|y| canvas.get(1, y) == Some(RGB::WHITE)));
+        assert_eq!(canvas.get(0, 0), Some(RGB::WHITE));
+        assert_eq!(canvas.get(0, 1), Some(red));
+    }
+
+    #[test]
+    fn maps_sample_a_pixel_per_matrix_cell() {
+        let mut mode = FixtureChannelMode::builder();
+        mode.total_channels(Channel::new(6).unwrap()).matrix(FixtureMatrix::new(vec![vec![rgb(1), rgb(4)]]));
+        let fixtures = vec![test_fixture_with(mode.build().unwrap(), 1)];
+        let mut canvas = PixelCanvas::new(2, 2);
+        canvas.set(0, 0, RGB::new(1.0, 0.0, 0.0));
+        canvas.set(1, 0, RGB::new(0.0, 0.0, 1.0));
+        canvas.set(0, 1, RGB::new(0.0, 1.0, 0.0));
+
+        let mut map = PixelMap::new(2, 2);
+        map.place(PixelMapPlacement::new(0, 0, 0));
+        let mut universes = vec![DMXUniverse::new()];
+        map.render(&canvas, &fixtures, &mut universes).unwrap();
+        assert_eq!(universes[0].channels[..6], [255, 0, 0, 0, 0, 255]);
+
+        // Vertical placements run the row down the canvas
+        map.placements[0].vertical = true;
+        map.render(&canvas, &fixtures, &mut universes).unwrap();
+        assert_eq!(universes[0].channels[..6], [255, 0, 0, 0, 255, 0]);
+    }
+}
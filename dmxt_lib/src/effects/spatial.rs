@@ -0,0 +1,177 @@
+use crate::components::Fixture;
+use crate::components::color::RGB;
+use crate::dmx::DMXUniverse;
+use crate::effects::movement::Shape;
+use crate::stage::{Placement, Point3};
+
+use std::f64::consts::TAU;
+
+use open_dmx::error::DMXError;
+
+use serde::{Serialize, Deserialize};
+
+// How an effect travels over the stage
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Spread {
+    // Straight fronts moving along a direction of the floor plan, in degrees counterclockwise from left to right as the audience sees it
+    Linear { angle: f64 },
+    // Rings moving out from a point
+    Radial { center: Point3 },
+}
+
+impl Spread {
+    // How far along the way the effect travels a point is, in meters
+    pub fn distance(&self, point: Point3) -> f64 {
+        match self {
+            Spread::Linear { angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                point.x * cos + point.y * sin
+            },
+            Spread::Radial { center } => point.distance(*center),
+        }
+    }
+}
+
+// Pulses of color that travel over the fixtures by where they are on stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Wave {
+    pub spread: Spread,
+    pub color: RGB,
+    pub background: RGB,
+    // Meters from one pulse to the next
+    pub wavelength: f64,
+    // Pulses per beat, negative speeds run backwards
+    pub speed: f64,
+    // Part of the wavelength that is lit, 1.0 fades from one pulse into the next
+    pub width: f64,
+}
+
+impl Wave {
+    pub fn new(spread: Spread, color: RGB) -> Self {
+        Self {
+            spread,
+            color,
+            background: RGB::BLACK,
+            wavelength: 4.0,
+            speed: 1.0,
+            width: 0.5,
+        }
+    }
+
+    // Level of the wave at a point (0.0 - 1.0), `beats` is a running position
+    pub fn level(&self, point: Point3, beats: f64) -> f64 {
+        let phase = (beats * self.speed - self.spread.distance(point) / self.wavelength.max(0.01)).rem_euclid(1.0);
+        let width = self.width.clamp(0.01, 1.0);
+        if phase < width { 0.5 - 0.5 * (TAU * phase / width).cos() } else { 0.0 }
+    }
+
+    // `placements` are by fixture like `Stage::placements`, fixtures that aren't placed are left alone
+    pub fn render(&self, beats: f64, fixtures: &[Fixture], placements: &[Option<Placement>], group: &[usize], universes: &mut [DMXUniverse]) -> Result<(), DMXError> {
+        for index in group {
+            let (Some(fixture), Some(Some(placement))) = (fixtures.get(*index), placements.get(*index)) else {
+                continue;
+            };
+            if let Some(universe) = universes.get_mut(fixture.universe) {
+                let color = self.background.lerp(self.color, self.level(placement.position, beats));
+                fixture.fill_color(universe, color)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Points moving heads at a spot on stage, taking their position and mounting into account.
+// The spot can move around in a shape, e.g. to circle the audience with every head
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Focus {
+    pub target: Point3,
+    pub shape: Option<Shape>,
+    // Meters the spot moves around the target on the floor plan
+    pub size: f64,
+    // Shape cycles per beat
+    pub speed: f64,
+    // Phase offset spread over the group in cycles, like `ShapeGenerator::spread`
+    pub spread: f64,
+}
+
+impl Focus {
+    pub fn new(target: Point3) -> Self {
+        Self {
+            target,
+            shape: None,
+            size: 1.0,
+            speed: 0.25,
+            spread: 0.0,
+        }
+    }
+
+    pub fn point(&self, beats: f64, index: usize, count: usize) -> Point3 {
+        let Some(shape) = self.shape else {
+            return self.target;
+        };
+        let offset = if count > 0 { self.spread * index as f64 / count as f64 } else { 0.0 };
+        let (x, y) = shape.offset(beats * self.speed + offset);
+        self.target + Point3::new(x, y, 0.0) * self.size
+    }
+
+    pub fn render(&self, beats: f64, fixtures: &[Fixture], placements: &[Option<Placement>], group: &[usize], universes: &mut [DMXUniverse]) -> Result<(), DMXError> {
+        for (order, index) in group.iter().enumerate() {
+            let (Some(fixture), Some(Some(placement))) = (fixtures.get(*index), placements.get(*index)) else {
+                continue;
+            };
+            if let Some(universe) = universes.get_mut(fixture.universe) {
+                fixture.write_position(universe, placement.aim(self.point(beats, order, group.len())))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::fixture::{FixtureChannelMode, FixtureMovement, MovementAxis};
+    use crate::dmx::Channel;
+    use crate::test_support::test_fixture_with;
+    use crate::stage::Mounting;
+    use crate::visualizer::FixtureState;
+
+    fn channel(id: u16) -> Channel {
+        Channel::new(id).unwrap()
+    }
+
+    #[test]
+    fn waves_travel_and_heads_focus_on_points() {
+        let mut wave = Wave::new(Spread::Linear { angle: 0.0 }, RGB::WHITE);
+        // The peak is a quarter of the wavelength in front of the start of a pulse
+        assert!((wave.level(Point3::new(0.0, 3.0, 6.0), 0.25) - 1.0).abs() < 1e-9);
+        assert!((wave.level(Point3::new(1.0, 0.0, 0.0), 0.5) - 1.0).abs() < 1e-9);
+        assert_eq!(wave.level(Point3::new(1.0, 0.0, 0.0), 0.25), 0.0);
+        wave.spread = Spread::Radial { center: Point3::new(0.0, 4.0, 0.0) };
+        assert!((wave.level(Point3::new(0.0, 6.0, 0.0), 0.75) - 1.0).abs() < 1e-9);
+
+        let mut pan = MovementAxis::new(channel(1).into(), None);
+        pan.fine(channel(2)).degrees(0.0, 540.0);
+        let mut tilt = MovementAxis::new(channel(3).into(), None);
+        tilt.fine(channel(4)).degrees(0.0, 270.0);
+        let mut mode = FixtureChannelMode::builder();
+        mode.total_channels(channel(4)).movement(FixtureMovement::builder().pan(pan).tilt(tilt).build().unwrap());
+        let mode = mode.build().unwrap();
+        let fixtures = vec![test_fixture_with(mode.clone(), 1), test_fixture_with(mode, 5)];
+        let mut floor = Placement::new(Point3::new(3.0, 4.0, 0.0));
+        floor.mounting = Mounting::Standing;
+        floor.rotation = 30.0;
+        let placements = vec![Some(Placement::new(Point3::new(-2.0, 1.0, 6.0))), Some(floor)];
+
+        let focus = Focus::new(Point3::new(0.0, 4.0, 2.0));
+        let mut universes = vec![DMXUniverse::new()];
+        focus.render(0.0, &fixtures, &placements, &[0, 1], &mut universes).unwrap();
+        for (fixture, placement) in fixtures.iter().zip(&placements) {
+            let placement = placement.unwrap();
+            let state = FixtureState::read(0, fixture, &universes);
+            let beam = placement.to_stage(state.direction());
+            let expected = (focus.target - placement.position).normalized();
+            assert!(beam.distance(expected) < 0.01, "{:?} points at {:?}", placement.mounting, beam);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::builders::fixture::{FixtureColorMode, FixtureLights};
+use crate::dmx::{Channel, Color, DMXAddress, DMXRange};
+
+use std::collections::BTreeMap;
+
+use roxmltree::Node;
+
+// Paths
+pub mod ofl;
+pub mod gdtf;
+pub mod qxf;
+mod error;
+mod report;
+// Re-exports
+pub use error::FormatError;
+pub use report::ConversionReport;
+
+// Colors with a fixed RGB value, imported colors snap to these when they match exactly
+fn standard_colors() -> [Color; 8] {
+    [Color::Red, Color::Green, Color::Blue, Color::Cyan, Color::Magenta, Color::Yellow, Color::White, Color::Black]
+}
+
+fn named_color(name: &str, rgb: (u8, u8, u8)) -> Color {
+    standard_colors().into_iter()
+        .find(|color| color.rgb() == Some(rgb))
+        .unwrap_or_else(|| Color::CustomRGB(name.into(), rgb))
+}
+
+// "#rrggbb" as OFL and QLC+ write colors
+fn parse_hex(hex: &str) -> Option<(u8, u8, u8)> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn children<'a, 'input: 'a>(node: Node<'a, 'input>, name: &'a str) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(move |child| child.has_tag_name(name))
+}
+
+// Color channels of one light collected while importing, components are named "Red", "Green", ...
+#[derive(Default)]
+struct CellLights {
+    components: BTreeMap<&'static str, DMXRange>,
+    dimmer: Option<DMXRange>,
+    dimmer_fine: Option<Channel>,
+    presets: Vec<(Color, DMXAddress)>,
+}
+
+impl CellLights {
+    fn is_colored(&self) -> bool {
+        !self.components.is_empty() || !self.presets.is_empty()
+    }
+
+    fn build(self, mode_name: &str, report: &mut ConversionReport) -> Option<FixtureLights> {
+        let c = |component: &str| self.components.get(component).copied();
+        let color_mode = if !self.presets.is_empty() {
+            if !self.components.is_empty() {
+                report.unmapped(format!("{}: color intensity channels next to color presets", mode_name));
+            }
+            FixtureColorMode::Presets(self.presets)
+        } else {
+            match (c("Red"), c("Green"), c("Blue"), c("Cyan"), c("Magenta"), c("Yellow"), c("White")) {
+                (Some(r), Some(g), Some(b), None, None, None, None) => FixtureColorMode::RGB(r, g, b),
+                (Some(r), Some(g), Some(b), None, None, None, Some(w)) => FixtureColorMode::RGBW(r, g, b, w),
+                (None, None, None, Some(c), Some(m), Some(y), None) => FixtureColorMode::CMY(c, m, y),
+                (None, None, None, Some(c), Some(m), Some(y), Some(w)) => FixtureColorMode::CMYW(c, m, y, w),
+                _ if self.components.is_empty() && self.dimmer.is_some() => FixtureColorMode::Custom("Dimmer".into(), vec![]),
+                _ if self.components.is_empty() => return None,
+                _ => {
+                    let names: Vec<&str> = self.components.keys().copied().collect();
+                    report.unmapped(format!("{}: color components {}", mode_name, names.join(", ")));
+                    FixtureColorMode::Custom(names.join(" "), self.components.values().copied().collect())
+                },
+            }
+        };
+        let mut lights = FixtureLights::new(color_mode, self.dimmer);
+        lights.dimmer_fine = self.dimmer_fine;
+        Some(lights)
+    }
+}
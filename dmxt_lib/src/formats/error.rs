@@ -0,0 +1,58 @@
+use crate::builders::error::BuildError;
+
+#[derive(Debug)]
+pub enum FormatError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Xml(roxmltree::Error),
+    Zip(zip::result::ZipError),
+    Build(BuildError),
+    MissingField(&'static str),
+    Invalid(String),
+}
+
+impl std::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatError::Io(error) => write!(f, "{}", error),
+            FormatError::Json(error) => write!(f, "invalid JSON: {}", error),
+            FormatError::Xml(error) => write!(f, "invalid XML: {}", error),
+            FormatError::Zip(error) => write!(f, "invalid archive: {}", error),
+            FormatError::Build(error) => write!(f, "invalid fixture: {}", error),
+            FormatError::MissingField(field) => write!(f, "missing {}", field),
+            FormatError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<std::io::Error> for FormatError {
+    fn from(error: std::io::Error) -> Self {
+        FormatError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for FormatError {
+    fn from(error: serde_json::Error) -> Self {
+        FormatError::Json(error)
+    }
+}
+
+impl From<BuildError> for FormatError {
+    fn from(error: BuildError) -> Self {
+        FormatError::Build(error)
+    }
+}
+
+impl From<roxmltree::Error> for FormatError {
+    fn from(error: roxmltree::Error) -> Self {
+        FormatError::Xml(error)
+    }
+}
+
+impl From<zip::result::ZipError> for FormatError {
+    fn from(error: zip::result::ZipError) -> Self {
+        FormatError::Zip(error)
+    }
+}
@@ -0,0 +1,546 @@
+use crate::builders::capability::{PhysicalRange, Unit};
+use crate::builders::fixture::{
+    FixtureChannelMode, FixtureCustomOperation, FixtureLights, FixtureMatrix, FixtureModel, FixtureMovement,
+    FixtureName, FixtureZoom, MovementAxis,
+};
+use crate::dmx::{Channel, Color, DMXAddress, DMXRange};
+use crate::formats::{child, children, named_color, CellLights, ConversionReport, FormatError};
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use roxmltree::{Document, Node};
+use zip::ZipArchive;
+
+// General Device Type Format (https://gdtf.eu) fixture files, a zip archive around `description.xml`
+
+const COLOR_ATTRIBUTES: [(&str, &str); 14] = [
+    ("ColorAdd_R", "Red"),
+    ("ColorAdd_G", "Green"),
+    ("ColorAdd_B", "Blue"),
+    ("ColorAdd_W", "White"),
+    ("ColorAdd_C", "Cyan"),
+    ("ColorAdd_M", "Magenta"),
+    ("ColorAdd_Y", "Yellow"),
+    ("ColorRGB_Red", "Red"),
+    ("ColorRGB_Green", "Green"),
+    ("ColorRGB_Blue", "Blue"),
+    ("ColorRGB_White", "White"),
+    ("ColorSub_C", "Cyan"),
+    ("ColorSub_M", "Magenta"),
+    ("ColorSub_Y", "Yellow"),
+];
+
+pub fn import_file(path: &Path) -> Result<(FixtureModel, ConversionReport), FormatError> {
+    import(File::open(path)?)
+}
+
+pub fn import<R: Read + Seek>(reader: R) -> Result<(FixtureModel, ConversionReport), FormatError> {
+    let mut archive = ZipArchive::new(reader)?;
+    let mut description = String::new();
+    archive.by_name("description.xml")?.read_to_string(&mut description)?;
+    import_description(&description)
+}
+
+pub fn import_description(xml: &str) -> Result<(FixtureModel, ConversionReport), FormatError> {
+    let document = Document::parse(xml)?;
+    let fixture_type = child(document.root_element(), "FixtureType").ok_or(FormatError::MissingField("FixtureType"))?;
+    let name = fixture_type.attribute("LongName")
+        .filter(|name| !name.is_empty())
+        .or_else(|| fixture_type.attribute("Name"))
+        .ok_or(FormatError::MissingField("Name"))?;
+    let manufacturer = fixture_type.attribute("Manufacturer").unwrap_or_default();
+
+    let mut report = ConversionReport::new();
+    let fixture = GdtfFixture::new(fixture_type);
+    let mut builder = FixtureModel::builder();
+    builder.model(FixtureName::new(name.into())).manufacturer(manufacturer.into());
+    let modes = child(fixture_type, "DMXModes").ok_or(FormatError::MissingField("DMXModes"))?;
+    for mode in children(modes, "DMXMode") {
+        builder.channel_mode(fixture.import_mode(mode, &mut report)?);
+    }
+    Ok((builder.build()?, report))
+}
+
+// Instance of a geometry that is repeated through geometry references, e.g. the pixels of a bar
+struct GeometryInstance {
+    name: String,
+    geometry: String,
+    // DMX offset of the instance's channels, 0 for the first channel
+    offset: u16,
+}
+
+struct ChannelFunction<'a> {
+    name: String,
+    attribute: String,
+    from: u8,
+    to: u8,
+    physical: Option<(f64, f64)>,
+    wheel: Option<&'a str>,
+    sets: Vec<ChannelSet>,
+}
+
+struct ChannelSet {
+    name: String,
+    from: u8,
+    slot: Option<usize>,
+}
+
+enum Mapped {
+    Color(&'static str, DMXRange),
+    Dimmer(DMXRange),
+    Pan(MovementAxis),
+    Tilt(MovementAxis),
+    Zoom(FixtureZoom),
+    Presets(Vec<(Color, DMXAddress)>),
+    Custom(Vec<FixtureCustomOperation>),
+}
+
+struct GdtfFixture<'a, 'input> {
+    wheels: HashMap<&'a str, Vec<Node<'a, 'input>>>,
+    instances: Vec<GeometryInstance>,
+    positions: HashMap<&'a str, (f64, f64)>,
+}
+
+impl<'a, 'input> GdtfFixture<'a, 'input> {
+    fn new(fixture_type: Node<'a, 'input>) -> Self {
+        let wheels = child(fixture_type, "Wheels").into_iter()
+            .flat_map(|wheels| children(wheels, "Wheel"))
+            .filter_map(|wheel| Some((wheel.attribute("Name")?, children(wheel, "Slot").collect())))
+            .collect();
+        let mut fixture = Self {
+            wheels,
+            instances: Vec::new(),
+            positions: HashMap::new(),
+        };
+        if let Some(geometries) = child(fixture_type, "Geometries") {
+            for geometry in geometries.children().filter(Node::is_element) {
+                fixture.collect_geometry(geometry, (0.0, 0.0));
+            }
+        }
+        fixture
+    }
+
+    // Walks the geometry tree, positions are summed up along the way
+    fn collect_geometry(&mut self, geometry: Node<'a, 'input>, parent: (f64, f64)) {
+        let (x, y) = geometry.attribute("Position").map_or((0.0, 0.0), translation);
+        let position = (parent.0 + x, parent.1 + y);
+        let name = match geometry.attribute("Name") {
+            Some(name) => name,
+            None => return,
+        };
+        self.positions.insert(name, position);
+        if geometry.has_tag_name("GeometryReference") {
+            let offset = children(geometry, "Break")
+                .find(|dmx_break| dmx_break.attribute("DMXBreak").unwrap_or("1") == "1")
+                .and_then(|dmx_break| dmx_break.attribute("DMXOffset")?.parse::<u16>().ok());
+            if let (Some(referenced), Some(offset)) = (geometry.attribute("Geometry"), offset) {
+                self.instances.push(GeometryInstance {
+                    name: name.into(),
+                    geometry: referenced.into(),
+                    offset: offset.saturating_sub(1),
+                });
+            }
+            return;
+        }
+        for geometry in geometry.children().filter(Node::is_element) {
+            self.collect_geometry(geometry, position);
+        }
+    }
+
+    fn import_mode(&self, mode: Node, report: &mut ConversionReport) -> Result<FixtureChannelMode, FormatError> {
+        let mode_name = mode.attribute("Name").ok_or(FormatError::MissingField("DMXMode Name"))?;
+        let mut builder = FixtureChannelMode::builder();
+        builder.name(FixtureName::new(mode_name.into()));
+
+        let mut cells: BTreeMap<String, CellLights> = BTreeMap::new();
+        let mut dimmers: Vec<(String, DMXRange, Option<Channel>)> = Vec::new();
+        let mut movement = FixtureMovement::default();
+        let mut zoomed = false;
+        let mut total_channels = 0;
+        for channel in child(mode, "DMXChannels").into_iter().flat_map(|channels| children(channels, "DMXChannel")) {
+            let geometry = channel.attribute("Geometry").unwrap_or_default();
+            if channel.attribute("DMXBreak").unwrap_or("1") != "1" {
+                report.unmapped(format!("{}: {} on a second DMX break", mode_name, channel_name(channel)));
+                continue;
+            }
+            let offsets: Vec<u16> = channel.attribute("Offset").unwrap_or_default()
+                .split(',')
+                .filter_map(|offset| offset.trim().parse().ok())
+                .collect();
+            if offsets.is_empty() {
+                report.unmapped(format!("{}: virtual channel {}", mode_name, channel_name(channel)));
+                continue;
+            }
+
+            // Channels of a referenced geometry repeat for every reference
+            let instances: Vec<(&str, u16)> = match self.instances.iter().filter(|instance| instance.geometry == geometry).collect::<Vec<_>>() {
+                instances if instances.is_empty() => vec![(geometry, 0)],
+                instances => instances.iter().map(|instance| (instance.name.as_str(), instance.offset)).collect(),
+            };
+            for (instance, shift) in instances {
+                let ids: Vec<u16> = offsets.iter()
+                    .map(|offset| offset.checked_add(shift))
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| FormatError::Invalid(format!("channel offset of {} in {}", channel_name(channel), instance)))?;
+                total_channels = ids.iter().copied().fold(total_channels, u16::max);
+                let coarse = Channel::new(ids[0]).map_err(|_| FormatError::Invalid(format!("channel offset {}", ids[0])))?;
+                let fine = ids.get(1).and_then(|id| Channel::new(*id).ok());
+                if ids.len() > 2 {
+                    report.unmapped(format!("{}: {} bit resolution of {}", mode_name, ids.len() * 8, channel_name(channel)));
+                }
+
+                let mut logical_channels = children(channel, "LogicalChannel");
+                let logical = match logical_channels.next() {
+                    Some(logical) => logical,
+                    None => continue,
+                };
+                if logical_channels.next().is_some() {
+                    report.unmapped(format!("{}: additional logical channels of {}", mode_name, channel_name(channel)));
+                }
+                match self.classify(mode_name, logical, coarse, fine, report) {
+                    Some(Mapped::Color(component, range)) => { cells.entry(instance.into()).or_default().components.insert(component, range); },
+                    Some(Mapped::Presets(presets)) => cells.entry(instance.into()).or_default().presets.extend(presets),
+                    Some(Mapped::Dimmer(range)) => dimmers.push((instance.into(), range, fine)),
+                    Some(Mapped::Pan(axis)) if movement.pan.is_none() => movement.pan = Some(axis),
+                    Some(Mapped::Tilt(axis)) if movement.tilt.is_none() => movement.tilt = Some(axis),
+                    Some(Mapped::Zoom(zoom)) if !zoomed => {
+                        builder.zoom(zoom);
+                        zoomed = true;
+                    },
+                    Some(Mapped::Custom(operations)) => {
+                        for operation in operations {
+                            builder.custom(operation);
+                        }
+                    },
+                    Some(_) => report.unmapped(format!("{}: second pan, tilt or zoom channel {}", mode_name, channel_name(channel))),
+                    None => {},
+                }
+            }
+        }
+        builder.total_channels(Channel::new(total_channels).map_err(|_| FormatError::Invalid(format!("mode {} has no channels", mode_name)))?);
+        if movement.pan.is_some() || movement.tilt.is_some() {
+            builder.movement(movement);
+        }
+
+        // Dimmers of the colored cells belong to them, a dimmer on a single light too, the rest stay master dimmers
+        cells.retain(|_, cell| cell.is_colored());
+        let single = cells.len() <= 1;
+        for (instance, range, fine) in dimmers {
+            let cell = if single {
+                let key = cells.keys().next().cloned().unwrap_or(instance);
+                Some(cells.entry(key).or_default())
+            } else {
+                cells.get_mut(&instance)
+            };
+            match cell {
+                Some(cell) if cell.dimmer.is_none() => {
+                    cell.dimmer = Some(range);
+                    cell.dimmer_fine = fine;
+                },
+                _ => {
+                    let name = FixtureName::new("Dimmer".into());
+                    builder.custom(match fine {
+                        Some(fine) => FixtureCustomOperation::FineSlider(name, range, fine),
+                        None => FixtureCustomOperation::Slider(name, range),
+                    });
+                },
+            }
+        }
+
+        if single {
+            if let Some(lights) = cells.into_values().next().and_then(|cell| cell.build(mode_name, report)) {
+                builder.lights(lights);
+            }
+        } else {
+            let mut placed: Vec<((f64, f64), FixtureLights)> = Vec::new();
+            for (instance, cell) in cells {
+                let position = self.positions.get(instance.as_str()).copied().unwrap_or_default();
+                if let Some(lights) = cell.build(mode_name, report) {
+                    placed.push((position, lights));
+                }
+            }
+            builder.matrix(matrix(placed)?);
+        }
+        Ok(builder.build()?)
+    }
+
+    fn classify(&self, mode_name: &str, logical: Node, channel: Channel, fine: Option<Channel>, report: &mut ConversionReport) -> Option<Mapped> {
+        let attribute = logical.attribute("Attribute").unwrap_or_default();
+        let functions = channel_functions(logical);
+        let first = functions.first()?;
+        let range = DMXRange::new(DMXAddress::new(channel, first.from), DMXAddress::new(channel, first.to));
+
+        if let Some((_, component)) = COLOR_ATTRIBUTES.iter().find(|(name, _)| *name == attribute) {
+            return Some(Mapped::Color(component, range));
+        }
+        match attribute {
+            "Dimmer" => return Some(Mapped::Dimmer(range)),
+            "Pan" | "Tilt" => {
+                let mut axis = MovementAxis::new(range, None);
+                if let Some(fine) = fine {
+                    axis.fine(fine);
+                }
+                if let Some((from, to)) = first.physical {
+                    axis.degrees(from, to);
+                }
+                return Some(if attribute == "Pan" { Mapped::Pan(axis) } else { Mapped::Tilt(axis) });
+            },
+            "Zoom" => {
+                let mut zoom = FixtureZoom::new(range, None);
+                if let Some(fine) = fine {
+                    zoom.fine(fine);
+                }
+                if let Some(physical) = physical_range(Unit::Degrees, first) {
+                    zoom.physical(physical);
+                }
+                return Some(Mapped::Zoom(zoom));
+            },
+            _ => {},
+        }
+
+        // Color wheels become presets, the other functions of the channel can't be represented next to them
+        let wheel_functions: Vec<&ChannelFunction> = functions.iter()
+            .filter(|function| function.attribute.starts_with("Color") && function.wheel.is_some() && function.sets.iter().any(|set| set.slot.is_some()))
+            .collect();
+        if !wheel_functions.is_empty() {
+            let mut presets = Vec::new();
+            for function in &functions {
+                if !wheel_functions.iter().any(|wheel_function| std::ptr::eq(*wheel_function, function)) {
+                    report.unmapped(format!("{}: {} on the color wheel channel", mode_name, function.name));
+                    continue;
+                }
+                for set in &function.sets {
+                    let slot = set.slot.and_then(|slot| self.wheels.get(function.wheel?)?.get(slot.checked_sub(1)?));
+                    match slot {
+                        Some(slot) => presets.push((slot_color(*slot, &set.name), DMXAddress::new(channel, set.from))),
+                        None => report.unmapped(format!("{}: color wheel set {}", mode_name, set.name)),
+                    }
+                }
+            }
+            return Some(Mapped::Presets(presets));
+        }
+
+        // Strobe functions keep their frequency range, everything else is a slider or a list of steps
+        let mut operations = Vec::new();
+        let mut steps = Vec::new();
+        for function in &functions {
+            let function_range = DMXRange::new(DMXAddress::new(channel, function.from), DMXAddress::new(channel, function.to));
+            let name = FixtureName::new(function.name.clone());
+            if function.attribute.contains("Strobe") {
+                if let Some(physical) = physical_range(Unit::Hertz, function) {
+                    operations.push(FixtureCustomOperation::Physical(name, function_range, physical));
+                    continue;
+                }
+            }
+            let named_sets: Vec<&ChannelSet> = function.sets.iter().filter(|set| !set.name.is_empty()).collect();
+            if named_sets.is_empty() {
+                steps.push((name, DMXAddress::new(channel, function.from)));
+            } else {
+                steps.extend(named_sets.iter().map(|set| (FixtureName::new(set.name.clone()), DMXAddress::new(channel, set.from))));
+            }
+        }
+        match steps.as_slice() {
+            [] => {},
+            [(name, address)] if functions.len() == 1 && function_is_continuous(first) => {
+                operations.push(match fine {
+                    Some(fine) => FixtureCustomOperation::FineSlider(name.clone(), DMXRange::new(*address, DMXAddress::new(channel, first.to)), fine),
+                    None => FixtureCustomOperation::Slider(name.clone(), DMXRange::new(*address, DMXAddress::new(channel, first.to))),
+                });
+            },
+            [(name, address)] => operations.push(FixtureCustomOperation::Button(name.clone(), *address)),
+            _ => operations.push(FixtureCustomOperation::Stepped(FixtureName::new(attribute.into()), steps)),
+        }
+        Some(Mapped::Custom(operations))
+    }
+}
+
+// A function without channel sets covers its whole DMX range
+fn function_is_continuous(function: &ChannelFunction) -> bool {
+    function.sets.iter().all(|set| set.name.is_empty())
+}
+
+fn channel_functions<'a>(logical: Node<'a, '_>) -> Vec<ChannelFunction<'a>> {
+    let nodes: Vec<Node> = children(logical, "ChannelFunction").collect();
+    let starts: Vec<u8> = nodes.iter().map(|node| node.attribute("DMXFrom").and_then(dmx_value).unwrap_or(0)).collect();
+    nodes.iter().enumerate().map(|(i, node)| {
+        let from = starts[i];
+        let to = starts.iter().filter(|start| **start > from).min().map_or(255, |next| next - 1);
+        let physical = match (node.attribute("PhysicalFrom").and_then(|value| value.parse().ok()), node.attribute("PhysicalTo").and_then(|value| value.parse().ok())) {
+            (Some(from), Some(to)) if from != to => Some((from, to)),
+            _ => None,
+        };
+        ChannelFunction {
+            name: node.attribute("Name").unwrap_or_default().into(),
+            attribute: node.attribute("Attribute").unwrap_or_default().into(),
+            from,
+            to,
+            physical,
+            wheel: node.attribute("Wheel"),
+            sets: children(*node, "ChannelSet").map(|set| ChannelSet {
+                name: set.attribute("Name").unwrap_or_default().into(),
+                from: set.attribute("DMXFrom").and_then(dmx_value).unwrap_or(from),
+                slot: set.attribute("WheelSlotIndex").and_then(|slot| slot.parse().ok()),
+            }).collect(),
+        }
+    }).collect()
+}
+
+fn physical_range(unit: Unit, function: &ChannelFunction) -> Option<PhysicalRange> {
+    let (from, to) = function.physical?;
+    PhysicalRange::builder().unit(unit).point(from, function.from).point(to, function.to).build().ok()
+}
+
+// Cells are sorted into rows by their height, top to bottom, and into columns from left to right
+fn matrix(mut cells: Vec<((f64, f64), FixtureLights)>) -> Result<FixtureMatrix, FormatError> {
+    cells.sort_by(|((ax, ay), _), ((bx, by), _)| by.total_cmp(ay).then(ax.total_cmp(bx)));
+    let mut builder = FixtureMatrix::builder();
+    let mut row: Vec<FixtureLights> = Vec::new();
+    let mut row_y = None;
+    for ((_, y), lights) in cells {
+        if row_y.is_some_and(|row_y: f64| (row_y - y).abs() > 1e-6) {
+            builder.row(std::mem::take(&mut row));
+        }
+        row_y = Some(y);
+        row.push(lights);
+    }
+    if !row.is_empty() {
+        builder.row(row);
+    }
+    Ok(builder.build()?)
+}
+
+fn slot_color(slot: Node, set_name: &str) -> Color {
+    let name = slot.attribute("Name").filter(|name| !name.is_empty()).unwrap_or(set_name);
+    if name == "Open" {
+        return Color::White;
+    }
+    match slot.attribute("Color").and_then(cie_to_rgb) {
+        Some(rgb) => named_color(name, rgb),
+        None => Color::Custom(FixtureName::new(name.into())),
+    }
+}
+
+// CIE 1931 "x,y,Y" to sRGB scaled to full brightness
+fn cie_to_rgb(color: &str) -> Option<(u8, u8, u8)> {
+    let values: Vec<f64> = color.split(',').filter_map(|value| value.trim().parse().ok()).collect();
+    let (x, y) = match values.as_slice() {
+        [x, y, ..] if *y > 0.0 => (*x, *y),
+        _ => return None,
+    };
+    let (cx, cz) = (x / y, (1.0 - x - y) / y);
+    let linear = [
+        3.2406 * cx - 1.5372 - 0.4986 * cz,
+        -0.9689 * cx + 1.8758 + 0.0415 * cz,
+        0.0557 * cx - 0.2040 + 1.0570 * cz,
+    ];
+    let max = linear.iter().copied().fold(0.0, f64::max);
+    if max <= 0.0 {
+        return None;
+    }
+    let gamma = |c: f64| {
+        let c = (c / max).max(0.0);
+        let c = if c <= 0.0031308 { 12.92 * c } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+        (c * 255.0).round().clamp(0.0, 255.0) as u8
+    };
+    Some((gamma(linear[0]), gamma(linear[1]), gamma(linear[2])))
+}
+
+// Coarse 8 bit value of a GDTF DMX value like "128/1" or "32768/2"
+fn dmx_value(value: &str) -> Option<u8> {
+    let (value, bytes) = value.split_once('/').unwrap_or((value, "1"));
+    let value: u64 = value.parse().ok()?;
+    let bytes: u32 = bytes.parse().ok()?;
+    Some((value >> (8 * bytes.saturating_sub(1))).min(255) as u8)
+}
+
+// Translation of a GDTF position matrix "{1,0,0,x}{0,1,0,y}{0,0,1,z}{0,0,0,1}"
+fn translation(position: &str) -> (f64, f64) {
+    let rows: Vec<Vec<f64>> = position.split('}')
+        .map(|row| row.trim_start_matches('{').split(',').filter_map(|value| value.trim().parse().ok()).collect())
+        .collect();
+    let at = |row: usize| rows.get(row).and_then(|row| row.get(3)).copied().unwrap_or(0.0);
+    (at(0), at(1))
+}
+
+fn channel_name(channel: Node) -> String {
+    let attribute = child(channel, "LogicalChannel").and_then(|logical| logical.attribute("Attribute")).unwrap_or_default();
+    format!("{}_{}", channel.attribute("Geometry").unwrap_or_default(), attribute)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::fixture::FixtureColorMode;
+
+    fn sample() -> (FixtureModel, ConversionReport) {
+        import_file(&Path::new(env!("CARGO_MANIFEST_DIR")).join("../data/gdtf/Sample@Beam_Bar_4.gdtf")).unwrap()
+    }
+
+    #[test]
+    fn imports_sample_modes() {
+        let (model, report) = sample();
+        assert_eq!(model.name.name(), "Beam Bar 4");
+        assert_eq!(model.manufacturer, "Sample");
+        assert_eq!(model.channel_modes.len(), 2);
+        // The color wheel spin has no place next to the presets
+        assert_eq!(report.unmapped.len(), 1);
+
+        let basic = &model.channel_modes[0];
+        assert_eq!(basic.total_channels.id(), 9);
+        let pan = basic.movement.as_ref().unwrap().pan.as_ref().unwrap();
+        assert_eq!(pan.fine.map(|fine| fine.id()), Some(2));
+        assert_eq!(pan.degrees, Some((-270.0, 270.0)));
+        let lights = &basic.lights.as_ref().unwrap().matrix[0][0];
+        assert!(lights.dimmer.is_some());
+        match &lights.color_mode {
+            FixtureColorMode::Presets(presets) => {
+                assert_eq!(presets.len(), 5);
+                assert!(matches!(presets[1].0, Color::Red));
+                assert!(matches!(&presets[4].0, Color::CustomRGB(name, _) if name == "Deep Orange"));
+            },
+            other => panic!("expected presets, got {:?}", other),
+        }
+        let zoom = basic.zoom.as_ref().unwrap().physical.as_ref().unwrap();
+        assert_eq!(zoom.bounds(), Some((4.0, 40.0)));
+        let custom = basic.custom.as_ref().unwrap();
+        assert!(custom.iter().any(|operation| matches!(operation, FixtureCustomOperation::Physical(name, _, range) if name.name() == "Strobe" && range.unit == Unit::Hertz)));
+        assert!(custom.iter().any(|operation| matches!(operation, FixtureCustomOperation::Button(name, address) if name.name() == "Reset" && address.value == 200)));
+    }
+
+    #[test]
+    fn geometry_references_become_matrix_cells() {
+        let (model, _) = sample();
+        let pixel = &model.channel_modes[1];
+        assert_eq!(pixel.total_channels.id(), 17);
+        let matrix = &pixel.lights.as_ref().unwrap().matrix;
+        assert_eq!(matrix.len(), 1);
+        assert_eq!(matrix[0].len(), 4);
+        let starts: Vec<u16> = matrix[0].iter().map(|lights| match &lights.color_mode {
+            FixtureColorMode::RGB(r, _, _) => r.channel().id(),
+            other => panic!("expected RGB, got {:?}", other),
+        }).collect();
+        assert_eq!(starts, vec![6, 9, 12, 15]);
+        // The beam dimmer dims all pixels at once
+        assert!(pixel.custom.as_ref().unwrap().iter().any(|operation| matches!(operation, FixtureCustomOperation::FineSlider(name, _, fine) if name.name() == "Dimmer" && fine.id() == 4)));
+    }
+
+    #[test]
+    fn offsets_past_the_last_channel_are_invalid() {
+        let description = |offset: &str| format!(r#"<GDTF><FixtureType Name="Bar" Manufacturer="Test">
+            <Geometries><Geometry Name="Body">
+                <Geometry Name="Pixel"/>
+                <GeometryReference Name="Pixel 1" Geometry="Pixel"><Break DMXBreak="1" DMXOffset="{}"/></GeometryReference>
+            </Geometry></Geometries>
+            <DMXModes><DMXMode Name="Pixel"><DMXChannels>
+                <DMXChannel Geometry="Pixel" Offset="2"><LogicalChannel Attribute="ColorAdd_R"/></DMXChannel>
+            </DMXChannels></DMXMode></DMXModes>
+        </FixtureType></GDTF>"#, offset);
+        assert!(matches!(import_description(&description("65535")), Err(FormatError::Invalid(_))));
+        assert!(matches!(import_description(&description("600")), Err(FormatError::Invalid(_))));
+        assert!(import_description(&description("3")).is_ok());
+    }
+}
@@ -0,0 +1,1201 @@
+use crate::builders::capability::{PhysicalRange, Unit};
+use crate::builders::fixture::{
+    FixtureChannelMode, FixtureColorMode, FixtureCustomOperation, FixtureLights, FixtureMatrix, FixtureModel,
+    FixtureMovement, FixtureName, FixtureOperationMode, FixtureZoom, MovementAxis, OperationModeType,
+};
+use crate::dmx::{Channel, Color, DMXAddress, DMXRange};
+use crate::formats::{parse_hex, standard_colors, CellLights, ConversionReport, FormatError};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use serde_json::{json, Map, Value};
+
+// Open Fixture Library (https://open-fixture-library.org) fixture definitions
+
+const SCHEMA: &str = "https://raw.githubusercontent.com/OpenLightingProject/open-fixture-library/master/schemas/fixture.json";
+const PIXEL_KEY: &str = "$pixelKey";
+const COLOR_COMPONENTS: [&str; 7] = ["Red", "Green", "Blue", "White", "Cyan", "Magenta", "Yellow"];
+// Switched channels can switch channels themselves, deeper nesting is most likely a loop
+const MAX_SWITCH_DEPTH: usize = 4;
+// Every pixel needs at least one channel, more than a universe of them can't be addressed
+const MAX_PIXELS: usize = 512;
+
+// OFL files don't carry the manufacturer, it is part of the library layout (`<manufacturer>/<fixture>.json`)
+pub fn import(json: &str, manufacturer: &str) -> Result<(FixtureModel, ConversionReport), FormatError> {
+    import_value(&serde_json::from_str(json)?, manufacturer)
+}
+
+pub fn import_value(ofl: &Value, manufacturer: &str) -> Result<(FixtureModel, ConversionReport), FormatError> {
+    let mut report = ConversionReport::new();
+    let name = ofl["name"].as_str().ok_or(FormatError::MissingField("name"))?;
+    let modes = ofl["modes"].as_array().ok_or(FormatError::MissingField("modes"))?;
+    let fixture = OflFixture::new(ofl)?;
+    let mut builder = FixtureModel::builder();
+    builder.model(FixtureName::new(name.into())).manufacturer(manufacturer.into());
+    for mode in modes {
+        builder.channel_mode(fixture.import_mode(mode, &mut report)?);
+    }
+    Ok((builder.build()?, report))
+}
+
+pub fn export(model: &FixtureModel) -> (Value, ConversionReport) {
+    let mut exporter = Exporter::default();
+    if model.name.icon_path().is_some() {
+        exporter.report.unmapped("fixture icon (OFL has no icons)");
+    }
+    let modes: Vec<Value> = model.channel_modes.iter().map(|mode| exporter.export_mode(mode)).collect();
+
+    let mut ofl = json!({
+        "$schema": SCHEMA,
+        "name": model.name.name(),
+        "categories": categories(model),
+        "meta": {
+            "authors": ["dmxt"],
+        },
+        "availableChannels": Value::Object(exporter.available),
+        "modes": modes,
+    });
+    if !exporter.templates.is_empty() {
+        ofl["templateChannels"] = Value::Object(exporter.templates);
+    }
+    if !exporter.pixel_rows.is_empty() {
+        let width = exporter.pixel_rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let rows: Vec<Value> = exporter.pixel_rows.iter()
+            .map(|row| (0..width).map(|x| row.get(x).map_or(Value::Null, |key| Value::from(key.as_str()))).collect())
+            .collect();
+        ofl["matrix"] = json!({ "pixelKeys": [rows] });
+    }
+    (ofl, exporter.report)
+}
+
+// The library requires creation and modification dates (YYYY-MM-DD), `export` leaves them out to stay deterministic
+pub fn set_dates(ofl: &mut Value, created: &str, modified: &str) {
+    ofl["meta"]["createDate"] = Value::from(created);
+    ofl["meta"]["lastModifyDate"] = Value::from(modified);
+}
+
+// ----------------------------------------------------------------------------
+// Import
+
+enum Lookup<'a> {
+    Channel(&'a Value, Option<String>),
+    Fine(String),
+    Switching,
+    Unknown,
+}
+
+struct Slot<'a> {
+    channel: Channel,
+    key: String,
+    definition: &'a Value,
+    pixel: Option<String>,
+}
+
+enum Mapped {
+    Color(&'static str, DMXRange),
+    Dimmer(DMXRange),
+    Pan(MovementAxis),
+    Tilt(MovementAxis),
+    Zoom(FixtureZoom),
+    Presets(Vec<(Color, DMXAddress)>),
+    Custom(Vec<FixtureCustomOperation>),
+    Operation(Vec<FixtureOperationMode>),
+    Nothing,
+}
+
+struct OflFixture<'a> {
+    available: HashMap<String, &'a Value>,
+    templates: Vec<(&'a str, &'a Value)>,
+    wheels: Option<&'a Map<String, Value>>,
+    pixel_rows: Vec<Vec<String>>,
+    fine_aliases: HashMap<String, String>,
+    switching: HashSet<String>,
+}
+
+impl<'a> OflFixture<'a> {
+    fn new(ofl: &'a Value) -> Result<Self, FormatError> {
+        let available: HashMap<String, &Value> = ofl["availableChannels"].as_object()
+            .map(|channels| channels.iter().map(|(key, value)| (key.clone(), value)).collect())
+            .unwrap_or_default();
+        let templates: Vec<(&str, &Value)> = ofl["templateChannels"].as_object()
+            .map(|channels| channels.iter().map(|(key, value)| (key.as_str(), value)).collect())
+            .unwrap_or_default();
+        let pixel_rows = pixel_rows(&ofl["matrix"])?;
+
+        let mut fine_aliases = HashMap::new();
+        let mut switching = HashSet::new();
+        for (key, definition) in &available {
+            if let Some(alias) = definition["fineChannelAliases"].get(0).and_then(Value::as_str) {
+                fine_aliases.insert(alias.to_string(), key.clone());
+            }
+            switching.extend(switch_keys(definition));
+        }
+        for (template, definition) in &templates {
+            if let Some(alias) = definition["fineChannelAliases"].get(0).and_then(Value::as_str) {
+                for pixel in pixel_rows.iter().flatten() {
+                    fine_aliases.insert(alias.replace(PIXEL_KEY, pixel), template.replace(PIXEL_KEY, pixel));
+                }
+            }
+            switching.extend(switch_keys(definition));
+        }
+
+        Ok(Self {
+            available,
+            templates,
+            wheels: ofl["wheels"].as_object(),
+            pixel_rows,
+            fine_aliases,
+            switching,
+        })
+    }
+
+    fn lookup(&self, key: &str) -> Lookup<'a> {
+        if let Some(definition) = self.available.get(key) {
+            return Lookup::Channel(definition, None);
+        }
+        for (template, definition) in &self.templates {
+            for pixel in self.pixel_rows.iter().flatten() {
+                if template.replace(PIXEL_KEY, pixel) == key {
+                    return Lookup::Channel(definition, Some(pixel.clone()));
+                }
+            }
+        }
+        if let Some(coarse) = self.fine_aliases.get(key) {
+            return Lookup::Fine(coarse.clone());
+        }
+        if self.switching.contains(key) {
+            return Lookup::Switching;
+        }
+        Lookup::Unknown
+    }
+
+    // Expands matrix insert blocks into plain channel keys, `None` marks an unused channel
+    fn channel_keys(&self, mode: &Value, report: &mut ConversionReport) -> Vec<Option<String>> {
+        let mut keys = Vec::new();
+        for channel in mode["channels"].as_array().into_iter().flatten() {
+            match channel {
+                Value::String(key) => keys.push(Some(key.clone())),
+                Value::Object(insert) if insert.get("insert").and_then(Value::as_str) == Some("matrixChannels") => {
+                    let pixels: Vec<String> = match &insert["repeatFor"] {
+                        Value::Array(pixels) => pixels.iter().filter_map(|pixel| pixel.as_str().map(String::from)).collect(),
+                        Value::String(order) if order.starts_with("eachPixel") && order != "eachPixelGroup" => {
+                            let mut pixels: Vec<String> = self.pixel_rows.iter().flatten().cloned().collect();
+                            if order == "eachPixelABC" {
+                                pixels.sort();
+                            }
+                            pixels
+                        },
+                        other => {
+                            report.unmapped(format!("matrix channels repeated for {}", other));
+                            continue;
+                        },
+                    };
+                    let templates: Vec<&str> = insert["templateChannels"].as_array().into_iter().flatten()
+                        .filter_map(Value::as_str)
+                        .collect();
+                    if insert["channelOrder"].as_str() == Some("perChannel") {
+                        for template in &templates {
+                            keys.extend(pixels.iter().map(|pixel| Some(template.replace(PIXEL_KEY, pixel))));
+                        }
+                    } else {
+                        for pixel in &pixels {
+                            keys.extend(templates.iter().map(|template| Some(template.replace(PIXEL_KEY, pixel))));
+                        }
+                    }
+                },
+                Value::Null => keys.push(None),
+                other => {
+                    report.unmapped(format!("mode channel {}", other));
+                    keys.push(None);
+                },
+            }
+        }
+        keys
+    }
+
+    fn import_mode(&self, mode: &Value, report: &mut ConversionReport) -> Result<FixtureChannelMode, FormatError> {
+        let mode_name = mode["name"].as_str().ok_or(FormatError::MissingField("mode name"))?;
+        let keys = self.channel_keys(mode, report);
+        let total_channels = Channel::new(keys.len() as u16)
+            .map_err(|_| FormatError::Invalid(format!("mode {} has {} channels", mode_name, keys.len())))?;
+
+        let mut slots = Vec::new();
+        let mut fines: HashMap<String, Channel> = HashMap::new();
+        let mut placeholders: HashMap<String, Channel> = HashMap::new();
+        for (index, key) in keys.iter().enumerate() {
+            let key = match key {
+                Some(key) => key,
+                None => continue,
+            };
+            let channel = Channel::new(index as u16 + 1).unwrap();
+            match self.lookup(key) {
+                Lookup::Channel(definition, pixel) => slots.push(Slot { channel, key: key.clone(), definition, pixel }),
+                Lookup::Fine(coarse) => { fines.insert(coarse, channel); },
+                Lookup::Switching => { placeholders.insert(key.clone(), channel); },
+                Lookup::Unknown => report.unmapped(format!("{}: unknown channel {}", mode_name, key)),
+            }
+        }
+
+        let mut builder = FixtureChannelMode::builder();
+        builder.name(FixtureName::new(mode_name.into())).total_channels(total_channels);
+        let mut pixels: BTreeMap<Option<String>, CellLights> = BTreeMap::new();
+        let mut movement = FixtureMovement::default();
+        for slot in &slots {
+            let fine = fines.get(&slot.key).copied();
+            match self.classify(&slot.key, slot.definition, slot.channel, fine, &placeholders, 0, report)? {
+                Mapped::Color(component, range) => { pixels.entry(slot.pixel.clone()).or_default().components.insert(component, range); },
+                Mapped::Dimmer(range) => {
+                    let lights = pixels.entry(slot.pixel.clone()).or_default();
+                    lights.dimmer = Some(range);
+                    lights.dimmer_fine = fine;
+                },
+                Mapped::Presets(presets) => pixels.entry(slot.pixel.clone()).or_default().presets.extend(presets),
+                Mapped::Pan(axis) => movement.pan = Some(axis),
+                Mapped::Tilt(axis) => movement.tilt = Some(axis),
+                Mapped::Zoom(zoom) => { builder.zoom(zoom); },
+                Mapped::Custom(operations) => {
+                    for operation in operations {
+                        builder.custom(operation);
+                    }
+                },
+                Mapped::Operation(operation_modes) => {
+                    for operation_mode in operation_modes {
+                        builder.operation_mode(operation_mode);
+                    }
+                },
+                Mapped::Nothing => {},
+            }
+        }
+        if movement.pan.is_some() || movement.tilt.is_some() {
+            builder.movement(movement);
+        }
+
+        let master = pixels.remove(&None);
+        if pixels.is_empty() {
+            if let Some(lights) = master.and_then(|lights| lights.build(mode_name, report)) {
+                builder.lights(lights);
+            }
+        } else {
+            if master.is_some() {
+                report.unmapped(format!("{}: master color channels of a pixel matrix", mode_name));
+            }
+            let mut matrix = FixtureMatrix::builder();
+            for row in &self.pixel_rows {
+                let cells: Vec<FixtureLights> = row.iter()
+                    .filter_map(|pixel| pixels.remove(&Some(pixel.clone())))
+                    .filter_map(|lights| lights.build(mode_name, report))
+                    .collect();
+                if !cells.is_empty() {
+                    matrix.row(cells);
+                }
+            }
+            for pixel in pixels.keys().flatten() {
+                report.unmapped(format!("{}: pixel {} is not part of the matrix", mode_name, pixel));
+            }
+            if let Ok(matrix) = matrix.build() {
+                builder.matrix(matrix);
+            }
+        }
+        Ok(builder.build()?)
+    }
+
+    // `depth` counts the switched channels this one is nested in
+    #[allow(clippy::too_many_arguments)]
+    fn classify(&self, key: &str, definition: &Value, channel: Channel, fine: Option<Channel>, placeholders: &HashMap<String, Channel>, depth: usize, report: &mut ConversionReport) -> Result<Mapped, FormatError> {
+        let capabilities = capabilities(definition);
+        if capabilities.iter().any(|(_, _, capability)| capability.get("switchChannels").is_some()) {
+            return Ok(Mapped::Operation(self.operation_modes(key, channel, &capabilities, placeholders, depth, report)?));
+        }
+        let reset = capabilities.iter()
+            .find(|(_, _, capability)| is_reset(capability))
+            .map(|(start, _, _)| DMXAddress::new(channel, *start));
+        let meaningful: Vec<&(u8, u8, &Value)> = capabilities.iter()
+            .filter(|(_, _, capability)| capability["type"] != "NoFunction" && !is_reset(capability))
+            .collect();
+
+        Ok(match meaningful.as_slice() {
+            [] => Mapped::Nothing,
+            [(start, end, capability)] => {
+                let range = DMXRange::new(DMXAddress::new(channel, *start), DMXAddress::new(channel, *end));
+                match capability["type"].as_str().unwrap_or_default() {
+                    "ColorIntensity" => {
+                        let color = capability["color"].as_str().unwrap_or_default();
+                        match COLOR_COMPONENTS.iter().find(|component| **component == color) {
+                            Some(component) => Mapped::Color(component, range),
+                            None => {
+                                report.unmapped(format!("{}: color intensity {}", key, color));
+                                Mapped::Nothing
+                            },
+                        }
+                    },
+                    "Intensity" => Mapped::Dimmer(range),
+                    kind @ ("Pan" | "Tilt") => {
+                        let mut axis = MovementAxis::new(range, reset);
+                        if let Some(fine) = fine {
+                            axis.fine(fine);
+                        }
+                        if let (Some((start, Unit::Degrees)), Some((end, Unit::Degrees))) = (entity(&capability["angleStart"]), entity(&capability["angleEnd"])) {
+                            axis.degrees(start, end);
+                        }
+                        if kind == "Pan" { Mapped::Pan(axis) } else { Mapped::Tilt(axis) }
+                    },
+                    "Zoom" => {
+                        let mut zoom = FixtureZoom::new(range, reset);
+                        if let Some(fine) = fine {
+                            zoom.fine(fine);
+                        }
+                        if let Some(physical) = physical_range(capability, *start, *end) {
+                            zoom.physical(physical);
+                        }
+                        Mapped::Zoom(zoom)
+                    },
+                    _ => {
+                        let name = FixtureName::new(capability_name(capability).unwrap_or(key).into());
+                        if let Some(physical) = physical_range(capability, *start, *end) {
+                            if fine.is_some() {
+                                report.unmapped(format!("{}: fine channel of a physical range", key));
+                            }
+                            return Ok(Mapped::Custom(vec![FixtureCustomOperation::Physical(name, range, physical)]));
+                        }
+                        match fine {
+                            _ if start == end => Mapped::Custom(vec![FixtureCustomOperation::Button(name, range.start)]),
+                            Some(fine) => Mapped::Custom(vec![FixtureCustomOperation::FineSlider(name, range, fine)]),
+                            None => Mapped::Custom(vec![FixtureCustomOperation::Slider(name, range)]),
+                        }
+                    },
+                }
+            },
+            _ => {
+                let presets: Option<Vec<(Color, DMXAddress)>> = meaningful.iter()
+                    .map(|(start, _, capability)| self.preset_color(key, capability).map(|color| (color, DMXAddress::new(channel, *start))))
+                    .collect();
+                if presets.is_none() && meaningful.iter().any(|(_, _, capability)| self.preset_color(key, capability).is_some()) {
+                    report.unmapped(format!("{}: color presets next to other capabilities are imported as steps", key));
+                }
+                let steps = meaningful.iter()
+                    .map(|(start, _, capability)| (FixtureName::new(capability_name(capability).unwrap_or(key).into()), DMXAddress::new(channel, *start)));
+                match presets {
+                    Some(presets) => Mapped::Presets(presets),
+                    // Single values are buttons, ranges select until the next step
+                    None if meaningful.iter().all(|(start, end, _)| start == end) => Mapped::Custom(
+                        steps.map(|(name, address)| FixtureCustomOperation::Button(name, address)).collect(),
+                    ),
+                    None => Mapped::Custom(vec![FixtureCustomOperation::Stepped(FixtureName::new(key.into()), steps.collect())]),
+                }
+            },
+        })
+    }
+
+    fn operation_modes(&self, key: &str, channel: Channel, capabilities: &[(u8, u8, &Value)], placeholders: &HashMap<String, Channel>, depth: usize, report: &mut ConversionReport) -> Result<Vec<FixtureOperationMode>, FormatError> {
+        if depth >= MAX_SWITCH_DEPTH {
+            return Err(FormatError::Invalid(format!("{}: switched channels nest deeper than {} levels", key, MAX_SWITCH_DEPTH)));
+        }
+        let mut operation_modes = Vec::new();
+        for (start, _, capability) in capabilities {
+            if capability["type"] == "NoFunction" {
+                continue;
+            }
+            let name = capability_name(capability).unwrap_or(key);
+            let mode_type = match name {
+                "Off" => OperationModeType::Off,
+                "On" => OperationModeType::On,
+                "Auto" => OperationModeType::Auto,
+                _ if capability["soundControlled"] == true => OperationModeType::SoundToLight,
+                _ => OperationModeType::DMX(FixtureName::new(name.into())),
+            };
+            let mut submodes = Vec::new();
+            for (placeholder, target) in capability["switchChannels"].as_object().into_iter().flatten() {
+                let submode_channel = match placeholders.get(placeholder) {
+                    Some(channel) => *channel,
+                    None => continue,
+                };
+                let target = target.as_str().unwrap_or_default();
+                let definition = match self.available.get(target) {
+                    Some(definition) => definition,
+                    None => {
+                        report.unmapped(format!("{}: unknown switched channel {}", key, target));
+                        continue;
+                    },
+                };
+                match self.classify(target, definition, submode_channel, None, placeholders, depth + 1, report)? {
+                    Mapped::Custom(operations) => submodes.extend(operations),
+                    Mapped::Nothing => {},
+                    _ => report.unmapped(format!("{}: switched channel {} is not a custom operation", key, target)),
+                }
+            }
+            operation_modes.push(FixtureOperationMode::new(mode_type, Some(DMXAddress::new(channel, *start)), submodes));
+        }
+        Ok(operation_modes)
+    }
+
+    fn preset_color(&self, key: &str, capability: &Value) -> Option<Color> {
+        match capability["type"].as_str()? {
+            "ColorPreset" => {
+                let colors: Vec<&str> = capability["colors"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+                color(capability["comment"].as_str(), &colors)
+            },
+            "WheelSlot" => {
+                let wheel = capability["wheel"].as_str().unwrap_or(key);
+                let slot_number = capability["slotNumber"].as_f64()?;
+                if slot_number.fract() != 0.0 || slot_number < 1.0 {
+                    return None;
+                }
+                let slot = self.wheels?.get(wheel)?["slots"].get(slot_number as usize - 1)?;
+                match slot["type"].as_str()? {
+                    "Open" => Some(Color::White),
+                    "Color" => {
+                        let colors: Vec<&str> = slot["colors"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+                        color(slot["name"].as_str(), &colors)
+                    },
+                    _ => None,
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
+fn switch_keys(definition: &Value) -> Vec<String> {
+    capabilities(definition).iter()
+        .filter_map(|(_, _, capability)| capability["switchChannels"].as_object())
+        .flat_map(|switches| switches.keys().cloned())
+        .collect()
+}
+
+// Rows of pixel keys, z layers are stacked below each other
+fn pixel_rows(matrix: &Value) -> Result<Vec<Vec<String>>, FormatError> {
+    if let Some(layers) = matrix["pixelKeys"].as_array() {
+        return Ok(layers.iter()
+            .flat_map(|layer| layer.as_array().cloned().unwrap_or_default())
+            .map(|row| row.as_array().into_iter().flatten().filter_map(|key| key.as_str().map(String::from)).collect())
+            .collect());
+    }
+    if let Some(count) = matrix["pixelCount"].as_array() {
+        let count: Vec<usize> = count.iter().map(|n| n.as_u64().unwrap_or(1) as usize).collect();
+        let (x, y, z) = (count.first().copied().unwrap_or(1), count.get(1).copied().unwrap_or(1), count.get(2).copied().unwrap_or(1));
+        if x.saturating_mul(y).saturating_mul(z) > MAX_PIXELS {
+            return Err(FormatError::Invalid(format!("pixel count {}x{}x{} is larger than {} pixels", x, y, z, MAX_PIXELS)));
+        }
+        let dimensions = count.iter().filter(|n| **n > 1).count();
+        let mut rows = Vec::new();
+        for k in 1..=z {
+            for j in 1..=y {
+                rows.push((1..=x).map(|i| match dimensions {
+                    0 | 1 => (i * j * k).to_string(),
+                    2 if z == 1 => format!("({}, {})", i, j),
+                    _ => format!("({}, {}, {})", i, j, k),
+                }).collect());
+            }
+        }
+        return Ok(rows);
+    }
+    Ok(Vec::new())
+}
+
+fn capabilities(definition: &Value) -> Vec<(u8, u8, &Value)> {
+    let shift = match definition["dmxValueResolution"].as_str() {
+        Some("16bit") => 8,
+        Some("24bit") => 16,
+        _ => 0,
+    };
+    if definition.get("capability").is_some() {
+        return vec![(0, 255, &definition["capability"])];
+    }
+    definition["capabilities"].as_array().into_iter().flatten()
+        .filter_map(|capability| {
+            let range = capability["dmxRange"].as_array()?;
+            let start = (range.first()?.as_u64()? >> shift).min(255) as u8;
+            let end = (range.get(1)?.as_u64()? >> shift).min(255) as u8;
+            Some((start, end, capability))
+        })
+        .collect()
+}
+
+fn capability_name(capability: &Value) -> Option<&str> {
+    capability["comment"].as_str().or_else(|| capability["effectName"].as_str())
+}
+
+fn is_reset(capability: &Value) -> bool {
+    capability["type"] == "Maintenance" && capability_name(capability).is_some_and(|name| name.to_lowercase().contains("reset"))
+}
+
+// Parses an OFL entity like "540deg", "20Hz" or "50%", keywords like "fast" have no numeric value
+fn entity(value: &Value) -> Option<(f64, Unit)> {
+    let text = value.as_str()?;
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))?;
+    let number: f64 = text[..split].parse().ok()?;
+    match &text[split..] {
+        "deg" => Some((number, Unit::Degrees)),
+        "Hz" => Some((number, Unit::Hertz)),
+        "rpm" => Some((number, Unit::Rpm)),
+        "%" => Some((number, Unit::Percent)),
+        "s" => Some((number, Unit::Seconds)),
+        "ms" => Some((number / 1000.0, Unit::Seconds)),
+        unit => Some((number, Unit::Custom(unit.into()))),
+    }
+}
+
+fn physical_range(capability: &Value, start: u8, end: u8) -> Option<PhysicalRange> {
+    for (from, to, single) in [("speedStart", "speedEnd", "speed"), ("angleStart", "angleEnd", "angle"), ("durationStart", "durationEnd", "duration")] {
+        let (from, to) = match (entity(&capability[from]), entity(&capability[to]), entity(&capability[single])) {
+            (Some(from), Some(to), _) => (from, to),
+            (_, _, Some(single)) => (single.clone(), single),
+            _ => continue,
+        };
+        if from.1 != to.1 {
+            continue;
+        }
+        return PhysicalRange::builder().unit(from.1).point(from.0, start).point(to.0, end).build().ok();
+    }
+    None
+}
+
+fn color_name(color: &Color) -> String {
+    match color {
+        Color::CustomRGB(name, _) => name.clone(),
+        Color::Custom(name) => name.name().into(),
+        Color::ColorChange => "Color change".into(),
+        other => format!("{:?}", other),
+    }
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn color(name: Option<&str>, colors: &[&str]) -> Option<Color> {
+    match name {
+        Some("Color change") => return Some(Color::ColorChange),
+        Some("Auto") => return Some(Color::Auto),
+        Some("All") => return Some(Color::All),
+        Some("UV") => return Some(Color::UV),
+        _ => {},
+    }
+    match colors {
+        [single] => {
+            let rgb = parse_hex(single)?;
+            let standard = standard_colors().into_iter().find(|color| color.rgb() == Some(rgb));
+            match (standard, name) {
+                (Some(standard), None) => Some(standard),
+                (Some(standard), Some(name)) if color_name(&standard).eq_ignore_ascii_case(name) => Some(standard),
+                (_, name) => Some(Color::CustomRGB(name.unwrap_or(single).into(), rgb)),
+            }
+        },
+        _ => name.map(|name| Color::Custom(FixtureName::new(name.into()))),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Export
+
+struct Capability {
+    start: u8,
+    end: u8,
+    value: Value,
+}
+
+impl Capability {
+    fn new(start: u8, end: u8, value: Value) -> Self {
+        Self { start: start.min(end), end: start.max(end), value }
+    }
+}
+
+#[derive(Default)]
+struct ChannelDefinition {
+    name: String,
+    capabilities: Vec<Capability>,
+    fine: Option<Channel>,
+    fine_of: Option<Channel>,
+    pixel: Option<String>,
+}
+
+#[derive(Default)]
+struct Exporter {
+    available: Map<String, Value>,
+    templates: Map<String, Value>,
+    pixel_rows: Vec<Vec<String>>,
+    report: ConversionReport,
+}
+
+// Capabilities for values that select something, every value is valid up to the next one
+fn stepped<T>(steps: &[(T, DMXAddress)], value: impl Fn(&T) -> Value) -> Vec<(Channel, Capability)> {
+    let mut sorted: Vec<&(T, DMXAddress)> = steps.iter().collect();
+    sorted.sort_by_key(|(_, address)| (address.channel, address.value));
+    sorted.iter().enumerate().map(|(i, (step, address))| {
+        let end = sorted.get(i + 1)
+            .filter(|(_, next)| next.channel == address.channel && next.value > address.value)
+            .map_or(255, |(_, next)| next.value - 1);
+        (address.channel, Capability::new(address.value, end, value(step)))
+    }).collect()
+}
+
+fn operation_capability(mode_type: &OperationModeType) -> Value {
+    match mode_type {
+        OperationModeType::Auto => json!({ "type": "Effect", "effectName": "Auto" }),
+        OperationModeType::SoundToLight => json!({ "type": "Effect", "effectName": "Sound to light", "soundControlled": true }),
+        other => json!({ "type": "Generic", "comment": other.to_string() }),
+    }
+}
+
+fn physical_capability(name: &str, physical: &PhysicalRange) -> Option<Value> {
+    let first = physical.points.first()?;
+    let last = physical.points.last()?;
+    let format = |value: f64| format!("{}{}", value, match physical.unit {
+        Unit::Degrees => "deg",
+        Unit::Seconds => "s",
+        _ => physical.unit.symbol(),
+    });
+    let (kind, start, end) = match physical.unit {
+        Unit::Hertz => ("StrobeSpeed", "speedStart", "speedEnd"),
+        Unit::Rpm => ("Rotation", "speedStart", "speedEnd"),
+        Unit::Degrees => ("BeamAngle", "angleStart", "angleEnd"),
+        Unit::Seconds => ("EffectDuration", "durationStart", "durationEnd"),
+        Unit::Percent => ("EffectSpeed", "speedStart", "speedEnd"),
+        _ => return None,
+    };
+    let mut capability = json!({ "type": kind, "comment": name });
+    capability[start] = Value::from(format(first.value));
+    capability[end] = Value::from(format(last.value));
+    Some(capability)
+}
+
+impl Exporter {
+    fn export_mode(&mut self, mode: &FixtureChannelMode) -> Value {
+        let total = mode.total_channels.id();
+        let mode_name = mode.name.as_ref().map_or_else(|| format!("{}-channel", total), |name| name.name().to_string());
+        let mut definitions: BTreeMap<Channel, ChannelDefinition> = BTreeMap::new();
+        let mut placeholders: BTreeMap<Channel, String> = BTreeMap::new();
+
+        let cells: Vec<&FixtureLights> = mode.lights.iter().flat_map(|matrix| matrix.matrix.iter().flatten()).collect();
+        let pixel_keys: Vec<Option<String>> = if cells.len() > 1 {
+            (1..=cells.len()).map(|i| Some(i.to_string())).collect()
+        } else {
+            vec![None]
+        };
+        for (lights, pixel) in cells.iter().zip(&pixel_keys) {
+            self.export_lights(&mode_name, lights, pixel, &mut definitions);
+        }
+
+        if let Some(movement) = &mode.movement {
+            for (name, axis) in [("Pan", &movement.pan), ("Tilt", &movement.tilt)] {
+                if let Some(axis) = axis {
+                    let (start, end) = (axis.range.start.value, axis.range.end.value);
+                    let angles = match axis.degrees {
+                        Some((from, to)) => (format!("{}deg", from), format!("{}deg", to)),
+                        None => ("0%".into(), "100%".into()),
+                    };
+                    let (angle_start, angle_end) = if start <= end { angles } else { (angles.1, angles.0) };
+                    let capability = json!({ "type": name, "angleStart": angle_start, "angleEnd": angle_end });
+                    self.add(&mode_name, &mut definitions, axis.range.channel(), name, None, Capability::new(start, end, capability));
+                    if let Some(reset) = axis.reset {
+                        self.add(&mode_name, &mut definitions, reset.channel, name, None, Capability::new(reset.value, reset.value, json!({ "type": "Maintenance", "comment": "Reset" })));
+                    }
+                    if let Some(fine) = axis.fine {
+                        add_fine(&mut definitions, axis.range.channel(), fine);
+                    }
+                }
+            }
+        }
+
+        if let Some(zoom) = &mode.zoom {
+            let (angle_start, angle_end) = match zoom.physical.as_ref().filter(|physical| physical.unit == Unit::Degrees) {
+                Some(physical) => (
+                    format!("{}deg", physical.points.first().map_or(0.0, |point| point.value)),
+                    format!("{}deg", physical.points.last().map_or(0.0, |point| point.value)),
+                ),
+                None => ("narrow".into(), "wide".into()),
+            };
+            let capability = json!({ "type": "Zoom", "angleStart": angle_start, "angleEnd": angle_end });
+            self.add(&mode_name, &mut definitions, zoom.range.channel(), "Zoom", None, Capability::new(zoom.range.start.value, zoom.range.end.value, capability));
+            if let Some(reset) = zoom.reset {
+                self.add(&mode_name, &mut definitions, reset.channel, "Zoom", None, Capability::new(reset.value, reset.value, json!({ "type": "Maintenance", "comment": "Reset" })));
+            }
+            if let Some(fine) = zoom.fine {
+                add_fine(&mut definitions, zoom.range.channel(), fine);
+            }
+        }
+
+        for operation in mode.custom.iter().flatten() {
+            for (channel, capability) in self.custom_capabilities(&mode_name, operation) {
+                self.add(&mode_name, &mut definitions, channel, operation.name().name(), None, capability);
+            }
+            if let FixtureCustomOperation::FineSlider(_, range, fine) = operation {
+                add_fine(&mut definitions, range.channel(), *fine);
+            }
+        }
+
+        // Last, so that operation modes overlapping the functions of their channel are the ones reported
+        self.export_operation_modes(&mode_name, mode, &mut definitions, &mut placeholders);
+
+        self.channels(&mode_name, total, definitions, placeholders)
+    }
+
+    fn export_operation_modes(&mut self, mode_name: &str, mode: &FixtureChannelMode, definitions: &mut BTreeMap<Channel, ChannelDefinition>, placeholders: &mut BTreeMap<Channel, String>) {
+        let mut addressed = Vec::new();
+        for operation_mode in &mode.operation_modes {
+            let Some(address) = operation_mode.address else {
+                self.report.unmapped(format!("{}: operation mode {} without an address", mode_name, operation_mode.mode_type));
+                continue;
+            };
+            let taken = definitions.get(&address.channel).filter(|definition| {
+                definition.capabilities.iter().any(|capability| capability.start <= address.value && address.value <= capability.end)
+            });
+            match taken {
+                Some(definition) => self.report.unmapped(format!("{}: operation mode {} overlaps {}", mode_name, operation_mode.mode_type, definition.name)),
+                None => addressed.push((operation_mode, address)),
+            }
+        }
+
+        // Submodes become channels that switch their meaning with the operation mode
+        let mut switched: BTreeMap<Channel, Vec<(usize, Capability)>> = BTreeMap::new();
+        for (index, (operation_mode, address)) in addressed.iter().enumerate() {
+            for submode in &operation_mode.submodes {
+                for (channel, capability) in self.custom_capabilities(mode_name, submode) {
+                    if channel == address.channel {
+                        self.report.unmapped(format!("{}: submode {} on the operation mode channel", mode_name, submode.name()));
+                        continue;
+                    }
+                    switched.entry(channel).or_default().push((index, capability));
+                }
+            }
+        }
+        let mut switch_targets: BTreeMap<Channel, Vec<String>> = BTreeMap::new();
+        for (channel, capabilities) in switched {
+            let placeholder = format!("Channel {}", channel.id());
+            let mut targets = Vec::new();
+            for (index, (operation_mode, _)) in addressed.iter().enumerate() {
+                let mut definition = ChannelDefinition::default();
+                for (_, capability) in capabilities.iter().filter(|(i, _)| *i == index) {
+                    self.merge(mode_name, &mut definition, Capability::new(capability.start, capability.end, capability.value.clone()));
+                }
+                let (name, definition) = if definition.capabilities.is_empty() {
+                    (format!("{} (no function)", placeholder), json!({ "capability": { "type": "NoFunction" } }))
+                } else {
+                    (format!("{} ({})", placeholder, operation_mode.mode_type), self.definition(definition))
+                };
+                targets.push(self.insert(&name, mode_name, definition));
+            }
+            switch_targets.insert(channel, targets);
+            placeholders.insert(channel, placeholder);
+        }
+
+        for (index, (operation_mode, address)) in addressed.iter().enumerate() {
+            let mut capability = operation_capability(&operation_mode.mode_type);
+            if !switch_targets.is_empty() {
+                let switches: Map<String, Value> = switch_targets.iter()
+                    .map(|(channel, targets)| (placeholders[channel].clone(), Value::from(targets[index].as_str())))
+                    .collect();
+                capability["switchChannels"] = Value::Object(switches);
+            }
+            // Operation modes end where the next one or another function of the channel starts
+            let functions = definitions.get(&address.channel).into_iter().flat_map(|definition| &definition.capabilities);
+            let end = addressed.iter()
+                .filter(|(_, other)| other.channel == address.channel)
+                .map(|(_, other)| other.value)
+                .chain(functions.map(|capability| capability.start))
+                .filter(|start| *start > address.value)
+                .map(|start| start - 1)
+                .min()
+                .unwrap_or(255);
+            self.add(mode_name, definitions, address.channel, "Operation mode", None, Capability::new(address.value, end, capability));
+        }
+    }
+
+    fn export_lights(&mut self, mode_name: &str, lights: &FixtureLights, pixel: &Option<String>, definitions: &mut BTreeMap<Channel, ChannelDefinition>) {
+        let components: Vec<(&str, DMXRange)> = match &lights.color_mode {
+            FixtureColorMode::RGB(r, g, b) => vec![("Red", *r), ("Green", *g), ("Blue", *b)],
+            FixtureColorMode::RGBW(r, g, b, w) => vec![("Red", *r), ("Green", *g), ("Blue", *b), ("White", *w)],
+            FixtureColorMode::CMY(c, m, y) => vec![("Cyan", *c), ("Magenta", *m), ("Yellow", *y)],
+            FixtureColorMode::CMYW(c, m, y, w) => vec![("Cyan", *c), ("Magenta", *m), ("Yellow", *y), ("White", *w)],
+            FixtureColorMode::RgbTrailingChannels(range) => vec![("Red", *range), ("Green", range.shifted(1)), ("Blue", range.shifted(2))],
+            FixtureColorMode::RgbwTrailingChannels(range) => vec![("Red", *range), ("Green", range.shifted(1)), ("Blue", range.shifted(2)), ("White", range.shifted(3))],
+            FixtureColorMode::CmyTrailingChannels(range) => vec![("Cyan", *range), ("Magenta", range.shifted(1)), ("Yellow", range.shifted(2))],
+            FixtureColorMode::CmywTrailingChannels(range) => vec![("Cyan", *range), ("Magenta", range.shifted(1)), ("Yellow", range.shifted(2)), ("White", range.shifted(3))],
+            FixtureColorMode::Presets(presets) => {
+                let capabilities = stepped(presets, |color| {
+                    let mut capability = json!({ "type": "ColorPreset", "comment": color_name(color) });
+                    let colors: Option<Vec<Value>> = match color {
+                        Color::Custom(name) => name.name().split(" / ")
+                            .map(|part| standard_colors().into_iter().find(|color| color_name(color) == part).and_then(|color| color.rgb()).map(|rgb| Value::from(hex(rgb))))
+                            .collect(),
+                        other => other.rgb().map(|rgb| vec![Value::from(hex(rgb))]),
+                    };
+                    if let Some(colors) = colors {
+                        capability["colors"] = Value::from(colors);
+                    }
+                    capability
+                });
+                for (channel, capability) in capabilities {
+                    self.add(mode_name, definitions, channel, "Color", pixel.as_deref(), capability);
+                }
+                vec![]
+            },
+            FixtureColorMode::Custom(name, ranges) => {
+                self.report.unmapped(format!("{}: custom color mode {}", mode_name, name));
+                for (i, range) in ranges.iter().enumerate() {
+                    self.add(mode_name, definitions, range.channel(), &format!("{} {}", name, i + 1), pixel.as_deref(), Capability::new(range.start.value, range.end.value, json!({ "type": "Generic", "comment": name })));
+                }
+                vec![]
+            },
+        };
+        for (component, range) in components {
+            if range.start.value > range.end.value {
+                self.report.unmapped(format!("{}: inverted {} range", mode_name, component));
+            }
+            self.add(mode_name, definitions, range.channel(), component, pixel.as_deref(), Capability::new(range.start.value, range.end.value, json!({ "type": "ColorIntensity", "color": component })));
+        }
+        if let Some(dimmer) = lights.dimmer {
+            self.add(mode_name, definitions, dimmer.channel(), "Dimmer", pixel.as_deref(), Capability::new(dimmer.start.value, dimmer.end.value, json!({ "type": "Intensity" })));
+            if let Some(fine) = lights.dimmer_fine {
+                add_fine(definitions, dimmer.channel(), fine);
+            }
+        }
+    }
+
+    fn custom_capabilities(&mut self, mode_name: &str, operation: &FixtureCustomOperation) -> Vec<(Channel, Capability)> {
+        match operation {
+            FixtureCustomOperation::Slider(name, range) | FixtureCustomOperation::FineSlider(name, range, _) => {
+                if range.start.value > range.end.value {
+                    self.report.unmapped(format!("{}: inverted range of {}", mode_name, name));
+                }
+                vec![(range.channel(), Capability::new(range.start.value, range.end.value, json!({ "type": "Generic", "comment": name.name() })))]
+            },
+            FixtureCustomOperation::Button(name, address) => {
+                vec![(address.channel, Capability::new(address.value, address.value, json!({ "type": "Generic", "comment": name.name() })))]
+            },
+            FixtureCustomOperation::Stepped(_, steps) => stepped(steps, |step| json!({ "type": "Generic", "comment": step.name() })),
+            FixtureCustomOperation::Physical(name, range, physical) => {
+                if physical.points.len() > 2 {
+                    self.report.unmapped(format!("{}: calibration points between the ends of {}", mode_name, name));
+                }
+                let capability = physical_capability(name.name(), physical).unwrap_or_else(|| {
+                    self.report.unmapped(format!("{}: unit {} of {}", mode_name, physical.unit.symbol(), name));
+                    json!({ "type": "Generic", "comment": name.name() })
+                });
+                vec![(range.channel(), Capability::new(range.start.value, range.end.value, capability))]
+            },
+        }
+    }
+
+    fn add(&mut self, mode_name: &str, definitions: &mut BTreeMap<Channel, ChannelDefinition>, channel: Channel, name: &str, pixel: Option<&str>, capability: Capability) {
+        let definition = definitions.entry(channel).or_insert_with(|| ChannelDefinition {
+            name: name.into(),
+            pixel: pixel.map(String::from),
+            ..Default::default()
+        });
+        self.merge(mode_name, definition, capability);
+    }
+
+    fn merge(&mut self, mode_name: &str, definition: &mut ChannelDefinition, capability: Capability) {
+        if definition.capabilities.iter().any(|other| capability.start <= other.end && other.start <= capability.end) {
+            self.report.unmapped(format!("{}: overlapping capability {} on {}", mode_name, capability.value, definition.name));
+            return;
+        }
+        definition.capabilities.push(capability);
+    }
+
+    // Full OFL channel definition, gaps between capabilities are filled with `NoFunction`
+    fn definition(&self, mut definition: ChannelDefinition) -> Value {
+        definition.capabilities.sort_by_key(|capability| capability.start);
+        let switches = definition.capabilities.iter().find_map(|capability| capability.value.get("switchChannels").cloned());
+        let mut value = Map::new();
+        if let [capability] = definition.capabilities.as_slice() {
+            if capability.start == 0 && capability.end == 255 {
+                value.insert("capability".into(), capability.value.clone());
+                return Value::Object(value);
+            }
+        }
+        let mut capabilities = Vec::new();
+        let mut next = 0u16;
+        let no_function = |start: u16, end: u16| {
+            let mut capability = json!({ "dmxRange": [start, end], "type": "NoFunction" });
+            if let Some(switches) = &switches {
+                capability["switchChannels"] = switches.clone();
+            }
+            capability
+        };
+        for capability in &definition.capabilities {
+            if (capability.start as u16) > next {
+                capabilities.push(no_function(next, capability.start as u16 - 1));
+            }
+            let mut entry = json!({ "dmxRange": [capability.start, capability.end] });
+            for (key, value) in capability.value.as_object().into_iter().flatten() {
+                entry[key] = value.clone();
+            }
+            capabilities.push(entry);
+            next = capability.end as u16 + 1;
+        }
+        if next <= 255 {
+            capabilities.push(no_function(next, 255));
+        }
+        value.insert("capabilities".into(), Value::from(capabilities));
+        Value::Object(value)
+    }
+
+    // Inserts a channel under a fixture wide unique key, identical definitions share a key
+    fn insert(&mut self, name: &str, mode_name: &str, definition: Value) -> String {
+        let mut candidates = vec![name.to_string(), format!("{} ({})", name, mode_name)];
+        candidates.extend((2..).map(|i| format!("{} ({}) {}", name, mode_name, i)).take(510));
+        let key = candidates.into_iter()
+            .find(|key| self.available.get(key).is_none_or(|other| *other == definition))
+            .unwrap();
+        self.available.insert(key.clone(), definition);
+        key
+    }
+
+    fn channels(&mut self, mode_name: &str, total: u16, mut definitions: BTreeMap<Channel, ChannelDefinition>, placeholders: BTreeMap<Channel, String>) -> Value {
+        for channel in placeholders.keys() {
+            if let Some(definition) = definitions.remove(channel) {
+                self.report.unmapped(format!("{}: capabilities of {} on a switching channel", mode_name, definition.name));
+            }
+        }
+        let fines: Vec<(Channel, Channel)> = definitions.iter().filter_map(|(channel, definition)| definition.fine_of.map(|coarse| (*channel, coarse))).collect();
+
+        let mut keys: BTreeMap<Channel, Value> = BTreeMap::new();
+        let mut matrix = self.matrix_block(&definitions);
+        if matrix.is_none() && definitions.values().any(|definition| definition.pixel.is_some()) {
+            self.report.unmapped(format!("{}: pixels that are not laid out in equal blocks are exported as plain channels", mode_name));
+        }
+        let pixel_channels: BTreeSet<Channel> = match &matrix {
+            Some((_, channels, _)) => channels.iter().copied().collect(),
+            None => BTreeSet::new(),
+        };
+
+        let mut coarse_keys: BTreeMap<Channel, String> = BTreeMap::new();
+        let coarse: Vec<Channel> = definitions.iter().filter(|(_, definition)| definition.fine_of.is_none()).map(|(channel, _)| *channel).collect();
+        for channel in coarse {
+            if pixel_channels.contains(&channel) {
+                continue;
+            }
+            let definition = definitions.remove(&channel).unwrap();
+            let name = match &definition.pixel {
+                Some(pixel) => format!("{} {}", definition.name, pixel),
+                None => definition.name.clone(),
+            };
+            let has_fine = definition.fine.is_some();
+            let value = self.definition(definition);
+            let key = self.insert(&name, mode_name, value);
+            // The alias follows the final key so renamed channels keep unique fine channels
+            if has_fine {
+                self.available[&key]["fineChannelAliases"] = json!([format!("{} fine", key)]);
+            }
+            keys.insert(channel, Value::from(key.as_str()));
+            coarse_keys.insert(channel, key);
+        }
+        for (fine, coarse) in fines {
+            match coarse_keys.get(&coarse) {
+                Some(key) => { keys.insert(fine, Value::from(format!("{} fine", key))); },
+                None => self.report.unmapped(format!("{}: fine channel {} without a coarse channel", mode_name, fine.id())),
+            }
+        }
+        for (channel, placeholder) in &placeholders {
+            keys.insert(*channel, Value::from(placeholder.as_str()));
+        }
+
+        let mut channels = Vec::new();
+        let mut id = 1;
+        while id <= total {
+            let channel = Channel::new(id).unwrap();
+            if let Some((start, block_channels, insert)) = matrix.as_ref() {
+                if *start == channel {
+                    channels.push(insert.clone());
+                    id += block_channels.len() as u16;
+                    matrix = None;
+                    continue;
+                }
+            }
+            channels.push(keys.remove(&channel).unwrap_or(Value::Null));
+            id += 1;
+        }
+        json!({ "name": mode_name, "channels": channels })
+    }
+
+    // Pixels laid out as equal consecutive channel blocks become template channels and a matrix insert
+    fn matrix_block(&mut self, definitions: &BTreeMap<Channel, ChannelDefinition>) -> Option<(Channel, Vec<Channel>, Value)> {
+        let mut pixels: Vec<(&String, Vec<(&Channel, &ChannelDefinition)>)> = Vec::new();
+        for (channel, definition) in definitions {
+            if let Some(pixel) = &definition.pixel {
+                match pixels.iter_mut().find(|(key, _)| *key == pixel) {
+                    Some((_, channels)) => channels.push((channel, definition)),
+                    None => pixels.push((pixel, vec![(channel, definition)])),
+                }
+            }
+        }
+        let (_, first) = pixels.first()?;
+        let width = first.len() as u16;
+        let start = *first[0].0;
+        let names: Vec<&String> = first.iter().map(|(_, definition)| &definition.name).collect();
+        for (i, (_, channels)) in pixels.iter().enumerate() {
+            let base = start.id() + i as u16 * width;
+            let uniform = channels.len() as u16 == width && channels.iter().enumerate().all(|(offset, (channel, definition))| {
+                channel.id() == base + offset as u16 && definition.name == *names[offset] && definition.fine.is_none()
+            });
+            if !uniform {
+                return None;
+            }
+        }
+        // All pixels need identical capabilities to share a template
+        let template = |definition: &ChannelDefinition| {
+            let mut capabilities: Vec<(u8, u8, &Value)> = definition.capabilities.iter().map(|c| (c.start, c.end, &c.value)).collect();
+            capabilities.sort_by_key(|c| c.0);
+            format!("{:?}", capabilities)
+        };
+        if pixels.iter().any(|(_, channels)| channels.iter().zip(first).any(|((_, a), (_, b))| template(a) != template(b))) {
+            return None;
+        }
+
+        let mut template_names = Vec::new();
+        for (_, definition) in first {
+            let name = format!("{} {}", definition.name, PIXEL_KEY);
+            let value = self.definition(ChannelDefinition {
+                name: definition.name.clone(),
+                capabilities: definition.capabilities.iter().map(|c| Capability::new(c.start, c.end, c.value.clone())).collect(),
+                ..Default::default()
+            });
+            if self.templates.get(&name).is_some_and(|other| *other != value) {
+                return None;
+            }
+            self.templates.insert(name.clone(), value);
+            template_names.push(name);
+        }
+        let keys: Vec<String> = pixels.iter().map(|(key, _)| key.to_string()).collect();
+        if self.pixel_rows.is_empty() {
+            self.pixel_rows.push(keys.clone());
+        } else if self.pixel_rows.iter().flatten().count() < keys.len() {
+            self.pixel_rows = vec![keys.clone()];
+        }
+        let channels: Vec<Channel> = pixels.iter().flat_map(|(_, channels)| channels.iter().map(|(channel, _)| **channel)).collect();
+        let insert = json!({
+            "insert": "matrixChannels",
+            "repeatFor": keys,
+            "channelOrder": "perPixel",
+            "templateChannels": template_names,
+        });
+        Some((start, channels, insert))
+    }
+}
+
+fn add_fine(definitions: &mut BTreeMap<Channel, ChannelDefinition>, coarse: Channel, fine: Channel) {
+    if let Some(definition) = definitions.get_mut(&coarse) {
+        definition.fine = Some(fine);
+    }
+    definitions.insert(fine, ChannelDefinition { fine_of: Some(coarse), ..Default::default() });
+}
+
+fn categories(model: &FixtureModel) -> Vec<&'static str> {
+    let mut categories = Vec::new();
+    for mode in &model.channel_modes {
+        let cells = mode.lights.as_ref().map_or(0, |lights| lights.matrix.iter().flatten().count());
+        let category = if mode.movement.is_some() {
+            "Scanner"
+        } else if cells > 1 {
+            "Pixel Bar"
+        } else if cells == 1 {
+            "Color Changer"
+        } else {
+            "Other"
+        };
+        if !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+    categories
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(name: &str) -> FixtureModel {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../data/fixtures").join(name).join("fixture_config.json");
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn existing_fixtures_round_trip() {
+        for name in ["Laserworld_El230RGB_MKII", "Stairville_LedBar2408RGBDMX30"] {
+            let model = load(name);
+            let (exported, _) = export(&model);
+            let (imported, _) = import(&exported.to_string(), &model.manufacturer).unwrap();
+            assert_eq!(imported.name.name(), model.name.name());
+            assert_eq!(imported.manufacturer, model.manufacturer);
+            assert_eq!(imported.channel_modes.len(), model.channel_modes.len());
+            for (imported, original) in imported.channel_modes.iter().zip(&model.channel_modes) {
+                assert_eq!(imported.total_channels, original.total_channels);
+                // Color presets win over operation modes on the same channel
+                let presets = |mode: &FixtureChannelMode| mode.lights.iter().flat_map(|lights| lights.matrix.iter().flatten())
+                    .filter_map(|lights| match &lights.color_mode {
+                        FixtureColorMode::Presets(presets) => Some(presets.clone()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(presets(imported), presets(original));
+                let dimensions = |mode: &FixtureChannelMode| mode.lights.as_ref().map(|lights| lights.matrix.iter().map(Vec::len).collect::<Vec<_>>());
+                if dimensions(original).is_some_and(|rows| rows.iter().sum::<usize>() > 1) {
+                    assert_eq!(dimensions(imported), dimensions(original));
+                }
+            }
+            // Categories follow what survived the import, the channels have to be identical
+            let (reexported, _) = export(&imported);
+            for key in ["availableChannels", "templateChannels", "matrix", "modes"] {
+                assert_eq!(reexported[key], exported[key], "{} of {} changed in the round trip", key, name);
+            }
+        }
+    }
+
+    #[test]
+    fn library_files_keep_or_report_color_presets() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../data/ofl/sample/spot.json");
+        let (model, report) = import(&std::fs::read_to_string(path).unwrap(), "Sample").unwrap();
+        let [standard, macros] = &model.channel_modes[..] else {
+            panic!("expected two modes");
+        };
+        assert_eq!(standard.total_channels.id(), 7);
+        let pan = standard.movement.as_ref().unwrap().pan.as_ref().unwrap();
+        assert_eq!((pan.fine.map(|fine| fine.id()), pan.degrees), (Some(2), Some((0.0, 540.0))));
+        assert!(standard.zoom.as_ref().unwrap().physical.is_some());
+        // A wheel that also rotates can't be a preset channel, its slots become steps
+        assert!(report.unmapped.contains(&"Color Wheel: color presets next to other capabilities are imported as steps".to_string()));
+
+        let lights = &macros.lights.as_ref().unwrap().matrix[0][0];
+        let FixtureColorMode::Presets(presets) = &lights.color_mode else {
+            panic!("color macros are not presets: {:?}", lights.color_mode);
+        };
+        let colors: Vec<&Color> = presets.iter().map(|(color, _)| color).collect();
+        assert_eq!(colors, [
+            &Color::Red,
+            &Color::CustomRGB("Lavender".into(), (0xb5, 0x7e, 0xdc)),
+            &Color::CustomRGB("Ámbar".into(), (0xff, 0xbf, 0x00)),
+            &Color::Custom(FixtureName::new("Rainbow".into())),
+        ]);
+        assert_eq!(presets[1].1, DMXAddress::new(Channel::new(1).unwrap(), 20));
+    }
+
+    #[test]
+    fn malformed_colors_are_ignored() {
+        assert_eq!(parse_hex("#b57edc"), Some((0xb5, 0x7e, 0xdc)));
+        assert_eq!(parse_hex("#aé123"), None);
+        assert_eq!(parse_hex("#12345"), None);
+        assert_eq!(parse_hex("b57edc"), None);
+        assert_eq!(color(None, &["#aé123"]), None);
+    }
+
+    #[test]
+    fn switching_loops_and_huge_matrices_are_rejected() {
+        let looping = json!({
+            "name": "Loop",
+            "availableChannels": { "Mode": { "capability": { "type": "Maintenance", "switchChannels": { "Sub": "Mode" } } } },
+            "modes": [{ "name": "2ch", "channels": ["Mode", "Sub"] }],
+        });
+        assert!(matches!(import_value(&looping, "Test"), Err(FormatError::Invalid(_))));
+
+        let huge = json!({
+            "name": "Huge",
+            "matrix": { "pixelCount": [100000, 100000, 100000] },
+            "availableChannels": { "Dimmer": { "capability": { "type": "Intensity" } } },
+            "modes": [{ "name": "1ch", "channels": ["Dimmer"] }],
+        });
+        assert!(matches!(import_value(&huge, "Test"), Err(FormatError::Invalid(_))));
+    }
+}
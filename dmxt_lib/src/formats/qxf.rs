@@ -0,0 +1,467 @@
+use crate::builders::capability::{PhysicalRange, Unit};
+use crate::builders::fixture::{
+    FixtureChannelMode, FixtureCustomOperation, FixtureMatrix, FixtureModel, FixtureMovement, FixtureName,
+    FixtureZoom, MovementAxis,
+};
+use crate::dmx::{Channel, Color, DMXAddress, DMXRange};
+use crate::formats::{child, children, named_color, parse_hex, CellLights, ConversionReport, FormatError};
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use roxmltree::{Document, Node};
+
+// QLC+ fixture definitions (.qxf)
+
+const COLORS: [&str; 7] = ["Red", "Green", "Blue", "White", "Cyan", "Magenta", "Yellow"];
+
+pub fn import_file(path: &Path) -> Result<(FixtureModel, ConversionReport), FormatError> {
+    import(&std::fs::read_to_string(path)?)
+}
+
+pub fn import(xml: &str) -> Result<(FixtureModel, ConversionReport), FormatError> {
+    let document = Document::parse(xml)?;
+    let definition = document.root_element();
+    let text = |name: &str| child(definition, name).and_then(|node| node.text()).map(str::trim);
+    let model = text("Model").ok_or(FormatError::MissingField("Model"))?;
+    let manufacturer = text("Manufacturer").unwrap_or_default();
+
+    let channels: HashMap<&str, QxfChannel> = children(definition, "Channel")
+        .filter_map(|channel| Some((channel.attribute("Name")?, QxfChannel::new(channel))))
+        .collect();
+    let physical = child(definition, "Physical");
+
+    let mut report = ConversionReport::new();
+    let mut builder = FixtureModel::builder();
+    builder.model(FixtureName::new(model.into())).manufacturer(manufacturer.into());
+    for mode in children(definition, "Mode") {
+        builder.channel_mode(import_mode(mode, &channels, physical, &mut report)?);
+    }
+    Ok((builder.build()?, report))
+}
+
+// What a channel controls, taken from the channel preset or the older group element
+#[derive(Debug, PartialEq)]
+enum Kind {
+    Intensity(Option<String>),
+    Pan,
+    Tilt,
+    Colour,
+    Zoom { reversed: bool },
+    Other(String),
+    Nothing,
+}
+
+struct QxfChannel<'a, 'input> {
+    node: Node<'a, 'input>,
+    kind: Kind,
+    fine: bool,
+}
+
+impl<'a, 'input> QxfChannel<'a, 'input> {
+    fn new(node: Node<'a, 'input>) -> Self {
+        let name = node.attribute("Name").unwrap_or_default();
+        let (kind, fine) = match node.attribute("Preset") {
+            Some(preset) => {
+                let fine = preset.ends_with("Fine");
+                let base = preset.trim_end_matches("Fine");
+                let kind = match base {
+                    "IntensityMasterDimmer" | "IntensityDimmer" => Kind::Intensity(None),
+                    intensity if intensity.starts_with("Intensity") => Kind::Intensity(Some(intensity.trim_start_matches("Intensity").into())),
+                    "PositionPan" => Kind::Pan,
+                    "PositionTilt" => Kind::Tilt,
+                    "BeamZoomSmallBig" | "BeamZoom" => Kind::Zoom { reversed: false },
+                    "BeamZoomBigSmall" => Kind::Zoom { reversed: true },
+                    colour if colour.starts_with("Color") => Kind::Colour,
+                    other => Kind::Other(group_of(other).into()),
+                };
+                (kind, fine)
+            },
+            None => {
+                let group = child(node, "Group");
+                let fine = group.and_then(|group| group.attribute("Byte")) == Some("1");
+                let kind = match group.and_then(|group| group.text()).map(str::trim).unwrap_or("Nothing") {
+                    "Intensity" => Kind::Intensity(child(node, "Colour").and_then(|colour| colour.text()).map(|colour| colour.trim().into())),
+                    "Pan" => Kind::Pan,
+                    "Tilt" => Kind::Tilt,
+                    "Colour" => Kind::Colour,
+                    "Beam" if name.to_lowercase().contains("zoom") => Kind::Zoom { reversed: false },
+                    "Nothing" => Kind::Nothing,
+                    other => Kind::Other(other.into()),
+                };
+                (kind, fine)
+            },
+        };
+        Self { node, kind, fine }
+    }
+
+    fn name(&self) -> &'a str {
+        self.node.attribute("Name").unwrap_or_default()
+    }
+
+    fn capabilities(&self) -> Vec<Capability<'a, 'input>> {
+        children(self.node, "Capability").filter_map(|capability| Some(Capability {
+            node: capability,
+            min: capability.attribute("Min")?.parse().ok()?,
+            max: capability.attribute("Max")?.parse().ok()?,
+            name: capability.text().map(str::trim).unwrap_or_default(),
+        })).collect()
+    }
+}
+
+struct Capability<'a, 'input> {
+    node: Node<'a, 'input>,
+    min: u8,
+    max: u8,
+    name: &'a str,
+}
+
+impl Capability<'_, '_> {
+    // Older definitions store the color in `Color`, newer ones in `Res1` next to a color preset
+    fn color(&self) -> Option<(u8, u8, u8)> {
+        parse_hex(self.node.attribute("Color").or_else(|| self.node.attribute("Res1"))?)
+    }
+
+    fn frequency(&self) -> Option<(f64, f64)> {
+        match self.node.attribute("Preset")? {
+            "StrobeFreqRange" | "PulseFreqRange" => Some((self.node.attribute("Res1")?.parse().ok()?, self.node.attribute("Res2")?.parse().ok()?)),
+            "StrobeFrequency" | "PulseFrequency" => {
+                let frequency = self.node.attribute("Res1")?.parse().ok()?;
+                Some((frequency, frequency))
+            },
+            _ => None,
+        }
+    }
+}
+
+// Group name of a QLC+ channel preset like "ShutterStrobeSlowFast" or "GoboWheel"
+fn group_of(preset: &str) -> &str {
+    ["Shutter", "Speed", "Gobo", "Prism", "Beam", "Effect", "Maintenance", "Nothing"].into_iter()
+        .find(|group| preset.starts_with(group))
+        .unwrap_or(preset)
+}
+
+fn import_mode(mode: Node, channels: &HashMap<&str, QxfChannel>, physical: Option<Node>, report: &mut ConversionReport) -> Result<FixtureChannelMode, FormatError> {
+    let mode_name = mode.attribute("Name").ok_or(FormatError::MissingField("Mode Name"))?;
+    let physical = child(mode, "Physical").or(physical);
+    let mut builder = FixtureChannelMode::builder();
+    builder.name(FixtureName::new(mode_name.into()));
+
+    // Channel numbers start at 0 in QLC+
+    let mut used: Vec<(Channel, &QxfChannel)> = Vec::new();
+    let mut total_channels = 0;
+    for channel in children(mode, "Channel") {
+        let number: u16 = match channel.attribute("Number").and_then(|number| number.parse().ok()) {
+            Some(number) => number,
+            None => continue,
+        };
+        let name = channel.text().map(str::trim).unwrap_or_default();
+        let id = number.checked_add(1).ok_or_else(|| FormatError::Invalid(format!("channel number {}", number)))?;
+        total_channels = total_channels.max(id);
+        match channels.get(name) {
+            Some(definition) => used.push((Channel::new(id).map_err(|_| FormatError::Invalid(format!("channel number {}", number)))?, definition)),
+            None => report.unmapped(format!("{}: unknown channel {}", mode_name, name)),
+        }
+    }
+    builder.total_channels(Channel::new(total_channels).map_err(|_| FormatError::Invalid(format!("mode {} has no channels", mode_name)))?);
+
+    // Fine channels belong to the coarse channel of the same kind
+    let mut fines: HashMap<usize, Channel> = HashMap::new();
+    for (channel, definition) in used.iter().filter(|(_, definition)| definition.fine) {
+        match used.iter().position(|(_, coarse)| !coarse.fine && coarse.kind == definition.kind) {
+            Some(coarse) if !fines.contains_key(&coarse) => { fines.insert(coarse, *channel); },
+            _ => report.unmapped(format!("{}: fine channel {} without a coarse channel", mode_name, definition.name())),
+        }
+    }
+
+    // Heads group channels into matrix cells, without heads the whole fixture is one light
+    let heads: Vec<Vec<Channel>> = children(mode, "Head")
+        .map(|head| children(head, "Channel")
+            .filter_map(|channel| channel.text()?.trim().parse::<u16>().ok())
+            .map(|number| number.checked_add(1)
+                .and_then(|id| Channel::new(id).ok())
+                .ok_or_else(|| FormatError::Invalid(format!("head channel number {}", number))))
+            .collect::<Result<_, _>>())
+        .collect::<Result<_, _>>()?;
+    let mut cells: Vec<CellLights> = (0..heads.len().max(1)).map(|_| CellLights::default()).collect();
+    let mut master_dimmers = Vec::new();
+    let mut movement = FixtureMovement::default();
+    let focus = physical.and_then(|physical| child(physical, "Focus"));
+    let lens = physical.and_then(|physical| child(physical, "Lens"));
+
+    for (index, (channel, definition)) in used.iter().enumerate() {
+        if definition.fine {
+            continue;
+        }
+        let fine = fines.get(&index).copied();
+        let head = heads.iter().position(|head| head.contains(channel));
+        let cell = match (head, heads.is_empty()) {
+            (Some(head), _) => Some(head),
+            (None, true) => Some(0),
+            (None, false) => None,
+        };
+        let full = DMXRange::new(DMXAddress::new(*channel, 0), DMXAddress::new(*channel, 255));
+        match &definition.kind {
+            Kind::Intensity(None) => match cell {
+                Some(cell) if cells[cell].dimmer.is_none() => {
+                    cells[cell].dimmer = Some(full);
+                    cells[cell].dimmer_fine = fine;
+                },
+                _ => master_dimmers.push((definition.name(), full, fine)),
+            },
+            Kind::Intensity(Some(colour)) => match (COLORS.iter().find(|name| *name == colour), cell) {
+                (Some(component), Some(cell)) => {
+                    cells[cell].components.insert(component, full);
+                    // Color components are 8 bit, the fine channel is left alone
+                    if fine.is_some() {
+                        report.unmapped(format!("{}: fine channel of {}", mode_name, definition.name()));
+                    }
+                },
+                (Some(_), None) => report.unmapped(format!("{}: {} is outside of all heads", mode_name, definition.name())),
+                (None, _) => report.unmapped(format!("{}: {} intensity of {}", mode_name, colour, definition.name())),
+            },
+            Kind::Pan | Kind::Tilt => {
+                let mut axis = MovementAxis::new(full, None);
+                if let Some(fine) = fine {
+                    axis.fine(fine);
+                }
+                let maximum = focus.and_then(|focus| focus.attribute(if definition.kind == Kind::Pan { "PanMax" } else { "TiltMax" }))
+                    .and_then(|maximum| maximum.parse::<f64>().ok())
+                    .filter(|maximum| *maximum > 0.0);
+                if let Some(maximum) = maximum {
+                    axis.degrees(-maximum / 2.0, maximum / 2.0);
+                }
+                let slot = if definition.kind == Kind::Pan { &mut movement.pan } else { &mut movement.tilt };
+                if slot.is_some() {
+                    report.unmapped(format!("{}: second {} channel", mode_name, definition.name()));
+                }
+                slot.get_or_insert(axis);
+            },
+            Kind::Zoom { reversed } => {
+                let mut zoom = FixtureZoom::new(full, None);
+                if let Some(fine) = fine {
+                    zoom.fine(fine);
+                }
+                let degrees = lens.and_then(|lens| Some((lens.attribute("DegreesMin")?.parse::<f64>().ok()?, lens.attribute("DegreesMax")?.parse::<f64>().ok()?)));
+                if let Some((min, max)) = degrees.filter(|(min, max)| min < max) {
+                    let (start, end) = if *reversed { (max, min) } else { (min, max) };
+                    if let Ok(physical) = PhysicalRange::builder().unit(Unit::Degrees).point(start, 0).point(end, 255).build() {
+                        zoom.physical(physical);
+                    }
+                }
+                builder.zoom(zoom);
+            },
+            Kind::Colour => {
+                let presets = colour_presets(mode_name, *channel, definition, report);
+                match cell {
+                    Some(cell) if !presets.is_empty() => cells[cell].presets.extend(presets),
+                    Some(_) => {},
+                    None => report.unmapped(format!("{}: {} is outside of all heads", mode_name, definition.name())),
+                }
+            },
+            Kind::Other(group) => {
+                for operation in operations(mode_name, group, *channel, fine, definition, report) {
+                    builder.custom(operation);
+                }
+            },
+            Kind::Nothing => {},
+        }
+    }
+
+    if movement.pan.is_some() || movement.tilt.is_some() {
+        builder.movement(movement);
+    }
+    for (name, range, fine) in master_dimmers {
+        let name = FixtureName::new(name.into());
+        builder.custom(match fine {
+            Some(fine) => FixtureCustomOperation::FineSlider(name, range, fine),
+            None => FixtureCustomOperation::Slider(name, range),
+        });
+    }
+
+    let lights: Vec<_> = cells.into_iter().filter_map(|cell| cell.build(mode_name, report)).collect();
+    if heads.len() > 1 && lights.len() > 1 {
+        // The layout describes how the heads are arranged, row by row
+        let width = physical.and_then(|physical| child(physical, "Layout"))
+            .and_then(|layout| layout.attribute("Width")?.parse::<usize>().ok())
+            .filter(|width| *width > 0)
+            .unwrap_or(lights.len());
+        let mut matrix = FixtureMatrix::builder();
+        for row in lights.chunks(width) {
+            matrix.row(row.to_vec());
+        }
+        builder.matrix(matrix.build()?);
+    } else if let Some(lights) = lights.into_iter().next() {
+        builder.lights(lights);
+    }
+    Ok(builder.build()?)
+}
+
+fn colour_presets(mode_name: &str, channel: Channel, definition: &QxfChannel, report: &mut ConversionReport) -> Vec<(Color, DMXAddress)> {
+    let mut presets = Vec::new();
+    for capability in definition.capabilities() {
+        let color = match (capability.color(), capability.name) {
+            (Some(rgb), name) => named_color(name, rgb),
+            (None, "Open" | "White") => Color::White,
+            (None, name) if name.to_lowercase().contains("rainbow") || name.to_lowercase().contains("color change") => Color::ColorChange,
+            (None, name) => {
+                report.unmapped(format!("{}: {} capability {} has no color", mode_name, definition.name(), name));
+                continue;
+            },
+        };
+        if capability.node.attribute("Res2").is_some() {
+            report.unmapped(format!("{}: second color of {}", mode_name, capability.name));
+        }
+        presets.push((color, DMXAddress::new(channel, capability.min)));
+    }
+    presets
+}
+
+fn operations(mode_name: &str, group: &str, channel: Channel, fine: Option<Channel>, definition: &QxfChannel, report: &mut ConversionReport) -> Vec<FixtureCustomOperation> {
+    let name = FixtureName::new(definition.name().into());
+    let capabilities = definition.capabilities();
+    let known = ["Shutter", "Speed", "Gobo", "Prism", "Beam", "Effect", "Maintenance"];
+    if !known.contains(&group) {
+        report.unmapped(format!("{}: {} group of {}", mode_name, group, definition.name()));
+    }
+    if group == "Gobo" && capabilities.iter().any(|capability| capability.node.attribute("Res").or_else(|| capability.node.attribute("Res1")).is_some()) {
+        report.unmapped(format!("{}: gobo images of {}", mode_name, definition.name()));
+    }
+
+    let mut operations = Vec::new();
+    let mut steps = Vec::new();
+    for capability in &capabilities {
+        let range = DMXRange::new(DMXAddress::new(channel, capability.min), DMXAddress::new(channel, capability.max));
+        let capability_name = FixtureName::new(capability.name.into());
+        if let Some((from, to)) = capability.frequency() {
+            if let Ok(physical) = PhysicalRange::builder().unit(Unit::Hertz).point(from, capability.min).point(to, capability.max).build() {
+                operations.push(FixtureCustomOperation::Physical(capability_name, range, physical));
+                continue;
+            }
+        }
+        steps.push((capability_name, range));
+    }
+
+    match steps.as_slice() {
+        [] if capabilities.is_empty() => operations.push(match fine {
+            Some(fine) => FixtureCustomOperation::FineSlider(name, DMXRange::new(DMXAddress::new(channel, 0), DMXAddress::new(channel, 255)), fine),
+            None => FixtureCustomOperation::Slider(name, DMXRange::new(DMXAddress::new(channel, 0), DMXAddress::new(channel, 255))),
+        }),
+        [] => {},
+        [(_, range)] if capabilities.len() == 1 => operations.push(match fine {
+            Some(fine) => FixtureCustomOperation::FineSlider(name, *range, fine),
+            None => FixtureCustomOperation::Slider(name, *range),
+        }),
+        _ if steps.iter().all(|(_, range)| range.start.value == range.end.value) => {
+            operations.extend(steps.into_iter().map(|(name, range)| FixtureCustomOperation::Button(name, range.start)));
+        },
+        _ => operations.push(FixtureCustomOperation::Stepped(name, steps.into_iter().map(|(name, range)| (name, range.start)).collect())),
+    }
+    if fine.is_some() && !matches!(operations.last(), Some(FixtureCustomOperation::FineSlider(..))) {
+        report.unmapped(format!("{}: fine channel of {}", mode_name, definition.name()));
+    }
+    operations
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::fixture::FixtureColorMode;
+
+    const BAR: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE FixtureDefinition>
+<FixtureDefinition xmlns="http://www.qlcplus.org/FixtureDefinition">
+ <Creator><Name>Q Light Controller Plus</Name><Version>4.12.7</Version><Author>dmxt</Author></Creator>
+ <Manufacturer>Generic</Manufacturer>
+ <Model>RGB Bar 2</Model>
+ <Type>LED Bar (Pixels)</Type>
+ <Channel Name="Dimmer" Preset="IntensityMasterDimmer"/>
+ <Channel Name="Red 1" Preset="IntensityRed"/>
+ <Channel Name="Green 1" Preset="IntensityGreen"/>
+ <Channel Name="Blue 1" Preset="IntensityBlue"/>
+ <Channel Name="Red 2" Preset="IntensityRed"/>
+ <Channel Name="Green 2" Preset="IntensityGreen"/>
+ <Channel Name="Blue 2" Preset="IntensityBlue"/>
+ <Channel Name="UV" Preset="IntensityUV"/>
+ <Channel Name="Color Macros">
+  <Group Byte="0">Colour</Group>
+  <Capability Min="0" Max="9" Preset="ColorMacro" Res1="#000000">Off</Capability>
+  <Capability Min="10" Max="19" Preset="ColorMacro" Res1="#ff0000">Red</Capability>
+  <Capability Min="20" Max="29" Preset="ColorMacro" Res1="#ff8000">Orange</Capability>
+  <Capability Min="30" Max="255">Gobo shake</Capability>
+ </Channel>
+ <Channel Name="Strobe">
+  <Group Byte="0">Shutter</Group>
+  <Capability Min="0" Max="9" Preset="ShutterOpen">Open</Capability>
+  <Capability Min="10" Max="255" Preset="StrobeFreqRange" Res1="1" Res2="25">Strobe</Capability>
+ </Channel>
+ <Mode Name="2 Pixel">
+  <Channel Number="0">Dimmer</Channel>
+  <Channel Number="1">Red 1</Channel>
+  <Channel Number="2">Green 1</Channel>
+  <Channel Number="3">Blue 1</Channel>
+  <Channel Number="4">Red 2</Channel>
+  <Channel Number="5">Green 2</Channel>
+  <Channel Number="6">Blue 2</Channel>
+  <Channel Number="7">Strobe</Channel>
+  <Head><Channel>1</Channel><Channel>2</Channel><Channel>3</Channel></Head>
+  <Head><Channel>4</Channel><Channel>5</Channel><Channel>6</Channel></Head>
+ </Mode>
+ <Mode Name="Macro">
+  <Channel Number="0">Dimmer</Channel>
+  <Channel Number="1">Color Macros</Channel>
+  <Channel Number="2">UV</Channel>
+ </Mode>
+ <Physical>
+  <Layout Width="2" Height="1"/>
+ </Physical>
+</FixtureDefinition>"##;
+
+    #[test]
+    fn imports_heads_and_reports_unsupported() {
+        let (model, report) = import(BAR).unwrap();
+        assert_eq!(model.manufacturer, "Generic");
+
+        let pixels = &model.channel_modes[0];
+        assert_eq!(pixels.total_channels.id(), 8);
+        let matrix = &pixels.lights.as_ref().unwrap().matrix;
+        assert_eq!(matrix[0].len(), 2);
+        assert!(matches!(matrix[0][1].color_mode, FixtureColorMode::RGB(r, _, _) if r.channel().id() == 5));
+        let custom = pixels.custom.as_ref().unwrap();
+        assert!(custom.iter().any(|operation| matches!(operation, FixtureCustomOperation::Physical(name, _, _) if name.name() == "Strobe")));
+        assert!(custom.iter().any(|operation| matches!(operation, FixtureCustomOperation::Slider(name, _) if name.name() == "Dimmer")));
+
+        let macros = &model.channel_modes[1];
+        let lights = &macros.lights.as_ref().unwrap().matrix[0][0];
+        assert!(lights.dimmer.is_some());
+        assert!(matches!(&lights.color_mode, FixtureColorMode::Presets(presets) if presets.len() == 3));
+        assert_eq!(report.unmapped.len(), 2, "{:?}", report.unmapped);
+    }
+
+    #[test]
+    fn malformed_numbers_and_colors_are_rejected() {
+        assert!(matches!(import(&BAR.replace(r#"<Channel Number="7">"#, r#"<Channel Number="65535">"#)), Err(FormatError::Invalid(_))));
+        assert!(matches!(import(&BAR.replace("<Channel>6</Channel>", "<Channel>65535</Channel>")), Err(FormatError::Invalid(_))));
+        // A color that isn't hex leaves the macro out
+        let (model, report) = import(&BAR.replace("#ff8000", "#ffé00")).unwrap();
+        let lights = &model.channel_modes[1].lights.as_ref().unwrap().matrix[0][0];
+        assert!(matches!(&lights.color_mode, FixtureColorMode::Presets(presets) if presets.len() == 2));
+        assert!(report.unmapped.iter().any(|message| message.contains("Orange has no color")), "{:?}", report.unmapped);
+    }
+
+    #[test]
+    fn fine_color_channels_are_reported() {
+        let fine = BAR
+            .replace(r#"<Channel Name="UV""#, r#"<Channel Name="Red 1 Fine" Preset="IntensityRedFine"/>
+ <Channel Name="UV""#)
+            .replace(r#"<Channel Number="7">Strobe</Channel>"#, r#"<Channel Number="7">Strobe</Channel>
+  <Channel Number="8">Red 1 Fine</Channel>"#);
+        let (model, report) = import(&fine).unwrap();
+        let pixels = &model.channel_modes[0];
+        assert_eq!(pixels.total_channels.id(), 9);
+        let matrix = &pixels.lights.as_ref().unwrap().matrix;
+        assert!(matches!(matrix[0][0].color_mode, FixtureColorMode::RGB(r, _, _) if r.channel().id() == 2));
+        assert!(report.unmapped.contains(&"2 Pixel: fine channel of Red 1".to_string()), "{:?}", report.unmapped);
+    }
+}
@@ -0,0 +1,19 @@
+// Everything that could not be carried over while converting between fixture formats
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConversionReport {
+    pub unmapped: Vec<String>,
+}
+
+impl ConversionReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn unmapped(&mut self, message: impl Into<String>) {
+        self.unmapped.push(message.into());
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.unmapped.is_empty()
+    }
+}
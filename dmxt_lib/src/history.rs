@@ -0,0 +1,271 @@
+use std::collections::VecDeque;
+
+pub const DEFAULT_MEMORY_LIMIT: usize = 32 * 1024 * 1024;
+
+// A reversible change of `Target`
+pub trait Command {
+    type Target;
+    type Error;
+
+    fn apply(&mut self, target: &mut Self::Target) -> Result<(), Self::Error>;
+    // Only called after a successful `apply`, in reverse order of the applies
+    fn revert(&mut self, target: &mut Self::Target);
+    fn description(&self) -> String;
+    // Approximate memory the command holds on to
+    fn size(&self) -> usize;
+
+    // Takes over `next` if both change the same thing, e.g. the steps of a slider drag
+    fn merge(&mut self, _next: &Self) -> bool {
+        false
+    }
+}
+
+struct Entry<C> {
+    commands: Vec<C>,
+    size: usize,
+}
+
+// Undo and redo stacks of commands. Commands executed while a group is open end up in one entry,
+// the oldest entries are dropped when the history needs more than its memory limit
+pub struct History<C> {
+    undo: VecDeque<Entry<C>>,
+    redo: Vec<Entry<C>>,
+    memory_limit: usize,
+    memory: usize,
+    // Length of `undo` when the show was saved, None if that state can't be reached anymore
+    saved: Option<usize>,
+    group: Group,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Group {
+    Closed,
+    Open,
+    // The group already has an entry that the next commands are added to
+    Filled,
+}
+
+impl<C: Command> History<C> {
+    pub fn new(memory_limit: usize) -> History<C> {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            memory_limit,
+            memory: 0,
+            saved: Some(0),
+            group: Group::Closed,
+        }
+    }
+
+    pub fn execute(&mut self, mut command: C, target: &mut C::Target) -> Result<(), C::Error> {
+        command.apply(target)?;
+        self.memory -= self.redo.drain(..).map(|entry| entry.size).sum::<usize>();
+        if self.saved.is_some_and(|saved| saved > self.undo.len()) {
+            self.saved = None;
+        }
+        match (self.group, self.undo.back_mut()) {
+            (Group::Filled, Some(entry)) => {
+                let merged = entry.commands.last_mut().is_some_and(|last| last.merge(&command));
+                if !merged {
+                    self.memory += command.size();
+                    entry.size += command.size();
+                    entry.commands.push(command);
+                }
+            },
+            _ => {
+                let size = command.size();
+                self.memory += size;
+                self.undo.push_back(Entry { commands: vec![command], size });
+                if self.group == Group::Open {
+                    self.group = Group::Filled;
+                }
+            },
+        }
+        self.trim();
+        Ok(())
+    }
+
+    // Everything executed until `end_group` is undone at once
+    pub fn begin_group(&mut self) {
+        self.group = Group::Open;
+    }
+
+    pub fn end_group(&mut self) {
+        self.group = Group::Closed;
+    }
+
+    // Returns the description of the undone entry
+    pub fn undo(&mut self, target: &mut C::Target) -> Option<String> {
+        self.end_group();
+        let mut entry = self.undo.pop_back()?;
+        for command in entry.commands.iter_mut().rev() {
+            command.revert(target);
+        }
+        let description = entry_description(&entry);
+        self.redo.push(entry);
+        Some(description)
+    }
+
+    pub fn redo(&mut self, target: &mut C::Target) -> Result<Option<String>, C::Error> {
+        self.end_group();
+        let Some(mut entry) = self.redo.pop() else {
+            return Ok(None);
+        };
+        for (index, command) in entry.commands.iter_mut().enumerate() {
+            if let Err(error) = command.apply(target) {
+                // Leave the target as it was before the redo
+                for command in entry.commands[..index].iter_mut().rev() {
+                    command.revert(target);
+                }
+                self.redo.push(entry);
+                return Err(error);
+            }
+        }
+        let description = entry_description(&entry);
+        self.undo.push_back(entry);
+        Ok(Some(description))
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo_description(&self) -> Option<String> {
+        self.undo.back().map(entry_description)
+    }
+
+    pub fn redo_description(&self) -> Option<String> {
+        self.redo.last().map(entry_description)
+    }
+
+    pub fn memory(&self) -> usize {
+        self.memory
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved = Some(self.undo.len());
+    }
+
+    // True if the show changed since it was saved or loaded
+    pub fn is_dirty(&self) -> bool {
+        self.saved != Some(self.undo.len())
+    }
+
+    // Forgets everything, e.g. after loading another show
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.memory = 0;
+        self.saved = Some(0);
+        self.group = Group::Closed;
+    }
+
+    // The newest entry is always kept, even if it's bigger than the limit
+    fn trim(&mut self) {
+        while self.memory > self.memory_limit && self.undo.len() > 1 {
+            let entry = self.undo.pop_front().unwrap();
+            self.memory -= entry.size;
+            self.saved = self.saved.and_then(|saved| saved.checked_sub(1));
+        }
+    }
+}
+
+fn entry_description<C: Command>(entry: &Entry<C>) -> String {
+    let description = entry.commands.first().map(Command::description).unwrap_or_default();
+    match entry.commands.len() {
+        0 | 1 => description,
+        count => format!("{} and {} more", description, count - 1),
+    }
+}
+
+impl<C: Command> Default for History<C> {
+    fn default() -> Self {
+        Self::new(DEFAULT_MEMORY_LIMIT)
+    }
+}
+
+impl<C: Command> std::fmt::Debug for History<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("History")
+        .field("undo", &self.undo.len())
+        .field("redo", &self.redo.len())
+        .field("memory", &self.memory)
+        .field("dirty", &self.is_dirty())
+        .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sets a value, like a slider
+    struct Set {
+        value: i32,
+        previous: i32,
+    }
+
+    impl Command for Set {
+        type Target = i32;
+        type Error = ();
+
+        fn apply(&mut self, target: &mut i32) -> Result<(), ()> {
+            self.previous = *target;
+            *target = self.value;
+            Ok(())
+        }
+
+        fn revert(&mut self, target: &mut i32) {
+            *target = self.previous;
+        }
+
+        fn description(&self) -> String {
+            format!("Set {}", self.value)
+        }
+
+        fn size(&self) -> usize {
+            10
+        }
+
+        fn merge(&mut self, next: &Self) -> bool {
+            self.value = next.value;
+            true
+        }
+    }
+
+    fn set(value: i32) -> Set {
+        Set { value, previous: 0 }
+    }
+
+    #[test]
+    fn drags_are_one_entry_and_old_entries_are_dropped() {
+        let mut value = 0;
+        let mut history = History::new(30);
+        history.execute(set(1), &mut value).unwrap();
+        history.mark_saved();
+        history.begin_group();
+        for step in 2..=10 {
+            history.execute(set(step), &mut value).unwrap();
+        }
+        history.end_group();
+        assert!(history.is_dirty());
+        assert_eq!(history.undo(&mut value), Some("Set 10".into()));
+        assert_eq!(value, 1);
+        assert!(!history.is_dirty());
+        history.redo(&mut value).unwrap();
+        assert_eq!(value, 10);
+
+        for step in 11..=13 {
+            history.execute(set(step), &mut value).unwrap();
+        }
+        assert_eq!(history.memory(), 30);
+        while history.undo(&mut value).is_some() {}
+        // The entry of the save was dropped, so no state is the saved one anymore
+        assert_eq!(value, 10);
+        assert!(history.is_dirty());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::components::Fixture;
+use crate::dmx::Channel;
+use crate::patch::{Patch, PatchError};
+
+use std::collections::BTreeMap;
+
+// Where a fixture is patched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AddressPlacement {
+    pub universe: usize,
+    pub address: u16,
+}
+
+impl AddressPlacement {
+    fn of(fixture: &Fixture) -> AddressPlacement {
+        AddressPlacement { universe: fixture.universe, address: fixture.address.channel.id() }
+    }
+}
+
+impl std::fmt::Display for AddressPlacement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:03}", self.universe + 1, self.address)
+    }
+}
+
+// What happens to imported fixtures whose channels are already used
+#[derive(Debug, Clone, PartialEq)]
+pub enum Remap {
+    // Moved up by this many channels
+    Offset(u16),
+    // Moved to the first free channels of their universe
+    NextFree,
+    // Patched where the user put them, fixtures without a placement keep theirs
+    Manual(BTreeMap<usize, AddressPlacement>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportEntry {
+    // Index in the source patch
+    pub source: usize,
+    pub name: String,
+    pub from: AddressPlacement,
+    pub to: AddressPlacement,
+    // Why the fixture can't be imported
+    pub conflict: Option<String>,
+}
+
+impl ImportEntry {
+    pub fn is_moved(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+// Dry run of an import, nothing is changed until the fixtures are added to the patch
+#[derive(Debug, Clone, Default)]
+pub struct ImportPlan {
+    pub entries: Vec<ImportEntry>,
+    fixtures: Vec<Fixture>,
+    // Index in the target patch the first imported fixture will have
+    first_index: usize,
+}
+
+impl ImportPlan {
+    // Places the selected fixtures of `source` into `target`, in the order of the selection
+    pub fn new(target: &Patch, source: &Patch, selection: &[usize], remap: &Remap) -> ImportPlan {
+        let mut plan = ImportPlan { first_index: target.len(), ..ImportPlan::default() };
+        // Later fixtures of the import must not collide with earlier ones either
+        let mut patch = target.clone();
+        for &index in selection {
+            let Some(fixture) = source.get(index) else {
+                continue;
+            };
+            let mut fixture = fixture.clone();
+            let from = AddressPlacement::of(&fixture);
+            let to = match remap {
+                Remap::Manual(placements) => placements.get(&index).copied().unwrap_or(from),
+                _ if patch.collisions(&fixture).is_empty() => from,
+                Remap::Offset(offset) => AddressPlacement { address: from.address.saturating_add(*offset), ..from },
+                Remap::NextFree => patch.next_free(from.universe, fixture.channel_count())
+                    .map_or(from, |channel| AddressPlacement { address: channel.id(), ..from }),
+            };
+            let conflict = place(&mut fixture, to).and_then(|_| patch.add(fixture.clone())).err().map(|error| match error {
+                PatchError::Collision(other) => format!("the channels are used by {}", patch.fixtures()[other].name),
+                error => error.to_string(),
+            });
+            if conflict.is_none() {
+                plan.fixtures.push(fixture.clone());
+            }
+            plan.entries.push(ImportEntry { source: index, name: fixture.name.clone(), from, to, conflict });
+        }
+        plan
+    }
+
+    // Fixtures to add to the target patch, without the conflicting ones
+    pub fn fixtures(&self) -> &[Fixture] {
+        &self.fixtures
+    }
+
+    pub fn conflicts(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.conflict.is_some()).count()
+    }
+
+    // Index in the source patch to index in the target patch, for moving values of scenes along
+    pub fn fixture_map(&self) -> BTreeMap<usize, usize> {
+        self.entries.iter()
+            .filter(|entry| entry.conflict.is_none())
+            .enumerate()
+            .map(|(offset, entry)| (entry.source, self.first_index + offset))
+            .collect()
+    }
+
+    // Universes the target needs for the imported fixtures
+    pub fn universes(&self) -> usize {
+        self.fixtures.iter().map(|fixture| fixture.universe + 1).max().unwrap_or(0)
+    }
+
+    pub fn report(&self) -> String {
+        let mut report = format!("{} fixtures are imported", self.fixtures.len());
+        let moved = self.entries.iter().filter(|entry| entry.conflict.is_none() && entry.is_moved()).count();
+        if moved > 0 {
+            report += &format!(", {} of them at another address", moved);
+        }
+        for entry in &self.entries {
+            match &entry.conflict {
+                Some(conflict) => report += &format!("\n{} ({}) is skipped: {}", entry.name, entry.to, conflict),
+                None if entry.is_moved() => report += &format!("\n{} is moved from {} to {}", entry.name, entry.from, entry.to),
+                None => {},
+            }
+        }
+        report
+    }
+}
+
+fn place(fixture: &mut Fixture, placement: AddressPlacement) -> Result<(), PatchError> {
+    fixture.universe = placement.universe;
+    fixture.address.channel = Channel::new(placement.address).map_err(|_| PatchError::OutsideUniverse)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_fixture;
+
+    fn fixture(name: &str, address: u16) -> Fixture {
+        let mut fixture = test_fixture(4, address);
+        fixture.name = name.into();
+        fixture
+    }
+
+    #[test]
+    fn colliding_fixtures_are_moved_or_reported() {
+        let mut target = Patch::new();
+        target.add(fixture("Front", 1)).unwrap();
+        let mut source = Patch::new();
+        source.add(fixture("Back", 1)).unwrap();
+        source.add(fixture("Side", 5)).unwrap();
+        source.add(fixture("Top", 9)).unwrap();
+
+        let plan = ImportPlan::new(&target, &source, &[0, 1], &Remap::NextFree);
+        assert_eq!(plan.entries[0].to.address, 5);
+        // Side now collides with the moved Back fixture
+        assert_eq!(plan.entries[1].to.address, 9);
+        assert_eq!(plan.fixture_map(), BTreeMap::from([(0, 1), (1, 2)]));
+
+        target.add(fixture("Rear", 5)).unwrap();
+        let plan = ImportPlan::new(&target, &source, &[0, 2], &Remap::Offset(4));
+        assert_eq!(plan.entries[0].conflict.as_deref(), Some("the channels are used by Rear"));
+        assert_eq!(plan.entries[1].to.address, 9);
+        assert_eq!(plan.fixture_map(), BTreeMap::from([(2, 2)]));
+    }
+}
@@ -0,0 +1,318 @@
+use crate::dmx::{DMXUniverse, DMX_CHANNELS};
+use crate::recording::RecorderHandle;
+use crate::threads::shared::Lock;
+
+use std::collections::{BTreeMap, HashSet};
+use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::{mpsc, Arc, Mutex, Weak};
+use std::thread;
+use std::time;
+
+pub const ARTNET_PORT: u16 = 6454;
+pub const SACN_PORT: u16 = 5568;
+
+const ARTNET_ID: &[u8] = b"Art-Net\0";
+const ARTNET_OP_DMX: u16 = 0x5000;
+const SACN_ID: &[u8] = b"ASC-E1.17\0\0\0";
+const SACN_DATA_START: usize = 126;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Protocol {
+    ArtNet,
+    Sacn,
+}
+
+impl std::fmt::Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Protocol::ArtNet => write!(f, "Art-Net"),
+            Protocol::Sacn => write!(f, "sACN"),
+        }
+    }
+}
+
+// Data and sender of a received datagram
+pub type Packet = (Vec<u8>, SocketAddr);
+
+// A port can only be listened on once, discovery and input share one socket per port.
+// The socket is closed when the last receiver of its port is dropped
+struct SharedSocket {
+    socket: UdpSocket,
+    subscribers: Mutex<Vec<mpsc::Sender<Packet>>>,
+    multicast: Mutex<HashSet<Ipv4Addr>>,
+}
+
+static SHARED_SOCKETS: Mutex<Vec<(u16, Weak<SharedSocket>)>> = Mutex::new(Vec::new());
+
+// Gets every packet that arrives on a port
+pub struct PortReceiver {
+    shared: Arc<SharedSocket>,
+    packets: mpsc::Receiver<Packet>,
+}
+
+impl PortReceiver {
+    pub fn bind(port: u16) -> Result<PortReceiver, InputError> {
+        let mut sockets = SHARED_SOCKETS.lock().unwrap();
+        sockets.retain(|(_, shared)| shared.strong_count() > 0);
+        let shared = match sockets.iter().find(|(bound, _)| *bound == port).and_then(|(_, shared)| shared.upgrade()) {
+            Some(shared) => shared,
+            None => {
+                let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))?;
+                socket.set_broadcast(true)?;
+                // The timeout lets the thread notice that nobody listens anymore
+                socket.set_read_timeout(Some(time::Duration::from_millis(100)))?;
+                let shared = Arc::new(SharedSocket {
+                    socket,
+                    subscribers: Mutex::new(Vec::new()),
+                    multicast: Mutex::new(HashSet::new()),
+                });
+                let weak = Arc::downgrade(&shared);
+                thread::spawn(move || {
+                    let mut buffer = [0; 1024];
+                    while let Some(shared) = weak.upgrade() {
+                        if let Ok((length, source)) = shared.socket.recv_from(&mut buffer) {
+                            shared.subscribers.lock().unwrap().retain(|subscriber| subscriber.send((buffer[..length].to_vec(), source)).is_ok());
+                        }
+                    }
+                });
+                sockets.push((port, Arc::downgrade(&shared)));
+                shared
+            },
+        };
+        let (tx, packets) = mpsc::channel();
+        shared.subscribers.lock().unwrap().push(tx);
+        Ok(PortReceiver { shared, packets })
+    }
+
+    pub fn join_multicast(&self, group: Ipv4Addr) -> Result<(), InputError> {
+        if self.shared.multicast.lock().unwrap().insert(group) {
+            self.shared.socket.join_multicast_v4(&group, &Ipv4Addr::UNSPECIFIED)?;
+        }
+        Ok(())
+    }
+
+    // Sends from the shared port, e.g. a poll that nodes answer to the port
+    pub fn send_to(&self, packet: &[u8], target: impl ToSocketAddrs) -> Result<(), InputError> {
+        self.shared.socket.send_to(packet, target)?;
+        Ok(())
+    }
+
+    // The next packet that has arrived, None if there is none
+    pub fn try_recv(&self) -> Option<Packet> {
+        self.packets.try_recv().ok()
+    }
+
+    pub fn recv_timeout(&self, timeout: time::Duration) -> Option<Packet> {
+        self.packets.recv_timeout(timeout).ok()
+    }
+}
+
+// Received universes by their network universe number
+pub type NetworkUniverses = Lock<BTreeMap<u16, DMXUniverse>>;
+
+// Receives DMX from the network on a background thread
+pub struct NetworkInput {
+    protocol: Protocol,
+    universes: NetworkUniverses,
+    recorder: RecorderHandle,
+    stop: Option<mpsc::Sender<()>>,
+}
+
+impl NetworkInput {
+    pub fn listen_artnet() -> Result<NetworkInput, InputError> {
+        Ok(NetworkInput::listen(Protocol::ArtNet, PortReceiver::bind(ARTNET_PORT)?))
+    }
+
+    // sACN is multicast, every universe has to be joined
+    pub fn listen_sacn(universes: &[u16]) -> Result<NetworkInput, InputError> {
+        let receiver = PortReceiver::bind(SACN_PORT)?;
+        for universe in universes {
+            receiver.join_multicast(sacn_multicast(*universe))?;
+        }
+        Ok(NetworkInput::listen(Protocol::Sacn, receiver))
+    }
+
+    fn listen(protocol: Protocol, receiver: PortReceiver) -> NetworkInput {
+        let (tx, rx) = mpsc::channel();
+        let universes = Lock::new(BTreeMap::new());
+        let received = universes.clone();
+        let recorder = RecorderHandle::default();
+        let recording = recorder.clone();
+        thread::spawn(move || {
+            loop {
+                match rx.try_recv() {
+                    Ok(()) | Err(mpsc::TryRecvError::Disconnected) => break,
+                    Err(mpsc::TryRecvError::Empty) => {},
+                }
+                // The timeout lets the thread notice `stop` without traffic
+                let Some((packet, _)) = receiver.recv_timeout(time::Duration::from_millis(100)) else {
+                    continue;
+                };
+                let packet = match protocol {
+                    Protocol::ArtNet => parse_artdmx(&packet),
+                    Protocol::Sacn => parse_sacn(&packet),
+                };
+                if let Some((universe, data)) = packet {
+                    let mut received = received.write().unwrap();
+                    received.insert(universe, data);
+                    if recording.is_recording() {
+                        recording.capture(&recorded_universes(&received));
+                    }
+                }
+            }
+        });
+        NetworkInput {
+            protocol,
+            universes,
+            recorder,
+            stop: Some(tx),
+        }
+    }
+
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    pub fn universes(&self) -> NetworkUniverses {
+        self.universes.clone()
+    }
+
+    // Records every received packet
+    pub fn recorder(&self) -> RecorderHandle {
+        self.recorder.clone()
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(tx) = self.stop.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+impl std::fmt::Debug for NetworkInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NetworkInput")
+        .field("protocol", &self.protocol)
+        .field("universes", &self.universes.read().unwrap().len())
+        .finish()
+    }
+}
+
+impl Drop for NetworkInput {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// Recordings index universes by position, network universe n is recorded at index n.
+// Universes nothing was received for are recorded blacked out
+fn recorded_universes(received: &BTreeMap<u16, DMXUniverse>) -> Vec<DMXUniverse> {
+    let count = received.keys().next_back().map_or(0, |last| *last as usize + 1);
+    let mut universes = vec![DMXUniverse::new(); count];
+    for (universe, data) in received {
+        universes[*universe as usize] = *data;
+    }
+    universes
+}
+
+pub fn sacn_multicast(universe: u16) -> Ipv4Addr {
+    let [high, low] = universe.to_be_bytes();
+    Ipv4Addr::new(239, 255, high, low)
+}
+
+// Port address (net, sub-net and universe) and data of an ArtDmx packet
+pub fn parse_artdmx(packet: &[u8]) -> Option<(u16, DMXUniverse)> {
+    if packet.len() < 18 || !packet.starts_with(ARTNET_ID) || u16::from_le_bytes([packet[8], packet[9]]) != ARTNET_OP_DMX {
+        return None;
+    }
+    let universe = u16::from_le_bytes([packet[14], packet[15] & 0x7f]);
+    let length = u16::from_be_bytes([packet[16], packet[17]]) as usize;
+    Some((universe, universe_from(packet.get(18..18 + length)?)))
+}
+
+// Universe and data of an E1.31 data packet, other start codes are ignored
+pub fn parse_sacn(packet: &[u8]) -> Option<(u16, DMXUniverse)> {
+    if packet.len() < SACN_DATA_START || packet.get(4..16)? != SACN_ID || packet[40..44] != [0, 0, 0, 2] || packet[125] != 0 {
+        return None;
+    }
+    let universe = u16::from_be_bytes([packet[113], packet[114]]);
+    // The count includes the start code
+    let count = u16::from_be_bytes([packet[123], packet[124]]) as usize;
+    Some((universe, universe_from(packet.get(SACN_DATA_START..SACN_DATA_START + count.checked_sub(1)?)?)))
+}
+
+fn universe_from(data: &[u8]) -> DMXUniverse {
+    let mut universe = DMXUniverse::new();
+    let length = data.len().min(DMX_CHANNELS);
+    universe.channels[..length].copy_from_slice(&data[..length]);
+    universe
+}
+
+#[derive(Debug)]
+pub enum InputError {
+    Socket(std::io::Error),
+}
+
+impl From<std::io::Error> for InputError {
+    fn from(error: std::io::Error) -> Self {
+        InputError::Socket(error)
+    }
+}
+
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::Socket(error) => write!(f, "could not listen on the network: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn network_packets_are_parsed() {
+        let mut artdmx = b"Art-Net\0".to_vec();
+        artdmx.extend([0x00, 0x50, 0, 14, 0, 0, 0x12, 0x01, 0, 4, 1, 2, 3, 4]);
+        let (universe, data) = parse_artdmx(&artdmx).unwrap();
+        assert_eq!(universe, 0x0112);
+        assert_eq!(data.channels[..5], [1, 2, 3, 4, 0]);
+
+        let mut sacn = vec![0; SACN_DATA_START];
+        sacn[4..16].copy_from_slice(SACN_ID);
+        sacn[40..44].copy_from_slice(&[0, 0, 0, 2]);
+        sacn[113..115].copy_from_slice(&7u16.to_be_bytes());
+        sacn[123..125].copy_from_slice(&3u16.to_be_bytes());
+        sacn.extend([255, 128]);
+        let (universe, data) = parse_sacn(&sacn).unwrap();
+        assert_eq!(universe, 7);
+        assert_eq!(data.channels[..3], [255, 128, 0]);
+
+        sacn[125] = 0xdd;
+        assert_eq!(parse_sacn(&sacn), None);
+        assert_eq!(sacn_multicast(258), Ipv4Addr::new(239, 255, 1, 2));
+    }
+
+    #[test]
+    fn receivers_share_a_port() {
+        let port = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap().port();
+        let first = PortReceiver::bind(port).unwrap();
+        let second = PortReceiver::bind(port).unwrap();
+        let sender = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        sender.send_to(b"Art-Net\0", (Ipv4Addr::LOCALHOST, port)).unwrap();
+        let timeout = time::Duration::from_secs(1);
+        assert_eq!(first.recv_timeout(timeout).unwrap().0, b"Art-Net\0");
+        assert_eq!(second.recv_timeout(timeout).unwrap().0, b"Art-Net\0");
+
+        // The port is closed shortly after the last receiver is gone
+        drop((first, second));
+        let start = time::Instant::now();
+        while UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port)).is_err() {
+            assert!(start.elapsed() < timeout, "port {} is still bound", port);
+            thread::sleep(time::Duration::from_millis(10));
+        }
+    }
+}
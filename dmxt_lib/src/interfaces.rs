@@ -0,0 +1,65 @@
+use serde::{Serialize, Deserialize};
+
+use std::net::Ipv4Addr;
+
+// Paths
+pub mod artnet;
+pub mod sacn;
+mod manager;
+// Re-exports
+pub use manager::{InterfaceManager, InterfaceStatus};
+
+// Something that DMX can be sent to
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Interface {
+    // USB DMX interfaces. The serial number finds the device again when the OS gives it another port
+    Serial { port: String, serial_number: Option<String> },
+    // Output port of an Art-Net node, `universe` is the 15 bit port address
+    ArtNet { address: Ipv4Addr, universe: u16 },
+    Sacn { universe: u16 },
+}
+
+impl std::fmt::Display for Interface {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Interface::Serial { port, .. } => write!(f, "Serial {}", port),
+            Interface::ArtNet { address, universe } => write!(f, "Art-Net {} universe {}", address, universe),
+            Interface::Sacn { universe } => write!(f, "sACN universe {}", universe),
+        }
+    }
+}
+
+// Which universe is sent to an interface, this is what the show file stores
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterfaceMapping {
+    pub interface: Interface,
+    pub universe: usize,
+}
+
+// An interface found by discovery
+#[derive(Debug, Clone, PartialEq)]
+pub struct Device {
+    pub interface: Interface,
+    pub name: String,
+}
+
+// USB serial ports that could be DMX interfaces. Built-in legacy ports are left out
+pub fn serial_ports() -> Vec<Device> {
+    let ports = serialport::available_ports().unwrap_or_default();
+    ports.into_iter()
+        .filter(|port| !port.port_name.starts_with("/dev/ttyS"))
+        .map(|port| match port.port_type {
+            serialport::SerialPortType::UsbPort(usb) => {
+                let name = [usb.manufacturer.as_deref(), usb.product.as_deref()].into_iter().flatten().collect::<Vec<_>>().join(" ");
+                Device {
+                    name: if name.is_empty() { port.port_name.clone() } else { format!("{} ({})", name, port.port_name) },
+                    interface: Interface::Serial { port: port.port_name, serial_number: usb.serial_number },
+                }
+            },
+            _ => Device {
+                name: port.port_name.clone(),
+                interface: Interface::Serial { port: port.port_name, serial_number: None },
+            },
+        })
+        .collect()
+}
@@ -0,0 +1,124 @@
+use crate::dmx::{DMXUniverse, DMX_CHANNELS};
+use crate::input::ARTNET_PORT;
+use crate::interfaces::{Device, Interface};
+use crate::output::{DMXOutput, OutputError};
+
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+
+const ID: &[u8] = b"Art-Net\0";
+const OP_POLL: u16 = 0x2000;
+const OP_POLL_REPLY: u16 = 0x2100;
+const OP_DMX: u16 = 0x5000;
+const PROTOCOL_VERSION: [u8; 2] = [0, 14];
+// Bit of a port type that marks a port which outputs DMX it receives over the network
+const PORT_OUTPUT: u8 = 0x80;
+
+pub fn poll_packet() -> Vec<u8> {
+    let mut packet = header(OP_POLL);
+    // Flags and diagnostics priority
+    packet.extend([0, 0]);
+    packet
+}
+
+pub fn dmx_packet(universe: u16, sequence: u8, data: &DMXUniverse) -> Vec<u8> {
+    let mut packet = header(OP_DMX);
+    let [net, sub_uni] = universe.to_be_bytes();
+    packet.extend([sequence, 0, sub_uni, net & 0x7f]);
+    packet.extend((DMX_CHANNELS as u16).to_be_bytes());
+    packet.extend(data.channels);
+    packet
+}
+
+fn header(op_code: u16) -> Vec<u8> {
+    let mut packet = ID.to_vec();
+    packet.extend(op_code.to_le_bytes());
+    packet.extend(PROTOCOL_VERSION);
+    packet
+}
+
+// The output ports of a node that answered an ArtPoll
+pub fn parse_poll_reply(packet: &[u8]) -> Option<Vec<Device>> {
+    if packet.len() < 194 || !packet.starts_with(ID) || u16::from_le_bytes([packet[8], packet[9]]) != OP_POLL_REPLY {
+        return None;
+    }
+    let address = Ipv4Addr::new(packet[10], packet[11], packet[12], packet[13]);
+    let name = text(&packet[44..108]);
+    let name = if name.is_empty() { text(&packet[26..44]) } else { name };
+    let net = packet[18] & 0x7f;
+    let sub_net = packet[19] & 0x0f;
+    let ports = (packet[173] as usize).min(4);
+    let devices = (0..ports)
+        .filter(|port| packet[174 + port] & PORT_OUTPUT != 0)
+        .map(|port| {
+            let universe = u16::from_be_bytes([net, sub_net << 4 | (packet[190 + port] & 0x0f)]);
+            Device {
+                interface: Interface::ArtNet { address, universe },
+                name: format!("{} port {}", name, port + 1),
+            }
+        })
+        .collect();
+    Some(devices)
+}
+
+fn text(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|byte| *byte == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
+// Sends ArtDmx to one node
+pub struct ArtNetOutput {
+    socket: UdpSocket,
+    target: SocketAddr,
+    universe: u16,
+    sequence: u8,
+}
+
+impl ArtNetOutput {
+    pub fn new(address: Ipv4Addr, universe: u16) -> Result<ArtNetOutput, OutputError> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|error| OutputError::Device(error.to_string()))?;
+        Ok(ArtNetOutput {
+            socket,
+            target: (address, ARTNET_PORT).into(),
+            universe,
+            sequence: 0,
+        })
+    }
+}
+
+impl DMXOutput for ArtNetOutput {
+    fn send(&mut self, universe: &DMXUniverse) -> Result<(), OutputError> {
+        // Sequence 0 disables reordering on the node, so it is skipped
+        self.sequence = self.sequence.checked_add(1).unwrap_or(1);
+        let packet = dmx_packet(self.universe, self.sequence, universe);
+        self.socket.send_to(&packet, self.target).map(|_| ()).map_err(|error| OutputError::Device(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::parse_artdmx;
+
+    #[test]
+    fn poll_replies_list_output_ports() {
+        let mut reply = header(OP_POLL_REPLY);
+        reply.truncate(10);
+        reply.resize(239, 0);
+        reply[10..14].copy_from_slice(&[192, 168, 1, 20]);
+        reply[18] = 1;
+        reply[19] = 2;
+        reply[26..30].copy_from_slice(b"Node");
+        reply[173] = 2;
+        reply[174..176].copy_from_slice(&[PORT_OUTPUT, PORT_OUTPUT]);
+        reply[190..192].copy_from_slice(&[3, 4]);
+        let devices = parse_poll_reply(&reply).unwrap();
+        assert_eq!(devices[1], Device {
+            interface: Interface::ArtNet { address: Ipv4Addr::new(192, 168, 1, 20), universe: 0x0124 },
+            name: "Node port 2".into(),
+        });
+
+        let mut data = DMXUniverse::new();
+        data.channels[0] = 42;
+        assert_eq!(parse_artdmx(&dmx_packet(0x0124, 1, &data)), Some((0x0124, data)));
+    }
+}
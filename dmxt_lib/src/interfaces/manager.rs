@@ -0,0 +1,359 @@
+use crate::dmx::DMXUniverse;
+use crate::input::{sacn_multicast, InputError, PortReceiver, ARTNET_PORT, SACN_PORT};
+use crate::interfaces::{artnet, sacn, serial_ports, Device, Interface, InterfaceMapping};
+use crate::output::{send_guarded, DMXOutput, OutputEngine, OutputError};
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use open_dmx::DMXSerial;
+
+const REFRESH: Duration = Duration::from_secs(1);
+// Network devices that stop answering for this long are removed
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SOURCE_NAME: &str = "DMXT";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterfaceStatus {
+    Connected,
+    // The device is not there, it is connected as soon as it shows up
+    Waiting,
+    Failed(String),
+}
+
+impl std::fmt::Display for InterfaceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterfaceStatus::Connected => write!(f, "connected"),
+            InterfaceStatus::Waiting => write!(f, "waiting for the device"),
+            InterfaceStatus::Failed(error) => write!(f, "failed: {}", error),
+        }
+    }
+}
+
+struct Connection {
+    mapping: InterfaceMapping,
+    device: Option<Box<dyn DMXOutput>>,
+    status: InterfaceStatus,
+    // The device is taken out by a router that is sending to it
+    sending: bool,
+}
+
+type Connections = Arc<Mutex<Vec<Connection>>>;
+
+// Output of one engine universe, forwards it to every interface mapped to the universe
+struct Router {
+    universe: usize,
+    connections: Connections,
+}
+
+impl DMXOutput for Router {
+    fn send(&mut self, universe: &DMXUniverse) -> Result<(), OutputError> {
+        // Devices are sent to without holding the lock, a slow or panicking one can't block or poison the others
+        let devices: Vec<(Interface, Box<dyn DMXOutput>)> = self.connections.lock().unwrap().iter_mut()
+            .filter(|connection| connection.mapping.universe == self.universe)
+            .filter_map(|connection| {
+                let device = connection.device.take()?;
+                connection.sending = true;
+                Some((connection.mapping.interface.clone(), device))
+            })
+            .collect();
+        let mut result = Ok(());
+        for (interface, mut device) in devices {
+            let sent = send_guarded(device.as_mut(), universe);
+            let mut connections = self.connections.lock().unwrap();
+            // Interfaces that were unmapped in the meantime are closed by dropping the device
+            let Some(connection) = connections.iter_mut().find(|connection| connection.sending && connection.mapping.interface == interface) else {
+                continue;
+            };
+            connection.sending = false;
+            match sent {
+                Ok(()) => connection.device = Some(device),
+                Err(error) => {
+                    // Dropping the device closes it, `refresh` opens it again
+                    connection.status = InterfaceStatus::Failed(error.to_string());
+                    result = Err(error);
+                },
+            }
+        }
+        result
+    }
+}
+
+struct Discovery {
+    artnet: PortReceiver,
+    sacn: PortReceiver,
+}
+
+// Finds interfaces, connects the mapped ones to the output engine and reconnects them
+// when they come back after a disconnect
+pub struct InterfaceManager {
+    connections: Connections,
+    serial: Vec<Device>,
+    network: HashMap<Interface, (Device, Instant)>,
+    discovery: Option<Discovery>,
+    cid: [u8; 16],
+    // Universes of the engine that have a router
+    attached: usize,
+    last_refresh: Option<Instant>,
+}
+
+impl InterfaceManager {
+    pub fn new() -> InterfaceManager {
+        InterfaceManager {
+            connections: Arc::new(Mutex::new(Vec::new())),
+            serial: Vec::new(),
+            network: HashMap::new(),
+            discovery: None,
+            cid: sacn::cid(),
+            attached: 0,
+            last_refresh: None,
+        }
+    }
+
+    pub fn mappings(&self) -> Vec<InterfaceMapping> {
+        self.connections.lock().unwrap().iter().map(|connection| connection.mapping.clone()).collect()
+    }
+
+    pub fn statuses(&self) -> Vec<(InterfaceMapping, InterfaceStatus)> {
+        self.connections.lock().unwrap().iter().map(|connection| (connection.mapping.clone(), connection.status.clone())).collect()
+    }
+
+    pub fn map(&mut self, mapping: InterfaceMapping) {
+        let mut connection = Connection {
+            mapping,
+            device: None,
+            status: InterfaceStatus::Waiting,
+            sending: false,
+        };
+        self.connect(&mut connection);
+        self.connections.lock().unwrap().push(connection);
+    }
+
+    pub fn unmap(&mut self, index: usize) -> Option<InterfaceMapping> {
+        let mut connections = self.connections.lock().unwrap();
+        (index < connections.len()).then(|| connections.remove(index).mapping)
+    }
+
+    pub fn set_universe(&mut self, index: usize, universe: usize) {
+        if let Some(connection) = self.connections.lock().unwrap().get_mut(index) {
+            connection.mapping.universe = universe;
+        }
+    }
+
+    // Replaces all mappings, e.g. with the ones of a show file. Interfaces that stay mapped keep their connection
+    pub fn set_mappings(&mut self, mappings: Vec<InterfaceMapping>) {
+        let mut previous = std::mem::take(&mut *self.connections.lock().unwrap());
+        self.serial = serial_ports();
+        for mapping in mappings {
+            match previous.iter().position(|connection| connection.mapping.interface == mapping.interface) {
+                Some(index) => {
+                    let mut connection = previous.remove(index);
+                    connection.mapping.universe = mapping.universe;
+                    self.connections.lock().unwrap().push(connection);
+                },
+                None => self.map(mapping),
+            }
+        }
+    }
+
+    // Serial ports and, while discovering, the network devices that answered
+    pub fn devices(&self) -> Vec<Device> {
+        let mut network: Vec<Device> = self.network.values().map(|(device, _)| device.clone()).collect();
+        network.sort_by(|a, b| a.name.cmp(&b.name));
+        self.serial.iter().cloned().chain(network).collect()
+    }
+
+    pub fn is_discovering(&self) -> bool {
+        self.discovery.is_some()
+    }
+
+    // Listens for Art-Net nodes and sACN sources until `stop_discovery`
+    pub fn start_discovery(&mut self) -> Result<(), InputError> {
+        let artnet = PortReceiver::bind(ARTNET_PORT)?;
+        let sacn = PortReceiver::bind(SACN_PORT)?;
+        sacn.join_multicast(sacn_multicast(sacn::DISCOVERY_UNIVERSE))?;
+        self.discovery = Some(Discovery { artnet, sacn });
+        self.last_refresh = None;
+        Ok(())
+    }
+
+    pub fn stop_discovery(&mut self) {
+        self.discovery = None;
+        self.network.clear();
+    }
+
+    // Gives every universe of the engine a router and refreshes about once a second.
+    // Call it regularly, e.g. once per UI frame
+    pub fn poll(&mut self, engine: &mut OutputEngine) {
+        for universe in self.attached..engine.universe_count() {
+            let router = Router {
+                universe,
+                connections: self.connections.clone(),
+            };
+            if engine.add_output(universe, Box::new(router)).is_ok() {
+                self.attached = universe + 1;
+            }
+        }
+        if self.last_refresh.is_none_or(|last| last.elapsed() >= REFRESH) {
+            self.refresh();
+        }
+    }
+
+    pub fn refresh(&mut self) {
+        self.last_refresh = Some(Instant::now());
+        self.serial = serial_ports();
+        if let Some(discovery) = &self.discovery {
+            let now = Instant::now();
+            while let Some((packet, _)) = discovery.artnet.try_recv() {
+                for device in artnet::parse_poll_reply(&packet).into_iter().flatten() {
+                    self.network.insert(device.interface.clone(), (device, now));
+                }
+            }
+            while let Some((packet, _)) = discovery.sacn.try_recv() {
+                for device in sacn::parse_discovery(&packet).into_iter().flatten() {
+                    self.network.insert(device.interface.clone(), (device, now));
+                }
+            }
+            let _ = discovery.artnet.send_to(&artnet::poll_packet(), (Ipv4Addr::BROADCAST, ARTNET_PORT));
+            self.network.retain(|_, (_, seen)| seen.elapsed() < TIMEOUT);
+        }
+
+        let waiting: Vec<InterfaceMapping> = {
+            let mut connections = self.connections.lock().unwrap();
+            for connection in connections.iter_mut() {
+                let unplugged = matches!(connection.mapping.interface, Interface::Serial { .. }) && self.find_serial(&connection.mapping.interface).is_none();
+                if connection.device.is_some() && unplugged {
+                    connection.device = None;
+                    connection.status = InterfaceStatus::Waiting;
+                }
+            }
+            connections.iter()
+                .filter(|connection| connection.device.is_none() && !connection.sending)
+                .map(|connection| connection.mapping.clone())
+                .collect()
+        };
+        // Devices are opened without holding the lock, the routers keep sending to the connected ones
+        for mapping in waiting {
+            let mut connection = Connection {
+                mapping: mapping.clone(),
+                device: None,
+                status: InterfaceStatus::Waiting,
+                sending: false,
+            };
+            self.connect(&mut connection);
+            let mut connections = self.connections.lock().unwrap();
+            if let Some(waiting) = connections.iter_mut().find(|waiting| waiting.device.is_none() && !waiting.sending && waiting.mapping == mapping) {
+                *waiting = connection;
+            }
+        }
+    }
+
+    fn find_serial(&self, interface: &Interface) -> Option<&Device> {
+        let Interface::Serial { port, serial_number } = interface else {
+            return None;
+        };
+        self.serial.iter().find(|device| match &device.interface {
+            Interface::Serial { serial_number: Some(found), .. } if serial_number.is_some() => Some(found) == serial_number.as_ref(),
+            Interface::Serial { port: found, .. } => found == port,
+            _ => false,
+        })
+    }
+
+    fn connect(&self, connection: &mut Connection) {
+        // USB interfaces are only opened while they are plugged in, possibly on another port
+        if let Interface::Serial { .. } = connection.mapping.interface {
+            match self.find_serial(&connection.mapping.interface) {
+                Some(device) => connection.mapping.interface = device.interface.clone(),
+                None => {
+                    connection.status = InterfaceStatus::Waiting;
+                    return;
+                },
+            }
+        }
+        match open(&connection.mapping.interface, self.cid) {
+            Ok(device) => {
+                connection.device = Some(device);
+                connection.status = InterfaceStatus::Connected;
+            },
+            Err(error) => connection.status = InterfaceStatus::Failed(error.to_string()),
+        }
+    }
+}
+
+fn open(interface: &Interface, cid: [u8; 16]) -> Result<Box<dyn DMXOutput>, OutputError> {
+    Ok(match interface {
+        Interface::Serial { port, .. } => {
+            let device = DMXSerial::open(port).map_err(|error| OutputError::Device(format!("{:?}", error)))?;
+            Box::new(device)
+        },
+        Interface::ArtNet { address, universe } => Box::new(artnet::ArtNetOutput::new(*address, *universe)?),
+        Interface::Sacn { universe } => Box::new(sacn::SacnOutput::new(cid, SOURCE_NAME.into(), *universe)?),
+    })
+}
+
+impl Default for InterfaceManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for InterfaceManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InterfaceManager")
+        .field("mappings", &self.mappings())
+        .field("devices", &self.devices().len())
+        .field("discovering", &self.is_discovering())
+        .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_serial_devices_wait_for_reconnect() {
+        let mut engine = OutputEngine::new(2);
+        let mut manager = InterfaceManager::new();
+        let mapping = InterfaceMapping {
+            interface: Interface::Serial { port: "/dev/dmxt-missing".into(), serial_number: Some("DMXT0001".into()) },
+            universe: 1,
+        };
+        manager.set_mappings(vec![mapping.clone()]);
+        manager.poll(&mut engine);
+        assert_eq!(manager.statuses(), vec![(mapping.clone(), InterfaceStatus::Waiting)]);
+        // Nothing is connected, so there is nothing to fail
+        engine.send_frame().unwrap();
+        assert_eq!(manager.unmap(0), Some(mapping));
+        assert!(manager.mappings().is_empty());
+    }
+
+    struct Unplugged;
+
+    impl DMXOutput for Unplugged {
+        fn send(&mut self, _: &DMXUniverse) -> Result<(), OutputError> {
+            panic!("the port is gone");
+        }
+    }
+
+    #[test]
+    fn panicking_devices_fail_without_poisoning_the_connections() {
+        let manager = InterfaceManager::new();
+        let mapping = InterfaceMapping { interface: Interface::Sacn { universe: 1 }, universe: 0 };
+        manager.connections.lock().unwrap().push(Connection {
+            mapping: mapping.clone(),
+            device: Some(Box::new(Unplugged)),
+            status: InterfaceStatus::Connected,
+            sending: false,
+        });
+        let mut router = Router { universe: 0, connections: manager.connections.clone() };
+        assert!(matches!(router.send(&DMXUniverse::new()), Err(OutputError::Device(_))));
+        assert!(matches!(&manager.statuses()[..], [(failed, InterfaceStatus::Failed(_))] if *failed == mapping));
+        // The device is closed and opened again by the next refresh
+        let connections = manager.connections.lock().unwrap();
+        assert!(connections[0].device.is_none() && !connections[0].sending);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::dmx::{DMXUniverse, DMX_CHANNELS};
+use crate::input::{sacn_multicast, SACN_PORT};
+use crate::interfaces::{Device, Interface};
+use crate::output::{DMXOutput, OutputError};
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::net::{Ipv4Addr, UdpSocket};
+
+// Universe the universe discovery packets are sent on
+pub const DISCOVERY_UNIVERSE: u16 = 64214;
+pub const DEFAULT_PRIORITY: u8 = 100;
+
+const ID: &[u8] = b"ASC-E1.17\0\0\0";
+const ROOT_DATA: u32 = 0x0000_0004;
+const ROOT_EXTENDED: u32 = 0x0000_0008;
+const FRAMING_DATA: u32 = 0x0000_0002;
+const FRAMING_DISCOVERY: u32 = 0x0000_0002;
+const DISCOVERY_LIST: u32 = 0x0000_0001;
+const SOURCE_NAME_LENGTH: usize = 64;
+
+pub fn data_packet(cid: &[u8; 16], source_name: &str, universe: u16, sequence: u8, data: &DMXUniverse) -> Vec<u8> {
+    let length = 126 + DMX_CHANNELS;
+    let mut packet = Vec::with_capacity(length);
+    // Root layer
+    packet.extend([0x00, 0x10, 0x00, 0x00]);
+    packet.extend(ID);
+    packet.extend(flags_and_length(length - 16));
+    packet.extend(ROOT_DATA.to_be_bytes());
+    packet.extend(cid);
+    // Framing layer
+    packet.extend(flags_and_length(length - 38));
+    packet.extend(FRAMING_DATA.to_be_bytes());
+    let mut name = source_name.as_bytes().to_vec();
+    name.resize(SOURCE_NAME_LENGTH - 1, 0);
+    name.push(0);
+    packet.extend(name);
+    packet.extend([DEFAULT_PRIORITY, 0, 0, sequence, 0]);
+    packet.extend(universe.to_be_bytes());
+    // DMP layer, the property values are the start code and the channels
+    packet.extend(flags_and_length(length - 115));
+    packet.extend([0x02, 0xa1, 0x00, 0x00, 0x00, 0x01]);
+    packet.extend((DMX_CHANNELS as u16 + 1).to_be_bytes());
+    packet.push(0);
+    packet.extend(data.channels);
+    packet
+}
+
+fn flags_and_length(length: usize) -> [u8; 2] {
+    (0x7000 | length as u16).to_be_bytes()
+}
+
+// Source name and universes of a universe discovery packet
+pub fn parse_discovery(packet: &[u8]) -> Option<Vec<Device>> {
+    if packet.len() < 120 || packet.get(4..16)? != ID || !vector(packet, 18, ROOT_EXTENDED) || !vector(packet, 40, FRAMING_DISCOVERY) || !vector(packet, 114, DISCOVERY_LIST) {
+        return None;
+    }
+    let name_bytes = &packet[44..44 + SOURCE_NAME_LENGTH];
+    let end = name_bytes.iter().position(|byte| *byte == 0).unwrap_or(SOURCE_NAME_LENGTH);
+    let name = String::from_utf8_lossy(&name_bytes[..end]).to_string();
+    let layer_length = (u16::from_be_bytes([packet[112], packet[113]]) & 0x0fff) as usize;
+    let end = (112 + layer_length).min(packet.len());
+    let devices = packet.get(120..end)?
+        .chunks_exact(2)
+        .map(|universe| Device {
+            interface: Interface::Sacn { universe: u16::from_be_bytes([universe[0], universe[1]]) },
+            name: name.clone(),
+        })
+        .collect();
+    Some(devices)
+}
+
+fn vector(packet: &[u8], at: usize, expected: u32) -> bool {
+    packet.get(at..at + 4) == Some(&expected.to_be_bytes()[..])
+}
+
+// Component identifier of this program, stays the same while it runs
+pub fn cid() -> [u8; 16] {
+    let mut cid = [0; 16];
+    for (index, half) in cid.chunks_exact_mut(8).enumerate() {
+        let mut hasher = DefaultHasher::new();
+        (std::process::id(), index, std::time::SystemTime::now()).hash(&mut hasher);
+        half.copy_from_slice(&hasher.finish().to_be_bytes());
+    }
+    cid
+}
+
+// Sends a universe to its multicast group
+pub struct SacnOutput {
+    socket: UdpSocket,
+    cid: [u8; 16],
+    source_name: String,
+    universe: u16,
+    sequence: u8,
+}
+
+impl SacnOutput {
+    pub fn new(cid: [u8; 16], source_name: String, universe: u16) -> Result<SacnOutput, OutputError> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|error| OutputError::Device(error.to_string()))?;
+        Ok(SacnOutput {
+            socket,
+            cid,
+            source_name,
+            universe,
+            sequence: 0,
+        })
+    }
+}
+
+impl DMXOutput for SacnOutput {
+    fn send(&mut self, universe: &DMXUniverse) -> Result<(), OutputError> {
+        self.sequence = self.sequence.wrapping_add(1);
+        let packet = data_packet(&self.cid, &self.source_name, self.universe, self.sequence, universe);
+        self.socket.send_to(&packet, (sacn_multicast(self.universe), SACN_PORT))
+            .map(|_| ())
+            .map_err(|error| OutputError::Device(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::parse_sacn;
+
+    #[test]
+    fn packets_round_trip() {
+        let mut data = DMXUniverse::new();
+        data.channels[511] = 7;
+        let packet = data_packet(&[1; 16], "DMXT", 3, 9, &data);
+        assert_eq!(packet.len(), 638);
+        assert_eq!(parse_sacn(&packet), Some((3, data)));
+
+        let mut discovery = packet[..120].to_vec();
+        discovery[18..22].copy_from_slice(&ROOT_EXTENDED.to_be_bytes());
+        discovery[112..114].copy_from_slice(&flags_and_length(12));
+        discovery[114..118].copy_from_slice(&DISCOVERY_LIST.to_be_bytes());
+        discovery.extend([0, 1, 0, 5]);
+        let universes: Vec<Interface> = parse_discovery(&discovery).unwrap().into_iter().map(|device| device.interface).collect();
+        assert_eq!(universes, vec![Interface::Sacn { universe: 1 }, Interface::Sacn { universe: 5 }]);
+    }
+}
@@ -4,6 +4,27 @@ pub mod timing;
 pub mod builders;
 pub mod macros;
 pub mod components;
+pub mod effects;
+pub mod formats;
+pub mod output;
+pub mod patch;
+pub mod input;
+pub mod interfaces;
+pub mod show;
+pub mod history;
+pub mod preferences;
+pub mod assets;
+pub mod import;
+pub mod library;
+pub mod scripting;
+pub mod recording;
+pub mod timeline;
+pub mod render;
+pub mod stage;
+pub mod visualizer;
+
+#[cfg(test)]
+mod test_support;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,71 @@
+use crate::builders::fixture::FixtureModel;
+
+use std::path::{Path, PathBuf};
+
+// How deep fixture configs are searched in the library folders, e.g. `<manufacturer>/<fixture>/fixture_config.json`
+const SEARCH_DEPTH: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct LibraryEntry {
+    pub path: PathBuf,
+    pub model: FixtureModel,
+}
+
+impl LibraryEntry {
+    pub fn manufacturer(&self) -> &str {
+        &self.model.manufacturer
+    }
+
+    pub fn name(&self) -> &str {
+        self.model.name.name()
+    }
+}
+
+// Fixture configs found in the library folders of the preferences
+#[derive(Debug, Clone, Default)]
+pub struct FixtureLibrary {
+    entries: Vec<LibraryEntry>,
+}
+
+impl FixtureLibrary {
+    // Files that aren't fixture configs are skipped
+    pub fn scan(folders: &[PathBuf]) -> FixtureLibrary {
+        let mut entries = Vec::new();
+        for folder in folders {
+            scan(folder, SEARCH_DEPTH, &mut entries);
+        }
+        entries.sort_by_key(|entry| (entry.manufacturer().to_lowercase(), entry.name().to_lowercase()));
+        FixtureLibrary { entries }
+    }
+
+    pub fn entries(&self) -> &[LibraryEntry] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Case insensitive, like people type them into spreadsheets
+    pub fn find(&self, manufacturer: &str, model: &str) -> Option<&LibraryEntry> {
+        self.entries.iter().find(|entry| {
+            entry.manufacturer().eq_ignore_ascii_case(manufacturer.trim()) && entry.name().eq_ignore_ascii_case(model.trim())
+        })
+    }
+}
+
+fn scan(folder: &Path, depth: usize, entries: &mut Vec<LibraryEntry>) {
+    let Ok(directory) = std::fs::read_dir(folder) else {
+        return;
+    };
+    for path in directory.flatten().map(|entry| entry.path()) {
+        if path.is_dir() && depth > 0 {
+            scan(&path, depth - 1, entries);
+        } else if path.extension().is_some_and(|extension| extension == "json") {
+            let model = std::fs::read_to_string(&path).ok().and_then(|json| serde_json::from_str(&json).ok());
+            if let Some(model) = model {
+                entries.push(LibraryEntry { path, model });
+            }
+        }
+    }
+}
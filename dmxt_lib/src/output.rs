@@ -0,0 +1,282 @@
+use crate::dmx::DMXUniverse;
+use crate::recording::RecorderHandle;
+use crate::threads::shared::Lock;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time;
+
+use open_dmx::DMXSerial;
+
+pub const DEFAULT_FRAME_RATE: f64 = 44.0;
+
+// Anything that can put a universe on the wire
+pub trait DMXOutput: Send {
+    fn send(&mut self, universe: &DMXUniverse) -> Result<(), OutputError>;
+}
+
+impl DMXOutput for DMXSerial {
+    fn send(&mut self, universe: &DMXUniverse) -> Result<(), OutputError> {
+        self.set_channels(universe.channels);
+        // open_dmx panics instead of returning an error once the port is gone, e.g. when the interface is unplugged
+        panic::catch_unwind(AssertUnwindSafe(|| self.update()))
+            .map_err(|_| OutputError::Device(String::from("the interface stopped responding")))
+    }
+}
+
+// Sends a universe and turns a panicking output into an error, so that it doesn't take down the frame loop
+pub(crate) fn send_guarded(output: &mut dyn DMXOutput, universe: &DMXUniverse) -> Result<(), OutputError> {
+    panic::catch_unwind(AssertUnwindSafe(|| output.send(universe)))
+        .unwrap_or_else(|_| Err(OutputError::Device(String::from("the output panicked"))))
+}
+
+#[derive(Default)]
+struct OutputList {
+    outputs: Vec<(usize, Box<dyn DMXOutput>)>,
+    // Counts `clear_outputs` calls, outputs taken out for sending before a clear aren't put back
+    cleared: u64,
+}
+
+type Outputs = Arc<Mutex<OutputList>>;
+
+// Holds the universes and sends them to their outputs at a fixed frame rate.
+// Everything that changes DMX values writes into `universes`, the engine only reads them
+pub struct OutputEngine {
+    universes: Lock<Vec<DMXUniverse>>,
+    outputs: Outputs,
+    frame_rate: Lock<f64>,
+    recorder: RecorderHandle,
+    // Stops the frame thread, which is joined so that no frame goes out after `stop`
+    stop: Option<(mpsc::Sender<()>, thread::JoinHandle<()>)>,
+}
+
+impl OutputEngine {
+    pub fn new(universes: usize) -> OutputEngine {
+        OutputEngine {
+            universes: Lock::new(vec![DMXUniverse::new(); universes]),
+            outputs: Arc::new(Mutex::new(OutputList::default())),
+            frame_rate: Lock::new(DEFAULT_FRAME_RATE),
+            recorder: RecorderHandle::default(),
+            stop: None,
+        }
+    }
+
+    // Shared handle to the universes, writes show up in the next frame
+    pub fn universes(&self) -> Lock<Vec<DMXUniverse>> {
+        self.universes.clone()
+    }
+
+    pub fn universe_count(&self) -> usize {
+        self.universes.read().unwrap().len()
+    }
+
+    pub fn add_output(&mut self, universe: usize, output: Box<dyn DMXOutput>) -> Result<(), OutputError> {
+        if universe >= self.universe_count() {
+            return Err(OutputError::UnknownUniverse(universe));
+        }
+        self.outputs.lock().unwrap().outputs.push((universe, output));
+        Ok(())
+    }
+
+    pub fn clear_outputs(&mut self) {
+        let mut outputs = self.outputs.lock().unwrap();
+        outputs.outputs.clear();
+        outputs.cleared += 1;
+    }
+
+    pub fn set_frame_rate(&mut self, frame_rate: f64) {
+        self.frame_rate.write().unwrap().clone_from(&frame_rate.max(1.0));
+    }
+
+    pub fn frame_rate(&self) -> f64 {
+        *self.frame_rate.read().unwrap()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.stop.is_some()
+    }
+
+    // Records every frame that is sent
+    pub fn recorder(&self) -> RecorderHandle {
+        self.recorder.clone()
+    }
+
+    // Sends every universe once, outputs that fail are still tried on the next frame
+    pub fn send_frame(&self) -> Result<(), OutputError> {
+        send_frame(&self.universes, &self.outputs, &self.recorder)
+    }
+
+    pub fn blackout(&self) -> Result<(), OutputError> {
+        self.universes.write().unwrap().iter_mut().for_each(DMXUniverse::clear);
+        self.send_frame()
+    }
+
+    pub fn start(&mut self) -> Result<(), OutputError> {
+        if self.stop.is_some() {
+            return Err(OutputError::AlreadyStarted);
+        }
+        let (tx, rx) = mpsc::channel();
+        let universes = self.universes.clone();
+        let outputs = self.outputs.clone();
+        let recorder = self.recorder.clone();
+        let frame_rate = self.frame_rate.read_only();
+        let handle = thread::spawn(move || {
+            loop {
+                match rx.try_recv() {
+                    Ok(()) | Err(mpsc::TryRecvError::Disconnected) => break,
+                    Err(mpsc::TryRecvError::Empty) => {},
+                }
+                let started = time::Instant::now();
+                // A failing device must not stop the other outputs
+                let _ = send_frame(&universes, &outputs, &recorder);
+                let frame = time::Duration::from_secs_f64(1.0 / *frame_rate.read().unwrap());
+                thread::sleep(frame.saturating_sub(started.elapsed()));
+            }
+        });
+        self.stop = Some((tx, handle));
+        Ok(())
+    }
+
+    // Returns once the last frame is sent
+    pub fn stop(&mut self) -> Result<(), OutputError> {
+        match self.stop.take() {
+            Some((tx, handle)) => {
+                let _ = tx.send(());
+                let _ = handle.join();
+                Ok(())
+            },
+            None => Err(OutputError::AlreadyStopped),
+        }
+    }
+}
+
+// One universe until interfaces are configured
+impl Default for OutputEngine {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl std::fmt::Debug for OutputEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutputEngine")
+        .field("universes", &self.universe_count())
+        .field("outputs", &self.outputs.lock().unwrap().outputs.len())
+        .field("running", &self.is_running())
+        .field("recording", &self.recorder.is_recording())
+        .finish()
+    }
+}
+
+impl Drop for OutputEngine {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+fn send_frame(universes: &Lock<Vec<DMXUniverse>>, outputs: &Outputs, recorder: &RecorderHandle) -> Result<(), OutputError> {
+    // Copy the universes first so writers aren't blocked by slow devices
+    let universes = universes.read().unwrap().clone();
+    recorder.capture(&universes);
+    // The outputs are taken out while they send, slow devices don't block `add_output` and panics can't poison the lock
+    let (mut sending, cleared) = {
+        let mut outputs = outputs.lock().unwrap();
+        (std::mem::take(&mut outputs.outputs), outputs.cleared)
+    };
+    let mut result = Ok(());
+    for (universe, output) in sending.iter_mut() {
+        // Outputs of universes that were removed in the meantime are skipped
+        let Some(universe) = universes.get(*universe) else {
+            continue;
+        };
+        if let Err(error) = send_guarded(output.as_mut(), universe) {
+            result = Err(error);
+        }
+    }
+    let mut outputs = outputs.lock().unwrap();
+    if outputs.cleared == cleared {
+        // Outputs added while sending go after the others
+        sending.append(&mut outputs.outputs);
+        outputs.outputs = sending;
+    }
+    result
+}
+
+#[derive(Debug)]
+pub enum OutputError {
+    AlreadyStarted,
+    AlreadyStopped,
+    UnknownUniverse(usize),
+    Device(String),
+}
+
+impl std::fmt::Display for OutputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputError::AlreadyStarted => write!(f, "the output engine is already running"),
+            OutputError::AlreadyStopped => write!(f, "the output engine is not running"),
+            OutputError::UnknownUniverse(universe) => write!(f, "universe {} does not exist", universe + 1),
+            OutputError::Device(error) => write!(f, "output device failed: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for OutputError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dmx::Channel;
+
+    struct Recorder(Arc<Mutex<Vec<DMXUniverse>>>);
+
+    impl DMXOutput for Recorder {
+        fn send(&mut self, universe: &DMXUniverse) -> Result<(), OutputError> {
+            self.0.lock().unwrap().push(*universe);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn frames_reach_the_output_of_their_universe() {
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = OutputEngine::new(2);
+        engine.add_output(1, Box::new(Recorder(frames.clone()))).unwrap();
+        assert!(matches!(engine.add_output(2, Box::new(Recorder(frames.clone()))), Err(OutputError::UnknownUniverse(2))));
+
+        engine.universes().write().unwrap()[1].set(Channel::new(3).unwrap(), 200).unwrap();
+        engine.send_frame().unwrap();
+        engine.blackout().unwrap();
+
+        let frames = frames.lock().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].get(Channel::new(3).unwrap()).unwrap(), 200);
+        assert_eq!(frames[1], DMXUniverse::new());
+    }
+
+    struct Unplugged;
+
+    impl DMXOutput for Unplugged {
+        fn send(&mut self, _: &DMXUniverse) -> Result<(), OutputError> {
+            panic!("the port is gone");
+        }
+    }
+
+    #[test]
+    fn panicking_outputs_dont_stop_the_others() {
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = OutputEngine::new(1);
+        engine.add_output(0, Box::new(Unplugged)).unwrap();
+        engine.add_output(0, Box::new(Recorder(frames.clone()))).unwrap();
+        assert!(matches!(engine.send_frame(), Err(OutputError::Device(_))));
+        // The outputs are still usable and the failing one is tried again
+        engine.add_output(0, Box::new(Recorder(frames.clone()))).unwrap();
+        assert!(matches!(engine.send_frame(), Err(OutputError::Device(_))));
+        assert_eq!(frames.lock().unwrap().len(), 3);
+
+        engine.clear_outputs();
+        engine.send_frame().unwrap();
+        assert_eq!(frames.lock().unwrap().len(), 3);
+    }
+}
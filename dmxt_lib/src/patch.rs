@@ -0,0 +1,268 @@
+use crate::builders::validation::channel_usages;
+use crate::components::Fixture;
+use crate::dmx::{Channel, DMX_CHANNELS};
+use crate::history::Command;
+
+use serde::{Serialize, Deserialize};
+
+// Paths
+pub mod sheet;
+
+// The fixtures of a show and where they sit in the universes
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Patch {
+    fixtures: Vec<Fixture>,
+}
+
+impl Patch {
+    pub fn new() -> Patch {
+        Patch::default()
+    }
+
+    pub fn fixtures(&self) -> &[Fixture] {
+        &self.fixtures
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Fixture> {
+        self.fixtures.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.fixtures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fixtures.is_empty()
+    }
+
+    pub fn add(&mut self, fixture: Fixture) -> Result<usize, PatchError> {
+        self.check(&fixture, None)?;
+        self.fixtures.push(fixture);
+        Ok(self.fixtures.len() - 1)
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Fixture> {
+        (index < self.fixtures.len()).then(|| self.fixtures.remove(index))
+    }
+
+    // Replaces a patched fixture, e.g. after moving it to another address
+    pub fn update(&mut self, index: usize, fixture: Fixture) -> Result<(), PatchError> {
+        if index >= self.fixtures.len() {
+            return Err(PatchError::UnknownFixture(index));
+        }
+        self.check(&fixture, Some(index))?;
+        self.fixtures[index] = fixture;
+        Ok(())
+    }
+
+    // Indices of the fixtures that share channels with `fixture`
+    pub fn collisions(&self, fixture: &Fixture) -> Vec<usize> {
+        let (start, end) = fixture.channels();
+        self.fixtures.iter().enumerate()
+            .filter(|(_, other)| other.universe == fixture.universe)
+            .filter(|(_, other)| {
+                let (other_start, other_end) = other.channels();
+                start <= other_end && other_start <= end
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    // First address in the universe with `count` unused channels
+    pub fn next_free(&self, universe: usize, count: u16) -> Option<Channel> {
+        let mut used: Vec<(u16, u16)> = self.fixtures.iter()
+            .filter(|fixture| fixture.universe == universe)
+            .map(Fixture::channels)
+            .collect();
+        used.sort();
+        let mut start = 1;
+        for (used_start, used_end) in used {
+            if start + count <= used_start {
+                break;
+            }
+            start = start.max(used_end + 1);
+        }
+        if start + count.max(1) - 1 > DMX_CHANNELS as u16 {
+            return None;
+        }
+        Channel::new(start).ok()
+    }
+
+    // What the channels of a universe do, indexed like `DMXUniverse::channels`.
+    // A channel can have several labels, one per value range
+    pub fn channel_labels(&self, universe: usize) -> Vec<Vec<ChannelLabel>> {
+        let mut labels = vec![Vec::new(); DMX_CHANNELS];
+        for fixture in self.fixtures.iter().filter(|fixture| fixture.universe == universe) {
+            let Some(mode) = fixture.channel_mode() else {
+                continue;
+            };
+            for usage in channel_usages(mode) {
+                let Ok(index) = usage.channel.absolute(fixture.address.channel).and_then(|channel| channel.index()) else {
+                    continue;
+                };
+                labels[index].push(ChannelLabel {
+                    fixture: fixture.name.clone(),
+                    function: usage.function,
+                    start: usage.start,
+                    end: usage.end,
+                });
+            }
+        }
+        labels
+    }
+
+    fn check(&self, fixture: &Fixture, ignore: Option<usize>) -> Result<(), PatchError> {
+        if fixture.channel_mode().is_none() {
+            return Err(PatchError::UnknownMode(fixture.mode));
+        }
+        if fixture.channels().1 > DMX_CHANNELS as u16 {
+            return Err(PatchError::OutsideUniverse);
+        }
+        match self.collisions(fixture).into_iter().find(|index| Some(*index) != ignore) {
+            Some(index) => Err(PatchError::Collision(index)),
+            None => Ok(()),
+        }
+    }
+}
+
+// Undoable change of the patch
+#[derive(Debug, Clone)]
+pub enum PatchCommand {
+    Add { fixture: Fixture, index: Option<usize> },
+    Remove { index: usize, fixture: Option<Fixture> },
+    Update { index: usize, fixture: Fixture, previous: Option<Box<Fixture>> },
+}
+
+impl PatchCommand {
+    pub fn add(fixture: Fixture) -> PatchCommand {
+        PatchCommand::Add { fixture, index: None }
+    }
+
+    pub fn remove(index: usize) -> PatchCommand {
+        PatchCommand::Remove { index, fixture: None }
+    }
+
+    pub fn update(index: usize, fixture: Fixture) -> PatchCommand {
+        PatchCommand::Update { index, fixture, previous: None }
+    }
+}
+
+impl Command for PatchCommand {
+    type Target = Patch;
+    type Error = PatchError;
+
+    fn apply(&mut self, patch: &mut Patch) -> Result<(), PatchError> {
+        match self {
+            PatchCommand::Add { fixture, index } => *index = Some(patch.add(fixture.clone())?),
+            PatchCommand::Remove { index, fixture } => *fixture = Some(patch.remove(*index).ok_or(PatchError::UnknownFixture(*index))?),
+            PatchCommand::Update { index, fixture, previous } => {
+                *previous = patch.get(*index).cloned().map(Box::new);
+                patch.update(*index, fixture.clone())?;
+            },
+        }
+        Ok(())
+    }
+
+    fn revert(&mut self, patch: &mut Patch) {
+        match self {
+            PatchCommand::Add { index: Some(index), .. } => {
+                patch.remove(*index);
+            },
+            PatchCommand::Remove { index, fixture: Some(fixture) } => patch.fixtures.insert(*index, fixture.clone()),
+            PatchCommand::Update { index, previous: Some(previous), .. } => patch.fixtures[*index] = (**previous).clone(),
+            _ => {},
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            PatchCommand::Add { fixture, .. } => format!("Patch {}", fixture.name),
+            PatchCommand::Remove { fixture, index } => match fixture {
+                Some(fixture) => format!("Remove {}", fixture.name),
+                None => format!("Remove fixture {}", index + 1),
+            },
+            PatchCommand::Update { fixture, .. } => format!("Edit {}", fixture.name),
+        }
+    }
+
+    fn size(&self) -> usize {
+        let fixtures = match self {
+            PatchCommand::Add { fixture, .. } => vec![fixture],
+            PatchCommand::Remove { fixture, .. } => fixture.iter().collect(),
+            PatchCommand::Update { fixture, previous, .. } => std::iter::once(fixture).chain(previous.as_deref()).collect(),
+        };
+        // Models are the bulk of a fixture, their JSON size is close enough
+        std::mem::size_of::<Self>() + fixtures.iter()
+            .map(|fixture| serde_json::to_vec(fixture.model()).map_or(0, |json| json.len()))
+            .sum::<usize>()
+    }
+
+    fn merge(&mut self, next: &Self) -> bool {
+        match (self, next) {
+            (PatchCommand::Update { index, fixture, .. }, PatchCommand::Update { index: next_index, fixture: next_fixture, .. }) if index == next_index => {
+                *fixture = next_fixture.clone();
+                true
+            },
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelLabel {
+    pub fixture: String,
+    pub function: String,
+    pub start: u8,
+    pub end: u8,
+}
+
+impl ChannelLabel {
+    pub fn contains(&self, value: u8) -> bool {
+        self.start <= value && value <= self.end
+    }
+}
+
+impl std::fmt::Display for ChannelLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.fixture, self.function)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchError {
+    UnknownFixture(usize),
+    UnknownMode(usize),
+    OutsideUniverse,
+    // Index of the patched fixture that already uses the channels
+    Collision(usize),
+}
+
+impl std::fmt::Display for PatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchError::UnknownFixture(index) => write!(f, "fixture {} is not patched", index + 1),
+            PatchError::UnknownMode(mode) => write!(f, "the fixture has no channel mode {}", mode + 1),
+            PatchError::OutsideUniverse => write!(f, "the fixture does not fit into the universe"),
+            PatchError::Collision(index) => write!(f, "the channels are used by fixture {}", index + 1),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_fixture;
+
+    #[test]
+    fn overlapping_fixtures_are_rejected() {
+        let mut patch = Patch::new();
+        patch.add(test_fixture(4, 1)).unwrap();
+        patch.add(test_fixture(4, 9)).unwrap();
+        assert_eq!(patch.add(test_fixture(4, 3)), Err(PatchError::Collision(0)));
+        assert_eq!(patch.next_free(0, 4), Some(Channel::new(5).unwrap()));
+        assert_eq!(patch.next_free(0, 5), Some(Channel::new(13).unwrap()));
+        assert_eq!(patch.add(test_fixture(4, 510)), Err(PatchError::OutsideUniverse));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::components::Fixture;
+use crate::dmx::{Channel, DMXAddress};
+use crate::library::FixtureLibrary;
+use crate::patch::{Patch, PatchError};
+
+// Patch sheets as CSV, the way rigging teams pass them around in spreadsheets.
+// Universes and addresses start at 1, modes are written by name or number
+
+const HEADER: [&str; 7] = ["Name", "Manufacturer", "Model", "Mode", "Universe", "Address", "Position"];
+
+pub fn export(patch: &Patch) -> Result<String, SheetError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(HEADER)?;
+    for fixture in patch.fixtures() {
+        let model = fixture.model();
+        let mode = fixture.channel_mode()
+            .and_then(|mode| mode.name.as_ref())
+            .map_or((fixture.mode + 1).to_string(), |name| name.name().to_string());
+        writer.write_record([
+            fixture.name.as_str(),
+            &model.manufacturer,
+            model.name.name(),
+            &mode,
+            &(fixture.universe + 1).to_string(),
+            &fixture.address.channel.id().to_string(),
+            &fixture.position,
+        ])?;
+    }
+    let bytes = writer.into_inner().map_err(|error| SheetError::Io(error.into_error()))?;
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+// A row that could not be patched. Rows are numbered like in a spreadsheet, the header is row 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    pub row: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Row {}: {}", self.row, self.message)
+    }
+}
+
+// Columns can be in any order, only Manufacturer, Model and Address are required.
+// The rows that work are returned as a patch of their own, rows colliding with earlier rows are errors
+pub fn import(text: &str, library: &FixtureLibrary) -> Result<(Patch, Vec<RowError>), SheetError> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).trim(csv::Trim::All).from_reader(text.as_bytes());
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|header| header.eq_ignore_ascii_case(name));
+    let required = |name: &'static str| column(name).ok_or(SheetError::MissingColumn(name));
+    let (manufacturer, model, address) = (required("Manufacturer")?, required("Model")?, required("Address")?);
+    let (name, mode, universe, position) = (column("Name"), column("Mode"), column("Universe"), column("Position"));
+
+    let mut patch = Patch::new();
+    let mut errors = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let row = index + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(error) => {
+                errors.push(RowError { row, message: error.to_string() });
+                continue;
+            },
+        };
+        let field = |column: Option<usize>| column.and_then(|column| record.get(column)).unwrap_or_default();
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let result = parse_row(library, &patch, Row {
+            name: field(name),
+            manufacturer: field(Some(manufacturer)),
+            model: field(Some(model)),
+            mode: field(mode),
+            universe: field(universe),
+            address: field(Some(address)),
+            position: field(position),
+        });
+        match result.and_then(|fixture| patch.add(fixture).map_err(|error| describe(&patch, error))) {
+            Ok(_) => {},
+            Err(message) => errors.push(RowError { row, message }),
+        }
+    }
+    Ok((patch, errors))
+}
+
+struct Row<'a> {
+    name: &'a str,
+    manufacturer: &'a str,
+    model: &'a str,
+    mode: &'a str,
+    universe: &'a str,
+    address: &'a str,
+    position: &'a str,
+}
+
+fn parse_row(library: &FixtureLibrary, patch: &Patch, row: Row) -> Result<Fixture, String> {
+    let entry = library.find(row.manufacturer, row.model)
+        .ok_or_else(|| format!("{} {} is not in the fixture library", row.manufacturer, row.model))?;
+    let modes = &entry.model.channel_modes;
+    let mode = if row.mode.is_empty() {
+        0
+    } else {
+        modes.iter().position(|mode| mode.name.as_ref().is_some_and(|name| name.name().eq_ignore_ascii_case(row.mode)))
+            .or_else(|| row.mode.parse::<usize>().ok().filter(|mode| (1..=modes.len()).contains(mode)).map(|mode| mode - 1))
+            .ok_or_else(|| format!("{} {} has no mode {}", row.manufacturer, row.model, row.mode))?
+    };
+    let universe = match row.universe {
+        "" => 0,
+        universe => universe.parse::<usize>().ok().filter(|universe| *universe >= 1)
+            .ok_or_else(|| format!("{} is not a universe", universe))? - 1,
+    };
+    let channel = row.address.parse::<u16>().ok().and_then(|address| Channel::new(address).ok())
+        .ok_or_else(|| format!("{} is not a DMX address", row.address))?;
+    let name = match row.name {
+        "" => format!("{} {}", entry.name(), patch.len() + 1),
+        name => name.to_string(),
+    };
+    let mut fixture = Fixture::new(name, DMXAddress::new(channel, 0), entry.model.clone()).map_err(|error| format!("{:?}", error))?;
+    fixture.mode = mode;
+    fixture.universe = universe;
+    fixture.position = row.position.to_string();
+    fixture.source = Some(entry.path.clone());
+    Ok(fixture)
+}
+
+fn describe(patch: &Patch, error: PatchError) -> String {
+    match error {
+        PatchError::Collision(other) => format!("the channels are used by {}", patch.fixtures()[other].name),
+        error => error.to_string(),
+    }
+}
+
+#[derive(Debug)]
+pub enum SheetError {
+    Io(std::io::Error),
+    Csv(csv::Error),
+    MissingColumn(&'static str),
+}
+
+impl From<csv::Error> for SheetError {
+    fn from(error: csv::Error) -> Self {
+        SheetError::Csv(error)
+    }
+}
+
+impl std::fmt::Display for SheetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SheetError::Io(error) => write!(f, "{}", error),
+            SheetError::Csv(error) => write!(f, "invalid CSV: {}", error),
+            SheetError::MissingColumn(column) => write!(f, "the sheet has no {} column", column),
+        }
+    }
+}
+
+impl std::error::Error for SheetError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::fixture::{FixtureChannelMode, FixtureName};
+    use crate::test_support::test_fixture_with;
+
+    #[test]
+    fn sheets_round_trip_and_report_bad_rows() {
+        let directory = std::env::temp_dir().join(format!("dmxt_sheet_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut mode = FixtureChannelMode::builder();
+        mode.total_channels(Channel::new(4).unwrap()).name(FixtureName::new("4ch".into()));
+        let fixture = test_fixture_with(mode.build().unwrap(), 1);
+        std::fs::write(directory.join("par.json"), serde_json::to_string(fixture.model()).unwrap()).unwrap();
+        let library = FixtureLibrary::scan(std::slice::from_ref(&directory));
+
+        let sheet = "Address,Model,Manufacturer,Name,Universe,Position\n\
+            1,par,test,Front 1,1,FOH\n\
+            3,Par,Test,Front 2,1,FOH\n\
+            1,Spot,Test,Back,1,LX1\n\
+            5,Par,Test,Front 3,2,\n";
+        let (patch, errors) = import(sheet, &library).unwrap();
+        assert_eq!(patch.len(), 2);
+        assert_eq!(errors, vec![
+            RowError { row: 3, message: "the channels are used by Front 1".into() },
+            RowError { row: 4, message: "Test Spot is not in the fixture library".into() },
+        ]);
+        assert_eq!(patch.fixtures()[1].universe, 1);
+
+        let (reimported, errors) = import(&export(&patch).unwrap(), &library).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(reimported.fixtures()[0].position, "FOH");
+        assert!(matches!(import("Model\nPar", &library), Err(SheetError::MissingColumn("Manufacturer"))));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
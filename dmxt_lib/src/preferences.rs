@@ -0,0 +1,273 @@
+use crate::interfaces::Interface;
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const PREFERENCES_VERSION: u32 = 1;
+pub const MAX_RECENT_FILES: usize = 10;
+pub const DEFAULT_OSC_PORT: u16 = 8000;
+
+// Each entry upgrades the JSON of one version to the next, MIGRATIONS[0] turns version 1 into 2.
+// Fields that are only added don't need one, missing fields get their defaults
+const MIGRATIONS: &[fn(&mut Value)] = &[];
+
+// Settings of the user, shared by all shows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub version: u32,
+    // Folders that are searched for fixture configs
+    pub fixture_library: Vec<PathBuf>,
+    pub frame_rate: f64,
+    // Mapped to the first universe of new shows
+    pub default_interface: Option<Interface>,
+    pub theme: Theme,
+    // Newest first
+    pub recent_files: Vec<PathBuf>,
+    pub key_bindings: BTreeMap<Action, KeyBinding>,
+    pub osc_port: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+// Things that can be bound to a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    New,
+    Open,
+    Save,
+    SaveAs,
+    Undo,
+    Redo,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [Action::New, Action::Open, Action::Save, Action::SaveAs, Action::Undo, Action::Redo];
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::New => write!(f, "New show"),
+            Action::Open => write!(f, "Open show"),
+            Action::Save => write!(f, "Save show"),
+            Action::SaveAs => write!(f, "Save show as"),
+            Action::Undo => write!(f, "Undo"),
+            Action::Redo => write!(f, "Redo"),
+        }
+    }
+}
+
+// A key with modifiers, written like "Ctrl+Shift+S". Ctrl is Cmd on macOS
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyBinding {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    // Name of the key, e.g. "S", "5", "F1" or "Space"
+    pub key: String,
+}
+
+impl std::str::FromStr for KeyBinding {
+    type Err = PreferencesError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut binding = KeyBinding { ctrl: false, shift: false, alt: false, key: String::new() };
+        let mut parts = text.split('+').map(str::trim).peekable();
+        while let Some(part) = parts.next() {
+            if parts.peek().is_none() {
+                binding.key = part.to_string();
+                break;
+            }
+            match part.to_lowercase().as_str() {
+                "ctrl" | "cmd" => binding.ctrl = true,
+                "shift" => binding.shift = true,
+                "alt" => binding.alt = true,
+                _ => return Err(PreferencesError::KeyBinding(text.to_string())),
+            }
+        }
+        if binding.key.is_empty() {
+            return Err(PreferencesError::KeyBinding(text.to_string()));
+        }
+        Ok(binding)
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = PreferencesError;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+
+impl From<KeyBinding> for String {
+    fn from(binding: KeyBinding) -> Self {
+        binding.to_string()
+    }
+}
+
+impl std::fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl Preferences {
+    // The per-user config file, None if the OS has no config directory
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("dmxt").join("preferences.json"))
+    }
+
+    pub fn load(path: &Path) -> Result<Preferences, PreferencesError> {
+        let value: Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        Preferences::from_value(value)
+    }
+
+    // Defaults if there is no config file yet
+    pub fn load_or_default(path: &Path) -> Result<Preferences, PreferencesError> {
+        match Preferences::load(path) {
+            Err(PreferencesError::Io(error)) if error.kind() == std::io::ErrorKind::NotFound => Ok(Preferences::default()),
+            result => result,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), PreferencesError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // Upgrades the JSON of older versions before reading it
+    pub fn from_value(mut value: Value) -> Result<Preferences, PreferencesError> {
+        let version = value.get("version").and_then(Value::as_u64).unwrap_or(1) as u32;
+        if version > PREFERENCES_VERSION {
+            return Err(PreferencesError::Version(version));
+        }
+        for migration in MIGRATIONS.iter().skip((version as usize).saturating_sub(1)) {
+            migration(&mut value);
+        }
+        let mut preferences: Preferences = serde_json::from_value(value)?;
+        preferences.version = PREFERENCES_VERSION;
+        // Actions added in newer versions get their default keys
+        for (action, binding) in default_key_bindings() {
+            preferences.key_bindings.entry(action).or_insert(binding);
+        }
+        Ok(preferences)
+    }
+
+    // Moves `path` to the front of the recent files
+    pub fn add_recent(&mut self, path: &Path) {
+        self.recent_files.retain(|recent| recent != path);
+        self.recent_files.insert(0, path.to_path_buf());
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
+    pub fn key_binding(&self, action: Action) -> Option<&KeyBinding> {
+        self.key_bindings.get(&action)
+    }
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            version: PREFERENCES_VERSION,
+            fixture_library: Vec::new(),
+            frame_rate: crate::output::DEFAULT_FRAME_RATE,
+            default_interface: None,
+            theme: Theme::Dark,
+            recent_files: Vec::new(),
+            key_bindings: default_key_bindings(),
+            osc_port: DEFAULT_OSC_PORT,
+        }
+    }
+}
+
+fn default_key_bindings() -> BTreeMap<Action, KeyBinding> {
+    let binding = |shift: bool, key: &str| KeyBinding { ctrl: true, shift, alt: false, key: key.to_string() };
+    BTreeMap::from([
+        (Action::New, binding(false, "N")),
+        (Action::Open, binding(false, "O")),
+        (Action::Save, binding(false, "S")),
+        (Action::SaveAs, binding(true, "S")),
+        (Action::Undo, binding(false, "Z")),
+        (Action::Redo, binding(false, "Y")),
+    ])
+}
+
+#[derive(Debug)]
+pub enum PreferencesError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    // The file was written by a newer version
+    Version(u32),
+    KeyBinding(String),
+}
+
+impl From<std::io::Error> for PreferencesError {
+    fn from(error: std::io::Error) -> Self {
+        PreferencesError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for PreferencesError {
+    fn from(error: serde_json::Error) -> Self {
+        PreferencesError::Json(error)
+    }
+}
+
+impl std::fmt::Display for PreferencesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PreferencesError::Io(error) => write!(f, "{}", error),
+            PreferencesError::Json(error) => write!(f, "invalid preferences: {}", error),
+            PreferencesError::Version(version) => write!(f, "the preferences have version {}, this version of DMXT reads up to {}", version, PREFERENCES_VERSION),
+            PreferencesError::KeyBinding(text) => write!(f, "{} is not a key binding", text),
+        }
+    }
+}
+
+impl std::error::Error for PreferencesError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_files_get_defaults_and_newer_files_are_rejected() {
+        let value = serde_json::json!({
+            "version": 1,
+            "frame_rate": 30.0,
+            "key_bindings": { "Undo": "Ctrl+Alt+U" },
+            "removed_setting": true,
+        });
+        let preferences = Preferences::from_value(value).unwrap();
+        assert_eq!(preferences.frame_rate, 30.0);
+        assert_eq!(preferences.osc_port, DEFAULT_OSC_PORT);
+        assert_eq!(preferences.key_binding(Action::Undo).unwrap().to_string(), "Ctrl+Alt+U");
+        assert_eq!(preferences.key_binding(Action::SaveAs).unwrap().to_string(), "Ctrl+Shift+S");
+
+        let newer = serde_json::json!({ "version": PREFERENCES_VERSION + 1 });
+        assert!(matches!(Preferences::from_value(newer), Err(PreferencesError::Version(_))));
+        assert!("Ctrl+Hyper+S".parse::<KeyBinding>().is_err());
+    }
+}
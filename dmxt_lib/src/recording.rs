@@ -0,0 +1,430 @@
+use crate::dmx::{DMXUniverse, DMX_CHANNELS};
+
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+const MAGIC: &[u8; 4] = b"DMXR";
+// 1 stored every frame in full, 2 only the channels that changed since the frame before
+pub const RECORDING_VERSION: u8 = 2;
+
+// Universes as they were at one point in time, in seconds since the start of the recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub time: f64,
+    pub universes: Vec<DMXUniverse>,
+}
+
+// Stream of DMX frames, e.g. a rendered timeline or what was sent in a venue
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    frames: Vec<Frame>,
+}
+
+impl Recording {
+    pub fn new() -> Recording {
+        Recording::default()
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // Length in seconds
+    pub fn duration(&self) -> f64 {
+        self.frames.last().map_or(0.0, |frame| frame.time)
+    }
+
+    // Frames are kept in order, a frame older than the last one is moved to its time
+    pub fn push(&mut self, time: f64, universes: &[DMXUniverse]) {
+        let time = time.max(self.duration());
+        self.frames.push(Frame { time, universes: universes.to_vec() });
+    }
+
+    // The last frame at or before the time, it holds until the next one
+    pub fn frame_at(&self, time: f64) -> Option<&Frame> {
+        let next = self.frames.partition_point(|frame| frame.time <= time);
+        next.checked_sub(1).map(|index| &self.frames[index])
+    }
+
+    // First channel that differs from an expected recording, e.g. a golden file of an effect.
+    // Frames are compared in order, their times have to match to the microsecond
+    pub fn difference(&self, expected: &Recording) -> Option<Difference> {
+        for (index, (frame, other)) in self.frames.iter().zip(&expected.frames).enumerate() {
+            if (frame.time - other.time).abs() >= 0.000_001 {
+                return Some(Difference { frame: index, time: other.time, universe: None, expected: 0, actual: 0 });
+            }
+            let count = frame.universes.len().max(other.universes.len());
+            for universe in 0..count {
+                let channels = |frame: &Frame| frame.universes.get(universe).copied().unwrap_or_default().channels;
+                let (actual, wanted) = (channels(frame), channels(other));
+                if let Some(channel) = (0..DMX_CHANNELS).find(|channel| actual[*channel] != wanted[*channel]) {
+                    return Some(Difference {
+                        frame: index,
+                        time: other.time,
+                        universe: Some((universe, channel as u16 + 1)),
+                        expected: wanted[channel],
+                        actual: actual[channel],
+                    });
+                }
+            }
+        }
+        (self.len() != expected.len()).then(|| {
+            let frame = self.len().min(expected.len());
+            let time = expected.frames.get(frame).or(self.frames.get(frame)).map_or(0.0, |frame| frame.time);
+            Difference { frame, time, universe: None, expected: 0, actual: 0 }
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), RecordingError> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Recording, RecordingError> {
+        Recording::read(&mut std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+
+    // Header, then every frame as its time in microseconds, the number of universes and
+    // per universe the spans of channels that changed since the frame before
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), RecordingError> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[RECORDING_VERSION])?;
+        writer.write_all(&(self.frames.len() as u32).to_le_bytes())?;
+        let mut previous: Vec<DMXUniverse> = Vec::new();
+        for frame in &self.frames {
+            writer.write_all(&((frame.time * 1_000_000.0).round() as u64).to_le_bytes())?;
+            writer.write_all(&(frame.universes.len() as u16).to_le_bytes())?;
+            for (index, universe) in frame.universes.iter().enumerate() {
+                let before = previous.get(index).copied().unwrap_or_default();
+                let spans = changed_spans(&before.channels, &universe.channels);
+                writer.write_all(&(spans.len() as u16).to_le_bytes())?;
+                for (start, end) in spans {
+                    writer.write_all(&(start as u16).to_le_bytes())?;
+                    writer.write_all(&((end - start) as u16).to_le_bytes())?;
+                    writer.write_all(&universe.channels[start..end])?;
+                }
+            }
+            previous.clone_from(&frame.universes);
+        }
+        Ok(())
+    }
+
+    pub fn read(reader: &mut impl Read) -> Result<Recording, RecordingError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(RecordingError::Format);
+        }
+        let version = read_bytes::<1>(reader)?[0];
+        if version == 0 {
+            return Err(RecordingError::Format);
+        }
+        if version > RECORDING_VERSION {
+            return Err(RecordingError::Version(version));
+        }
+        let count = u32::from_le_bytes(read_bytes(reader)?);
+        let mut recording = Recording::new();
+        let mut previous: Vec<DMXUniverse> = Vec::new();
+        for _ in 0..count {
+            let time = u64::from_le_bytes(read_bytes(reader)?) as f64 / 1_000_000.0;
+            let mut universes = Vec::new();
+            for index in 0..u16::from_le_bytes(read_bytes(reader)?) as usize {
+                let universe = match version {
+                    1 => DMXUniverse { channels: read_bytes(reader)? },
+                    _ => {
+                        let mut universe = previous.get(index).copied().unwrap_or_default();
+                        for _ in 0..u16::from_le_bytes(read_bytes(reader)?) {
+                            let start = u16::from_le_bytes(read_bytes(reader)?) as usize;
+                            let length = u16::from_le_bytes(read_bytes(reader)?) as usize;
+                            let span = universe.channels.get_mut(start..start + length).ok_or(RecordingError::Format)?;
+                            reader.read_exact(span)?;
+                        }
+                        universe
+                    },
+                };
+                universes.push(universe);
+            }
+            previous.clone_from(&universes);
+            recording.frames.push(Frame { time, universes });
+        }
+        Ok(recording)
+    }
+}
+
+// Ranges of channels that differ. Gaps of a few equal channels are included, a new span costs more than them
+fn changed_spans(before: &[u8; DMX_CHANNELS], after: &[u8; DMX_CHANNELS]) -> Vec<(usize, usize)> {
+    const MERGE_GAP: usize = 4;
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    for channel in (0..DMX_CHANNELS).filter(|channel| before[*channel] != after[*channel]) {
+        match spans.last_mut() {
+            Some((_, end)) if channel - *end <= MERGE_GAP => *end = channel + 1,
+            _ => spans.push((channel, channel + 1)),
+        }
+    }
+    spans
+}
+
+// Where a recording differs from the expected one. Without a universe the frames are at different times or missing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Difference {
+    pub frame: usize,
+    pub time: f64,
+    // Universe index and channel
+    pub universe: Option<(usize, u16)>,
+    pub expected: u8,
+    pub actual: u8,
+}
+
+impl std::fmt::Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.universe {
+            Some((universe, channel)) => write!(f, "frame {} ({:.3} s): universe {} channel {} is {}, expected {}",
+                self.frame, self.time, universe + 1, channel, self.actual, self.expected),
+            None => write!(f, "frame {} ({:.3} s) is missing or at a different time", self.frame, self.time),
+        }
+    }
+}
+
+// Collects frames while something runs, e.g. the output engine or a network input
+#[derive(Debug)]
+pub struct Recorder {
+    started: Instant,
+    recording: Recording,
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder { started: Instant::now(), recording: Recording::new() }
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    // Frames equal to the last one are skipped, players hold the last frame anyway
+    pub fn capture(&mut self, universes: &[DMXUniverse]) {
+        if self.recording.frames.last().is_some_and(|frame| frame.universes == universes) {
+            return;
+        }
+        self.recording.push(self.started.elapsed().as_secs_f64(), universes);
+    }
+
+    // The last frame is repeated at the end so the recording is as long as it ran
+    pub fn finish(mut self) -> Recording {
+        if let Some(last) = self.recording.frames.last().cloned() {
+            self.recording.push(self.started.elapsed().as_secs_f64(), &last.universes);
+        }
+        self.recording
+    }
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Starts and stops recording of something running on another thread, e.g. the output engine or a network input
+#[derive(Debug, Clone, Default)]
+pub struct RecorderHandle(Arc<Mutex<Option<Recorder>>>);
+
+impl RecorderHandle {
+    // A running recording starts over
+    pub fn start(&self) {
+        *self.0.lock().unwrap() = Some(Recorder::new());
+    }
+
+    pub fn stop(&self) -> Option<Recording> {
+        self.0.lock().unwrap().take().map(Recorder::finish)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.0.lock().unwrap().is_some()
+    }
+
+    // Frames recorded so far
+    pub fn frames(&self) -> usize {
+        self.0.lock().unwrap().as_ref().map_or(0, |recorder| recorder.recording().len())
+    }
+
+    pub(crate) fn capture(&self, universes: &[DMXUniverse]) {
+        if let Some(recorder) = self.0.lock().unwrap().as_mut() {
+            recorder.capture(universes);
+        }
+    }
+}
+
+// Plays a recording back, in seconds of the recording
+#[derive(Debug, Clone)]
+pub struct Player {
+    recording: Recording,
+    position: f64,
+    pub playing: bool,
+    pub looped: bool,
+    // 1.0 is the original speed
+    pub speed: f64,
+}
+
+impl Player {
+    pub fn new(recording: Recording) -> Player {
+        Player { recording, position: 0.0, playing: false, looped: false, speed: 1.0 }
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.position = 0.0;
+    }
+
+    pub fn seek(&mut self, position: f64) {
+        self.position = position.clamp(0.0, self.recording.duration());
+    }
+
+    // Moves on by elapsed seconds. Looped players start over, others stop at the end
+    pub fn advance(&mut self, elapsed: f64) {
+        if !self.playing {
+            return;
+        }
+        let duration = self.recording.duration();
+        let position = self.position + elapsed * self.speed.max(0.0);
+        if position < duration {
+            self.position = position;
+        } else if self.looped && duration > 0.0 {
+            self.position = position.rem_euclid(duration);
+        } else {
+            self.position = duration;
+            self.playing = false;
+        }
+    }
+
+    pub fn frame(&self) -> Option<&Frame> {
+        self.recording.frame_at(self.position)
+    }
+
+    // Copies the current frame into the universes, universes the recording doesn't have are left alone
+    pub fn apply(&self, universes: &mut [DMXUniverse]) {
+        if let Some(frame) = self.frame() {
+            for (universe, recorded) in universes.iter_mut().zip(&frame.universes) {
+                *universe = *recorded;
+            }
+        }
+    }
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> Result<[u8; N], RecordingError> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(std::io::Error),
+    // Not a recording or cut off
+    Format,
+    // The recording was made by a newer version
+    Version(u8),
+}
+
+impl From<std::io::Error> for RecordingError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::UnexpectedEof => RecordingError::Format,
+            _ => RecordingError::Io(error),
+        }
+    }
+}
+
+impl std::fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordingError::Io(error) => write!(f, "{}", error),
+            RecordingError::Format => write!(f, "not a DMX recording or the file is incomplete"),
+            RecordingError::Version(version) => write!(f, "the recording has version {}, this version of dmxt reads up to {}", version, RECORDING_VERSION),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dmx::Channel;
+
+    #[test]
+    fn deltas_round_trip_and_players_seek_and_loop() {
+        let mut recording = Recording::new();
+        let mut universes = vec![DMXUniverse::new(); 2];
+        for frame in 0..20u8 {
+            universes[0].set(Channel::new(1).unwrap(), frame).unwrap();
+            universes[1].set(Channel::new(512).unwrap(), 255 - frame).unwrap();
+            recording.push(frame as f64 * 0.1, &universes);
+        }
+        let mut bytes = Vec::new();
+        recording.write(&mut bytes).unwrap();
+        // Two changed channels per frame instead of two full universes
+        assert!(bytes.len() < 20 * 32);
+        let read = Recording::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read.difference(&recording), None);
+        assert!(matches!(Recording::read(&mut &bytes[..bytes.len() - 1]), Err(RecordingError::Format)));
+
+        let mut changed = read.clone();
+        changed.frames[5].universes[1].channels[511] = 0;
+        let difference = changed.difference(&recording).unwrap();
+        assert_eq!((difference.frame, difference.universe, difference.expected), (5, Some((1, 512)), 250));
+
+        let mut player = Player::new(read);
+        player.playing = true;
+        player.looped = true;
+        player.speed = 2.0;
+        player.seek(1.5);
+        player.advance(0.25);
+        assert!((player.position() - 0.1).abs() < 1e-9);
+        let mut output = vec![DMXUniverse::new()];
+        player.apply(&mut output);
+        assert_eq!(output[0].channels[0], 1);
+    }
+
+    #[test]
+    fn version_1_files_are_read() {
+        // Written by version 1: two frames of one full universe each
+        let mut bytes = b"DMXR\x01".to_vec();
+        bytes.extend(2u32.to_le_bytes());
+        for (time, value) in [(0u64, 10u8), (500_000, 20)] {
+            bytes.extend(time.to_le_bytes());
+            bytes.extend(1u16.to_le_bytes());
+            let mut channels = [0; DMX_CHANNELS];
+            channels[2] = value;
+            bytes.extend(channels);
+        }
+        let recording = Recording::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(recording.len(), 2);
+        assert_eq!(recording.duration(), 0.5);
+        assert_eq!(recording.frames()[1].universes[0].channels[..3], [0, 0, 20]);
+
+        bytes[4] = 0;
+        assert!(matches!(Recording::read(&mut bytes.as_slice()), Err(RecordingError::Format)));
+        bytes[4] = RECORDING_VERSION + 1;
+        assert!(matches!(Recording::read(&mut bytes.as_slice()), Err(RecordingError::Version(_))));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::dmx::DMXUniverse;
+use crate::patch::Patch;
+use crate::recording::Recording;
+use crate::scripting::{Script, ScriptError};
+use crate::show::Show;
+use crate::stage::Stage;
+use crate::threads::shared::Lock;
+use crate::timeline::{Playback, Timeline};
+use crate::timing::{ManualClock, Metronome, BPM};
+
+use open_dmx::error::DMXError;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+// Scripts are still stopped by the wall clock, offline they get more time so slow CI machines render the same frames
+const OFFLINE_TIME_LIMIT: Duration = Duration::from_secs(1);
+// Random numbers of scripts repeat from render to render
+const OFFLINE_SEED: u64 = 0x444D_5854;
+
+// Steps a show at a fixed frame rate on a manual clock, without threads or the wall clock,
+// so the same show always renders the same frames. Universes keep their values between frames like the live output
+pub struct OfflineRenderer {
+    clock: ManualClock,
+    frame_rate: f64,
+    frame: u64,
+    patch: Lock<Patch>,
+    universes: Lock<Vec<DMXUniverse>>,
+    stage: Stage,
+    timelines: Vec<Timeline>,
+    // Timeline and the time it started at
+    playing: Option<(usize, f64)>,
+    metronome: Option<Metronome>,
+    script: Option<Script>,
+}
+
+impl OfflineRenderer {
+    pub fn new(show: &Show, frame_rate: f64) -> OfflineRenderer {
+        OfflineRenderer {
+            clock: ManualClock::new(),
+            frame_rate: frame_rate.max(1.0),
+            frame: 0,
+            patch: Lock::new(show.patch.clone()),
+            universes: Lock::new(vec![DMXUniverse::new(); show.universes.max(1)]),
+            stage: show.stage.clone(),
+            timelines: show.timelines.clone(),
+            playing: None,
+            metronome: None,
+            script: None,
+        }
+    }
+
+    pub fn clock(&self) -> ManualClock {
+        self.clock.clone()
+    }
+
+    // Time of the next frame in seconds
+    pub fn time(&self) -> f64 {
+        self.frame as f64 / self.frame_rate
+    }
+
+    // Plays a timeline of the show from the start, false if the show doesn't have it
+    pub fn play_timeline(&mut self, index: usize) -> bool {
+        self.playing = (index < self.timelines.len()).then_some((index, self.time()));
+        self.playing.is_some()
+    }
+
+    // Beats go to the `on_beat` hook of the script, clips of timelines in beats cycle on them
+    pub fn set_metronome(&mut self, bpm: BPM) {
+        self.metronome = Some(Metronome::with_clock(bpm, Arc::new(self.clock.clone())));
+    }
+
+    pub fn load_script(&mut self, source: &str) -> Result<(), ScriptError> {
+        let mut script = Script::new(self.patch.clone(), self.universes.clone());
+        script.set_clock(Arc::new(self.clock.clone()));
+        script.set_time_limit(OFFLINE_TIME_LIMIT);
+        script.set_seed(OFFLINE_SEED);
+        script.compile(source)?;
+        self.script = Some(script);
+        Ok(())
+    }
+
+    // Renders the next frame, the first one is at time 0. Timelines are written first, scripts on top
+    pub fn step(&mut self) -> Result<Vec<DMXUniverse>, RenderError> {
+        self.clock.set(Duration::from_secs_f64(self.time()));
+        if let Some(metronome) = &mut self.metronome {
+            let before = metronome.beats();
+            metronome.tick();
+            if let Some(script) = &mut self.script {
+                for beat in before + 1..=metronome.beats() {
+                    script.beat(beat, metronome.get_bpm())?;
+                }
+            }
+        }
+        if let Some((index, started)) = self.playing {
+            let timeline = &self.timelines[index];
+            let mut playback = Playback { position: 0.0, playing: true };
+            playback.advance(timeline, self.time() - started);
+            let beat = self.metronome.as_ref().map(Metronome::position);
+            timeline.render_synced(playback.position, beat, &self.patch.read().unwrap(), &self.stage, &mut self.universes.write().unwrap())?;
+        }
+        if let Some(script) = &mut self.script {
+            script.frame()?;
+        }
+        self.frame += 1;
+        Ok(self.universes.read().unwrap().clone())
+    }
+
+    pub fn render(&mut self, frames: usize) -> Result<Vec<Vec<DMXUniverse>>, RenderError> {
+        (0..frames).map(|_| self.step()).collect()
+    }
+
+    // Frames up to and including the time, e.g. to compare with a golden file
+    pub fn record(&mut self, seconds: f64) -> Result<Recording, RenderError> {
+        let mut recording = Recording::new();
+        while self.time() <= seconds + 1e-9 {
+            let time = self.time();
+            recording.push(time, &self.step()?);
+        }
+        Ok(recording)
+    }
+}
+
+impl std::fmt::Debug for OfflineRenderer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OfflineRenderer")
+        .field("frame_rate", &self.frame_rate)
+        .field("frame", &self.frame)
+        .field("timeline", &self.playing.map(|(index, _)| index))
+        .field("script", &self.script)
+        .finish()
+    }
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    Dmx(DMXError),
+    Script(ScriptError),
+}
+
+impl From<DMXError> for RenderError {
+    fn from(error: DMXError) -> Self {
+        RenderError::Dmx(error)
+    }
+}
+
+impl From<ScriptError> for RenderError {
+    fn from(error: ScriptError) -> Self {
+        RenderError::Script(error)
+    }
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::Dmx(error) => write!(f, "{:?}", error),
+            RenderError::Script(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_fixture;
+    use crate::timeline::{Attribute, Curve, Keyframe, Lane, Target, Track, Value};
+
+    fn show() -> Show {
+        let mut show = Show::default();
+        show.patch.add(test_fixture(2, 1)).unwrap();
+        let mut lane = Lane::new(Attribute::Channel(1));
+        lane.insert(Keyframe { time: 0.0, value: Value::Level(0.0), curve: Curve::Linear });
+        lane.insert(Keyframe { time: 4.0, value: Value::Level(1.0), curve: Curve::Step });
+        let mut track = Track::new("Front".into(), Target::Fixtures(vec![0]));
+        track.lanes.push(lane);
+        let mut timeline = Timeline::new("Ramp".into());
+        timeline.length = 4.0;
+        timeline.tracks.push(track);
+        show.timelines.push(timeline);
+        show
+    }
+
+    fn render(show: &Show) -> Recording {
+        let mut renderer = OfflineRenderer::new(show, 10.0);
+        assert!(renderer.play_timeline(0));
+        renderer.set_metronome(120.0);
+        renderer.load_script("fn on_beat(beat) { set_channel(0, 2, beat * 10); }").unwrap();
+        renderer.record(2.0).unwrap()
+    }
+
+    #[test]
+    fn shows_render_the_same_frames_every_time() {
+        let show = show();
+        let recording = render(&show);
+        assert_eq!(recording.len(), 21);
+        // 4 beats at 120 BPM take 2 seconds, beats come at 0.0, 0.5 and 1.0 seconds
+        let frame = &recording.frames()[10];
+        assert_eq!(frame.time, 1.0);
+        assert_eq!(&frame.universes[0].channels[..2], &[128, 30]);
+        assert_eq!(recording.frames()[20].universes[0].channels[..2], [255, 50]);
+        assert_eq!(render(&show).difference(&recording), None);
+    }
+}
@@ -0,0 +1,379 @@
+use crate::components::color::RGB;
+use crate::components::Fixture;
+use crate::dmx::{Channel, DMXUniverse};
+use crate::patch::Patch;
+use crate::threads::shared::Lock;
+use crate::timing::{SharedClock, SystemClock};
+
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+// Rhai scripts that drive the universes. A script can define these hooks:
+//   fn on_load()        after loading and after every reload
+//   fn on_frame(time)   every output frame, `time` in seconds since the script was loaded
+//   fn on_beat(beat)    every metronome beat, counting from 1
+// Hooks can't see global variables, state that should survive between calls goes into `this`:
+//   fn on_beat(beat) { if beat % 4 == 0 { for f in fixtures_at("LX1") { set_color(f, random(), random(), random()); } } }
+
+pub const DEFAULT_TIME_LIMIT: Duration = Duration::from_millis(10);
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+#[derive(Debug, Default)]
+struct Tempo {
+    bpm: f64,
+    beat: u64,
+}
+
+pub struct Script {
+    engine: Engine,
+    ast: Option<AST>,
+    // `this` of the hooks
+    state: Dynamic,
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    // Hooks get their time from the clock, the time limit always uses the wall clock
+    clock: SharedClock,
+    loaded: Duration,
+    time_limit: Arc<Mutex<Duration>>,
+    deadline: Arc<Mutex<Instant>>,
+    tempo: Arc<Mutex<Tempo>>,
+    // State of `random` and `random_int`
+    random: Arc<Mutex<u64>>,
+}
+
+impl Script {
+    pub fn new(patch: Lock<Patch>, universes: Lock<Vec<DMXUniverse>>) -> Script {
+        let deadline = Arc::new(Mutex::new(Instant::now()));
+        let tempo = Arc::new(Mutex::new(Tempo::default()));
+        let seed = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(1, |time| time.as_nanos() as u64);
+        let random = Arc::new(Mutex::new(seed.max(1)));
+        let mut engine = Engine::new();
+        // Scripts can't take the output down: runaway loops, deep recursion and huge values are stopped
+        let progress_deadline = deadline.clone();
+        engine.on_progress(move |_| (Instant::now() > *progress_deadline.lock().unwrap()).then(|| Dynamic::from("time limit")));
+        engine.set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(64 * 1024)
+            .set_max_array_size(64 * 1024)
+            .set_max_map_size(64 * 1024)
+            .disable_symbol("eval");
+        register_api(&mut engine, patch, universes, tempo.clone(), random.clone());
+        Script {
+            engine,
+            ast: None,
+            state: Dynamic::from(Map::new()),
+            path: None,
+            modified: None,
+            clock: SystemClock::shared(),
+            loaded: Duration::ZERO,
+            time_limit: Arc::new(Mutex::new(DEFAULT_TIME_LIMIT)),
+            deadline,
+            tempo,
+            random,
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: SharedClock) {
+        self.loaded = clock.now();
+        self.clock = clock;
+    }
+
+    // How long one hook may run
+    pub fn set_time_limit(&mut self, limit: Duration) {
+        *self.time_limit.lock().unwrap() = limit;
+    }
+
+    // Random numbers repeat for the same seed, e.g. when rendering offline
+    pub fn set_seed(&mut self, seed: u64) {
+        // xorshift never leaves 0
+        *self.random.lock().unwrap() = seed.max(1);
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn load(&mut self, path: &Path) -> Result<(), ScriptError> {
+        self.modified = modified(path);
+        self.path = Some(path.to_path_buf());
+        self.compile(&std::fs::read_to_string(path)?)
+    }
+
+    // The old script keeps running if the new one doesn't compile
+    pub fn compile(&mut self, source: &str) -> Result<(), ScriptError> {
+        let ast = self.engine.compile(source).map_err(|error| ScriptError::Compile(error.to_string()))?;
+        self.limit();
+        self.engine.run_ast_with_scope(&mut Scope::new(), &ast).map_err(ScriptError::from)?;
+        self.ast = Some(ast);
+        self.loaded = self.clock.now();
+        self.call("on_load", ())
+    }
+
+    // Loads the file again if it was saved since it was loaded, returns true if it was
+    pub fn reload_if_changed(&mut self) -> Result<bool, ScriptError> {
+        let Some(path) = self.path.clone() else {
+            return Ok(false);
+        };
+        let modified = modified(&path);
+        if modified.is_none() || modified == self.modified {
+            return Ok(false);
+        }
+        self.load(&path).map(|_| true)
+    }
+
+    pub fn frame(&mut self) -> Result<(), ScriptError> {
+        let time = self.clock.now().saturating_sub(self.loaded).as_secs_f64();
+        self.call("on_frame", (time,))
+    }
+
+    pub fn beat(&mut self, beat: u64, bpm: f64) -> Result<(), ScriptError> {
+        *self.tempo.lock().unwrap() = Tempo { bpm, beat };
+        self.call("on_beat", (beat as i64,))
+    }
+
+    fn limit(&self) {
+        *self.deadline.lock().unwrap() = Instant::now() + *self.time_limit.lock().unwrap();
+    }
+
+    fn call(&mut self, hook: &str, args: impl rhai::FuncArgs) -> Result<(), ScriptError> {
+        let Some(ast) = &self.ast else {
+            return Ok(());
+        };
+        if !ast.iter_functions().any(|function| function.name == hook) {
+            return Ok(());
+        }
+        self.limit();
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut self.state);
+        self.engine.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), ast, hook, args)
+            .map(|_| ())
+            .map_err(|error| match ScriptError::from(error) {
+                ScriptError::Runtime(error) => ScriptError::Runtime(format!("{}: {}", hook, error)),
+                error => error,
+            })
+    }
+}
+
+impl std::fmt::Debug for Script {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Script")
+        .field("path", &self.path)
+        .field("loaded", &self.ast.is_some())
+        .finish()
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+// Functions scripts can call. Fixtures are patch indices, channels and addresses start at 1
+fn register_api(engine: &mut Engine, patch: Lock<Patch>, universes: Lock<Vec<DMXUniverse>>, tempo: Arc<Mutex<Tempo>>, random: Arc<Mutex<u64>>) {
+    let with_fixture = {
+        let (patch, universes) = (patch.clone(), universes.clone());
+        move |index: i64, write: &dyn Fn(&Fixture, &mut DMXUniverse) -> Result<(), String>| -> ScriptResult<()> {
+            let patch = patch.read().unwrap();
+            let fixture = usize::try_from(index).ok().and_then(|index| patch.get(index)).ok_or(format!("there is no fixture {}", index))?;
+            let mut universes = universes.write().unwrap();
+            let universe = universes.get_mut(fixture.universe).ok_or(format!("{} is in a universe that does not exist", fixture.name))?;
+            write(fixture, universe).map_err(Into::into)
+        }
+    };
+
+    let names = patch.clone();
+    engine.register_fn("fixtures", move || -> Array {
+        names.read().unwrap().fixtures().iter().map(|fixture| Dynamic::from(fixture.name.clone())).collect()
+    });
+    let find = patch.clone();
+    engine.register_fn("fixture", move |name: &str| -> i64 {
+        find.read().unwrap().fixtures().iter().position(|fixture| fixture.name == name).map_or(-1, |index| index as i64)
+    });
+    let positions = patch;
+    engine.register_fn("fixtures_at", move |position: &str| -> Array {
+        positions.read().unwrap().fixtures().iter().enumerate()
+            .filter(|(_, fixture)| fixture.position.eq_ignore_ascii_case(position))
+            .map(|(index, _)| Dynamic::from(index as i64))
+            .collect()
+    });
+
+    let color = with_fixture.clone();
+    engine.register_fn("set_color", move |fixture: i64, r: f64, g: f64, b: f64| {
+        color(fixture, &|fixture, universe| {
+            fixture.fill_color(universe, RGB::new(r, g, b)).map_err(|error| format!("{:?}", error))
+        })
+    });
+    let channel = with_fixture.clone();
+    engine.register_fn("set_channel", move |fixture: i64, relative: i64, value: i64| {
+        channel(fixture, &|fixture, universe| {
+            let relative = u16::try_from(relative).ok().and_then(|relative| Channel::new(relative).ok())
+                .filter(|relative| relative.id() <= fixture.channel_count())
+                .ok_or(format!("{} has no channel {}", fixture.name, relative))?;
+            fixture.write(universe, relative, value.clamp(0, 255) as u8).map_err(|error| format!("{:?}", error))
+        })
+    });
+    let position = with_fixture;
+    engine.register_fn("set_position", move |fixture: i64, pan: f64, tilt: f64| {
+        position(fixture, &|fixture, universe| {
+            fixture.write_position_normalized(universe, pan.clamp(0.0, 1.0), tilt.clamp(0.0, 1.0)).map_err(|error| format!("{:?}", error))
+        })
+    });
+
+    let set = universes.clone();
+    engine.register_fn("set_dmx", move |universe: i64, address: i64, value: i64| -> ScriptResult<()> {
+        let mut universes = set.write().unwrap();
+        let (universe, channel) = dmx_channel(&mut universes, universe, address)?;
+        universe.set(channel, value.clamp(0, 255) as u8).map_err(|error| error.to_string().into())
+    });
+    let get = universes.clone();
+    engine.register_fn("get_dmx", move |universe: i64, address: i64| -> ScriptResult<i64> {
+        let mut universes = get.write().unwrap();
+        let (universe, channel) = dmx_channel(&mut universes, universe, address)?;
+        Ok(universe.get(channel).map_err(|error| error.to_string())? as i64)
+    });
+    let blackout = universes;
+    engine.register_fn("blackout", move || {
+        blackout.write().unwrap().iter_mut().for_each(DMXUniverse::clear);
+    });
+
+    let bpm = tempo.clone();
+    engine.register_fn("bpm", move || bpm.lock().unwrap().bpm);
+    engine.register_fn("beat", move || tempo.lock().unwrap().beat as i64);
+
+    let next = move || {
+        // xorshift64
+        let mut state = random.lock().unwrap();
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        (*state >> 11) as f64 / (1u64 << 53) as f64
+    };
+    let float = next.clone();
+    engine.register_fn("random", float);
+    engine.register_fn("random_int", move |min: i64, max: i64| -> ScriptResult<i64> {
+        if min > max {
+            return Err(format!("random_int: {} is above {}", min, max).into());
+        }
+        // The range of all i64 doesn't fit into an i64
+        let span = max as i128 - min as i128 + 1;
+        Ok((min as i128 + (next() * span as f64) as i128).min(max as i128) as i64)
+    });
+}
+
+fn dmx_channel(universes: &mut [DMXUniverse], universe: i64, address: i64) -> ScriptResult<(&mut DMXUniverse, Channel)> {
+    let channel = u16::try_from(address).ok().and_then(|address| Channel::new(address).ok())
+        .ok_or(format!("{} is not a DMX address", address))?;
+    let universe = universe.checked_sub(1).and_then(|universe| usize::try_from(universe).ok()).and_then(|universe| universes.get_mut(universe))
+        .ok_or(format!("universe {} does not exist", universe))?;
+    Ok((universe, channel))
+}
+
+#[derive(Debug)]
+pub enum ScriptError {
+    Io(std::io::Error),
+    Compile(String),
+    Runtime(String),
+    // A hook ran longer than the time limit and was stopped
+    TimeLimit,
+}
+
+impl From<std::io::Error> for ScriptError {
+    fn from(error: std::io::Error) -> Self {
+        ScriptError::Io(error)
+    }
+}
+
+impl From<Box<EvalAltResult>> for ScriptError {
+    fn from(error: Box<EvalAltResult>) -> Self {
+        match *error {
+            EvalAltResult::ErrorTerminated(..) => ScriptError::TimeLimit,
+            error => ScriptError::Runtime(error.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScriptError::Io(error) => write!(f, "{}", error),
+            ScriptError::Compile(error) => write!(f, "the script does not compile: {}", error),
+            ScriptError::Runtime(error) => write!(f, "{}", error),
+            ScriptError::TimeLimit => write!(f, "the script took too long and was stopped"),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_fixture;
+
+    #[test]
+    fn hooks_write_channels_and_runaway_scripts_are_stopped() {
+        let mut fixture = test_fixture(4, 10);
+        fixture.name = "Front".into();
+        fixture.position = "LX1".into();
+        let mut patch = Patch::new();
+        patch.add(fixture).unwrap();
+        let universes = Lock::new(vec![DMXUniverse::new()]);
+        let mut script = Script::new(Lock::new(patch), universes.clone());
+
+        script.compile("
+            fn on_load() { this.flashes = 0; }
+            fn on_beat(beat) {
+                if beat % 4 == 0 {
+                    this.flashes += 1;
+                    for f in fixtures_at(\"lx1\") { set_channel(f, 2, this.flashes * 10); }
+                }
+            }
+            fn on_frame(time) { loop {} }
+        ").unwrap();
+        for beat in 1..=8 {
+            script.beat(beat, 120.0).unwrap();
+        }
+        assert_eq!(universes.read().unwrap()[0].channels[10], 20);
+        assert!(matches!(script.frame(), Err(ScriptError::TimeLimit)));
+        assert!(matches!(script.compile("fn on_beat("), Err(ScriptError::Compile(_))));
+    }
+
+    #[test]
+    fn out_of_range_numbers_are_script_errors() {
+        let universes = Lock::new(vec![DMXUniverse::new()]);
+        let mut script = Script::new(Lock::default(), universes.clone());
+        for source in [
+            "set_dmx(-9223372036854775807 - 1, 1, 255);",
+            "get_dmx(0, 1);",
+            "set_dmx(1, 513, 255);",
+            "random_int(5, 1);",
+        ] {
+            assert!(matches!(script.compile(source), Err(ScriptError::Runtime(_))), "{}", source);
+        }
+        script.compile("
+            let wide = random_int(-9223372036854775807 - 1, 9223372036854775807);
+            let one = random_int(7, 7);
+            if one != 7 { throw \"random_int(7, 7) is \" + one; }
+            for i in 0..100 {
+                let value = random_int(-2, 2);
+                if value < -2 || value > 2 { throw \"random_int(-2, 2) is \" + value; }
+            }
+            set_dmx(1, 512, 255);
+        ").unwrap();
+        assert_eq!(universes.read().unwrap()[0].channels[511], 255);
+
+        // The same seed gives the same numbers
+        let random = |seed| {
+            let universes = Lock::new(vec![DMXUniverse::new()]);
+            let mut script = Script::new(Lock::default(), universes.clone());
+            script.set_seed(seed);
+            script.compile("for address in 1..=16 { set_dmx(1, address, random_int(0, 255)); }").unwrap();
+            let channels = universes.read().unwrap()[0].channels;
+            channels
+        };
+        assert_eq!(random(3), random(3));
+        assert_ne!(random(3), random(4));
+    }
+}
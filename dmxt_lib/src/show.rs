@@ -0,0 +1,325 @@
+use crate::dmx::DMXUniverse;
+use crate::history::Command;
+use crate::interfaces::{InterfaceManager, InterfaceMapping};
+use crate::patch::{Patch, PatchCommand, PatchError};
+use crate::stage::Stage;
+use crate::threads::shared::Lock;
+use crate::timeline::Timeline;
+
+use serde::{Serialize, Deserialize};
+
+use std::path::Path;
+
+pub const SHOW_VERSION: u32 = 1;
+
+// Everything of a show that is saved to the show file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Show {
+    pub version: u32,
+    pub universes: usize,
+    #[serde(default)]
+    pub interfaces: Vec<InterfaceMapping>,
+    #[serde(default)]
+    pub patch: Patch,
+    #[serde(default)]
+    pub timelines: Vec<Timeline>,
+    #[serde(default)]
+    pub stage: Stage,
+}
+
+impl Show {
+    pub fn load(path: &Path) -> Result<Show, ShowError> {
+        let show: Show = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if show.version > SHOW_VERSION {
+            return Err(ShowError::Version(show.version));
+        }
+        Ok(show)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ShowError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+impl Default for Show {
+    fn default() -> Self {
+        Self {
+            version: SHOW_VERSION,
+            universes: 1,
+            interfaces: Vec::new(),
+            patch: Patch::new(),
+            timelines: Vec::new(),
+            stage: Stage::default(),
+        }
+    }
+}
+
+// The open show, every part behind its own lock so the pages and the output can share it
+#[derive(Debug, Clone)]
+pub struct ShowState {
+    pub patch: Lock<Patch>,
+    pub timelines: Lock<Vec<Timeline>>,
+    pub stage: Lock<Stage>,
+    pub interfaces: Lock<InterfaceManager>,
+    // The universes of the output engine, only their count belongs to the show
+    pub universes: Lock<Vec<DMXUniverse>>,
+}
+
+impl ShowState {
+    pub fn new(universes: Lock<Vec<DMXUniverse>>) -> ShowState {
+        ShowState {
+            patch: Lock::default(),
+            timelines: Lock::default(),
+            stage: Lock::default(),
+            interfaces: Lock::default(),
+            universes,
+        }
+    }
+
+    pub fn show(&self) -> Show {
+        Show {
+            universes: self.universes.read().unwrap().len(),
+            interfaces: self.interfaces.read().unwrap().mappings(),
+            patch: self.patch.read().unwrap().clone(),
+            timelines: self.timelines.read().unwrap().clone(),
+            stage: self.stage.read().unwrap().clone(),
+            ..Show::default()
+        }
+    }
+
+    pub fn load(&self, show: Show) {
+        self.universes.write().unwrap().resize(show.universes.max(1), DMXUniverse::new());
+        self.interfaces.write().unwrap().set_mappings(show.interfaces);
+        *self.patch.write().unwrap() = show.patch;
+        *self.timelines.write().unwrap() = show.timelines;
+        *self.stage.write().unwrap() = show.stage;
+    }
+}
+
+impl Default for ShowState {
+    fn default() -> Self {
+        Self::new(Lock::new(vec![DMXUniverse::new()]))
+    }
+}
+
+// Undoable change of the show. Apart from the patch, editors change a copy of a part
+// and the command swaps it in, the copy it replaced is kept for undo
+#[derive(Debug, Clone)]
+pub enum ShowCommand {
+    // Removing a fixture moves the timeline targets along, `timelines` are the ones from before
+    Patch { command: PatchCommand, timelines: Option<Vec<Timeline>> },
+    Timelines { timelines: Vec<Timeline>, previous: Option<Vec<Timeline>> },
+    Stage { stage: Stage, previous: Option<Stage> },
+    Interfaces { mappings: Vec<InterfaceMapping>, previous: Option<Vec<InterfaceMapping>> },
+    Universes { count: usize, previous: Option<usize> },
+}
+
+impl ShowCommand {
+    pub fn timelines(timelines: Vec<Timeline>) -> ShowCommand {
+        ShowCommand::Timelines { timelines, previous: None }
+    }
+
+    pub fn stage(stage: Stage) -> ShowCommand {
+        ShowCommand::Stage { stage, previous: None }
+    }
+
+    pub fn interfaces(mappings: Vec<InterfaceMapping>) -> ShowCommand {
+        ShowCommand::Interfaces { mappings, previous: None }
+    }
+
+    pub fn universes(count: usize) -> ShowCommand {
+        ShowCommand::Universes { count: count.max(1), previous: None }
+    }
+}
+
+impl From<PatchCommand> for ShowCommand {
+    fn from(command: PatchCommand) -> Self {
+        ShowCommand::Patch { command, timelines: None }
+    }
+}
+
+impl Command for ShowCommand {
+    type Target = ShowState;
+    type Error = PatchError;
+
+    fn apply(&mut self, show: &mut ShowState) -> Result<(), PatchError> {
+        match self {
+            ShowCommand::Patch { command, timelines } => {
+                command.apply(&mut show.patch.write().unwrap())?;
+                if let PatchCommand::Remove { index, .. } = command {
+                    let mut current = show.timelines.write().unwrap();
+                    *timelines = Some(current.clone());
+                    current.iter_mut().for_each(|timeline| timeline.remove_fixture(*index));
+                }
+            },
+            ShowCommand::Timelines { timelines, previous } => *previous = Some(std::mem::replace(&mut *show.timelines.write().unwrap(), timelines.clone())),
+            ShowCommand::Stage { stage, previous } => *previous = Some(std::mem::replace(&mut *show.stage.write().unwrap(), stage.clone())),
+            ShowCommand::Interfaces { mappings, previous } => {
+                let mut interfaces = show.interfaces.write().unwrap();
+                *previous = Some(interfaces.mappings());
+                interfaces.set_mappings(mappings.clone());
+            },
+            ShowCommand::Universes { count, previous } => {
+                let mut universes = show.universes.write().unwrap();
+                *previous = Some(universes.len());
+                universes.resize(*count, DMXUniverse::new());
+            },
+        }
+        Ok(())
+    }
+
+    fn revert(&mut self, show: &mut ShowState) {
+        match self {
+            ShowCommand::Patch { command, timelines } => {
+                command.revert(&mut show.patch.write().unwrap());
+                if let Some(timelines) = timelines.take() {
+                    *show.timelines.write().unwrap() = timelines;
+                }
+            },
+            ShowCommand::Timelines { previous: Some(previous), .. } => *show.timelines.write().unwrap() = previous.clone(),
+            ShowCommand::Stage { previous: Some(previous), .. } => *show.stage.write().unwrap() = previous.clone(),
+            ShowCommand::Interfaces { previous: Some(previous), .. } => show.interfaces.write().unwrap().set_mappings(previous.clone()),
+            ShowCommand::Universes { previous: Some(previous), .. } => show.universes.write().unwrap().resize(*previous, DMXUniverse::new()),
+            _ => {},
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            ShowCommand::Patch { command, .. } => command.description(),
+            ShowCommand::Timelines { .. } => String::from("Edit timelines"),
+            ShowCommand::Stage { .. } => String::from("Edit stage"),
+            ShowCommand::Interfaces { .. } => String::from("Change interface mappings"),
+            ShowCommand::Universes { count, .. } => format!("Use {} universes", count),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            ShowCommand::Patch { command, timelines } => command.size() + json_size(timelines),
+            ShowCommand::Timelines { timelines, previous } => std::mem::size_of::<Self>() + json_size(timelines) + json_size(previous),
+            ShowCommand::Stage { stage, previous } => std::mem::size_of::<Self>() + json_size(stage) + json_size(previous),
+            ShowCommand::Interfaces { mappings, previous } => std::mem::size_of::<Self>() + json_size(mappings) + json_size(previous),
+            ShowCommand::Universes { .. } => std::mem::size_of::<Self>(),
+        }
+    }
+
+    // Slider drags and typing replace the same part every frame
+    fn merge(&mut self, next: &Self) -> bool {
+        match (self, next) {
+            (ShowCommand::Patch { command, .. }, ShowCommand::Patch { command: next, .. }) => return command.merge(next),
+            (ShowCommand::Timelines { timelines, .. }, ShowCommand::Timelines { timelines: next, .. }) => *timelines = next.clone(),
+            (ShowCommand::Stage { stage, .. }, ShowCommand::Stage { stage: next, .. }) => *stage = next.clone(),
+            (ShowCommand::Interfaces { mappings, .. }, ShowCommand::Interfaces { mappings: next, .. }) => *mappings = next.clone(),
+            (ShowCommand::Universes { count, .. }, ShowCommand::Universes { count: next, .. }) => *count = *next,
+            _ => return false,
+        }
+        true
+    }
+}
+
+// Like the fixture models of the patch, the JSON size is close enough
+fn json_size<T: Serialize>(value: &T) -> usize {
+    serde_json::to_vec(value).map_or(0, |json| json.len())
+}
+
+#[derive(Debug)]
+pub enum ShowError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    // The show was saved by a newer version
+    Version(u32),
+}
+
+impl From<std::io::Error> for ShowError {
+    fn from(error: std::io::Error) -> Self {
+        ShowError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ShowError {
+    fn from(error: serde_json::Error) -> Self {
+        ShowError::Json(error)
+    }
+}
+
+impl std::fmt::Display for ShowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShowError::Io(error) => write!(f, "{}", error),
+            ShowError::Json(error) => write!(f, "invalid show file: {}", error),
+            ShowError::Version(version) => write!(f, "the show file has version {}, this version of DMXT reads up to {}", version, SHOW_VERSION),
+        }
+    }
+}
+
+impl std::error::Error for ShowError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::History;
+    use crate::test_support::test_fixture;
+
+    #[test]
+    fn edits_of_every_part_are_undone() {
+        let mut state = ShowState::default();
+        let mut history = History::default();
+        history.execute(PatchCommand::add(test_fixture(1, 1)).into(), &mut state).unwrap();
+        history.mark_saved();
+
+        history.begin_group();
+        for count in 2..=4 {
+            history.execute(ShowCommand::universes(count), &mut state).unwrap();
+        }
+        history.end_group();
+        let mut timelines = state.timelines.read().unwrap().clone();
+        timelines.push(Timeline::new("Intro".into()));
+        history.execute(ShowCommand::timelines(timelines), &mut state).unwrap();
+        let stage = Stage { width: 12.0, ..Stage::default() };
+        history.execute(ShowCommand::stage(stage.clone()), &mut state).unwrap();
+        assert!(history.is_dirty());
+        assert_eq!(state.show().universes, 4);
+        assert_eq!(state.show().stage, stage);
+
+        assert_eq!(history.undo(&mut state), Some("Edit stage".into()));
+        assert_eq!(history.undo(&mut state), Some("Edit timelines".into()));
+        assert!(state.timelines.read().unwrap().is_empty());
+        // The drag of the universe count is one step
+        assert_eq!(history.undo(&mut state), Some("Use 4 universes".into()));
+        assert_eq!(state.universes.read().unwrap().len(), 1);
+        assert!(!history.is_dirty());
+        assert_eq!(state.patch.read().unwrap().len(), 1);
+        assert_eq!(state.show().stage, Stage::default());
+    }
+
+    #[test]
+    fn tracks_keep_their_fixtures_when_one_is_removed() {
+        use crate::timeline::{Target, Track};
+
+        let mut state = ShowState::default();
+        let mut history = History::default();
+        for address in [1, 2, 3] {
+            history.execute(PatchCommand::add(test_fixture(1, address)).into(), &mut state).unwrap();
+        }
+        let mut timeline = Timeline::new("Intro".into());
+        timeline.tracks.push(Track::new("Sides".into(), Target::Fixtures(vec![0, 2])));
+        history.execute(ShowCommand::timelines(vec![timeline]), &mut state).unwrap();
+        let addresses = |state: &ShowState| {
+            let patch = state.patch.read().unwrap();
+            state.timelines.read().unwrap()[0].tracks[0].target.fixtures(&patch).iter()
+                .map(|index| patch.fixtures()[*index].address.channel.id())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(addresses(&state), [1, 3]);
+
+        history.execute(PatchCommand::remove(0).into(), &mut state).unwrap();
+        assert_eq!(state.timelines.read().unwrap()[0].tracks[0].target, Target::Fixtures(vec![1]));
+        assert_eq!(addresses(&state), [3]);
+        history.undo(&mut state);
+        assert_eq!(addresses(&state), [1, 3]);
+        history.redo(&mut state).unwrap();
+        assert_eq!(addresses(&state), [3]);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::effects::movement::PanTilt;
+use crate::patch::Patch;
+
+use serde::{Serialize, Deserialize};
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+// Stage coordinates are meters from the downstage center on the floor,
+// x runs to the right as seen from the audience, y upstage and z up
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Point3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Point3 {
+    pub const ORIGIN: Point3 = Point3 { x: 0.0, y: 0.0, z: 0.0 };
+    pub const UP: Point3 = Point3 { x: 0.0, y: 0.0, z: 1.0 };
+
+    pub fn new(x: f64, y: f64, z: f64) -> Point3 {
+        Point3 { x, y, z }
+    }
+
+    pub fn dot(&self, other: Point3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: Point3) -> Point3 {
+        Point3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length(&self) -> f64 {
+        self.dot(*self).sqrt()
+    }
+
+    pub fn distance(&self, other: Point3) -> f64 {
+        (*self - other).length()
+    }
+
+    // Unit vector in the same direction, the zero vector stays zero
+    pub fn normalized(&self) -> Point3 {
+        let length = self.length();
+        if length > 0.0 { *self * (1.0 / length) } else { *self }
+    }
+
+    pub fn lerp(&self, other: Point3, t: f64) -> Point3 {
+        *self + (other - *self) * t
+    }
+}
+
+impl Add for Point3 {
+    type Output = Point3;
+
+    fn add(self, other: Point3) -> Point3 {
+        Point3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Point3;
+
+    fn sub(self, other: Point3) -> Point3 {
+        Point3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Mul<f64> for Point3 {
+    type Output = Point3;
+
+    fn mul(self, factor: f64) -> Point3 {
+        Point3::new(self.x * factor, self.y * factor, self.z * factor)
+    }
+}
+
+impl Neg for Point3 {
+    type Output = Point3;
+
+    fn neg(self) -> Point3 {
+        Point3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+// Fixture coordinates have z along the axis from the beam to the base, the beam of a
+// moving head at pan 0 / tilt 0 points along -z and pan 0 turns it towards -y
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mounting {
+    // Base up, e.g. clamped to a truss
+    #[default]
+    Hanging,
+    // Base down on the floor
+    Standing,
+    // Base against a wall or a vertical pipe, the beam points towards the audience
+    Sideways,
+}
+
+impl Mounting {
+    pub const ALL: [Mounting; 3] = [Mounting::Hanging, Mounting::Standing, Mounting::Sideways];
+
+    fn orient(&self, direction: Point3) -> Point3 {
+        let Point3 { x, y, z } = direction;
+        match self {
+            Mounting::Hanging => direction,
+            Mounting::Standing => Point3::new(x, -y, -z),
+            Mounting::Sideways => Point3::new(x, z, -y),
+        }
+    }
+
+    fn unorient(&self, direction: Point3) -> Point3 {
+        let Point3 { x, y, z } = direction;
+        match self {
+            Mounting::Hanging => direction,
+            Mounting::Standing => Point3::new(x, -y, -z),
+            Mounting::Sideways => Point3::new(x, -z, y),
+        }
+    }
+}
+
+impl std::fmt::Display for Mounting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mounting::Hanging => write!(f, "Hanging"),
+            Mounting::Standing => write!(f, "Standing"),
+            Mounting::Sideways => write!(f, "Sideways"),
+        }
+    }
+}
+
+// Direction of the beam in fixture coordinates for a position in degrees from the middle of both axes
+pub fn beam_direction(position: PanTilt) -> Point3 {
+    let (pan, tilt) = (position.pan.to_radians(), position.tilt.to_radians());
+    Point3::new(tilt.sin() * pan.sin(), -tilt.sin() * pan.cos(), -tilt.cos())
+}
+
+// Where a fixture is on stage and how it is mounted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Placement {
+    pub position: Point3,
+    // Degrees around the vertical axis, counterclockwise seen from above
+    #[serde(default)]
+    pub rotation: f64,
+    #[serde(default)]
+    pub mounting: Mounting,
+}
+
+impl Placement {
+    pub fn new(position: Point3) -> Placement {
+        Placement { position, ..Placement::default() }
+    }
+
+    // Turns a direction in fixture coordinates into stage coordinates
+    pub fn to_stage(&self, direction: Point3) -> Point3 {
+        let Point3 { x, y, z } = self.mounting.orient(direction);
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        Point3::new(x * cos - y * sin, x * sin + y * cos, z)
+    }
+
+    // Inverse of `to_stage`
+    pub fn to_fixture(&self, direction: Point3) -> Point3 {
+        let Point3 { x, y, z } = direction;
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        self.mounting.unorient(Point3::new(x * cos + y * sin, -x * sin + y * cos, z))
+    }
+
+    // Pan and tilt from the middle of both axes that point the beam at a point, the inverse of `beam_direction`
+    pub fn aim(&self, target: Point3) -> PanTilt {
+        let direction = self.to_fixture(target - self.position).normalized();
+        if direction == Point3::ORIGIN {
+            return PanTilt::default();
+        }
+        let tilt = (-direction.z).clamp(-1.0, 1.0).acos();
+        // Straight down any pan will do, keep it at home
+        let pan = if tilt.sin().abs() < 1e-9 { 0.0 } else { direction.x.atan2(-direction.y) };
+        PanTilt::new(pan.to_degrees(), tilt.to_degrees())
+    }
+
+    // Where a beam in stage coordinates hits the floor, None if it doesn't point down
+    pub fn floor_hit(&self, direction: Point3) -> Option<Point3> {
+        if direction.z >= -1e-3 || self.position.z <= 0.0 {
+            return None;
+        }
+        Some(self.position + direction * (self.position.z / -direction.z))
+    }
+}
+
+// A pipe or truss, fixtures with its name as rigging position can be hung on it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Truss {
+    pub name: String,
+    pub start: Point3,
+    pub end: Point3,
+}
+
+impl Truss {
+    pub fn new(name: String, start: Point3, end: Point3) -> Truss {
+        Truss { name, start, end }
+    }
+
+    pub fn length(&self) -> f64 {
+        self.start.distance(self.end)
+    }
+
+    // Evenly spaced points, with half a space at both ends
+    pub fn spread(&self, count: usize) -> Vec<Point3> {
+        (0..count).map(|index| self.start.lerp(self.end, (index as f64 + 0.5) / count as f64)).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stage {
+    pub width: f64,
+    pub depth: f64,
+    #[serde(default)]
+    pub trusses: Vec<Truss>,
+}
+
+impl Stage {
+    // Rigging positions match truss names regardless of case, like timeline targets
+    pub fn truss(&self, name: &str) -> Option<&Truss> {
+        self.trusses.iter().find(|truss| truss.name.eq_ignore_ascii_case(name))
+    }
+
+    // Placements that spread the fixtures of every truss along it in patch order.
+    // Fixtures keep their rotation and mounting, fixtures without a truss are left out
+    pub fn hang(&self, patch: &Patch) -> Vec<(usize, Placement)> {
+        let mut placements = Vec::new();
+        for truss in &self.trusses {
+            let fixtures: Vec<usize> = patch.fixtures().iter().enumerate()
+                .filter(|(_, fixture)| fixture.position.eq_ignore_ascii_case(&truss.name))
+                .map(|(index, _)| index)
+                .collect();
+            for (index, position) in fixtures.iter().zip(truss.spread(fixtures.len())) {
+                let placement = patch.fixtures()[*index].placement.unwrap_or_default();
+                placements.push((*index, Placement { position, ..placement }));
+            }
+        }
+        placements
+    }
+
+    // Placement of every fixture, fixtures that haven't been placed hang on the truss of their rigging position
+    pub fn placements(&self, patch: &Patch) -> Vec<Option<Placement>> {
+        let mut placements: Vec<Option<Placement>> = patch.fixtures().iter().map(|fixture| fixture.placement).collect();
+        for (index, placement) in self.hang(patch) {
+            placements[index].get_or_insert(placement);
+        }
+        placements
+    }
+}
+
+impl Default for Stage {
+    fn default() -> Self {
+        Self {
+            width: 10.0,
+            depth: 8.0,
+            trusses: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_fixture;
+
+    fn close(a: Point3, b: Point3) -> bool {
+        a.distance(b) < 1e-9
+    }
+
+    #[test]
+    fn placements_orient_beams_and_hang_on_trusses() {
+        let down = Point3::new(0.0, 0.0, -1.0);
+        let mut placement = Placement::new(Point3::new(1.0, 2.0, 5.0));
+        assert_eq!(placement.floor_hit(down), Some(Point3::new(1.0, 2.0, 0.0)));
+        placement.mounting = Mounting::Standing;
+        assert!(close(placement.to_stage(down), Point3::UP));
+        assert_eq!(placement.floor_hit(placement.to_stage(down)), None);
+        placement.mounting = Mounting::Sideways;
+        placement.rotation = 90.0;
+        // Facing the audience, then turned a quarter counterclockwise to point to the right of the audience
+        let beam = placement.to_stage(down);
+        assert!(close(beam, Point3::new(1.0, 0.0, 0.0)));
+        assert!(close(placement.to_fixture(beam), down));
+
+        let mut patch = Patch::new();
+        for (address, position) in [(1, "LX1"), (2, "floor"), (3, "lx1")] {
+            let mut fixture = test_fixture(1, address);
+            fixture.position = position.into();
+            patch.add(fixture).unwrap();
+        }
+        let mut stage = Stage::default();
+        stage.trusses.push(Truss::new("LX1".into(), Point3::new(-2.0, 3.0, 6.0), Point3::new(2.0, 3.0, 6.0)));
+        let placements = stage.placements(&patch);
+        assert_eq!(placements[0].unwrap().position, Point3::new(-1.0, 3.0, 6.0));
+        assert_eq!(placements[1], None);
+        assert_eq!(placements[2].unwrap().position, Point3::new(1.0, 3.0, 6.0));
+    }
+}
@@ -0,0 +1,20 @@
+use crate::builders::fixture::{FixtureChannelMode, FixtureModel, FixtureName};
+use crate::components::Fixture;
+use crate::dmx::{Channel, DMXAddress};
+
+// A "Par" by "Test" with a single channel mode, shared by the tests of every module
+pub(crate) fn test_fixture_with(mode: FixtureChannelMode, address: u16) -> Fixture {
+    let model = FixtureModel::builder()
+        .model(FixtureName::new("Par".into()))
+        .manufacturer("Test".into())
+        .channel_mode(mode)
+        .build()
+        .unwrap();
+    Fixture::new("Par".into(), DMXAddress::new(Channel::new(address).unwrap(), 0), model).unwrap()
+}
+
+pub(crate) fn test_fixture(channels: u16, address: u16) -> Fixture {
+    let mut mode = FixtureChannelMode::builder();
+    mode.total_channels(Channel::new(channels).unwrap());
+    test_fixture_with(mode.build().unwrap(), address)
+}
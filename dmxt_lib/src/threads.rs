@@ -6,6 +6,21 @@ pub mod shared {
         inner: Arc<RwLock<T>>,
     }
 
+    // Clones share the value, like cloning an `Arc`
+    impl<T> Clone for Lock<T> {
+        fn clone(&self) -> Self {
+            Self {
+                inner: self.inner.clone(),
+            }
+        }
+    }
+
+    impl<T: Default> Default for Lock<T> {
+        fn default() -> Self {
+            Self::new(T::default())
+        }
+    }
+
     impl<T> Lock<T> {
         pub fn new(val: T) -> Self {
             Self {
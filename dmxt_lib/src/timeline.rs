@@ -0,0 +1,504 @@
+use crate::components::color::RGB;
+use crate::components::Fixture;
+use crate::dmx::{Channel, DMXUniverse};
+use crate::effects::movement::ShapeGenerator;
+use crate::effects::spatial::{Focus, Wave};
+use crate::patch::Patch;
+use crate::recording::Recording;
+use crate::stage::{Placement, Stage};
+use crate::timing::BPM;
+
+use open_dmx::error::DMXError;
+
+use serde::{Serialize, Deserialize};
+
+// Pre-programmed shows, e.g. synced to a song. Everything on a timeline is positioned in its time base,
+// so a timeline in beats follows tempo changes and one in seconds stays fixed
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeBase {
+    Beats,
+    Seconds,
+}
+
+impl std::fmt::Display for TimeBase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeBase::Beats => write!(f, "Beats"),
+            TimeBase::Seconds => write!(f, "Seconds"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Timeline {
+    pub name: String,
+    pub time_base: TimeBase,
+    // Tempo beats are played at
+    pub bpm: BPM,
+    // In the time base
+    pub length: f64,
+    pub looped: bool,
+    pub tracks: Vec<Track>,
+}
+
+impl Timeline {
+    pub fn new(name: String) -> Timeline {
+        Timeline {
+            name,
+            time_base: TimeBase::Beats,
+            bpm: 120.0,
+            length: 32.0,
+            looped: false,
+            tracks: Vec::new(),
+        }
+    }
+
+    // Position in the time base to seconds and back
+    pub fn seconds(&self, position: f64) -> f64 {
+        match self.time_base {
+            TimeBase::Beats => position * 60.0 / self.bpm.max(1.0),
+            TimeBase::Seconds => position,
+        }
+    }
+
+    pub fn position(&self, seconds: f64) -> f64 {
+        match self.time_base {
+            TimeBase::Beats => seconds * self.bpm.max(1.0) / 60.0,
+            TimeBase::Seconds => seconds,
+        }
+    }
+
+    // Keyframes are written first, then clips on top. Later tracks win over earlier ones.
+    // Spatial clips use where the fixtures are on the stage
+    pub fn render(&self, position: f64, patch: &Patch, stage: &Stage, universes: &mut [DMXUniverse]) -> Result<(), DMXError> {
+        self.render_synced(position, None, patch, stage, universes)
+    }
+
+    // Like `render`, with the beat position of a running metronome. Clips of a timeline in beats
+    // then cycle on its beats instead of the timeline tempo, so they stay on a tapped tempo
+    pub fn render_synced(&self, position: f64, beat: Option<f64>, patch: &Patch, stage: &Stage, universes: &mut [DMXUniverse]) -> Result<(), DMXError> {
+        let placements = stage.placements(patch);
+        for track in self.tracks.iter().filter(|track| !track.muted) {
+            let group = track.target.fixtures(patch);
+            for lane in &track.lanes {
+                if let Some(value) = lane.value_at(position) {
+                    for fixture in group.iter().filter_map(|index| patch.get(*index)) {
+                        if let Some(universe) = universes.get_mut(fixture.universe) {
+                            lane.attribute.write(fixture, universe, value)?;
+                        }
+                    }
+                }
+            }
+            for clip in track.clips.iter().filter(|clip| clip.contains(position)) {
+                let phase = match (beat, self.time_base) {
+                    (Some(beat), TimeBase::Beats) => beat * clip.rate,
+                    _ => (position - clip.start) * clip.rate,
+                };
+                clip.effect.render(phase, patch.fixtures(), &placements, &group, universes)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Keeps the targets on the same fixtures after a fixture was removed from the patch
+    pub fn remove_fixture(&mut self, index: usize) {
+        for track in &mut self.tracks {
+            track.target.remove_fixture(index);
+        }
+    }
+
+    // The whole timeline at a fixed frame rate, every frame starts from blacked out universes
+    pub fn render_frames(&self, patch: &Patch, stage: &Stage, universes: usize, frame_rate: f64) -> Result<Recording, DMXError> {
+        let mut recording = Recording::new();
+        let mut frame = vec![DMXUniverse::new(); universes];
+        let duration = self.seconds(self.length);
+        let frame_rate = frame_rate.max(1.0);
+        for index in 0..=(duration * frame_rate).floor() as usize {
+            let time = index as f64 / frame_rate;
+            frame.iter_mut().for_each(DMXUniverse::clear);
+            self.render(self.position(time), patch, stage, &mut frame)?;
+            recording.push(time, &frame);
+        }
+        Ok(recording)
+    }
+}
+
+// Fixtures a track controls. Patch indices, or every fixture rigged at a position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Target {
+    Fixtures(Vec<usize>),
+    Position(String),
+}
+
+impl Target {
+    pub fn fixtures(&self, patch: &Patch) -> Vec<usize> {
+        match self {
+            Target::Fixtures(fixtures) => fixtures.iter().copied().filter(|index| *index < patch.len()).collect(),
+            Target::Position(position) => patch.fixtures().iter().enumerate()
+                .filter(|(_, fixture)| fixture.position.eq_ignore_ascii_case(position))
+                .map(|(index, _)| index)
+                .collect(),
+        }
+    }
+
+    // The removed fixture is dropped, the fixtures after it move down one index
+    pub fn remove_fixture(&mut self, removed: usize) {
+        if let Target::Fixtures(fixtures) = self {
+            fixtures.retain(|index| *index != removed);
+            fixtures.iter_mut().filter(|index| **index > removed).for_each(|index| *index -= 1);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Track {
+    pub name: String,
+    pub target: Target,
+    pub muted: bool,
+    pub lanes: Vec<Lane>,
+    pub clips: Vec<Clip>,
+}
+
+impl Track {
+    pub fn new(name: String, target: Target) -> Track {
+        Track {
+            name,
+            target,
+            muted: false,
+            lanes: Vec::new(),
+            clips: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Attribute {
+    Color,
+    // Normalized pan and tilt
+    Position,
+    // Fixture relative channel, starting at 1
+    Channel(u16),
+}
+
+impl Attribute {
+    pub fn default_value(&self) -> Value {
+        match self {
+            Attribute::Color => Value::Color(RGB::WHITE),
+            Attribute::Position => Value::Position(0.5, 0.5),
+            Attribute::Channel(_) => Value::Level(0.0),
+        }
+    }
+
+    // Values of another attribute are ignored
+    fn write(&self, fixture: &Fixture, universe: &mut DMXUniverse, value: Value) -> Result<(), DMXError> {
+        match (self, value) {
+            (Attribute::Color, Value::Color(color)) => fixture.fill_color(universe, color),
+            (Attribute::Position, Value::Position(pan, tilt)) => fixture.write_position_normalized(universe, pan, tilt),
+            (Attribute::Channel(channel), Value::Level(level)) if *channel <= fixture.channel_count() => {
+                fixture.write(universe, Channel::new(*channel)?, (level.clamp(0.0, 1.0) * 255.0).round() as u8)
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Attribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Attribute::Color => write!(f, "Color"),
+            Attribute::Position => write!(f, "Position"),
+            Attribute::Channel(channel) => write!(f, "Channel {}", channel),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Value {
+    Level(f64),
+    Color(RGB),
+    Position(f64, f64),
+}
+
+impl Value {
+    // Values of different kinds jump at the end
+    pub fn lerp(&self, other: Value, t: f64) -> Value {
+        let mix = |a: f64, b: f64| a + (b - a) * t;
+        match (*self, other) {
+            (Value::Level(a), Value::Level(b)) => Value::Level(mix(a, b)),
+            (Value::Color(a), Value::Color(b)) => Value::Color(a.lerp(b, t)),
+            (Value::Position(pan, tilt), Value::Position(other_pan, other_tilt)) => Value::Position(mix(pan, other_pan), mix(tilt, other_tilt)),
+            (value, other) => if t < 1.0 { value } else { other },
+        }
+    }
+}
+
+// How a keyframe moves on to the next one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Curve {
+    Step,
+    Linear,
+    Smooth,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub time: f64,
+    pub value: Value,
+    pub curve: Curve,
+}
+
+// Keyframes of one attribute, sorted by time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lane {
+    pub attribute: Attribute,
+    keyframes: Vec<Keyframe>,
+}
+
+impl Lane {
+    pub fn new(attribute: Attribute) -> Lane {
+        Lane { attribute, keyframes: Vec::new() }
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    // Replaces a keyframe at the same time, returns the index of the keyframe
+    pub fn insert(&mut self, keyframe: Keyframe) -> usize {
+        let index = self.keyframes.partition_point(|other| other.time < keyframe.time);
+        match self.keyframes.get(index) {
+            Some(other) if other.time == keyframe.time => self.keyframes[index] = keyframe,
+            _ => self.keyframes.insert(index, keyframe),
+        }
+        index
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Keyframe> {
+        (index < self.keyframes.len()).then(|| self.keyframes.remove(index))
+    }
+
+    // Moving a keyframe can change its place, returns the new index
+    pub fn update(&mut self, index: usize, keyframe: Keyframe) -> Option<usize> {
+        self.remove(index).map(|_| self.insert(keyframe))
+    }
+
+    // The first and last keyframe hold their value before and after them
+    pub fn value_at(&self, position: f64) -> Option<Value> {
+        let next = self.keyframes.partition_point(|keyframe| keyframe.time <= position);
+        let (from, to) = match (next.checked_sub(1).map(|index| &self.keyframes[index]), self.keyframes.get(next)) {
+            (None, to) => return to.map(|keyframe| keyframe.value),
+            (Some(from), None) => return Some(from.value),
+            (Some(from), Some(to)) => (from, to),
+        };
+        let t = (position - from.time) / (to.time - from.time);
+        let t = match from.curve {
+            Curve::Step => 0.0,
+            Curve::Linear => t,
+            Curve::Smooth => t * t * (3.0 - 2.0 * t),
+        };
+        Some(from.value.lerp(to.value, t))
+    }
+}
+
+// An effect running for a while on the fixtures of a track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Clip {
+    pub start: f64,
+    pub length: f64,
+    // Effect cycles per unit of the time base
+    pub rate: f64,
+    pub effect: ClipEffect,
+}
+
+impl Clip {
+    pub fn new(start: f64, length: f64, effect: ClipEffect) -> Clip {
+        Clip { start, length, rate: 1.0, effect }
+    }
+
+    pub fn end(&self) -> f64 {
+        self.start + self.length
+    }
+
+    pub fn contains(&self, position: f64) -> bool {
+        position >= self.start && position < self.end()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClipEffect {
+    Movement(ShapeGenerator),
+    // Hues spread over the group, 1.0 shows the whole rainbow at once
+    Rainbow { spread: f64 },
+    // `width` fixtures lit at a time, running through the group once per cycle
+    Chase { color: RGB, width: usize },
+    // On for the first half of every cycle
+    Strobe { color: RGB },
+    Wave(Wave),
+    Focus(Focus),
+}
+
+impl ClipEffect {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClipEffect::Movement(_) => "Movement",
+            ClipEffect::Rainbow { .. } => "Rainbow",
+            ClipEffect::Chase { .. } => "Chase",
+            ClipEffect::Strobe { .. } => "Strobe",
+            ClipEffect::Wave(_) => "Wave",
+            ClipEffect::Focus(_) => "Focus",
+        }
+    }
+
+    // `group` indexes into `fixtures`, `placements` are by fixture
+    pub fn render(&self, phase: f64, fixtures: &[Fixture], placements: &[Option<Placement>], group: &[usize], universes: &mut [DMXUniverse]) -> Result<(), DMXError> {
+        match self {
+            ClipEffect::Movement(generator) => generator.render(phase, fixtures, group, universes),
+            ClipEffect::Wave(wave) => wave.render(phase, fixtures, placements, group, universes),
+            ClipEffect::Focus(focus) => focus.render(phase, fixtures, placements, group, universes),
+            ClipEffect::Rainbow { spread } => fill_group(fixtures, group, universes, |index, count| {
+                RGB::from_hsv(phase + spread * index as f64 / count as f64, 1.0, 1.0)
+            }),
+            ClipEffect::Chase { color, width } => fill_group(fixtures, group, universes, |index, count| {
+                let head = (phase.rem_euclid(1.0) * count as f64) as usize;
+                if (index + count - head) % count < (*width).max(1) { *color } else { RGB::BLACK }
+            }),
+            ClipEffect::Strobe { color } => fill_group(fixtures, group, universes, |_, _| {
+                if phase.rem_euclid(1.0) < 0.5 { *color } else { RGB::BLACK }
+            }),
+        }
+    }
+}
+
+// Fills every fixture of the group with the color for its place in the group, `color` gets the place and the group size
+fn fill_group(fixtures: &[Fixture], group: &[usize], universes: &mut [DMXUniverse], color: impl Fn(usize, usize) -> RGB) -> Result<(), DMXError> {
+    for (index, fixture) in group.iter().enumerate() {
+        let Some(fixture) = fixtures.get(*fixture) else {
+            continue;
+        };
+        if let Some(universe) = universes.get_mut(fixture.universe) {
+            fixture.fill_color(universe, color(index, group.len()))?;
+        }
+    }
+    Ok(())
+}
+
+// Playhead of a timeline, in its time base
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Playback {
+    pub position: f64,
+    pub playing: bool,
+}
+
+impl Playback {
+    pub fn stop(&mut self) {
+        *self = Playback::default();
+    }
+
+    pub fn seek(&mut self, timeline: &Timeline, position: f64) {
+        self.position = position.clamp(0.0, timeline.length.max(0.0));
+    }
+
+    // Moves the playhead by elapsed seconds. Looped timelines start over, others stop at the end
+    pub fn advance(&mut self, timeline: &Timeline, elapsed: f64) {
+        if !self.playing {
+            return;
+        }
+        let position = self.position + timeline.position(elapsed);
+        if position < timeline.length {
+            self.position = position;
+        } else if timeline.looped && timeline.length > 0.0 {
+            self.position = position.rem_euclid(timeline.length);
+        } else {
+            self.position = timeline.length.max(0.0);
+            self.playing = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_fixture;
+
+    #[test]
+    fn keyframes_render_and_playback_loops() {
+        let mut patch = Patch::new();
+        let mut fixture = test_fixture(2, 5);
+        fixture.position = "FOH".into();
+        patch.add(fixture).unwrap();
+
+        let mut lane = Lane::new(Attribute::Channel(2));
+        lane.insert(Keyframe { time: 4.0, value: Value::Level(1.0), curve: Curve::Step });
+        lane.insert(Keyframe { time: 0.0, value: Value::Level(0.0), curve: Curve::Linear });
+        let mut track = Track::new("Front".into(), Target::Position("foh".into()));
+        track.lanes.push(lane);
+        let mut timeline = Timeline::new("Intro".into());
+        timeline.length = 8.0;
+        timeline.tracks.push(track);
+
+        let mut universes = vec![DMXUniverse::new()];
+        timeline.render(2.0, &patch, &Stage::default(), &mut universes).unwrap();
+        assert_eq!(universes[0].channels[5], 128);
+        timeline.render(6.0, &patch, &Stage::default(), &mut universes).unwrap();
+        assert_eq!(universes[0].channels[5], 255);
+
+        // 8 beats at 120 BPM are 4 seconds
+        let recording = timeline.render_frames(&patch, &Stage::default(), 1, 10.0).unwrap();
+        assert_eq!(recording.len(), 41);
+        assert_eq!(recording.frames()[10].universes[0].channels[5], 128);
+        let mut bytes = Vec::new();
+        recording.write(&mut bytes).unwrap();
+        assert_eq!(Recording::read(&mut bytes.as_slice()).unwrap(), recording);
+
+        let mut playback = Playback { position: 7.0, playing: true };
+        timeline.looped = true;
+        playback.advance(&timeline, 1.0);
+        assert_eq!(playback.position, 1.0);
+        timeline.looped = false;
+        playback.advance(&timeline, 5.0);
+        assert_eq!(playback, Playback { position: 8.0, playing: false });
+    }
+
+    #[test]
+    fn color_clips_fill_their_group() {
+        use crate::builders::fixture::{FixtureChannelMode, FixtureColorMode, FixtureLights, FixtureMatrix};
+        use crate::test_support::test_fixture_with;
+
+        let channel = |id| Channel::new(id).unwrap();
+        let lights = FixtureLights::new(FixtureColorMode::RGB(channel(1).into(), channel(2).into(), channel(3).into()), None);
+        let mut mode = FixtureChannelMode::builder();
+        mode.total_channels(channel(3)).matrix(FixtureMatrix::new(vec![vec![lights]]));
+        let mode = mode.build().unwrap();
+        let fixtures: Vec<Fixture> = (0..4).map(|index| test_fixture_with(mode.clone(), 1 + index * 3)).collect();
+        let red = |universes: &[DMXUniverse]| universes[0].channels.iter().step_by(3).take(4).copied().collect::<Vec<_>>();
+        let mut universes = vec![DMXUniverse::new()];
+
+        // Halfway through a cycle the chase is at the third fixture of the group
+        ClipEffect::Chase { color: RGB::WHITE, width: 1 }.render(0.5, &fixtures, &[], &[0, 1, 2, 3], &mut universes).unwrap();
+        assert_eq!(red(&universes), [0, 0, 255, 0]);
+        ClipEffect::Strobe { color: RGB::WHITE }.render(0.25, &fixtures, &[], &[0, 1], &mut universes).unwrap();
+        assert_eq!(red(&universes), [255, 255, 255, 0]);
+        ClipEffect::Strobe { color: RGB::WHITE }.render(0.75, &fixtures, &[], &[0, 1, 2], &mut universes).unwrap();
+        assert_eq!(red(&universes), [0, 0, 0, 0]);
+        ClipEffect::Rainbow { spread: 0.0 }.render(0.0, &fixtures, &[], &[3, 7], &mut universes).unwrap();
+        assert_eq!(red(&universes), [0, 0, 0, 255]);
+
+        // Clips of a timeline in beats strobe on the beat of the metronome, not on the timeline tempo
+        let mut patch = Patch::new();
+        for fixture in fixtures {
+            patch.add(fixture).unwrap();
+        }
+        let mut track = Track::new("Strobe".into(), Target::Fixtures(vec![0]));
+        track.clips.push(Clip::new(0.0, 8.0, ClipEffect::Strobe { color: RGB::WHITE }));
+        let mut timeline = Timeline::new("Drop".into());
+        timeline.tracks.push(track);
+        timeline.render_synced(0.25, Some(0.75), &patch, &Stage::default(), &mut universes).unwrap();
+        assert_eq!(red(&universes)[0], 0);
+        timeline.render_synced(0.75, Some(2.25), &patch, &Stage::default(), &mut universes).unwrap();
+        assert_eq!(red(&universes)[0], 255);
+        timeline.time_base = TimeBase::Seconds;
+        timeline.render_synced(0.75, Some(2.25), &patch, &Stage::default(), &mut universes).unwrap();
+        assert_eq!(red(&universes)[0], 0);
+    }
+}
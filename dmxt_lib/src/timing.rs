@@ -8,26 +8,155 @@ use std::sync::Arc;
 use std::sync::Mutex;
 pub type BPM = f64;
 
+// Source of time for everything that moves on its own
+pub trait Clock: Send + Sync {
+    // Time since the clock was created
+    fn now(&self) -> time::Duration;
+    fn sleep(&self, duration: time::Duration);
+    // Whether sleeping actually waits. Threads pacing themselves on a clock that doesn't would spin
+    fn waits(&self) -> bool {
+        true
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+// The wall clock
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    started: time::Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock { started: time::Instant::now() }
+    }
+
+    pub fn shared() -> SharedClock {
+        Arc::new(SystemClock::new())
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> time::Duration {
+        self.started.elapsed()
+    }
+
+    fn sleep(&self, duration: time::Duration) {
+        thread::sleep(duration);
+    }
+}
+
+// Only moves when it is advanced, for offline rendering and tests. Clones share their time
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Arc<Mutex<time::Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock::default()
+    }
+
+    pub fn advance(&self, duration: time::Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+
+    pub fn set(&self, now: time::Duration) {
+        *self.now.lock().unwrap() = now;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> time::Duration {
+        *self.now.lock().unwrap()
+    }
+
+    // Sleeping moves the clock, so code waiting for a time gets there right away
+    fn sleep(&self, duration: time::Duration) {
+        self.advance(duration);
+    }
+
+    fn waits(&self) -> bool {
+        false
+    }
+}
+
 pub struct Metronome {
     bpm: Lock<BPM>,
     content: MetronomeContent,
+    clock: SharedClock,
+    // Time, beat position and tempo of the first tick or last tempo change, when stepped with `tick` instead of a thread.
+    // Beats are counted from there, adding up the beats of every tick would drift
+    anchor: Option<(time::Duration, f64, BPM)>,
+    position: f64,
+    last_tick: Option<time::Duration>,
+    beats: u64,
 
     buffer_cap: u8,
     bpm_buffer: VecDeque<BPM>,
-    last_tap: Option<time::Instant>,
+    last_tap: Option<time::Duration>,
 }
 
 impl Metronome {
     pub fn new(bpm: BPM) -> Metronome {
+        Metronome::with_clock(bpm, SystemClock::shared())
+    }
+
+    pub fn with_clock(bpm: BPM, clock: SharedClock) -> Metronome {
         Metronome {
             bpm: Lock::new(bpm),
             content: MetronomeContent::Callback(Arc::new(Mutex::new(|| {}))),
+            clock,
+            anchor: None,
+            position: 0.0,
+            last_tick: None,
+            beats: 0,
             buffer_cap: 16,
             bpm_buffer: VecDeque::with_capacity(u8::MAX as usize + 1),
             last_tap: None,
         }
     }
 
+    // Beats counted by `tick`
+    pub fn beats(&self) -> u64 {
+        self.beats
+    }
+
+    // Beats since the first tick including the part of the current one, beat n starts at n - 1.0
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    // Calls the callback for every beat since the last tick and returns how many there were.
+    // The first tick is the first beat. Stepping a stopped metronome this way needs no thread
+    pub fn tick(&mut self) -> u64 {
+        let now = self.clock.now();
+        let bpm = self.get_bpm();
+        let (since, start) = match self.anchor {
+            Some((since, start, anchored)) if anchored == bpm => (since, start),
+            _ => (self.last_tick.unwrap_or(now), self.position),
+        };
+        self.anchor = Some((since, start, bpm));
+        self.last_tick = Some(now);
+        self.position = start + now.saturating_sub(since).as_secs_f64() * bpm / 60.0;
+        let beats = self.position.floor() as u64 + 1;
+        let new = beats.saturating_sub(self.beats);
+        if let MetronomeContent::Callback(callback) = &self.content {
+            for _ in 0..new {
+                callback.lock().unwrap()();
+            }
+        }
+        self.beats = self.beats.max(beats);
+        new
+    }
+
     pub fn set_callback(&mut self, callback: Callback) -> Result<(), MetronomeError>{
         match &self.content {
             MetronomeContent::Callback(_) => {
@@ -41,6 +170,9 @@ impl Metronome {
     }
 
     pub fn set_bpm(&mut self, bpm: BPM) -> Result<(), MetronomeError> {
+        if !bpm.is_finite() || bpm <= 0.0 {
+            return Err(MetronomeError::InvalidBpm(bpm));
+        }
         self.bpm.write().unwrap().clone_from(&bpm);
         Ok(())
     }
@@ -49,10 +181,14 @@ impl Metronome {
         self.bpm.read().unwrap().clone()
     }
 
+    // Runs the callback on its own thread, which needs a clock that waits. Step other clocks with `tick`
     pub fn start(&mut self) -> Result<(), MetronomeError>{
         if let MetronomeContent::Channel(_) = &self.content {
             return Err(MetronomeError::AlreadyStarted);
         }
+        if !self.clock.waits() {
+            return Err(MetronomeError::ClockDoesntWait);
+        }
         let (tx, rx) = mpsc::channel();
         let callback = match &self.content {
             MetronomeContent::Callback(callback) => callback.clone(),
@@ -60,6 +196,7 @@ impl Metronome {
         };
         self.content = MetronomeContent::Channel(tx);
         let bpm_view = self.bpm.read_only();
+        let clock = self.clock.clone();
         let _ = thread::spawn(move || {
             let mut true_callback = callback;
             loop {
@@ -78,7 +215,7 @@ impl Metronome {
                 //execute callback
                 true_callback.lock().unwrap()();
                 let bpm = bpm_view.read().unwrap().clone();
-                thread::sleep(beat_duration(bpm));
+                clock.sleep(beat_duration(bpm));
                 
             }
         });
@@ -98,9 +235,13 @@ impl Metronome {
     }
 
     pub fn tap(&mut self) {
-        let now = time::Instant::now();
+        let now = self.clock.now();
         if let Some(last) = self.last_tap.replace(now) {
-            let time_elapsed = now.saturating_duration_since(last).as_secs_f64();
+            let time_elapsed = now.saturating_sub(last).as_secs_f64();
+            // Taps at the same instant have no tempo
+            if time_elapsed <= 0.0 {
+                return;
+            }
             self.bpm_buffer.push_back(60.0 / time_elapsed);
 
             while self.bpm_buffer.len() > self.buffer_cap as usize {
@@ -137,6 +278,8 @@ pub enum MetronomeCommand {
 pub enum MetronomeError {
     AlreadyStarted,
     AlreadyStopped,
+    ClockDoesntWait,
+    InvalidBpm(BPM),
     SendError(mpsc::SendError<MetronomeCommand>),
     OtherError(String),
 }
@@ -155,4 +298,78 @@ impl From<String> for MetronomeError {
 
 fn beat_duration(bpm: BPM) -> time::Duration {
     time::Duration::from_secs_f64(60.0 / bpm)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_clocks_only_move_when_told() {
+        let clock = ManualClock::new();
+        let shared = clock.clone();
+        assert_eq!(clock.now(), time::Duration::ZERO);
+        clock.advance(time::Duration::from_millis(250));
+        shared.sleep(time::Duration::from_millis(250));
+        assert_eq!(clock.now(), time::Duration::from_millis(500));
+        shared.set(time::Duration::from_secs(3));
+        assert_eq!(clock.now(), time::Duration::from_secs(3));
+    }
+
+    #[test]
+    fn ticks_count_beats_across_tempo_changes() {
+        let clock = ManualClock::new();
+        let mut metronome = Metronome::with_clock(120.0, Arc::new(clock.clone()));
+        let called = Arc::new(Mutex::new(0));
+        let counter = called.clone();
+        metronome.set_callback(Arc::new(Mutex::new(move || *counter.lock().unwrap() += 1))).unwrap();
+
+        // The first tick is the first beat, at 120 BPM the next ones come every half second
+        assert_eq!(metronome.tick(), 1);
+        clock.advance(time::Duration::from_millis(1250));
+        assert_eq!(metronome.tick(), 2);
+        assert_eq!(metronome.beats(), 3);
+
+        // From beat 2.5 on at 60 BPM, the tempo change doesn't skip or repeat beats
+        metronome.set_bpm(60.0).unwrap();
+        clock.advance(time::Duration::from_millis(250));
+        assert_eq!(metronome.tick(), 0);
+        clock.advance(time::Duration::from_millis(250));
+        assert_eq!(metronome.tick(), 1);
+        assert_eq!(metronome.position(), 3.0);
+        clock.advance(time::Duration::from_secs(2));
+        assert_eq!(metronome.tick(), 2);
+        assert_eq!(metronome.beats(), 6);
+        assert_eq!(*called.lock().unwrap(), 6);
+    }
+
+    #[test]
+    fn invalid_tempos_are_rejected() {
+        let clock = ManualClock::new();
+        let mut metronome = Metronome::with_clock(120.0, Arc::new(clock.clone()));
+        for bpm in [0.0, -60.0, BPM::NAN, BPM::INFINITY] {
+            assert!(matches!(metronome.set_bpm(bpm), Err(MetronomeError::InvalidBpm(_))));
+        }
+        assert_eq!(metronome.get_bpm(), 120.0);
+
+        // Two taps at the same instant don't make an infinite tempo
+        metronome.tap();
+        metronome.tap();
+        assert_eq!(metronome.get_bpm(), 120.0);
+        clock.advance(time::Duration::from_millis(500));
+        metronome.tap();
+        assert_eq!(metronome.get_bpm(), 120.0);
+        clock.advance(time::Duration::from_secs(1));
+        metronome.tap();
+        assert_eq!(metronome.get_bpm(), 90.0);
+        assert_eq!(metronome.tick(), 1);
+    }
+
+    #[test]
+    fn manual_clocks_cant_start_a_thread() {
+        let mut metronome = Metronome::with_clock(120.0, Arc::new(ManualClock::new()));
+        assert!(matches!(metronome.start(), Err(MetronomeError::ClockDoesntWait)));
+        assert!(matches!(metronome.stop(), Err(MetronomeError::AlreadyStopped)));
+        assert_eq!(metronome.tick(), 1);
+    }
+}
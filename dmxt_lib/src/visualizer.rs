@@ -0,0 +1,97 @@
+use crate::components::color::RGB;
+use crate::components::Fixture;
+use crate::dmx::DMXUniverse;
+use crate::effects::movement::PanTilt;
+use crate::patch::Patch;
+use crate::stage::{self, Point3};
+
+// What a patched fixture shows, read back from the universes
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixtureState {
+    pub fixture: usize,
+    // Colors of the matrix cells by row, scaled by the dimmer
+    pub cells: Vec<Vec<RGB>>,
+    // Degrees from the middle of both axes, see `Fixture::read_position`
+    pub position: Option<PanTilt>,
+}
+
+impl FixtureState {
+    pub fn read(index: usize, fixture: &Fixture, universes: &[DMXUniverse]) -> FixtureState {
+        let Some(universe) = universes.get(fixture.universe) else {
+            return FixtureState { fixture: index, cells: Vec::new(), position: None };
+        };
+        let rows = fixture.channel_mode().and_then(|mode| mode.lights.as_ref()).map_or(&[][..], |lights| &lights.matrix[..]);
+        let cells = rows.iter().enumerate().map(|(row, lights)| {
+            (0..lights.len()).map(|column| fixture.read_color(universe, row, column).unwrap_or_default()).collect()
+        }).collect();
+        FixtureState { fixture: index, cells, position: fixture.read_position(universe) }
+    }
+
+    // Average of the cells
+    pub fn color(&self) -> RGB {
+        let cells: Vec<&RGB> = self.cells.iter().flatten().collect();
+        if cells.is_empty() {
+            return RGB::BLACK;
+        }
+        let sum = cells.iter().fold((0.0, 0.0, 0.0), |sum, cell| (sum.0 + cell.r, sum.1 + cell.g, sum.2 + cell.b));
+        let count = cells.len() as f64;
+        RGB::new(sum.0 / count, sum.1 / count, sum.2 / count)
+    }
+
+    pub fn intensity(&self) -> f64 {
+        self.cells.iter().flatten().map(RGB::max).fold(0.0, f64::max)
+    }
+
+    // Unit vector of the beam in fixture coordinates, fixtures without movement point straight away from their base
+    pub fn direction(&self) -> Point3 {
+        stage::beam_direction(self.position.unwrap_or_default())
+    }
+}
+
+pub fn read_states(patch: &Patch, universes: &[DMXUniverse]) -> Vec<FixtureState> {
+    patch.fixtures().iter().enumerate().map(|(index, fixture)| FixtureState::read(index, fixture, universes)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::fixture::{FixtureChannelMode, FixtureColorMode, FixtureLights, FixtureMatrix, FixtureMovement, MovementAxis};
+    use crate::dmx::{Channel, DMXRange};
+    use crate::test_support::test_fixture_with;
+
+    fn channel(id: u16) -> Channel {
+        Channel::new(id).unwrap()
+    }
+
+    #[test]
+    fn states_read_back_what_was_written() {
+        let lights = FixtureLights::new(FixtureColorMode::RGB(channel(2).into(), channel(3).into(), channel(4).into()), Some(DMXRange::from(channel(1))));
+        let mut pan = MovementAxis::new(channel(5).into(), None);
+        pan.fine(channel(6)).degrees(0.0, 540.0);
+        let movement = FixtureMovement::builder().pan(pan).tilt(MovementAxis::new(channel(7).into(), None)).build().unwrap();
+        let mut mode = FixtureChannelMode::builder();
+        mode.total_channels(channel(7))
+            .matrix(FixtureMatrix::builder().row(vec![lights.clone(), lights]).build().unwrap())
+            .movement(movement);
+        let mut fixture = test_fixture_with(mode.build().unwrap(), 10);
+        fixture.movement.invert_tilt = true;
+        let mut patch = Patch::new();
+        patch.add(fixture).unwrap();
+
+        let fixture = &patch.fixtures()[0];
+        let mut universes = vec![DMXUniverse::new()];
+        fixture.write_color(&mut universes[0], 0, 0, RGB::new(0.0, 0.5, 1.0)).unwrap();
+        fixture.write_position_normalized(&mut universes[0], 360.0 / 540.0, 0.25).unwrap();
+
+        let state = &read_states(&patch, &universes)[0];
+        let color = state.cells[0][0];
+        assert!(color.r < 0.01 && (color.g - 0.5).abs() < 0.01 && color.b > 0.99);
+        assert_eq!(state.cells[0].len(), 2);
+        assert!((state.intensity() - 1.0).abs() < 0.01);
+        let position = state.position.unwrap();
+        assert!((position.pan - 90.0).abs() < 0.1);
+        assert!((position.tilt + 67.5).abs() < 1.0);
+        let direction = state.direction();
+        assert!(direction.x < -0.9 && direction.z < -0.3);
+    }
+}
@@ -0,0 +1,324 @@
+use dmxt_lib::interfaces::{InterfaceManager, InterfaceStatus};
+use dmxt_lib::output::{OutputEngine, OutputError};
+use dmxt_lib::preferences::Preferences;
+use dmxt_lib::recording::{Player, Recording};
+use dmxt_lib::scripting::Script;
+use dmxt_lib::show::Show;
+use dmxt_lib::threads::shared::Lock;
+use dmxt_lib::timeline::{Playback, Timeline};
+use dmxt_lib::timing::{Metronome, BPM};
+
+use clap::Parser;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often interfaces are polled for reconnects and status changes
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Plays a show without the GUI, e.g. as a service of an installation
+#[derive(Parser)]
+#[command(
+    name = "dmxt_runner",
+    about = "Run a dmxt show without the GUI",
+    after_help = "Not supported yet: cue lists and OSC/MIDI control. Shows play timelines, scripts or recordings."
+)]
+struct Cli {
+    show: PathBuf,
+    /// Frames per second, defaults to the refresh rate of the preferences
+    #[arg(long)]
+    frame_rate: Option<f64>,
+    /// Start the metronome at this tempo
+    #[arg(long, value_parser = parse_bpm)]
+    bpm: Option<BPM>,
+    /// Play a timeline of the show, by name or number. Looped timelines start over at the end
+    #[arg(long, conflicts_with = "replay")]
+    timeline: Option<String>,
+    /// Seconds between status lines, 0 only logs changes
+    #[arg(long, default_value_t = 60)]
+    status_interval: u64,
+    /// Rhai script with on_frame and on_beat hooks, reloaded when the file changes
+    #[arg(long)]
+    script: Option<PathBuf>,
+    /// Record everything that is sent and save it to this file on shutdown
+    #[arg(long)]
+    record: Option<PathBuf>,
+    /// Play a recording instead of a script, over and over
+    #[arg(long, conflicts_with = "script")]
+    replay: Option<PathBuf>,
+    /// Speed of the replay, 1.0 is the original speed
+    #[arg(long, default_value_t = 1.0, requires = "replay")]
+    speed: f64,
+}
+
+fn parse_bpm(value: &str) -> Result<BPM, String> {
+    match value.parse::<BPM>() {
+        Ok(bpm) if bpm.is_finite() && bpm > 0.0 => Ok(bpm),
+        Ok(_) => Err(String::from("the tempo has to be above 0")),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+// Timelines are picked by their name or by their number, counting from 1
+fn find_timeline<'a>(timelines: &'a [Timeline], name: &str) -> Option<&'a Timeline> {
+    match name.parse::<usize>() {
+        Ok(number) => number.checked_sub(1).and_then(|index| timelines.get(index)),
+        Err(_) => timelines.iter().find(|timeline| timeline.name.eq_ignore_ascii_case(name)),
+    }
+}
+
+// Stops the output and sends a blackout frame. Stopping waits for the last regular frame, so it can't overwrite the blackout
+fn shutdown(output: &mut OutputEngine) -> Result<(), OutputError> {
+    let _ = output.stop();
+    output.blackout()
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let started = Instant::now();
+    let log = |message: String| {
+        let seconds = started.elapsed().as_secs();
+        eprintln!("[{:02}:{:02}:{:02}] {}", seconds / 3600, seconds / 60 % 60, seconds % 60, message);
+    };
+
+    let show = match Show::load(&cli.show) {
+        Ok(show) => show,
+        Err(error) => {
+            log(format!("Could not open {}: {}", cli.show.display(), error));
+            return ExitCode::FAILURE;
+        },
+    };
+    let frame_rate = cli.frame_rate.unwrap_or_else(|| {
+        Preferences::path()
+            .and_then(|path| Preferences::load_or_default(&path).ok())
+            .unwrap_or_default()
+            .frame_rate
+    });
+    log(format!("Opened {}: {} universes, {} fixtures, {} interfaces", cli.show.display(), show.universes, show.patch.len(), show.interfaces.len()));
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler = running.clone();
+    // Ctrl+C and SIGTERM
+    if let Err(error) = ctrlc::set_handler(move || handler.store(false, Ordering::SeqCst)) {
+        log(format!("Could not handle signals: {}", error));
+    }
+
+    let mut output = OutputEngine::new(show.universes.max(1));
+    output.set_frame_rate(frame_rate);
+    let mut interfaces = InterfaceManager::new();
+    interfaces.set_mappings(show.interfaces);
+    interfaces.poll(&mut output);
+    if let Err(error) = output.start() {
+        log(format!("Could not start the output: {}", error));
+        return ExitCode::FAILURE;
+    }
+    log(format!("Sending {} frames per second", output.frame_rate()));
+
+    // Ticked every frame, so timelines get the beat position and scripts every beat
+    let mut metronome = cli.bpm.map(|bpm| {
+        log(format!("Metronome at {} BPM", bpm));
+        Metronome::new(bpm)
+    });
+
+    let mut player = match &cli.replay {
+        Some(path) => match Recording::load(path) {
+            Ok(recording) => {
+                log(format!("Replaying {}: {} frames, {:.1} s", path.display(), recording.len(), recording.duration()));
+                let mut player = Player::new(recording);
+                player.looped = true;
+                player.playing = true;
+                player.speed = cli.speed;
+                Some(player)
+            },
+            Err(error) => {
+                log(format!("Could not load {}: {}", path.display(), error));
+                return ExitCode::FAILURE;
+            },
+        },
+        None => None,
+    };
+    if cli.record.is_some() {
+        output.recorder().start();
+    }
+
+    let mut timeline = match &cli.timeline {
+        Some(name) => match find_timeline(&show.timelines, name) {
+            Some(timeline) => {
+                log(format!("Playing {}", timeline.name));
+                Some((timeline, Playback { position: 0.0, playing: true }))
+            },
+            None => {
+                log(format!("The show has no timeline {}", name));
+                return ExitCode::FAILURE;
+            },
+        },
+        None => None,
+    };
+
+    let mut script = cli.script.as_ref().map(|path| {
+        let mut script = Script::new(Lock::new(show.patch.clone()), output.universes());
+        match script.load(path) {
+            Ok(()) => log(format!("Running {}", path.display())),
+            Err(error) => log(format!("{}: {}", path.display(), error)),
+        }
+        script
+    });
+    // Timelines, scripts and replays run every frame, otherwise only the interfaces need to be looked after
+    let tick = if timeline.is_some() || script.is_some() || player.is_some() {
+        Duration::from_secs_f64(1.0 / output.frame_rate()).min(POLL_INTERVAL)
+    } else {
+        POLL_INTERVAL
+    };
+
+    let mut statuses: Vec<(String, InterfaceStatus)> = Vec::new();
+    let mut last_status = Instant::now();
+    let mut last_poll = Instant::now();
+    let mut last_beat = 0;
+    let mut last_tick = Instant::now();
+    while running.load(Ordering::SeqCst) {
+        let elapsed = last_tick.elapsed().as_secs_f64();
+        last_tick = Instant::now();
+        if let Some(metronome) = &mut metronome {
+            metronome.tick();
+        }
+        if let Some(player) = &mut player {
+            player.advance(elapsed);
+            player.apply(&mut output.universes().write().unwrap());
+        }
+        // Finished timelines keep their last frame
+        if let Some((timeline, playback)) = timeline.as_mut().filter(|(_, playback)| playback.playing) {
+            playback.advance(timeline, elapsed);
+            let beat = metronome.as_ref().map(Metronome::position);
+            if let Err(error) = timeline.render_synced(playback.position, beat, &show.patch, &show.stage, &mut output.universes().write().unwrap()) {
+                log(format!("{}: {:?}", timeline.name, error));
+            }
+            if !playback.playing {
+                log(format!("Finished {}", timeline.name));
+            }
+        }
+        if let Some(script) = &mut script {
+            let path = script.path().map(|path| path.display().to_string()).unwrap_or_default();
+            match script.reload_if_changed() {
+                Ok(true) => log(format!("Reloaded {}", path)),
+                Ok(false) => {},
+                Err(error) => log(format!("{}: {}", path, error)),
+            }
+            let beat = metronome.as_ref().map_or(0, Metronome::beats);
+            for beat in last_beat + 1..=beat {
+                if let Err(error) = script.beat(beat, cli.bpm.unwrap_or_default()) {
+                    log(format!("{}: {}", path, error));
+                }
+            }
+            last_beat = beat;
+            if let Err(error) = script.frame() {
+                log(format!("{}: {}", path, error));
+            }
+        }
+        thread::sleep(tick);
+        if last_poll.elapsed() < POLL_INTERVAL {
+            continue;
+        }
+        last_poll = Instant::now();
+        interfaces.poll(&mut output);
+        let current: Vec<(String, InterfaceStatus)> = interfaces.statuses().into_iter()
+            .map(|(mapping, status)| (format!("{} (universe {})", mapping.interface, mapping.universe + 1), status))
+            .collect();
+        for (interface, status) in &current {
+            if !statuses.contains(&(interface.clone(), status.clone())) {
+                log(format!("{} {}", interface, status));
+            }
+        }
+        statuses = current;
+        if cli.status_interval > 0 && last_status.elapsed() >= Duration::from_secs(cli.status_interval) {
+            let connected = statuses.iter().filter(|(_, status)| *status == InterfaceStatus::Connected).count();
+            let mut status = format!("Running, {} of {} interfaces connected", connected, statuses.len());
+            if let Some(metronome) = &metronome {
+                status += &format!(", {} beats", metronome.beats());
+            }
+            log(status);
+            last_status = Instant::now();
+        }
+    }
+
+    log(String::from("Shutting down"));
+    if let Some(path) = &cli.record {
+        let recording = output.recorder().stop().unwrap_or_default();
+        match recording.save(path) {
+            Ok(()) => log(format!("Saved {} frames to {}", recording.len(), path.display())),
+            Err(error) => log(format!("Could not save {}: {}", path.display(), error)),
+        }
+    }
+    match shutdown(&mut output) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            log(format!("Could not send the blackout: {}", error));
+            ExitCode::FAILURE
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dmxt_lib::dmx::{Channel, DMXUniverse};
+    use dmxt_lib::output::DMXOutput;
+
+    use std::sync::{mpsc, Mutex};
+
+    // Keeps every frame and tells the test about each one
+    struct Capture(Arc<Mutex<Vec<DMXUniverse>>>, mpsc::Sender<()>);
+
+    impl DMXOutput for Capture {
+        fn send(&mut self, universe: &DMXUniverse) -> Result<(), OutputError> {
+            self.0.lock().unwrap().push(*universe);
+            let _ = self.1.send(());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shutdown_ends_with_a_blackout() {
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let (sent, frame_sent) = mpsc::channel();
+        let mut output = OutputEngine::new(1);
+        output.set_frame_rate(100.0);
+        output.add_output(0, Box::new(Capture(frames.clone(), sent))).unwrap();
+        output.universes().write().unwrap()[0].set(Channel::new(1).unwrap(), 255).unwrap();
+        output.start().unwrap();
+        frame_sent.recv().unwrap();
+        shutdown(&mut output).unwrap();
+        assert!(!output.is_running());
+
+        // The blackout is the only dark frame and nothing comes after it
+        let frames = frames.lock().unwrap();
+        assert_eq!(frames[0].get(Channel::new(1).unwrap()).unwrap(), 255);
+        assert_eq!(frames.iter().position(|frame| *frame == DMXUniverse::new()), Some(frames.len() - 1));
+    }
+
+    #[test]
+    fn tempos_and_timelines_are_checked() {
+        assert_eq!(parse_bpm("128"), Ok(128.0));
+        assert!(parse_bpm("0").is_err());
+        assert!(parse_bpm("-60").is_err());
+        assert!(parse_bpm("inf").is_err());
+        assert!(Cli::try_parse_from(["dmxt_runner", "show.json", "--bpm", "0"]).is_err());
+
+        let timelines = vec![Timeline::new("Intro".into()), Timeline::new("Outro".into())];
+        assert_eq!(find_timeline(&timelines, "outro").map(|timeline| timeline.name.as_str()), Some("Outro"));
+        assert_eq!(find_timeline(&timelines, "1").map(|timeline| timeline.name.as_str()), Some("Intro"));
+        assert!(find_timeline(&timelines, "0").is_none());
+        assert!(find_timeline(&timelines, "Chorus").is_none());
+    }
+
+    #[test]
+    fn help_names_what_is_not_supported() {
+        use clap::CommandFactory;
+
+        let help = Cli::command().render_help().to_string();
+        assert!(help.contains("cue lists") && help.contains("OSC/MIDI"), "{}", help);
+    }
+}
@@ -7,6 +7,8 @@ use open_dmx::DMX_CHANNELS;
 use open_dmx::DMXSerial;
 use open_dmx::error::{DMXError, DMXErrorValidity};
 
+use dmxt_lib::interfaces::{serial_ports, Device, Interface};
+
 
 fn main() {
     let options = eframe::NativeOptions::default();
@@ -21,6 +23,7 @@ struct MyApp {
     channels: Vec<ChannelComponent>,
     is_free: [bool; DMX_CHANNELS],
     interface_path: String,
+    ports: Vec<Device>,
     dmx: Option<DMXSerial>,
     connection_error: bool,
     status: egui::RichText,
@@ -33,6 +36,7 @@ impl Default for MyApp {
             channels: vec![ChannelComponent::create_next(&mut is_free, 0).unwrap(); 1],
             is_free,
             interface_path: String::new(),
+            ports: serial_ports(),
             dmx: Option::None,
             connection_error: false,
             status: egui::RichText::new(""),
@@ -47,6 +51,18 @@ impl eframe::App for MyApp {
             ui.separator();
             ui.horizontal(|ui| {
                 ui.label("Interface:");
+                egui::ComboBox::from_id_source("interface_ports")
+                .selected_text(if self.interface_path.is_empty() { "Select a port" } else { self.interface_path.as_str() })
+                .show_ui(ui, |ui| {
+                    for port in &self.ports {
+                        if let Interface::Serial { port: path, .. } = &port.interface {
+                            ui.selectable_value(&mut self.interface_path, path.clone(), &port.name);
+                        }
+                    }
+                });
+                if ui.add_enabled(self.dmx.is_none(), egui::Button::new("⟳")).on_hover_text("Search for interfaces").clicked() {
+                    self.ports = serial_ports();
+                }
                 ui.add(
                     egui::TextEdit::singleline(&mut self.interface_path)
                     .hint_text("COM")
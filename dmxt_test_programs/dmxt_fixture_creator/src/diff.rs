@@ -0,0 +1,44 @@
+use serde_json::Value;
+
+// Differences between two serialized models, one line per changed value:
+// "+ path: value" (added), "- path: value" (removed) and "~ path: old -> new" (changed)
+pub fn diff(old: &Value, new: &Value) -> Vec<String> {
+    let mut changes = Vec::new();
+    compare("", old, new, &mut changes);
+    changes
+}
+
+fn compare(path: &str, old: &Value, new: &Value, changes: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, value) in old {
+                let path = join(path, key);
+                match new.get(key) {
+                    Some(new) => compare(&path, value, new, changes),
+                    None => changes.push(format!("- {}: {}", path, value)),
+                }
+            }
+            for (key, value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                changes.push(format!("+ {}: {}", join(path, key), value));
+            }
+        },
+        (Value::Array(old), Value::Array(new)) => {
+            for (index, value) in old.iter().enumerate() {
+                let path = format!("{}[{}]", path, index);
+                match new.get(index) {
+                    Some(new) => compare(&path, value, new, changes),
+                    None => changes.push(format!("- {}: {}", path, value)),
+                }
+            }
+            for (index, value) in new.iter().enumerate().skip(old.len()) {
+                changes.push(format!("+ {}[{}]: {}", path, index, value));
+            }
+        },
+        (old, new) if old != new => changes.push(format!("~ {}: {} -> {}", path, old, new)),
+        _ => {},
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() { key.into() } else { format!("{}.{}", path, key) }
+}
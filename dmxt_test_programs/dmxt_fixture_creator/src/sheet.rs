@@ -0,0 +1,34 @@
+use dmxt_lib::builders::fixture::FixtureModel;
+use dmxt_lib::builders::validation::channel_usages;
+
+use std::fmt::Write;
+
+// Channel table of every mode (or only `mode`, starting at 1) as plain text
+pub fn render(model: &FixtureModel, mode: Option<usize>) -> String {
+    let mut sheet = String::new();
+    writeln!(sheet, "{} by {}", model.name, model.manufacturer).unwrap();
+    for (index, channel_mode) in model.channel_modes.iter().enumerate() {
+        if mode.is_some_and(|mode| mode != index + 1) {
+            continue;
+        }
+        let name = channel_mode.name.as_ref().map_or_else(|| format!("Mode {}", index + 1), |name| name.to_string());
+        writeln!(sheet, "\n{} ({} channels)", name, channel_mode.total_channels.id()).unwrap();
+        writeln!(sheet, "  Ch | Values    | Function").unwrap();
+        writeln!(sheet, " ----+-----------+---------").unwrap();
+        let usages = channel_usages(channel_mode);
+        for id in 1..=channel_mode.total_channels.id() {
+            let mut on_channel = usages.iter().filter(|usage| usage.channel.id() == id).peekable();
+            if on_channel.peek().is_none() {
+                writeln!(sheet, " {:>3} |           | -", id).unwrap();
+            }
+            for usage in on_channel {
+                writeln!(sheet, " {:>3} | {:>3} - {:>3} | {}", id, usage.start, usage.end, usage.function).unwrap();
+            }
+        }
+        // Functions outside of the mode's channels are configuration errors, they are listed anyway
+        for usage in usages.iter().filter(|usage| usage.channel.id() > channel_mode.total_channels.id()) {
+            writeln!(sheet, " {:>3} | {:>3} - {:>3} | {} (outside of the mode)", usage.channel.id(), usage.start, usage.end, usage.function).unwrap();
+        }
+    }
+    sheet
+}
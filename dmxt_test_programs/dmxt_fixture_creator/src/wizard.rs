@@ -0,0 +1,175 @@
+use crate::{canonical, sheet, CONFIG_FILE};
+
+use std::error::Error;
+use std::fs;
+use std::io::{stdin, stdout, Write};
+use std::path::Path;
+
+use dmxt_lib::builders::fixture::{
+    FixtureChannelMode, FixtureColorMode, FixtureCustomOperation, FixtureLights, FixtureModel, FixtureMovement,
+    FixtureName, FixtureZoom, MovementAxis,
+};
+use dmxt_lib::builders::validation;
+use dmxt_lib::dmx::{Channel, DMXAddress, DMXRange};
+
+// Asks for a fixture on the terminal and writes it to `<root>/<Manufacturer>_<Model>/fixture_config.json`
+pub fn run(root: &Path) -> Result<(), Box<dyn Error>> {
+    let manufacturer = ask_required("Manufacturer")?;
+    let model_name = ask_required("Model")?;
+    let mut model = FixtureModel::builder();
+    model.model(FixtureName::new(model_name.clone())).manufacturer(manufacturer.clone());
+    loop {
+        model.channel_mode(ask_mode()?);
+        if !ask_yes_no("Add another channel mode?", false)? {
+            break;
+        }
+    }
+    let model = model.build()?;
+
+    print!("{}", sheet::render(&model, None));
+    for issue in validation::validate(&model) {
+        println!("{}", issue);
+    }
+    let directory = root.join(ask_default("Directory", &directory_name(&manufacturer, &model_name))?);
+    let path = directory.join(CONFIG_FILE);
+    if path.exists() && !ask_yes_no(&format!("{} exists, overwrite?", path.display()), false)? {
+        return Ok(());
+    }
+    fs::create_dir_all(&directory)?;
+    fs::write(&path, canonical(&model)?)?;
+    println!("wrote {}", path.display());
+    Ok(())
+}
+
+fn ask_mode() -> Result<FixtureChannelMode, Box<dyn Error>> {
+    let mut mode = FixtureChannelMode::builder();
+    let total = ask_number("Number of channels", 1, 512)?;
+    mode.total_channels(channel(total)?);
+    let name = ask_default("Mode name", &format!("{} Channel Mode", total))?;
+    mode.name(FixtureName::new(name));
+
+    let color = ask_default("Color mode (none, rgb, rgbw, cmy, cmyw)", "none")?.to_lowercase();
+    let components = match color.as_str() {
+        "rgb" | "cmy" => 3,
+        "rgbw" | "cmyw" => 4,
+        _ => 0,
+    };
+    let dimmer = ask_channel("Dimmer channel", total)?.map(full_range);
+    if components > 0 {
+        // The color channels follow each other starting at the first one
+        let first = ask_number("First color channel", 1, total.saturating_sub(components - 1).max(1))?;
+        let ranges: Vec<DMXRange> = (0..components).map(|offset| channel(first + offset).map(full_range)).collect::<Result<_, _>>()?;
+        let color_mode = match color.as_str() {
+            "rgb" => FixtureColorMode::RGB(ranges[0], ranges[1], ranges[2]),
+            "rgbw" => FixtureColorMode::RGBW(ranges[0], ranges[1], ranges[2], ranges[3]),
+            "cmy" => FixtureColorMode::CMY(ranges[0], ranges[1], ranges[2]),
+            _ => FixtureColorMode::CMYW(ranges[0], ranges[1], ranges[2], ranges[3]),
+        };
+        mode.lights(FixtureLights::new(color_mode, dimmer));
+    } else if let Some(dimmer) = dimmer {
+        mode.custom(FixtureCustomOperation::Slider(FixtureName::new("Dimmer".into()), dimmer));
+    }
+
+    let mut movement = FixtureMovement::default();
+    for (name, axis) in [("Pan", &mut movement.pan), ("Tilt", &mut movement.tilt)] {
+        if let Some(channel) = ask_channel(&format!("{} channel", name), total)? {
+            let mut movement_axis = MovementAxis::new(full_range(channel), None);
+            if let Some(fine) = ask_channel(&format!("{} fine channel", name), total)? {
+                movement_axis.fine(fine);
+            }
+            let range = ask_optional(&format!("{} range in degrees, e.g. -270 270", name))?;
+            let degrees: Vec<f64> = range.split_whitespace().filter_map(|degrees| degrees.parse().ok()).collect();
+            if let [start, end] = degrees.as_slice() {
+                movement_axis.degrees(*start, *end);
+            }
+            *axis = Some(movement_axis);
+        }
+    }
+    if movement.pan.is_some() || movement.tilt.is_some() {
+        mode.movement(movement);
+    }
+
+    if let Some(channel) = ask_channel("Zoom channel", total)? {
+        mode.zoom(FixtureZoom::new(full_range(channel), None));
+    }
+
+    loop {
+        let name = ask_optional("Slider name (empty to finish)")?;
+        if name.is_empty() {
+            break;
+        }
+        if let Some(channel) = ask_channel(&format!("{} channel", name), total)? {
+            mode.custom(FixtureCustomOperation::Slider(FixtureName::new(name), full_range(channel)));
+        }
+    }
+    Ok(mode.build()?)
+}
+
+fn channel(id: u16) -> Result<Channel, Box<dyn Error>> {
+    Channel::new(id).map_err(|error| format!("invalid channel {}: {:?}", id, error).into())
+}
+
+fn full_range(channel: Channel) -> DMXRange {
+    DMXRange::new(DMXAddress::new(channel, 0), DMXAddress::new(channel, 255))
+}
+
+// Same naming as the existing fixture directories, e.g. "Stairville_LedBar2408RGBDMX30"
+fn directory_name(manufacturer: &str, model: &str) -> String {
+    let clean = |text: &str| text.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-').collect::<String>();
+    format!("{}_{}", clean(manufacturer), clean(model))
+}
+
+fn ask_optional(question: &str) -> Result<String, Box<dyn Error>> {
+    print!("{}: ", question);
+    stdout().flush()?;
+    let mut answer = String::new();
+    if stdin().read_line(&mut answer)? == 0 {
+        return Err("input ended".into());
+    }
+    Ok(answer.trim().into())
+}
+
+fn ask_required(question: &str) -> Result<String, Box<dyn Error>> {
+    loop {
+        let answer = ask_optional(question)?;
+        if !answer.is_empty() {
+            return Ok(answer);
+        }
+    }
+}
+
+fn ask_default(question: &str, default: &str) -> Result<String, Box<dyn Error>> {
+    let answer = ask_optional(&format!("{} [{}]", question, default))?;
+    Ok(if answer.is_empty() { default.into() } else { answer })
+}
+
+fn ask_yes_no(question: &str, default: bool) -> Result<bool, Box<dyn Error>> {
+    let answer = ask_optional(&format!("{} [{}]", question, if default { "Y/n" } else { "y/N" }))?.to_lowercase();
+    Ok(match answer.as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
+
+fn ask_number(question: &str, min: u16, max: u16) -> Result<u16, Box<dyn Error>> {
+    loop {
+        match ask_optional(&format!("{} ({}-{})", question, min, max))?.parse::<u16>() {
+            Ok(number) if (min..=max).contains(&number) => return Ok(number),
+            _ => println!("Please enter a number between {} and {}", min, max),
+        }
+    }
+}
+
+fn ask_channel(question: &str, total: u16) -> Result<Option<Channel>, Box<dyn Error>> {
+    loop {
+        let answer = ask_optional(&format!("{} (1-{}, empty for none)", question, total))?;
+        if answer.is_empty() {
+            return Ok(None);
+        }
+        match answer.parse::<u16>() {
+            Ok(id) if (1..=total).contains(&id) => return Ok(Some(channel(id)?)),
+            _ => println!("Please enter a channel between 1 and {}", total),
+        }
+    }
+}
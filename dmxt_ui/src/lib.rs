@@ -1,2 +1,3 @@
 pub mod windows;
-pub mod pages;
\ No newline at end of file
+pub mod pages;
+pub mod widgets;
\ No newline at end of file
@@ -2,11 +2,17 @@ mod patch;
 pub use patch::PatchPage;
 mod scenes;
 pub use scenes::ScenePage;
+mod timeline;
+pub use timeline::TimelinePage;
+mod visualizer;
+pub use visualizer::VisualizerPage;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Page {
     Patch,
     Scenes,
+    Timeline,
+    Visualizer,
 }
 
 impl Default for Page {
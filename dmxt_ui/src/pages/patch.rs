@@ -1,18 +1,303 @@
-use eframe::egui::Ui;
+use eframe::egui::{self, Color32, ComboBox, Response, Ui};
 use crate::pages::PageUI;
+use crate::widgets::control_panel::ControlPanel;
 
+use dmxt_lib::builders::fixture::FixtureModel;
+use dmxt_lib::components::Fixture;
+use dmxt_lib::dmx::{Channel, DMXAddress};
+use dmxt_lib::history::History;
+use dmxt_lib::library::FixtureLibrary;
+use dmxt_lib::patch::PatchCommand;
+use dmxt_lib::show::{ShowCommand, ShowState};
+use dmxt_lib::threads::shared::Lock;
+
+use std::path::PathBuf;
+
+#[derive(Debug)]
 pub struct PatchPage {
+    show: ShowState,
+    history: Lock<History<ShowCommand>>,
+    selected: Option<usize>,
+    control: ControlPanel,
+    new_fixture: NewFixture,
+    library: FixtureLibrary,
+    error: Option<String>,
+}
+
+// Form for patching another fixture
+#[derive(Debug)]
+struct NewFixture {
+    path: String,
+    model: Option<FixtureModel>,
+    name: String,
+    mode: usize,
+    universe: usize,
+    address: u16,
+}
+
+impl Default for NewFixture {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            model: None,
+            name: String::new(),
+            mode: 0,
+            universe: 0,
+            address: 1,
+        }
+    }
+}
+
+impl PatchPage {
+    pub fn new(show: ShowState, history: Lock<History<ShowCommand>>) -> PatchPage {
+        PatchPage {
+            show,
+            history,
+            selected: None,
+            control: ControlPanel::default(),
+            new_fixture: NewFixture::default(),
+            library: FixtureLibrary::default(),
+            error: None,
+        }
+    }
+
+    pub fn set_fixture_library(&mut self, folders: &[PathBuf]) {
+        self.library = FixtureLibrary::scan(folders);
+    }
 
+    fn execute(&mut self, command: PatchCommand) -> bool {
+        let result = self.history.write().unwrap().execute(command.into(), &mut self.show);
+        self.error = result.err().map(|error| error.to_string());
+        self.error.is_none()
+    }
+
+    // Continuous edits like drags and typing become one undo step
+    fn group_edits(&self, response: &Response) {
+        if response.drag_started() || response.gained_focus() {
+            self.history.write().unwrap().begin_group();
+        }
+        if response.drag_released() || response.lost_focus() {
+            self.history.write().unwrap().end_group();
+        }
+    }
+
+    fn select(&mut self, selected: Option<usize>) {
+        if self.selected != selected {
+            self.selected = selected;
+            self.control = ControlPanel::default();
+        }
+    }
+
+    fn fixtures_ui(&mut self, ui: &mut Ui) {
+        let mut select = self.selected;
+        let mut remove = None;
+        let patch = self.show.patch.read().unwrap();
+        if patch.is_empty() {
+            ui.label("No fixtures patched");
+        }
+        egui::Grid::new("patched_fixtures").striped(true).num_columns(5).show(ui, |ui| {
+            for (index, fixture) in patch.fixtures().iter().enumerate() {
+                let (start, end) = fixture.channels();
+                if ui.selectable_label(select == Some(index), &fixture.name).clicked() {
+                    select = Some(index);
+                }
+                ui.label(fixture.model().name.name());
+                ui.label(format!("U{}", fixture.universe + 1));
+                ui.label(format!("{} - {}", start, end));
+                if ui.button("Remove").clicked() {
+                    remove = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        drop(patch);
+        if let Some(index) = remove.filter(|index| self.execute(PatchCommand::remove(*index))) {
+            select = match select {
+                Some(selected) if selected == index => None,
+                Some(selected) if selected > index => Some(selected - 1),
+                selected => selected,
+            };
+        }
+        self.select(select);
+    }
+
+    fn new_fixture_ui(&mut self, ui: &mut Ui) {
+        let universe_count = self.show.universes.read().unwrap().len();
+        let form = &mut self.new_fixture;
+        let mut load = false;
+        if !self.library.is_empty() {
+            ui.horizontal(|ui| {
+                ui.label("Library");
+                ComboBox::from_id_source("fixture_library").selected_text("Choose a fixture").show_ui(ui, |ui| {
+                    for entry in self.library.entries() {
+                        let path = entry.path.display().to_string();
+                        let name = format!("{} {}", entry.manufacturer(), entry.name());
+                        if ui.selectable_label(form.path == path, name).clicked() {
+                            form.path = path;
+                            load = true;
+                        }
+                    }
+                });
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label("Fixture config");
+            ui.text_edit_singleline(&mut form.path);
+            if ui.button("Load").clicked() || load {
+                let model = std::fs::read_to_string(&form.path)
+                    .map_err(|error| error.to_string())
+                    .and_then(|json| serde_json::from_str::<FixtureModel>(&json).map_err(|error| error.to_string()));
+                match model {
+                    Ok(model) => {
+                        form.name = model.name.name().to_string();
+                        form.mode = 0;
+                        form.model = Some(model);
+                        self.error = None;
+                    },
+                    Err(error) => self.error = Some(format!("Could not load {}: {}", form.path, error)),
+                }
+            }
+        });
+        let Some(model) = &form.model else {
+            return;
+        };
+        egui::Grid::new("new_fixture").num_columns(2).show(ui, |ui| {
+            ui.label("Name");
+            ui.text_edit_singleline(&mut form.name);
+            ui.end_row();
+            ui.label("Mode");
+            let mode_name = |index: usize| model.channel_modes.get(index).map_or(String::from("-"), |mode| {
+                let name = mode.name.as_ref().map_or(format!("Mode {}", index + 1), |name| name.name().to_string());
+                format!("{} ({} channels)", name, mode.total_channels.id())
+            });
+            ComboBox::from_id_source("new_fixture_mode").selected_text(mode_name(form.mode)).show_ui(ui, |ui| {
+                for index in 0..model.channel_modes.len() {
+                    ui.selectable_value(&mut form.mode, index, mode_name(index));
+                }
+            });
+            ui.end_row();
+            ui.label("Universe");
+            ui.add(egui::DragValue::new(&mut form.universe).clamp_range(0..=universe_count.saturating_sub(1)).custom_formatter(|value, _| format!("{}", value + 1.0)));
+            ui.end_row();
+            ui.label("Address");
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut form.address).clamp_range(1..=512));
+                if ui.button("Next free").clicked() {
+                    let count = model.channel_modes.get(form.mode).map_or(1, |mode| mode.total_channels.id());
+                    match self.show.patch.read().unwrap().next_free(form.universe, count) {
+                        Some(channel) => form.address = channel.id(),
+                        None => self.error = Some(format!("Universe {} has no {} free channels", form.universe + 1, count)),
+                    }
+                }
+            });
+            ui.end_row();
+        });
+        if ui.button("Patch").clicked() {
+            let fixture = Channel::new(form.address)
+                .and_then(|channel| Fixture::new(form.name.clone(), DMXAddress::new(channel, 0), model.clone()))
+                .map_err(|error| format!("{:?}", error));
+            match fixture {
+                Ok(mut fixture) => {
+                    fixture.universe = form.universe;
+                    fixture.mode = form.mode;
+                    fixture.source = Some(PathBuf::from(&form.path));
+                    if self.execute(PatchCommand::add(fixture)) {
+                        let index = self.show.patch.read().unwrap().len() - 1;
+                        self.select(Some(index));
+                    }
+                },
+                Err(error) => self.error = Some(format!("Could not patch {}: {}", self.new_fixture.name, error)),
+            }
+        }
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui, index: usize, fixture: &Fixture) {
+        let universe_count = self.show.universes.read().unwrap().len();
+        let mut edited = fixture.clone();
+        egui::Grid::new("fixture_settings").num_columns(2).show(ui, |ui| {
+            ui.label("Name");
+            let response = ui.text_edit_singleline(&mut edited.name);
+            self.group_edits(&response);
+            ui.end_row();
+            ui.label("Position");
+            let response = ui.text_edit_singleline(&mut edited.position);
+            self.group_edits(&response);
+            ui.end_row();
+            ui.label("Universe");
+            let response = ui.add(egui::DragValue::new(&mut edited.universe).clamp_range(0..=universe_count.saturating_sub(1)).custom_formatter(|value, _| format!("{}", value + 1.0)));
+            self.group_edits(&response);
+            ui.end_row();
+            ui.label("Address");
+            let mut address = edited.address.channel.id();
+            let response = ui.add(egui::DragValue::new(&mut address).clamp_range(1..=512));
+            self.group_edits(&response);
+            if let Ok(channel) = Channel::new(address) {
+                edited.address.channel = channel;
+            }
+            ui.end_row();
+            ui.label("Mode");
+            let modes = &fixture.model().channel_modes;
+            let mode_name = |index: usize| modes.get(index).and_then(|mode| mode.name.as_ref()).map_or(format!("Mode {}", index + 1), |name| name.name().to_string());
+            ComboBox::from_id_source("fixture_settings_mode").selected_text(mode_name(edited.mode)).show_ui(ui, |ui| {
+                for mode in 0..modes.len() {
+                    ui.selectable_value(&mut edited.mode, mode, mode_name(mode));
+                }
+            });
+            ui.end_row();
+            if fixture.channel_mode().is_some_and(|mode| mode.movement.is_some()) {
+                ui.label("Movement");
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut edited.movement.invert_pan, "Invert pan");
+                    ui.checkbox(&mut edited.movement.invert_tilt, "Invert tilt");
+                    ui.checkbox(&mut edited.movement.swap_axes, "Swap axes");
+                });
+                ui.end_row();
+            }
+        });
+        let changed = edited.name != fixture.name || edited.position != fixture.position || edited.universe != fixture.universe || edited.address != fixture.address
+            || edited.mode != fixture.mode || edited.movement != fixture.movement;
+        if changed {
+            self.execute(PatchCommand::update(index, edited));
+        }
+    }
 }
 
 impl Default for PatchPage {
     fn default() -> Self {
-        Self {}
+        Self::new(ShowState::default(), Lock::new(History::default()))
     }
 }
 
 impl PageUI for PatchPage {
     fn ui(&mut self, ui: &mut Ui) {
-        ui.label("Patch Page");
+        ui.columns(2, |columns| {
+            let ui = &mut columns[0];
+            ui.heading("Patch");
+            if let Some(error) = &self.error {
+                ui.colored_label(Color32::RED, error);
+            }
+            egui::ScrollArea::vertical().id_source("patch_list").max_height(ui.available_height() / 2.0).show(ui, |ui| {
+                self.fixtures_ui(ui);
+            });
+            ui.separator();
+            ui.label("Add fixture");
+            self.new_fixture_ui(ui);
+
+            let ui = &mut columns[1];
+            let fixture = self.selected.and_then(|index| self.show.patch.read().unwrap().get(index).cloned());
+            match fixture.zip(self.selected) {
+                Some((fixture, index)) => {
+                    egui::ScrollArea::vertical().id_source("control_panel").show(ui, |ui| {
+                        egui::CollapsingHeader::new("Settings").id_source("fixture_settings").show(ui, |ui| {
+                            self.settings_ui(ui, index, &fixture);
+                        });
+                        self.control.ui(ui, &fixture, &self.show.universes);
+                    });
+                },
+                None => {
+                    ui.label("Select a fixture to control it");
+                },
+            }
+        });
     }
-}
\ No newline at end of file
+}
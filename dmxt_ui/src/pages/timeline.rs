@@ -0,0 +1,595 @@
+use eframe::egui::{self, Align2, Color32, ComboBox, FontId, Rect, Sense, Stroke, Ui};
+use crate::pages::PageUI;
+
+use dmxt_lib::components::color::RGB;
+use dmxt_lib::effects::movement::{PanTilt, Shape, ShapeGenerator};
+use dmxt_lib::effects::spatial::{Focus, Spread, Wave};
+use dmxt_lib::history::History;
+use dmxt_lib::show::{ShowCommand, ShowState};
+use dmxt_lib::stage::Point3;
+use dmxt_lib::threads::shared::Lock;
+use dmxt_lib::timeline::{Attribute, Clip, ClipEffect, Curve, Keyframe, Lane, Playback, Target, TimeBase, Timeline, Track, Value};
+
+use std::time::Instant;
+
+const TRACK_HEADER_WIDTH: f32 = 140.0;
+const ROW_HEIGHT: f32 = 14.0;
+
+// Editor for the timelines of the show. Playing timelines write into the universes every frame
+#[derive(Debug)]
+pub struct TimelinePage {
+    show: ShowState,
+    history: Lock<History<ShowCommand>>,
+    // A drag or typing is going on, its changes are grouped into one undo step
+    editing: bool,
+    selected: usize,
+    track: Option<usize>,
+    lane: Option<usize>,
+    playback: Playback,
+    last_tick: Option<Instant>,
+    // Pixels per unit of the time base
+    zoom: f32,
+    export_path: String,
+    export_rate: f64,
+    message: Option<(Color32, String)>,
+}
+
+impl TimelinePage {
+    pub fn new(show: ShowState, history: Lock<History<ShowCommand>>) -> TimelinePage {
+        TimelinePage {
+            show,
+            history,
+            editing: false,
+            selected: 0,
+            track: None,
+            lane: None,
+            playback: Playback::default(),
+            last_tick: None,
+            zoom: 24.0,
+            export_path: String::new(),
+            export_rate: 30.0,
+            message: None,
+        }
+    }
+
+    // Called every frame, also while another page is open
+    pub fn tick(&mut self, ctx: &egui::Context) {
+        let now = Instant::now();
+        let elapsed = self.last_tick.replace(now).map_or(0.0, |last| (now - last).as_secs_f64());
+        if !self.playback.playing {
+            return;
+        }
+        let timelines = self.show.timelines.read().unwrap();
+        let Some(timeline) = timelines.get(self.selected) else {
+            self.playback.stop();
+            return;
+        };
+        self.playback.advance(timeline, elapsed);
+        self.render(timeline);
+        ctx.request_repaint();
+    }
+
+    fn render(&self, timeline: &Timeline) {
+        let patch = self.show.patch.read().unwrap();
+        let _ = timeline.render(self.playback.position, &patch, &self.show.stage.read().unwrap(), &mut self.show.universes.write().unwrap());
+    }
+
+    fn select(&mut self, selected: usize) {
+        if self.selected != selected {
+            self.selected = selected;
+            self.track = None;
+            self.lane = None;
+            self.playback.stop();
+        }
+    }
+
+    fn timelines_ui(&mut self, ui: &mut Ui, timelines: &mut Vec<Timeline>) {
+        ui.horizontal(|ui| {
+            let name = timelines.get(self.selected).map_or(String::from("No timelines"), |timeline| timeline.name.clone());
+            let mut selected = self.selected;
+            ComboBox::from_id_source("timelines").selected_text(name).show_ui(ui, |ui| {
+                for (index, timeline) in timelines.iter().enumerate() {
+                    ui.selectable_value(&mut selected, index, &timeline.name);
+                }
+            });
+            self.select(selected);
+            if ui.button("New timeline").clicked() {
+                timelines.push(Timeline::new(format!("Timeline {}", timelines.len() + 1)));
+                self.select(timelines.len() - 1);
+            }
+            if ui.add_enabled(self.selected < timelines.len(), egui::Button::new("Remove")).clicked() {
+                timelines.remove(self.selected);
+                let selected = self.selected.min(timelines.len().saturating_sub(1));
+                // Reset the track selection even if the index stays the same
+                self.selected = usize::MAX;
+                self.select(selected);
+            }
+        });
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui, timeline: &mut Timeline) {
+        ui.horizontal(|ui| {
+            ui.label("Name");
+            ui.add(egui::TextEdit::singleline(&mut timeline.name).desired_width(160.0));
+            ui.label("Time in");
+            for time_base in [TimeBase::Beats, TimeBase::Seconds] {
+                ui.selectable_value(&mut timeline.time_base, time_base, time_base.to_string());
+            }
+            if timeline.time_base == TimeBase::Beats {
+                ui.add(egui::DragValue::new(&mut timeline.bpm).clamp_range(20.0..=400.0).suffix(" BPM"));
+            }
+            ui.label("Length");
+            ui.add(egui::DragValue::new(&mut timeline.length).clamp_range(1.0..=100_000.0).speed(1.0));
+            ui.checkbox(&mut timeline.looped, "Loop");
+        });
+    }
+
+    fn transport_ui(&mut self, ui: &mut Ui, timeline: &Timeline) {
+        ui.horizontal(|ui| {
+            if ui.button("⏹").on_hover_text("Stop").clicked() {
+                self.playback.stop();
+            }
+            let play = if self.playback.playing { "⏸" } else { "▶" };
+            if ui.button(play).clicked() {
+                if !self.playback.playing && self.playback.position >= timeline.length {
+                    self.playback.position = 0.0;
+                }
+                self.playback.playing = !self.playback.playing;
+            }
+            let mut position = self.playback.position;
+            let unit = match timeline.time_base {
+                TimeBase::Beats => " beats",
+                TimeBase::Seconds => " s",
+            };
+            if ui.add(egui::Slider::new(&mut position, 0.0..=timeline.length).suffix(unit)).changed() {
+                self.seek(timeline, position);
+            }
+            ui.label("Zoom");
+            ui.add(egui::Slider::new(&mut self.zoom, 4.0..=120.0).show_value(false));
+        });
+    }
+
+    // Scrubbing shows the position on the outputs right away
+    fn seek(&mut self, timeline: &Timeline, position: f64) {
+        self.playback.seek(timeline, position);
+        self.render(timeline);
+    }
+
+    fn tracks_ui(&mut self, ui: &mut Ui, timeline: &mut Timeline) {
+        let width = timeline.length as f32 * self.zoom;
+        let mut seek = None;
+        egui::ScrollArea::horizontal().id_source("timeline_tracks").show(ui, |ui| {
+            for (index, track) in timeline.tracks.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.allocate_ui(egui::vec2(TRACK_HEADER_WIDTH, ROW_HEIGHT), |ui| {
+                        ui.set_width(TRACK_HEADER_WIDTH);
+                        ui.checkbox(&mut track.muted, "").on_hover_text("Mute");
+                        if ui.selectable_label(self.track == Some(index), &track.name).clicked() {
+                            self.track = Some(index);
+                            self.lane = None;
+                        }
+                    });
+                    let height = ROW_HEIGHT * (track.lanes.len() + 1) as f32 + 4.0;
+                    let (rect, response) = ui.allocate_exact_size(egui::vec2(width, height), Sense::click_and_drag());
+                    self.paint_track(ui, rect, timeline.length, track, self.track == Some(index));
+                    if let Some(pointer) = response.interact_pointer_pos() {
+                        self.track = Some(index);
+                        seek = Some(((pointer.x - rect.left()) / self.zoom) as f64);
+                    }
+                });
+            }
+        });
+        if let Some(position) = seek {
+            self.seek(timeline, position);
+        }
+        if ui.button("Add track").clicked() {
+            timeline.tracks.push(Track::new(format!("Track {}", timeline.tracks.len() + 1), Target::Fixtures(Vec::new())));
+            self.track = Some(timeline.tracks.len() - 1);
+            self.lane = None;
+        }
+    }
+
+    fn paint_track(&self, ui: &Ui, rect: Rect, length: f64, track: &Track, selected: bool) {
+        let painter = ui.painter_at(rect);
+        let visuals = ui.visuals();
+        painter.rect_filled(rect, 2.0, if selected { visuals.faint_bg_color } else { visuals.extreme_bg_color });
+        let x = |position: f64| rect.left() + position as f32 * self.zoom;
+        // One line per unit, stronger every 4
+        let step = if self.zoom < 8.0 { 4 } else { 1 };
+        for unit in (0..=length.ceil() as usize).step_by(step) {
+            let color = if unit % 4 == 0 { visuals.weak_text_color() } else { visuals.widgets.noninteractive.bg_stroke.color };
+            painter.line_segment([egui::pos2(x(unit as f64), rect.top()), egui::pos2(x(unit as f64), rect.bottom())], Stroke::new(1.0, color));
+        }
+        for clip in &track.clips {
+            let clip_rect = Rect::from_min_max(egui::pos2(x(clip.start), rect.top() + 2.0), egui::pos2(x(clip.end()), rect.top() + ROW_HEIGHT));
+            painter.rect_filled(clip_rect, 3.0, Color32::from_rgb(70, 110, 170));
+            painter.text(clip_rect.left_center() + egui::vec2(4.0, 0.0), Align2::LEFT_CENTER, clip.effect.name(), FontId::proportional(10.0), Color32::WHITE);
+        }
+        for (row, lane) in track.lanes.iter().enumerate() {
+            let y = rect.top() + ROW_HEIGHT * (row as f32 + 1.5) + 2.0;
+            let keyframes = lane.keyframes();
+            for pair in keyframes.windows(2) {
+                painter.line_segment([egui::pos2(x(pair[0].time), y), egui::pos2(x(pair[1].time), y)], Stroke::new(1.0, visuals.weak_text_color()));
+            }
+            for keyframe in keyframes {
+                let color = match keyframe.value {
+                    Value::Color(color) => {
+                        let (r, g, b) = color.to_u8();
+                        Color32::from_rgb(r, g, b)
+                    },
+                    _ => visuals.strong_text_color(),
+                };
+                painter.circle_filled(egui::pos2(x(keyframe.time), y), 4.0, color);
+            }
+        }
+        let playhead = x(self.playback.position);
+        painter.line_segment([egui::pos2(playhead, rect.top()), egui::pos2(playhead, rect.bottom())], Stroke::new(2.0, Color32::RED));
+    }
+
+    fn track_ui(&mut self, ui: &mut Ui, track: &mut Track) {
+        let patch = self.show.patch.read().unwrap();
+        egui::Grid::new("timeline_track").num_columns(2).show(ui, |ui| {
+            ui.label("Name");
+            ui.text_edit_singleline(&mut track.name);
+            ui.end_row();
+            ui.label("Fixtures");
+            ui.horizontal(|ui| {
+                let by_position = matches!(track.target, Target::Position(_));
+                if ui.selectable_label(!by_position, "Selected").clicked() && by_position {
+                    track.target = Target::Fixtures(track.target.fixtures(&patch));
+                }
+                if ui.selectable_label(by_position, "At position").clicked() && !by_position {
+                    track.target = Target::Position(String::new());
+                }
+            });
+            ui.end_row();
+            ui.label("");
+            match &mut track.target {
+                Target::Position(position) => {
+                    let mut positions: Vec<&str> = patch.fixtures().iter().map(|fixture| fixture.position.as_str()).filter(|position| !position.is_empty()).collect();
+                    positions.sort_unstable();
+                    positions.dedup();
+                    ComboBox::from_id_source("timeline_track_position").selected_text(position.as_str()).show_ui(ui, |ui| {
+                        for option in positions {
+                            if ui.selectable_label(position == option, option).clicked() {
+                                *position = option.to_string();
+                            }
+                        }
+                    });
+                },
+                Target::Fixtures(fixtures) => {
+                    ui.vertical(|ui| {
+                        for (index, fixture) in patch.fixtures().iter().enumerate() {
+                            let mut selected = fixtures.contains(&index);
+                            if ui.checkbox(&mut selected, &fixture.name).changed() {
+                                if selected {
+                                    fixtures.push(index);
+                                    fixtures.sort_unstable();
+                                } else {
+                                    fixtures.retain(|other| *other != index);
+                                }
+                            }
+                        }
+                    });
+                },
+            }
+            ui.end_row();
+        });
+    }
+
+    fn lanes_ui(&mut self, ui: &mut Ui, track: &mut Track, position: f64) {
+        ui.horizontal(|ui| {
+            for (index, lane) in track.lanes.iter().enumerate() {
+                if ui.selectable_label(self.lane == Some(index), lane.attribute.to_string()).clicked() {
+                    self.lane = Some(index);
+                }
+            }
+            ComboBox::from_id_source("timeline_add_lane").selected_text("Add lane").show_ui(ui, |ui| {
+                let used = |attribute: Attribute| track.lanes.iter().any(|lane| lane.attribute == attribute);
+                let next_channel = (1..).find(|channel| !used(Attribute::Channel(*channel))).unwrap_or(1);
+                let mut add = None;
+                for attribute in [Attribute::Color, Attribute::Position, Attribute::Channel(next_channel)] {
+                    if ui.add_enabled(!used(attribute), egui::SelectableLabel::new(false, attribute.to_string())).clicked() {
+                        add = Some(attribute);
+                    }
+                }
+                if let Some(attribute) = add {
+                    track.lanes.push(Lane::new(attribute));
+                    self.lane = Some(track.lanes.len() - 1);
+                }
+            });
+        });
+        let Some(lane_index) = self.lane.filter(|index| *index < track.lanes.len()) else {
+            return;
+        };
+        let lane = &mut track.lanes[lane_index];
+        let mut remove_lane = false;
+        ui.horizontal(|ui| {
+            if let Attribute::Channel(channel) = &mut lane.attribute {
+                ui.label("Channel");
+                ui.add(egui::DragValue::new(channel).clamp_range(1..=512));
+            }
+            if ui.button("Add keyframe at playhead").clicked() {
+                let value = lane.value_at(position).unwrap_or_else(|| lane.attribute.default_value());
+                lane.insert(Keyframe { time: position, value, curve: Curve::Linear });
+            }
+            remove_lane = ui.button("Remove lane").clicked();
+        });
+        let mut remove = None;
+        let mut update = None;
+        egui::Grid::new("timeline_keyframes").striped(true).num_columns(4).show(ui, |ui| {
+            for (index, keyframe) in lane.keyframes().iter().enumerate() {
+                let mut edited = *keyframe;
+                ui.add(egui::DragValue::new(&mut edited.time).clamp_range(0.0..=f64::MAX).speed(0.05));
+                value_ui(ui, &mut edited.value);
+                ComboBox::from_id_source(("timeline_curve", index)).selected_text(format!("{:?}", edited.curve)).show_ui(ui, |ui| {
+                    for curve in [Curve::Step, Curve::Linear, Curve::Smooth] {
+                        ui.selectable_value(&mut edited.curve, curve, format!("{:?}", curve));
+                    }
+                });
+                if ui.button("Remove").clicked() {
+                    remove = Some(index);
+                }
+                if edited != *keyframe {
+                    update = Some((index, edited));
+                }
+                ui.end_row();
+            }
+        });
+        if let Some((index, keyframe)) = update {
+            lane.update(index, keyframe);
+        }
+        if let Some(index) = remove {
+            lane.remove(index);
+        }
+        if remove_lane {
+            track.lanes.remove(lane_index);
+            self.lane = None;
+        }
+    }
+
+    fn clips_ui(&mut self, ui: &mut Ui, track: &mut Track, position: f64) {
+        ComboBox::from_id_source("timeline_add_clip").selected_text("Add clip at playhead").show_ui(ui, |ui| {
+            let effects = [
+                ClipEffect::Movement(ShapeGenerator::new(Shape::Circle, PanTilt::new(0.0, 0.0), PanTilt::new(30.0, 15.0))),
+                ClipEffect::Rainbow { spread: 1.0 },
+                ClipEffect::Chase { color: RGB::WHITE, width: 1 },
+                ClipEffect::Strobe { color: RGB::WHITE },
+                ClipEffect::Wave(Wave::new(Spread::Linear { angle: 0.0 }, RGB::WHITE)),
+                ClipEffect::Focus(Focus::new(Point3::new(0.0, 2.0, 0.0))),
+            ];
+            for effect in effects {
+                if ui.selectable_label(false, effect.name()).clicked() {
+                    track.clips.push(Clip::new(position, 4.0, effect));
+                }
+            }
+        });
+        let mut remove = None;
+        for (index, clip) in track.clips.iter_mut().enumerate() {
+            ui.push_id(("timeline_clip", index), |ui| {
+                ui.horizontal(|ui| {
+                    ui.strong(clip.effect.name());
+                    ui.label("Start");
+                    ui.add(egui::DragValue::new(&mut clip.start).clamp_range(0.0..=f64::MAX).speed(0.05));
+                    ui.label("Length");
+                    ui.add(egui::DragValue::new(&mut clip.length).clamp_range(0.0..=f64::MAX).speed(0.05));
+                    ui.label("Rate");
+                    ui.add(egui::DragValue::new(&mut clip.rate).clamp_range(0.0..=64.0).speed(0.01));
+                    if ui.button("Remove").clicked() {
+                        remove = Some(index);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.add_space(16.0);
+                    effect_ui(ui, &mut clip.effect);
+                });
+            });
+        }
+        if let Some(index) = remove {
+            track.clips.remove(index);
+        }
+    }
+
+    fn export_ui(&mut self, ui: &mut Ui, timeline: &Timeline) {
+        ui.horizontal(|ui| {
+            ui.label("Export frames");
+            ui.add(egui::TextEdit::singleline(&mut self.export_path).hint_text("intro.dmxr").desired_width(200.0));
+            ui.add(egui::DragValue::new(&mut self.export_rate).clamp_range(1.0..=100.0).suffix(" fps"));
+            if ui.add_enabled(!self.export_path.trim().is_empty(), egui::Button::new("Export")).clicked() {
+                let universes = self.show.universes.read().unwrap().len();
+                let path = self.export_path.trim();
+                let result = timeline.render_frames(&self.show.patch.read().unwrap(), &self.show.stage.read().unwrap(), universes, self.export_rate)
+                    .map_err(|error| format!("{:?}", error))
+                    .and_then(|recording| recording.save(path.as_ref()).map(|_| recording.len()).map_err(|error| error.to_string()));
+                self.message = Some(match result {
+                    Ok(frames) => (Color32::GRAY, format!("Exported {} frames to {}", frames, path)),
+                    Err(error) => (Color32::RED, format!("Could not export {}: {}", path, error)),
+                });
+            }
+        });
+    }
+
+    fn edit_ui(&mut self, ui: &mut Ui, timelines: &mut Vec<Timeline>) {
+        ui.heading("Timeline");
+        self.timelines_ui(ui, timelines);
+        let Some(timeline) = timelines.get_mut(self.selected) else {
+            ui.label("Create a timeline to program a show");
+            return;
+        };
+        self.settings_ui(ui, timeline);
+        self.transport_ui(ui, timeline);
+        if let Some((color, message)) = &self.message {
+            ui.colored_label(*color, message);
+        }
+        ui.separator();
+        self.tracks_ui(ui, timeline);
+        ui.separator();
+        let position = self.playback.position;
+        let Some(track) = self.track.and_then(|index| timeline.tracks.get_mut(index)) else {
+            self.export_ui(ui, timeline);
+            return;
+        };
+        let mut remove = false;
+        egui::ScrollArea::vertical().id_source("timeline_track_settings").max_height(ui.available_height() - 32.0).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.strong(&track.name);
+                remove = ui.button("Remove track").clicked();
+            });
+            self.track_ui(ui, track);
+            ui.separator();
+            ui.label("Keyframes");
+            self.lanes_ui(ui, track, position);
+            ui.separator();
+            ui.label("Clips");
+            self.clips_ui(ui, track, position);
+        });
+        if remove {
+            timeline.tracks.remove(self.track.unwrap_or_default());
+            self.track = None;
+            self.lane = None;
+        }
+        ui.separator();
+        self.export_ui(ui, timeline);
+    }
+}
+
+fn value_ui(ui: &mut Ui, value: &mut Value) {
+    match value {
+        Value::Level(level) => {
+            ui.add(egui::Slider::new(level, 0.0..=1.0));
+        },
+        Value::Color(color) => {
+            color_ui(ui, color);
+        },
+        Value::Position(pan, tilt) => {
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(pan).clamp_range(0.0..=1.0).speed(0.005).prefix("Pan "));
+                ui.add(egui::DragValue::new(tilt).clamp_range(0.0..=1.0).speed(0.005).prefix("Tilt "));
+            });
+        },
+    }
+}
+
+fn color_ui(ui: &mut Ui, color: &mut RGB) {
+    let mut rgb = [color.r as f32, color.g as f32, color.b as f32];
+    if ui.color_edit_button_rgb(&mut rgb).changed() {
+        *color = RGB::new(rgb[0] as f64, rgb[1] as f64, rgb[2] as f64);
+    }
+}
+
+fn effect_ui(ui: &mut Ui, effect: &mut ClipEffect) {
+    match effect {
+        ClipEffect::Movement(generator) => {
+            let shapes = [Shape::Circle, Shape::Figure8, Shape::Line(0.0), Shape::Random(1)];
+            let name = |shape: &Shape| format!("{:?}", shape).split('(').next().unwrap_or_default().to_string();
+            ComboBox::from_id_source("timeline_shape").selected_text(name(&generator.shape)).show_ui(ui, |ui| {
+                for shape in shapes {
+                    if ui.selectable_label(name(&generator.shape) == name(&shape), name(&shape)).clicked() && name(&generator.shape) != name(&shape) {
+                        generator.shape = shape;
+                    }
+                }
+            });
+            ui.label("Center");
+            ui.add(egui::DragValue::new(&mut generator.center.pan).suffix("°"));
+            ui.add(egui::DragValue::new(&mut generator.center.tilt).suffix("°"));
+            ui.label("Size");
+            ui.add(egui::DragValue::new(&mut generator.size.pan).suffix("°"));
+            ui.add(egui::DragValue::new(&mut generator.size.tilt).suffix("°"));
+            ui.label("Spread");
+            ui.add(egui::DragValue::new(&mut generator.spread).clamp_range(0.0..=1.0).speed(0.01));
+        },
+        ClipEffect::Rainbow { spread } => {
+            ui.label("Spread");
+            ui.add(egui::DragValue::new(spread).clamp_range(0.0..=4.0).speed(0.01));
+        },
+        ClipEffect::Chase { color, width } => {
+            color_ui(ui, color);
+            ui.label("Width");
+            ui.add(egui::DragValue::new(width).clamp_range(1..=64));
+        },
+        ClipEffect::Strobe { color } => {
+            color_ui(ui, color);
+        },
+        ClipEffect::Wave(wave) => {
+            color_ui(ui, &mut wave.color);
+            color_ui(ui, &mut wave.background);
+            let linear = matches!(wave.spread, Spread::Linear { .. });
+            ComboBox::from_id_source("timeline_spread").selected_text(if linear { "Linear" } else { "Radial" }).show_ui(ui, |ui| {
+                if ui.selectable_label(linear, "Linear").clicked() && !linear {
+                    wave.spread = Spread::Linear { angle: 0.0 };
+                }
+                if ui.selectable_label(!linear, "Radial").clicked() && linear {
+                    wave.spread = Spread::Radial { center: Point3::new(0.0, 2.0, 0.0) };
+                }
+            });
+            match &mut wave.spread {
+                Spread::Linear { angle } => {
+                    ui.add(egui::DragValue::new(angle).clamp_range(-180.0..=180.0).suffix("°")).on_hover_text("0° runs from left to right as the audience sees it");
+                },
+                Spread::Radial { center } => point_ui(ui, center),
+            }
+            ui.label("Wavelength");
+            ui.add(egui::DragValue::new(&mut wave.wavelength).clamp_range(0.1..=100.0).speed(0.05).suffix(" m"));
+            ui.label("Speed");
+            ui.add(egui::DragValue::new(&mut wave.speed).clamp_range(-16.0..=16.0).speed(0.01)).on_hover_text("Pulses per beat");
+            ui.label("Width");
+            ui.add(egui::DragValue::new(&mut wave.width).clamp_range(0.01..=1.0).speed(0.01));
+        },
+        ClipEffect::Focus(focus) => {
+            ui.label("Target");
+            point_ui(ui, &mut focus.target);
+            let shapes = [None, Some(Shape::Circle), Some(Shape::Figure8), Some(Shape::Line(0.0))];
+            let name = |shape: &Option<Shape>| shape.map_or("Fixed".to_string(), |shape| format!("{:?}", shape).split('(').next().unwrap_or_default().to_string());
+            ComboBox::from_id_source("timeline_focus_shape").selected_text(name(&focus.shape)).show_ui(ui, |ui| {
+                for shape in shapes {
+                    if ui.selectable_label(name(&focus.shape) == name(&shape), name(&shape)).clicked() && name(&focus.shape) != name(&shape) {
+                        focus.shape = shape;
+                    }
+                }
+            });
+            if focus.shape.is_some() {
+                ui.label("Size");
+                ui.add(egui::DragValue::new(&mut focus.size).clamp_range(0.0..=50.0).speed(0.05).suffix(" m"));
+                ui.label("Speed");
+                ui.add(egui::DragValue::new(&mut focus.speed).clamp_range(-16.0..=16.0).speed(0.01)).on_hover_text("Cycles per beat");
+                ui.label("Spread");
+                ui.add(egui::DragValue::new(&mut focus.spread).clamp_range(0.0..=1.0).speed(0.01));
+            }
+        },
+    }
+}
+
+fn point_ui(ui: &mut Ui, point: &mut Point3) {
+    ui.add(egui::DragValue::new(&mut point.x).speed(0.05).prefix("x "));
+    ui.add(egui::DragValue::new(&mut point.y).speed(0.05).prefix("y "));
+    ui.add(egui::DragValue::new(&mut point.z).speed(0.05).prefix("z "));
+}
+
+impl Default for TimelinePage {
+    fn default() -> Self {
+        Self::new(ShowState::default(), Lock::default())
+    }
+}
+
+impl PageUI for TimelinePage {
+    // Edits a copy of the timelines, a changed copy goes to the history
+    fn ui(&mut self, ui: &mut Ui) {
+        let current = self.show.timelines.read().unwrap().clone();
+        let mut timelines = current.clone();
+        self.edit_ui(ui, &mut timelines);
+        // Drags and typing end once the pointer is up and no field has focus
+        let continuous = ui.input().pointer.any_down() || ui.memory().focus().is_some();
+        let mut history = self.history.write().unwrap();
+        if timelines != current {
+            if continuous && !self.editing {
+                history.begin_group();
+                self.editing = true;
+            }
+            let _ = history.execute(ShowCommand::timelines(timelines), &mut self.show);
+        }
+        if self.editing && !continuous {
+            history.end_group();
+            self.editing = false;
+        }
+    }
+}
@@ -0,0 +1,492 @@
+use eframe::egui::{self, Align2, Color32, ComboBox, FontId, Painter, Pos2, Rect, Response, Sense, Shape, Stroke, Ui, Vec2};
+use crate::pages::PageUI;
+
+use dmxt_lib::components::color::RGB;
+use dmxt_lib::history::History;
+use dmxt_lib::patch::{Patch, PatchCommand};
+use dmxt_lib::show::{ShowCommand, ShowState};
+use dmxt_lib::stage::{Mounting, Placement, Point3, Stage, Truss};
+use dmxt_lib::threads::shared::Lock;
+use dmxt_lib::visualizer::{self, FixtureState};
+
+// Fixtures that aren't placed and don't hang on a truss are laid out in a row per rigging position
+const FIXTURE_SPACING: f64 = 1.0;
+const ROW_SPACING: f64 = 2.5;
+// Beam radius on the floor per meter of throw
+const BEAM_SPREAD: f64 = 0.15;
+// Beams that don't reach the floor are drawn this long
+const BEAM_LENGTH: f64 = 3.0;
+const PICK_RADIUS: f32 = 12.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    Plan,
+    Perspective,
+}
+
+// Orbiting camera of the perspective view, rendered in software with the egui painter
+#[derive(Debug, Clone, Copy)]
+struct Camera {
+    yaw: f64,
+    pitch: f64,
+}
+
+// Placement and stage changes of a frame, applied once the show isn't borrowed anymore
+#[derive(Debug, Default)]
+struct Edits {
+    placements: Vec<(usize, Option<Placement>)>,
+    stage: Option<Stage>,
+    begin_group: bool,
+    end_group: bool,
+}
+
+impl Edits {
+    // Continuous edits like drags and typing become one undo step
+    fn group(&mut self, response: &Response) {
+        self.begin_group |= response.drag_started() || response.gained_focus();
+        self.end_group |= response.drag_released() || response.lost_focus();
+    }
+}
+
+// Shows the patched fixtures on the stage as the universes drive them, and where they are placed
+#[derive(Debug)]
+pub struct VisualizerPage {
+    show: ShowState,
+    history: Lock<History<ShowCommand>>,
+    view: View,
+    camera: Camera,
+    // Pixels per meter of the plan
+    zoom: f32,
+    // Height of fixtures without a placement
+    rig_height: f64,
+    selected: Option<usize>,
+    dragging: Option<usize>,
+    error: Option<String>,
+}
+
+impl VisualizerPage {
+    pub fn new(show: ShowState, history: Lock<History<ShowCommand>>) -> VisualizerPage {
+        VisualizerPage {
+            show,
+            history,
+            view: View::Plan,
+            camera: Camera { yaw: 0.0, pitch: 0.5 },
+            zoom: 40.0,
+            rig_height: 6.0,
+            selected: None,
+            dragging: None,
+            error: None,
+        }
+    }
+
+    // Where every fixture is drawn, placed or not
+    fn placements(&self, patch: &Patch, stage: &Stage) -> Vec<Placement> {
+        let placed = stage.placements(patch);
+        let mut rows: Vec<(&str, Vec<usize>)> = Vec::new();
+        for (index, fixture) in patch.fixtures().iter().enumerate().filter(|(index, _)| placed[*index].is_none()) {
+            match rows.iter_mut().find(|(position, _)| *position == fixture.position) {
+                Some((_, fixtures)) => fixtures.push(index),
+                None => rows.push((&fixture.position, vec![index])),
+            }
+        }
+        let mut placements: Vec<Placement> = placed.iter().map(|placement| placement.unwrap_or_default()).collect();
+        for (row, (_, fixtures)) in rows.iter().enumerate() {
+            let width = (fixtures.len() - 1) as f64 * FIXTURE_SPACING;
+            for (column, index) in fixtures.iter().enumerate() {
+                let x = column as f64 * FIXTURE_SPACING - width / 2.0;
+                placements[*index].position = Point3::new(x, 1.0 + row as f64 * ROW_SPACING, self.rig_height);
+            }
+        }
+        placements
+    }
+
+    fn apply(&mut self, edits: Edits) {
+        if edits.placements.is_empty() && edits.stage.is_none() && !edits.begin_group && !edits.end_group {
+            return;
+        }
+        let mut history = self.history.write().unwrap();
+        if edits.begin_group {
+            history.begin_group();
+        }
+        if let Some(stage) = edits.stage {
+            let _ = history.execute(ShowCommand::stage(stage), &mut self.show);
+        }
+        for (index, placement) in edits.placements {
+            let Some(mut fixture) = self.show.patch.read().unwrap().get(index).cloned() else {
+                continue;
+            };
+            fixture.placement = placement;
+            if let Err(error) = history.execute(PatchCommand::update(index, fixture).into(), &mut self.show) {
+                self.error = Some(error.to_string());
+            }
+        }
+        if edits.end_group {
+            history.end_group();
+        }
+    }
+
+    fn toolbar_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.view, View::Plan, "Plan");
+            ui.selectable_value(&mut self.view, View::Perspective, "3D");
+            ui.separator();
+            ui.label("Zoom");
+            ui.add(egui::Slider::new(&mut self.zoom, 10.0..=120.0).show_value(false));
+            ui.label("Unplaced height");
+            ui.add(egui::DragValue::new(&mut self.rig_height).clamp_range(0.0..=30.0).speed(0.1).suffix(" m"));
+        });
+        ui.label(match self.view {
+            View::Plan => "Click a fixture to select it and drag it to place it, the audience is at the bottom",
+            View::Perspective => "Drag to orbit the camera",
+        });
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::RED, error);
+        }
+    }
+
+    // Edits a copy of the stage, a changed copy goes to the history
+    fn stage_ui(&mut self, ui: &mut Ui, patch: &Patch, current: &Stage, edits: &mut Edits) {
+        let mut stage = current.clone();
+        ui.heading("Stage");
+        egui::Grid::new("visualizer_stage").num_columns(2).show(ui, |ui| {
+            ui.label("Width");
+            edits.group(&ui.add(egui::DragValue::new(&mut stage.width).clamp_range(1.0..=100.0).speed(0.1).suffix(" m")));
+            ui.end_row();
+            ui.label("Depth");
+            edits.group(&ui.add(egui::DragValue::new(&mut stage.depth).clamp_range(1.0..=100.0).speed(0.1).suffix(" m")));
+            ui.end_row();
+        });
+        ui.label("Trusses");
+        let mut remove = None;
+        for (index, truss) in stage.trusses.iter_mut().enumerate() {
+            ui.push_id(index, |ui| {
+                ui.horizontal(|ui| {
+                    let response = ui.add(egui::TextEdit::singleline(&mut truss.name).desired_width(100.0));
+                    edits.group(&response.on_hover_text("Fixtures with this rigging position hang on the truss"));
+                    if ui.small_button("🗑").clicked() {
+                        remove = Some(index);
+                    }
+                });
+                point_ui(ui, "From", &mut truss.start, edits);
+                point_ui(ui, "To", &mut truss.end, edits);
+            });
+        }
+        if let Some(index) = remove {
+            stage.trusses.remove(index);
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Add truss").clicked() {
+                let y = stage.depth / 2.0;
+                let name = format!("Truss {}", stage.trusses.len() + 1);
+                stage.trusses.push(Truss::new(name, Point3::new(-stage.width / 2.0, y, 6.0), Point3::new(stage.width / 2.0, y, 6.0)));
+            }
+            let hang = stage.hang(patch);
+            if ui.add_enabled(!hang.is_empty(), egui::Button::new("Hang fixtures")).on_hover_text("Spreads the fixtures of every truss along it").clicked() {
+                edits.begin_group = true;
+                edits.end_group = true;
+                edits.placements.extend(hang.into_iter().map(|(index, placement)| (index, Some(placement))));
+            }
+        });
+        if stage != *current {
+            edits.stage = Some(stage);
+        }
+    }
+
+    fn fixture_ui(&mut self, ui: &mut Ui, patch: &Patch, placements: &[Placement], edits: &mut Edits) {
+        let Some((index, fixture)) = self.selected.and_then(|index| patch.get(index).map(|fixture| (index, fixture))) else {
+            ui.label("Select a fixture to place it");
+            return;
+        };
+        ui.heading(&fixture.name);
+        let Some(mut placement) = fixture.placement else {
+            ui.label(if fixture.position.is_empty() { "Not placed".to_string() } else { format!("Not placed, rigged at {}", fixture.position) });
+            if ui.button("Place here").on_hover_text("Keeps the fixture where it is drawn").clicked() {
+                edits.placements.push((index, Some(placements[index])));
+            }
+            return;
+        };
+        let mut changed = false;
+        egui::Grid::new("visualizer_placement").num_columns(2).show(ui, |ui| {
+            for (label, value) in [("X", &mut placement.position.x), ("Y", &mut placement.position.y), ("Height", &mut placement.position.z)] {
+                ui.label(label);
+                let response = ui.add(egui::DragValue::new(value).speed(0.05).suffix(" m"));
+                edits.group(&response);
+                changed |= response.changed();
+                ui.end_row();
+            }
+            ui.label("Rotation");
+            let response = ui.add(egui::DragValue::new(&mut placement.rotation).clamp_range(-180.0..=180.0).speed(1.0).suffix("°"));
+            edits.group(&response);
+            changed |= response.changed();
+            ui.end_row();
+            ui.label("Mounting");
+            ComboBox::from_id_source("visualizer_mounting").selected_text(placement.mounting.to_string()).show_ui(ui, |ui| {
+                for mounting in Mounting::ALL {
+                    changed |= ui.selectable_value(&mut placement.mounting, mounting, mounting.to_string()).changed();
+                }
+            });
+            ui.end_row();
+        });
+        if changed {
+            edits.placements.push((index, Some(placement)));
+        }
+        if ui.button("Remove placement").clicked() {
+            edits.placements.push((index, None));
+        }
+    }
+
+    fn plan_ui(&mut self, ui: &mut Ui, patch: &Patch, stage: &Stage, placements: &[Placement], states: &[FixtureState], edits: &mut Edits) {
+        let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+        let rect = response.rect;
+        painter.rect_filled(rect, 0.0, Color32::from_gray(20));
+        let zoom = self.zoom;
+        let center = rect.center();
+        let depth = stage.depth;
+        let to_screen = |point: Point3| center + egui::vec2(point.x as f32, (depth / 2.0 - point.y) as f32) * zoom;
+        let to_stage = |pos: Pos2| {
+            let offset = (pos - center) / zoom;
+            (offset.x as f64, depth / 2.0 - offset.y as f64)
+        };
+
+        let half = stage.width / 2.0;
+        let grid = Stroke::new(1.0, Color32::from_gray(35));
+        for x in 0..=stage.width.floor() as i32 {
+            let x = x as f64 - half.floor();
+            painter.line_segment([to_screen(Point3::new(x, 0.0, 0.0)), to_screen(Point3::new(x, depth, 0.0))], grid);
+        }
+        for y in 0..=depth.floor() as i32 {
+            painter.line_segment([to_screen(Point3::new(-half, y as f64, 0.0)), to_screen(Point3::new(half, y as f64, 0.0))], grid);
+        }
+        painter.rect_stroke(Rect::from_two_pos(to_screen(Point3::new(-half, 0.0, 0.0)), to_screen(Point3::new(half, depth, 0.0))), 0.0, Stroke::new(1.0, Color32::GRAY));
+        for truss in &stage.trusses {
+            let (start, end) = (to_screen(truss.start), to_screen(truss.end));
+            painter.line_segment([start, end], Stroke::new(4.0, Color32::from_gray(60)));
+            painter.text(start - egui::vec2(4.0, 0.0), Align2::RIGHT_CENTER, &truss.name, FontId::proportional(10.0), Color32::GRAY);
+        }
+
+        for state in states {
+            let placement = &placements[state.fixture];
+            if state.intensity() < 0.01 {
+                continue;
+            }
+            let color = beam_color(state.color(), 0.5);
+            let fixture = to_screen(placement.position);
+            let direction = placement.to_stage(state.direction());
+            match placement.floor_hit(direction) {
+                Some(hit) => {
+                    let pool = to_screen(hit);
+                    painter.line_segment([fixture, pool], Stroke::new(1.0, color));
+                    painter.circle_filled(pool, (hit.distance(placement.position) * BEAM_SPREAD) as f32 * zoom, color);
+                },
+                None => painter.line_segment([fixture, to_screen(placement.position + direction * BEAM_LENGTH)], Stroke::new(2.0, color)),
+            }
+        }
+
+        for (state, fixture) in states.iter().zip(patch.fixtures()) {
+            let position = to_screen(placements[state.fixture].position);
+            draw_fixture(&painter, position, state, self.selected == Some(state.fixture));
+            painter.text(position + egui::vec2(0.0, 12.0), Align2::CENTER_TOP, &fixture.name, FontId::proportional(10.0), Color32::GRAY);
+        }
+
+        let pick = |pointer: Pos2| placements.iter().enumerate()
+            .map(|(index, placement)| (index, to_screen(placement.position).distance(pointer)))
+            .filter(|(_, distance)| *distance < PICK_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index);
+        if response.clicked() {
+            self.selected = response.interact_pointer_pos().and_then(pick);
+        }
+        if response.drag_started() {
+            self.dragging = response.interact_pointer_pos().and_then(pick);
+            self.selected = self.dragging.or(self.selected);
+            edits.begin_group = self.dragging.is_some();
+        }
+        if response.drag_released() {
+            edits.end_group = self.dragging.take().is_some();
+        } else if let (Some(index), Some(pointer)) = (self.dragging, response.interact_pointer_pos()) {
+            let (x, y) = to_stage(pointer);
+            let placement = Placement { position: Point3::new(x, y, placements[index].position.z), ..placements[index] };
+            edits.placements.push((index, Some(placement)));
+        }
+    }
+
+    fn perspective_ui(&mut self, ui: &mut Ui, stage: &Stage, placements: &[Placement], states: &[FixtureState]) {
+        let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::drag());
+        let rect = response.rect;
+        painter.rect_filled(rect, 0.0, Color32::from_gray(10));
+        let delta = response.drag_delta();
+        self.camera.yaw -= delta.x as f64 * 0.01;
+        self.camera.pitch = (self.camera.pitch + delta.y as f64 * 0.01).clamp(0.05, 1.5);
+        let distance = 500.0 / self.zoom as f64;
+        let project = Projection::new(self.camera, distance, Point3::new(0.0, stage.depth / 2.0, 1.5), rect);
+
+        let half = stage.width / 2.0;
+        let grid = Stroke::new(1.0, Color32::from_gray(40));
+        let mut lines = Vec::new();
+        for x in 0..=stage.width.floor() as i32 {
+            let x = x as f64 - half.floor();
+            lines.push((Point3::new(x, 0.0, 0.0), Point3::new(x, stage.depth, 0.0)));
+        }
+        for y in 0..=stage.depth.floor() as i32 {
+            lines.push((Point3::new(-half, y as f64, 0.0), Point3::new(half, y as f64, 0.0)));
+        }
+        for (from, to) in lines {
+            if let (Some(from), Some(to)) = (project.point(from), project.point(to)) {
+                painter.line_segment([from, to], grid);
+            }
+        }
+        for truss in &stage.trusses {
+            if let (Some(start), Some(end)) = (project.point(truss.start), project.point(truss.end)) {
+                painter.line_segment([start, end], Stroke::new(3.0, Color32::from_gray(70)));
+            }
+        }
+
+        for state in states {
+            let placement = &placements[state.fixture];
+            let Some(fixture) = project.point(placement.position) else {
+                continue;
+            };
+            if state.intensity() >= 0.01 {
+                let direction = placement.to_stage(state.direction());
+                let hit = placement.floor_hit(direction);
+                let end = hit.unwrap_or(placement.position + direction * BEAM_LENGTH);
+                let radius = end.distance(placement.position) * BEAM_SPREAD;
+                if let Some(end_point) = project.point(end) {
+                    let side = (end_point - fixture).normalized().rot90() * project.scale(end, radius);
+                    painter.add(Shape::convex_polygon(vec![fixture, end_point + side, end_point - side], beam_color(state.color(), 0.25), Stroke::NONE));
+                }
+                if let Some(hit) = hit {
+                    let pool: Vec<Pos2> = (0..16).filter_map(|step| {
+                        let angle = step as f64 / 16.0 * std::f64::consts::TAU;
+                        project.point(hit + Point3::new(angle.cos(), angle.sin(), 0.0) * radius)
+                    }).collect();
+                    painter.add(Shape::convex_polygon(pool, beam_color(state.color(), 0.6), Stroke::NONE));
+                }
+            }
+            draw_fixture(&painter, fixture, state, self.selected == Some(state.fixture));
+        }
+    }
+}
+
+// Pinhole projection around a point the camera orbits
+struct Projection {
+    eye: Point3,
+    right: Point3,
+    up: Point3,
+    forward: Point3,
+    center: Pos2,
+    focal: f64,
+}
+
+impl Projection {
+    fn new(camera: Camera, distance: f64, target: Point3, rect: Rect) -> Projection {
+        let offset = Point3::new(
+            distance * camera.pitch.cos() * camera.yaw.sin(),
+            -distance * camera.pitch.cos() * camera.yaw.cos(),
+            distance * camera.pitch.sin(),
+        );
+        let eye = target + offset;
+        let forward = (target - eye).normalized();
+        let right = forward.cross(Point3::UP).normalized();
+        let up = right.cross(forward);
+        Projection { eye, right, up, forward, center: rect.center(), focal: rect.height().max(1.0) as f64 }
+    }
+
+    fn point(&self, point: Point3) -> Option<Pos2> {
+        let relative = point - self.eye;
+        let depth = relative.dot(self.forward);
+        if depth < 0.1 {
+            return None;
+        }
+        let scale = self.focal / depth;
+        Some(self.center + egui::vec2((relative.dot(self.right) * scale) as f32, (-relative.dot(self.up) * scale) as f32))
+    }
+
+    // Screen size of a length at a point
+    fn scale(&self, point: Point3, length: f64) -> f32 {
+        (length * self.focal / (point - self.eye).dot(self.forward).max(0.1)) as f32
+    }
+}
+
+fn point_ui(ui: &mut Ui, label: &str, point: &mut Point3, edits: &mut Edits) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        for value in [&mut point.x, &mut point.y, &mut point.z] {
+            edits.group(&ui.add(egui::DragValue::new(value).speed(0.05).suffix(" m")));
+        }
+    });
+}
+
+fn color32(color: RGB) -> Color32 {
+    let (r, g, b) = color.to_u8();
+    Color32::from_rgb(r, g, b)
+}
+
+// The brightest channel goes to full so dim beams keep their hue and fade out instead
+fn beam_color(color: RGB, alpha: f32) -> Color32 {
+    let intensity = color.max();
+    let (r, g, b) = if intensity > 0.0 { color.scale(1.0 / intensity) } else { color }.to_u8();
+    Color32::from_rgba_unmultiplied(r, g, b, (intensity as f32 * alpha * 255.0) as u8)
+}
+
+// Matrix fixtures show every cell, the others a single lens
+fn draw_fixture(painter: &Painter, position: Pos2, state: &FixtureState, selected: bool) {
+    let outline = Stroke::new(1.0, if selected { Color32::WHITE } else { Color32::from_gray(90) });
+    let rows = state.cells.len();
+    let columns = state.cells.iter().map(Vec::len).max().unwrap_or(0);
+    if rows * columns <= 1 {
+        let color = state.cells.first().and_then(|row| row.first()).copied().unwrap_or(RGB::BLACK);
+        painter.circle(position, 7.0, color32(color), outline);
+        return;
+    }
+    let cell = (20.0 / columns.max(rows) as f32).max(3.0);
+    let size = Vec2::new(columns as f32, rows as f32) * cell;
+    let frame = Rect::from_center_size(position, size);
+    for (row, cells) in state.cells.iter().enumerate() {
+        for (column, color) in cells.iter().enumerate() {
+            let min = frame.min + egui::vec2(column as f32, row as f32) * cell;
+            painter.rect_filled(Rect::from_min_size(min, Vec2::splat(cell)), 0.0, color32(*color));
+        }
+    }
+    painter.rect_stroke(frame, 0.0, outline);
+}
+
+impl Default for VisualizerPage {
+    fn default() -> Self {
+        Self::new(ShowState::default(), Lock::default())
+    }
+}
+
+impl PageUI for VisualizerPage {
+    fn ui(&mut self, ui: &mut Ui) {
+        let mut edits = Edits::default();
+        let patch = self.show.patch.clone();
+        let stage = self.show.stage.clone();
+        {
+            let patch = patch.read().unwrap();
+            let stage = stage.read().unwrap();
+            let placements = self.placements(&patch, &stage);
+            egui::SidePanel::right("visualizer_inspector").show_inside(ui, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    self.fixture_ui(ui, &patch, &placements, &mut edits);
+                    ui.separator();
+                    self.stage_ui(ui, &patch, &stage, &mut edits);
+                });
+            });
+            ui.heading("Visualizer");
+            self.toolbar_ui(ui);
+            if patch.is_empty() {
+                ui.label("Patch fixtures to see them on stage");
+            } else {
+                let states = visualizer::read_states(&patch, &self.show.universes.read().unwrap());
+                match self.view {
+                    View::Plan => self.plan_ui(ui, &patch, &stage, &placements, &states, &mut edits),
+                    View::Perspective => self.perspective_ui(ui, &stage, &placements, &states),
+                }
+            }
+        }
+        self.apply(edits);
+        // The universes change without egui noticing
+        ui.ctx().request_repaint();
+    }
+}
@@ -0,0 +1 @@
+pub mod control_panel;
\ No newline at end of file
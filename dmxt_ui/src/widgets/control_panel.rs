@@ -0,0 +1,272 @@
+use dmxt_lib::builders::fixture::{FixtureColorMode, FixtureCustomOperation, FixtureName, OperationModeType};
+use dmxt_lib::components::color::RGB;
+use dmxt_lib::components::Fixture;
+use dmxt_lib::dmx::DMXUniverse;
+use dmxt_lib::threads::shared::Lock;
+
+use eframe::egui::{self, Color32, ColorImage, ComboBox, Sense, Stroke, TextureHandle, Ui};
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const ICON_SIZE: f32 = 16.0;
+const PAD_SIZE: f32 = 160.0;
+
+// Controls generated from the channel mode of a patched fixture.
+// Every change is written into the fixture's universe right away
+#[derive(Debug)]
+pub struct ControlPanel {
+    operation_mode: Option<usize>,
+    // Normalized slider positions and physical values by control key
+    positions: HashMap<String, f64>,
+    steps: HashMap<String, usize>,
+    color: [f32; 3],
+    pan_tilt: (f64, f64),
+    zoom: f64,
+    icons: Icons,
+    error: Option<String>,
+}
+
+impl Default for ControlPanel {
+    fn default() -> Self {
+        Self {
+            operation_mode: None,
+            positions: HashMap::new(),
+            steps: HashMap::new(),
+            color: [0.0; 3],
+            pan_tilt: (0.5, 0.5),
+            zoom: 0.0,
+            icons: Icons::default(),
+            error: None,
+        }
+    }
+}
+
+impl ControlPanel {
+    pub fn ui(&mut self, ui: &mut Ui, fixture: &Fixture, universes: &Lock<Vec<DMXUniverse>>) {
+        let model = fixture.model();
+        ui.horizontal(|ui| {
+            self.icons.show(ui, fixture, &model.name);
+            ui.heading(&fixture.name);
+        });
+        ui.label(format!(
+            "{} {} - universe {}, channels {} - {}",
+            model.manufacturer, model.name, fixture.universe + 1, fixture.channels().0, fixture.channels().1,
+        ));
+        let Some(mode) = fixture.channel_mode() else {
+            ui.label("The fixture has no channel mode");
+            return;
+        };
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::RED, error);
+        }
+        ui.separator();
+
+        if !mode.operation_modes.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Mode");
+                for (index, operation_mode) in mode.operation_modes.iter().enumerate() {
+                    if let OperationModeType::DMX(name) = &operation_mode.mode_type {
+                        self.icons.show(ui, fixture, name);
+                    }
+                    let selectable = operation_mode.address.is_some();
+                    let label = egui::SelectableLabel::new(self.operation_mode == Some(index), operation_mode.mode_type.to_string());
+                    if ui.add_enabled(selectable, label).clicked() {
+                        self.operation_mode = Some(index);
+                        self.error = apply(universes, fixture, |universe| fixture.write_operation_mode(universe, index));
+                    }
+                }
+            });
+            if let Some(operation_mode) = self.operation_mode.and_then(|index| mode.operation_modes.get(index)) {
+                let key = format!("mode{}-", self.operation_mode.unwrap_or_default());
+                self.operations_ui(ui, fixture, universes, &operation_mode.submodes, &key);
+            }
+            ui.separator();
+        }
+
+        if let Some(lights) = &mode.lights {
+            let cells: Vec<(usize, usize)> = lights.matrix.iter().enumerate()
+                .flat_map(|(row, cells)| (0..cells.len()).map(move |column| (row, column)))
+                .collect();
+            ui.horizontal(|ui| {
+                ui.label("Color");
+                if ui.color_edit_button_rgb(&mut self.color).changed() {
+                    let [r, g, b] = self.color;
+                    let color = RGB::new(r as f64, g as f64, b as f64);
+                    self.error = apply(universes, fixture, |universe| {
+                        cells.iter().try_for_each(|(row, column)| fixture.write_color(universe, *row, *column, color))
+                    });
+                }
+                if cells.len() > 1 {
+                    ui.label(format!("on all {} cells", cells.len()));
+                }
+            });
+            // Preset colors get a button each, the color picker only finds the nearest one
+            for (row, cells) in lights.matrix.iter().enumerate() {
+                for (column, cell) in cells.iter().enumerate() {
+                    if let FixtureColorMode::Presets(presets) = &cell.color_mode {
+                        ui.push_id((row, column), |ui| ui.horizontal_wrapped(|ui| {
+                            for (color, address) in presets {
+                                let fill = color.rgb().map_or(Color32::TRANSPARENT, |(r, g, b)| Color32::from_rgb(r, g, b));
+                                let button = egui::Button::new("  ").fill(fill);
+                                if ui.add(button).on_hover_text(format!("{:?}", color)).clicked() {
+                                    let address = *address;
+                                    self.error = apply(universes, fixture, |universe| fixture.write_address(universe, address));
+                                }
+                            }
+                        }));
+                    }
+                }
+            }
+            ui.separator();
+        }
+
+        if let Some(movement) = &mode.movement {
+            ui.label(match (&movement.pan, &movement.tilt) {
+                (Some(_), Some(_)) => "Pan / Tilt",
+                (Some(_), None) => "Pan",
+                _ => "Tilt",
+            });
+            if pan_tilt_pad(ui, &mut self.pan_tilt) {
+                let (pan, tilt) = self.pan_tilt;
+                self.error = apply(universes, fixture, |universe| fixture.write_position_normalized(universe, pan, tilt));
+            }
+            ui.separator();
+        }
+
+        if let Some(zoom) = &mode.zoom {
+            ui.horizontal(|ui| {
+                ui.label("Zoom");
+                match zoom.physical.as_ref().and_then(|physical| physical.bounds()) {
+                    Some((min, max)) => {
+                        let mut degrees = min + (max - min) * self.zoom;
+                        if ui.add(egui::Slider::new(&mut degrees, min..=max).suffix("°")).changed() {
+                            self.zoom = if max > min { (degrees - min) / (max - min) } else { 0.0 };
+                            self.error = apply(universes, fixture, |universe| fixture.write_zoom_degrees(universe, degrees));
+                        }
+                    },
+                    None => {
+                        if ui.add(egui::Slider::new(&mut self.zoom, 0.0..=1.0).show_value(false)).changed() {
+                            let zoom = self.zoom;
+                            self.error = apply(universes, fixture, |universe| fixture.write_zoom(universe, zoom));
+                        }
+                    },
+                }
+            });
+            ui.separator();
+        }
+
+        if let Some(custom) = &mode.custom {
+            self.operations_ui(ui, fixture, universes, custom, "custom-");
+        }
+    }
+
+    fn operations_ui(&mut self, ui: &mut Ui, fixture: &Fixture, universes: &Lock<Vec<DMXUniverse>>, operations: &[FixtureCustomOperation], prefix: &str) {
+        egui::Grid::new(prefix).num_columns(2).show(ui, |ui| {
+            for (index, operation) in operations.iter().enumerate() {
+                let key = format!("{}{}", prefix, index);
+                ui.horizontal(|ui| {
+                    self.icons.show(ui, fixture, operation.name());
+                    ui.label(operation.name().name());
+                });
+                match operation {
+                    FixtureCustomOperation::Button(_, _) => {
+                        if ui.button("Trigger").clicked() {
+                            self.error = apply(universes, fixture, |universe| fixture.write_custom(universe, operation, 1.0));
+                        }
+                    },
+                    FixtureCustomOperation::Stepped(_, steps) => {
+                        let selected = self.steps.get(&key).copied();
+                        let text = selected.and_then(|step| steps.get(step)).map_or("-", |(name, _)| name.name());
+                        ComboBox::from_id_source(&key).selected_text(text).show_ui(ui, |ui| {
+                            for (step, (name, address)) in steps.iter().enumerate() {
+                                if ui.selectable_label(selected == Some(step), name.name()).clicked() {
+                                    self.steps.insert(key.clone(), step);
+                                    let address = *address;
+                                    self.error = apply(universes, fixture, |universe| fixture.write_address(universe, address));
+                                }
+                            }
+                        });
+                    },
+                    FixtureCustomOperation::Physical(_, _, physical) if physical.bounds().is_some() => {
+                        let (min, max) = physical.bounds().unwrap();
+                        let value = self.positions.entry(key).or_insert(min);
+                        let slider = egui::Slider::new(value, min..=max).suffix(format!(" {}", physical.unit.symbol()));
+                        if ui.add(slider).changed() {
+                            let value = *value;
+                            self.error = apply(universes, fixture, |universe| fixture.write_physical(universe, operation, value));
+                        }
+                    },
+                    FixtureCustomOperation::Slider(..) | FixtureCustomOperation::FineSlider(..) | FixtureCustomOperation::Physical(..) => {
+                        let position = self.positions.entry(key).or_insert(0.0);
+                        let slider = egui::Slider::new(position, 0.0..=1.0).custom_formatter(|value, _| format!("{:.0}%", value * 100.0));
+                        if ui.add(slider).changed() {
+                            let position = *position;
+                            self.error = apply(universes, fixture, |universe| fixture.write_custom(universe, operation, position));
+                        }
+                    },
+                }
+                ui.end_row();
+            }
+        });
+    }
+}
+
+// Writes into the fixture's universe, returns the error to show
+fn apply<E: std::fmt::Debug>(universes: &Lock<Vec<DMXUniverse>>, fixture: &Fixture, write: impl FnOnce(&mut DMXUniverse) -> Result<(), E>) -> Option<String> {
+    match universes.write().unwrap().get_mut(fixture.universe) {
+        Some(universe) => write(universe).err().map(|error| format!("{:?}", error)),
+        None => Some(format!("universe {} does not exist", fixture.universe + 1)),
+    }
+}
+
+// XY pad for pan (x) and tilt (y), both normalized. Returns true if the position changed
+fn pan_tilt_pad(ui: &mut Ui, position: &mut (f64, f64)) -> bool {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(PAD_SIZE, PAD_SIZE), Sense::click_and_drag());
+    let mut changed = false;
+    if let Some(pointer) = response.interact_pointer_pos() {
+        let x = ((pointer.x - rect.left()) / rect.width()).clamp(0.0, 1.0) as f64;
+        let y = ((pointer.y - rect.top()) / rect.height()).clamp(0.0, 1.0) as f64;
+        changed = (x, y) != *position;
+        *position = (x, y);
+    }
+    let painter = ui.painter();
+    let visuals = ui.style().visuals.widgets.inactive;
+    painter.rect(rect, 2.0, visuals.bg_fill, visuals.bg_stroke);
+    let point = egui::pos2(rect.left() + position.0 as f32 * rect.width(), rect.top() + position.1 as f32 * rect.height());
+    let stroke = Stroke::new(1.0, Color32::GRAY);
+    painter.line_segment([egui::pos2(rect.left(), point.y), egui::pos2(rect.right(), point.y)], stroke);
+    painter.line_segment([egui::pos2(point.x, rect.top()), egui::pos2(point.x, rect.bottom())], stroke);
+    painter.circle_filled(point, 4.0, ui.style().visuals.selection.bg_fill);
+    response.on_hover_text(format!("Pan {:.0}%, Tilt {:.0}%", position.0 * 100.0, position.1 * 100.0));
+    changed
+}
+
+// Icons of fixture names, loaded once next to the fixture config or in its `img` directory
+#[derive(Default)]
+struct Icons {
+    textures: HashMap<PathBuf, Option<TextureHandle>>,
+}
+
+impl std::fmt::Debug for Icons {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Icons").field("loaded", &self.textures.len()).finish()
+    }
+}
+
+impl Icons {
+    fn show(&mut self, ui: &mut Ui, fixture: &Fixture, name: &FixtureName) {
+        let Some(path) = name.icon_path().and_then(|icon| fixture.icon_file(icon)) else {
+            return;
+        };
+        let texture = self.textures.entry(path.clone()).or_insert_with(|| {
+            let image = image::open(&path).ok()?.to_rgba8();
+            let size = [image.width() as usize, image.height() as usize];
+            let image = ColorImage::from_rgba_unmultiplied(size, image.as_flat_samples().as_slice());
+            Some(ui.ctx().load_texture(path.display().to_string(), image, Default::default()))
+        });
+        if let Some(texture) = texture {
+            ui.image(texture.id(), [ICON_SIZE, ICON_SIZE]);
+        }
+    }
+}
@@ -1,9 +1,17 @@
 pub mod main_window;
 pub mod about_window;
+pub mod fixture;
+pub mod monitor;
+pub mod interfaces;
+pub mod file_prompt;
+pub mod preferences;
+pub mod missing_files;
+pub mod import;
+pub mod recorder;
 
 
 use eframe::egui::Context;
 
-trait SubWindow {
+pub trait SubWindow {
     fn ui(&mut self, ctx: &Context);
 }
\ No newline at end of file
@@ -0,0 +1,41 @@
+use eframe::egui::{self, Context, Key, Window};
+
+use std::path::{Path, PathBuf};
+
+// Asks for the path of a file to open or save
+#[derive(Debug, Default)]
+pub struct FilePrompt {
+    pub open: bool,
+    title: String,
+    path: String,
+}
+
+impl FilePrompt {
+    pub fn ask(&mut self, title: &str, path: Option<&Path>) {
+        self.open = true;
+        self.title = title.to_string();
+        self.path = path.map(|path| path.display().to_string()).unwrap_or_default();
+    }
+
+    // The path once it is confirmed
+    pub fn ui(&mut self, ctx: &Context) -> Option<PathBuf> {
+        let mut confirmed = None;
+        let mut open = self.open;
+        Window::new(&self.title)
+        .id(egui::Id::new("file_prompt"))
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let response = ui.add(egui::TextEdit::singleline(&mut self.path).hint_text("shows/show.json").desired_width(320.0));
+                let entered = response.lost_focus() && ui.input().key_pressed(Key::Enter);
+                if (ui.button("OK").clicked() || entered) && !self.path.trim().is_empty() {
+                    confirmed = Some(PathBuf::from(self.path.trim()));
+                }
+            });
+        });
+        self.open = open && confirmed.is_none();
+        confirmed
+    }
+}
@@ -1,8 +1,986 @@
-use dmxt_lib::components::Fixture;
+use crate::windows::SubWindow;
 
+use dmxt_lib::builders::capability::{CalibrationPoint, PhysicalRange, Unit};
+use dmxt_lib::builders::fixture::{
+    FixtureChannelMode, FixtureColorMode, FixtureCustomOperation, FixtureLights, FixtureMatrix, FixtureModel,
+    FixtureMovement, FixtureName, FixtureOperationMode, FixtureZoom, MovementAxis, OperationModeType,
+};
+use dmxt_lib::builders::validation::{self, Issue, Severity};
+use dmxt_lib::dmx::{Channel, Color, DMXAddress, DMXRange, DMXUniverse, DMX_CHANNELS};
+use dmxt_lib::threads::shared::Lock;
 
-struct FixtureWindow {
-    // ...
-    fixture: Fixture,
-    // ...
-}
\ No newline at end of file
+use eframe::egui::{self, CollapsingHeader, Color32, ComboBox, Context, DragValue, Grid, RichText, Sense, Ui, Window};
+
+use std::fs;
+use std::mem::discriminant;
+use std::path::{Path, PathBuf};
+
+// Editor for fixture models, changes are validated while editing and can be tried on a real fixture
+#[derive(Debug)]
+pub struct FixtureWindow {
+    pub open: bool,
+    model: FixtureModel,
+    path: String,
+    mode: usize,
+    cell: (usize, usize),
+    issues: Vec<Issue>,
+    output: Option<Lock<Vec<DMXUniverse>>>,
+    test_universe: usize,
+    test_start: u16,
+    status: Option<(String, bool)>,
+}
+
+impl Default for FixtureWindow {
+    fn default() -> Self {
+        Self::new(FixtureModel {
+            name: FixtureName::new(String::new()),
+            manufacturer: String::new(),
+            channel_modes: vec![new_mode(1)],
+        })
+    }
+}
+
+impl FixtureWindow {
+    pub fn new(model: FixtureModel) -> Self {
+        Self {
+            open: false,
+            issues: validation::validate(&model),
+            model,
+            path: String::new(),
+            mode: 0,
+            cell: (0, 0),
+            output: None,
+            test_universe: 0,
+            test_start: 1,
+            status: None,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let model = serde_json::from_str(&content).map_err(|error| error.to_string())?;
+        let mut window = Self::new(model);
+        window.path = path.display().to_string();
+        Ok(window)
+    }
+
+    pub fn model(&self) -> &FixtureModel {
+        &self.model
+    }
+
+    // Universes the "test" controls write into, usually the ones of the output engine
+    pub fn set_output(&mut self, universes: Lock<Vec<DMXUniverse>>) {
+        self.output = Some(universes);
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        let path = PathBuf::from(&self.path);
+        let json = serde_json::to_string_pretty(&self.model).map_err(|error| error.to_string())?;
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+        }
+        fs::write(&path, json).map_err(|error| error.to_string())
+    }
+
+    fn file_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("File");
+            ui.add(egui::TextEdit::singleline(&mut self.path).hint_text("data/fixtures/<fixture>/fixture_config.json").desired_width(320.0));
+            if ui.button("New").clicked() {
+                let (open, output) = (self.open, self.output.take());
+                *self = Self::default();
+                self.open = open;
+                self.output = output;
+            }
+            if ui.button("Load").clicked() {
+                match Self::load(Path::new(&self.path)) {
+                    Ok(mut window) => {
+                        window.open = self.open;
+                        window.output = self.output.take();
+                        window.status = Some((format!("Loaded {}", window.path), false));
+                        *self = window;
+                    },
+                    Err(error) => self.status = Some((error, true)),
+                }
+            }
+            if ui.button("Save").clicked() {
+                self.status = Some(match self.save() {
+                    Ok(()) => (format!("Saved {}", self.path), false),
+                    Err(error) => (error, true),
+                });
+            }
+        });
+        if let Some((status, error)) = &self.status {
+            let color = if *error { Color32::RED } else { Color32::GREEN };
+            ui.label(RichText::new(status).color(color));
+        }
+    }
+
+    fn model_ui(&mut self, ui: &mut Ui) {
+        Grid::new("fixture_model").num_columns(2).show(ui, |ui| {
+            ui.label("Manufacturer");
+            ui.text_edit_singleline(&mut self.model.manufacturer);
+            ui.end_row();
+            ui.label("Model");
+            name_ui(ui, &mut self.model.name);
+            ui.end_row();
+            ui.label("Icon");
+            icon_ui(ui, &mut self.model.name);
+            ui.end_row();
+        });
+    }
+
+    fn modes_ui(&mut self, ui: &mut Ui) {
+        let modes = &mut self.model.channel_modes;
+        ui.horizontal(|ui| {
+            ui.label("Channel mode");
+            let selected = modes.get(self.mode).map_or_else(String::new, |mode| mode_label(mode, self.mode));
+            ComboBox::from_id_source("fixture_channel_mode").selected_text(selected).show_ui(ui, |ui| {
+                for (index, mode) in modes.iter().enumerate() {
+                    ui.selectable_value(&mut self.mode, index, mode_label(mode, index));
+                }
+            });
+            if ui.button("Add").clicked() {
+                modes.push(new_mode(modes.len() + 1));
+                self.mode = modes.len() - 1;
+            }
+            if ui.add_enabled(self.mode < modes.len(), egui::Button::new("Duplicate")).clicked() {
+                let mut copy = modes[self.mode].clone();
+                copy.name = copy.name.map(|name| FixtureName::new(format!("{} (copy)", name)));
+                modes.push(copy);
+                self.mode = modes.len() - 1;
+            }
+            if ui.add_enabled(self.mode < modes.len(), egui::Button::new("Remove")).clicked() {
+                modes.remove(self.mode);
+                self.mode = self.mode.min(modes.len().saturating_sub(1));
+            }
+        });
+        match modes.get_mut(self.mode) {
+            Some(mode) => mode_ui(ui, mode, &mut self.cell),
+            None => {
+                ui.label("The fixture has no channel modes");
+            },
+        }
+    }
+
+    fn validation_ui(&mut self, ui: &mut Ui) {
+        if self.issues.is_empty() {
+            ui.label(RichText::new("No problems found").color(Color32::GREEN));
+        }
+        for issue in &self.issues {
+            let color = match issue.severity {
+                Severity::Error => Color32::RED,
+                Severity::Warning => Color32::YELLOW,
+            };
+            // Clicking an issue of a channel mode opens that mode
+            if ui.link(RichText::new(issue.to_string()).color(color)).clicked() {
+                if let Some(mode) = issue.mode {
+                    self.mode = mode;
+                }
+            }
+        }
+    }
+
+    fn test_ui(&mut self, ui: &mut Ui) {
+        let Some(mode) = self.model.channel_modes.get(self.mode) else {
+            return;
+        };
+        let Some(output) = &self.output else {
+            ui.label("No output connected");
+            return;
+        };
+        let universes = output.read().unwrap().len();
+        ui.horizontal(|ui| {
+            ui.label("Universe");
+            let mut universe = self.test_universe + 1;
+            ui.add(DragValue::new(&mut universe).clamp_range(1..=universes.max(1)));
+            self.test_universe = universe - 1;
+            ui.label("Address");
+            ui.add(DragValue::new(&mut self.test_start).clamp_range(1..=DMX_CHANNELS as u16));
+        });
+        let Ok(start) = Channel::new(self.test_start) else {
+            return;
+        };
+        let Some(universe) = output.read().unwrap().get(self.test_universe).copied() else {
+            ui.label(RichText::new("The universe does not exist").color(Color32::RED));
+            return;
+        };
+
+        let mut writes: Vec<(Channel, u8)> = Vec::new();
+        Grid::new("fixture_test").num_columns(4).striped(true).show(ui, |ui| {
+            ui.strong("Ch");
+            ui.strong("Function");
+            ui.strong("Values");
+            ui.strong("Output");
+            ui.end_row();
+            for (index, usage) in validation::channel_usages(mode).iter().enumerate() {
+                ui.label(usage.channel.id().to_string());
+                ui.label(&usage.function);
+                ui.label(format!("{} - {}", usage.start, usage.end));
+                match usage.channel.absolute(start) {
+                    Ok(channel) => {
+                        ui.push_id(index, |ui| ui.horizontal(|ui| {
+                            let current = universe.get(channel).unwrap_or_default();
+                            if ui.small_button("▶").on_hover_text(format!("Send {}", usage.start)).clicked() {
+                                writes.push((channel, usage.start));
+                            }
+                            if usage.start != usage.end {
+                                let mut value = current.clamp(usage.start, usage.end);
+                                if ui.add(egui::Slider::new(&mut value, usage.start..=usage.end)).changed() {
+                                    writes.push((channel, value));
+                                }
+                            } else {
+                                ui.label(current.to_string());
+                            }
+                        }));
+                    },
+                    Err(_) => {
+                        ui.label("outside of the universe");
+                    },
+                }
+                ui.end_row();
+            }
+        });
+        if ui.button("Release").on_hover_text("Set all channels of the fixture to 0").clicked() {
+            writes.extend((1..=mode.total_channels.id()).filter_map(|id| Channel::new(id).ok()?.absolute(start).ok()).map(|channel| (channel, 0)));
+        }
+
+        if !writes.is_empty() {
+            if let Some(universe) = output.write().unwrap().get_mut(self.test_universe) {
+                for (channel, value) in writes {
+                    let _ = universe.set(channel, value);
+                }
+            }
+        }
+    }
+}
+
+impl SubWindow for FixtureWindow {
+    fn ui(&mut self, ctx: &Context) {
+        self.issues = validation::validate(&self.model);
+        let mut open = self.open;
+        Window::new("Fixture Editor")
+        .open(&mut open)
+        .default_size([720.0, 640.0])
+        .vscroll(true)
+        .show(ctx, |ui| {
+            self.file_ui(ui);
+            ui.separator();
+            self.model_ui(ui);
+            ui.separator();
+            self.modes_ui(ui);
+            ui.separator();
+            let errors = self.issues.iter().filter(|issue| issue.severity == Severity::Error).count();
+            CollapsingHeader::new(format!("Validation ({} errors, {} warnings)", errors, self.issues.len() - errors))
+            .id_source("fixture_validation")
+            .default_open(true)
+            .show(ui, |ui| self.validation_ui(ui));
+            CollapsingHeader::new("Test on channel")
+            .id_source("fixture_test")
+            .show(ui, |ui| self.test_ui(ui));
+        });
+        self.open = open;
+    }
+}
+
+fn new_mode(number: usize) -> FixtureChannelMode {
+    FixtureChannelMode {
+        total_channels: channel(1),
+        operation_modes: Vec::new(),
+        movement: None,
+        lights: None,
+        name: Some(FixtureName::new(format!("Mode {}", number))),
+        zoom: None,
+        custom: None,
+    }
+}
+
+fn mode_label(mode: &FixtureChannelMode, index: usize) -> String {
+    match &mode.name {
+        Some(name) => format!("{} ({} ch)", name, mode.total_channels.id()),
+        None => format!("Mode {} ({} ch)", index + 1, mode.total_channels.id()),
+    }
+}
+
+// Channels are always in 1..=512 here, the editor never produces invalid ones
+fn channel(id: u16) -> Channel {
+    Channel::new(id.clamp(1, DMX_CHANNELS as u16)).unwrap()
+}
+
+fn full_range(channel: Channel) -> DMXRange {
+    DMXRange::from(channel)
+}
+
+fn mode_ui(ui: &mut Ui, mode: &mut FixtureChannelMode, cell: &mut (usize, usize)) {
+    Grid::new("fixture_mode_settings").num_columns(2).show(ui, |ui| {
+        ui.label("Name");
+        let mut name = mode.name.as_ref().map(|name| name.name().to_string()).unwrap_or_default();
+        if ui.text_edit_singleline(&mut name).changed() {
+            mode.name = (!name.is_empty()).then(|| FixtureName::new(name));
+        }
+        ui.end_row();
+        ui.label("Channels");
+        channel_ui(ui, &mut mode.total_channels);
+        ui.end_row();
+    });
+
+    CollapsingHeader::new(format!("Operation modes ({})", mode.operation_modes.len()))
+    .id_source("fixture_operation_modes")
+    .show(ui, |ui| operation_modes_ui(ui, &mut mode.operation_modes));
+    CollapsingHeader::new("Lights")
+    .id_source("fixture_lights")
+    .default_open(true)
+    .show(ui, |ui| matrix_ui(ui, &mut mode.lights, cell));
+    CollapsingHeader::new("Movement")
+    .id_source("fixture_movement")
+    .show(ui, |ui| movement_ui(ui, &mut mode.movement));
+    CollapsingHeader::new("Zoom")
+    .id_source("fixture_zoom")
+    .show(ui, |ui| zoom_ui(ui, &mut mode.zoom));
+    CollapsingHeader::new(format!("Custom operations ({})", mode.custom.as_ref().map_or(0, Vec::len)))
+    .id_source("fixture_custom")
+    .show(ui, |ui| {
+        let mut custom = mode.custom.take().unwrap_or_default();
+        operations_ui(ui, &mut custom);
+        mode.custom = (!custom.is_empty()).then_some(custom);
+    });
+}
+
+fn name_ui(ui: &mut Ui, name: &mut FixtureName) {
+    let mut text = name.name().to_string();
+    if ui.text_edit_singleline(&mut text).changed() {
+        let mut renamed = FixtureName::new(text);
+        if let Some(icon) = name.icon_path() {
+            renamed.icon(icon);
+        }
+        *name = renamed;
+    }
+}
+
+fn icon_ui(ui: &mut Ui, name: &mut FixtureName) {
+    let mut icon = name.icon_path().map(|icon| icon.display().to_string()).unwrap_or_default();
+    if ui.add(egui::TextEdit::singleline(&mut icon).hint_text("image next to the fixture config")).changed() {
+        let mut renamed = FixtureName::new(name.name().to_string());
+        if !icon.is_empty() {
+            renamed.icon(Path::new(&icon));
+        }
+        *name = renamed;
+    }
+}
+
+fn channel_ui(ui: &mut Ui, value: &mut Channel) {
+    let mut id = value.id();
+    if ui.add(DragValue::new(&mut id).clamp_range(1..=DMX_CHANNELS as u16).prefix("ch ")).changed() {
+        *value = channel(id);
+    }
+}
+
+fn optional_channel_ui(ui: &mut Ui, label: &str, value: &mut Option<Channel>, default: Channel) {
+    let mut enabled = value.is_some();
+    if ui.checkbox(&mut enabled, label).changed() {
+        *value = enabled.then_some(default);
+    }
+    if let Some(value) = value {
+        channel_ui(ui, value);
+    }
+}
+
+fn address_ui(ui: &mut Ui, address: &mut DMXAddress) {
+    channel_ui(ui, &mut address.channel);
+    ui.add(DragValue::new(&mut address.value));
+}
+
+fn optional_address_ui(ui: &mut Ui, label: &str, address: &mut Option<DMXAddress>, default: Channel) {
+    let mut enabled = address.is_some();
+    if ui.checkbox(&mut enabled, label).changed() {
+        *address = enabled.then(|| DMXAddress::new(default, 0));
+    }
+    if let Some(address) = address {
+        address_ui(ui, address);
+    }
+}
+
+// Ranges stay on one channel, the end follows the start channel
+fn range_ui(ui: &mut Ui, range: &mut DMXRange) {
+    channel_ui(ui, &mut range.start.channel);
+    range.end.channel = range.start.channel;
+    ui.add(DragValue::new(&mut range.start.value));
+    ui.label("-");
+    ui.add(DragValue::new(&mut range.end.value));
+}
+
+fn remove_button(ui: &mut Ui) -> bool {
+    ui.small_button("🗑").on_hover_text("Remove").clicked()
+}
+
+fn operation_modes_ui(ui: &mut Ui, operation_modes: &mut Vec<FixtureOperationMode>) {
+    let mut remove = None;
+    for (index, operation_mode) in operation_modes.iter_mut().enumerate() {
+        ui.push_id(index, |ui| {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    mode_type_ui(ui, &mut operation_mode.mode_type);
+                    optional_address_ui(ui, "Selected by", &mut operation_mode.address, channel(1));
+                    if remove_button(ui) {
+                        remove = Some(index);
+                    }
+                });
+                ui.label(format!("Submodes of {}", operation_mode.mode_type));
+                operations_ui(ui, &mut operation_mode.submodes);
+            });
+        });
+    }
+    if let Some(index) = remove {
+        operation_modes.remove(index);
+    }
+    if ui.button("Add operation mode").clicked() {
+        operation_modes.push(FixtureOperationMode::new(OperationModeType::On, None, Vec::new()));
+    }
+}
+
+fn mode_type_ui(ui: &mut Ui, mode_type: &mut OperationModeType) {
+    let kinds = [
+        OperationModeType::Off,
+        OperationModeType::On,
+        OperationModeType::Auto,
+        OperationModeType::SoundToLight,
+        OperationModeType::DMX(FixtureName::new("DMX".into())),
+    ];
+    ComboBox::from_id_source("mode_type").selected_text(mode_type.to_string()).show_ui(ui, |ui| {
+        for kind in kinds {
+            let selected = discriminant(mode_type) == discriminant(&kind);
+            let label = match &kind {
+                OperationModeType::DMX(_) => "Named".to_string(),
+                kind => kind.to_string(),
+            };
+            if ui.selectable_label(selected, label).clicked() && !selected {
+                *mode_type = kind;
+            }
+        }
+    });
+    if let OperationModeType::DMX(name) = mode_type {
+        name_ui(ui, name);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum OperationKind {
+    Slider,
+    FineSlider,
+    Physical,
+    Button,
+    Stepped,
+}
+
+impl OperationKind {
+    const ALL: [OperationKind; 5] = [OperationKind::Slider, OperationKind::FineSlider, OperationKind::Physical, OperationKind::Button, OperationKind::Stepped];
+
+    fn of(operation: &FixtureCustomOperation) -> Self {
+        match operation {
+            FixtureCustomOperation::Slider(..) => OperationKind::Slider,
+            FixtureCustomOperation::FineSlider(..) => OperationKind::FineSlider,
+            FixtureCustomOperation::Physical(..) => OperationKind::Physical,
+            FixtureCustomOperation::Button(..) => OperationKind::Button,
+            FixtureCustomOperation::Stepped(..) => OperationKind::Stepped,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            OperationKind::Slider => "Slider",
+            OperationKind::FineSlider => "Fine slider",
+            OperationKind::Physical => "Physical",
+            OperationKind::Button => "Button",
+            OperationKind::Stepped => "Steps",
+        }
+    }
+
+    // Converts an operation, keeping its name and channel
+    fn convert(&self, operation: &FixtureCustomOperation) -> FixtureCustomOperation {
+        let name = operation.name().clone();
+        let address = match operation {
+            FixtureCustomOperation::Slider(_, range) | FixtureCustomOperation::FineSlider(_, range, _) | FixtureCustomOperation::Physical(_, range, _) => range.start,
+            FixtureCustomOperation::Button(_, address) => *address,
+            FixtureCustomOperation::Stepped(_, steps) => steps.first().map_or(DMXAddress::new(channel(1), 0), |(_, address)| *address),
+        };
+        let range = full_range(address.channel);
+        match self {
+            OperationKind::Slider => FixtureCustomOperation::Slider(name, range),
+            OperationKind::FineSlider => FixtureCustomOperation::FineSlider(name, range, address.channel.offset(1)),
+            OperationKind::Physical => FixtureCustomOperation::Physical(name, range, PhysicalRange {
+                unit: Unit::Percent,
+                points: vec![CalibrationPoint { value: 0.0, dmx: 0 }, CalibrationPoint { value: 100.0, dmx: 255 }],
+            }),
+            OperationKind::Button => FixtureCustomOperation::Button(name, address),
+            OperationKind::Stepped => FixtureCustomOperation::Stepped(name, vec![(FixtureName::new("Step 1".into()), address)]),
+        }
+    }
+}
+
+fn operations_ui(ui: &mut Ui, operations: &mut Vec<FixtureCustomOperation>) {
+    let mut remove = None;
+    for (index, operation) in operations.iter_mut().enumerate() {
+        ui.push_id(index, |ui| {
+            ui.horizontal(|ui| {
+                if remove_button(ui) {
+                    remove = Some(index);
+                }
+                operation_ui(ui, operation);
+            });
+        });
+    }
+    if let Some(index) = remove {
+        operations.remove(index);
+    }
+    if ui.button("Add operation").clicked() {
+        operations.push(FixtureCustomOperation::Slider(FixtureName::new("New slider".into()), full_range(channel(1))));
+    }
+}
+
+fn operation_ui(ui: &mut Ui, operation: &mut FixtureCustomOperation) {
+    let kind = OperationKind::of(operation);
+    ComboBox::from_id_source("operation_kind").selected_text(kind.label()).show_ui(ui, |ui| {
+        for other in OperationKind::ALL {
+            if ui.selectable_label(other == kind, other.label()).clicked() && other != kind {
+                *operation = other.convert(operation);
+            }
+        }
+    });
+    match operation {
+        FixtureCustomOperation::Slider(name, range) => {
+            name_ui(ui, name);
+            range_ui(ui, range);
+        },
+        FixtureCustomOperation::FineSlider(name, range, fine) => {
+            name_ui(ui, name);
+            range_ui(ui, range);
+            ui.label("fine");
+            channel_ui(ui, fine);
+        },
+        FixtureCustomOperation::Physical(name, range, physical) => {
+            name_ui(ui, name);
+            range_ui(ui, range);
+            physical_ui(ui, physical);
+        },
+        FixtureCustomOperation::Button(name, address) => {
+            name_ui(ui, name);
+            address_ui(ui, address);
+        },
+        FixtureCustomOperation::Stepped(name, steps) => {
+            name_ui(ui, name);
+            ui.vertical(|ui| steps_ui(ui, steps));
+        },
+    }
+}
+
+fn steps_ui(ui: &mut Ui, steps: &mut Vec<(FixtureName, DMXAddress)>) {
+    let mut remove = None;
+    for (index, (name, address)) in steps.iter_mut().enumerate() {
+        ui.push_id(index, |ui| ui.horizontal(|ui| {
+            name_ui(ui, name);
+            address_ui(ui, address);
+            if remove_button(ui) {
+                remove = Some(index);
+            }
+        }));
+    }
+    if let Some(index) = remove {
+        steps.remove(index);
+    }
+    if ui.small_button("Add step").clicked() {
+        // New steps continue on the channel of the last step
+        let address = steps.last().map_or(DMXAddress::new(channel(1), 0), |(_, address)| {
+            DMXAddress::new(address.channel, address.value.saturating_add(10))
+        });
+        steps.push((FixtureName::new(format!("Step {}", steps.len() + 1)), address));
+    }
+}
+
+fn physical_ui(ui: &mut Ui, physical: &mut PhysicalRange) {
+    let units = [Unit::Hertz, Unit::Rpm, Unit::Degrees, Unit::Percent, Unit::Seconds, Unit::Index];
+    ComboBox::from_id_source("physical_unit").selected_text(physical.unit.symbol().to_string()).show_ui(ui, |ui| {
+        for unit in units {
+            let symbol = unit.symbol().to_string();
+            ui.selectable_value(&mut physical.unit, unit, symbol);
+        }
+    });
+    ui.vertical(|ui| {
+        let mut remove = None;
+        for (index, point) in physical.points.iter_mut().enumerate() {
+            ui.push_id(index, |ui| ui.horizontal(|ui| {
+                ui.add(DragValue::new(&mut point.dmx).prefix("dmx "));
+                ui.add(DragValue::new(&mut point.value).speed(0.1).suffix(physical.unit.symbol().to_string()));
+                if remove_button(ui) {
+                    remove = Some(index);
+                }
+            }));
+        }
+        if let Some(index) = remove {
+            physical.points.remove(index);
+        }
+        if ui.small_button("Add point").clicked() {
+            let last = physical.points.last().copied().unwrap_or(CalibrationPoint { value: 0.0, dmx: 0 });
+            physical.points.push(CalibrationPoint { value: last.value, dmx: last.dmx.saturating_add(1) });
+        }
+        // Calibration points are looked up in DMX order
+        physical.points.sort_by_key(|point| point.dmx);
+    });
+}
+
+// RGB on the first three channels
+fn default_lights() -> FixtureLights {
+    let first = full_range(channel(1));
+    FixtureLights::new(FixtureColorMode::RGB(first, first.shifted(1), first.shifted(2)), None)
+}
+
+fn matrix_ui(ui: &mut Ui, lights: &mut Option<FixtureMatrix>, cell: &mut (usize, usize)) {
+    let Some(matrix) = lights else {
+        if ui.button("Add lights").clicked() {
+            *lights = Some(FixtureMatrix::new(vec![vec![default_lights()]]));
+        }
+        return;
+    };
+
+    // Cells are laid out like on the fixture, rows from top to bottom
+    let mut add_column = None;
+    let mut remove_column = None;
+    Grid::new("fixture_matrix").show(ui, |ui| {
+        for (row, cells) in matrix.matrix.iter().enumerate() {
+            for (column, lights) in cells.iter().enumerate() {
+                let label = format!("{}/{}\nch {}", row + 1, column + 1, first_channel(lights).map_or(0, |channel| channel.id()));
+                if ui.selectable_label(*cell == (row, column), label).clicked() {
+                    *cell = (row, column);
+                }
+            }
+            if ui.small_button("+").on_hover_text("Add a cell to the row").clicked() {
+                add_column = Some(row);
+            }
+            if cells.len() > 1 && ui.small_button("-").on_hover_text("Remove the last cell of the row").clicked() {
+                remove_column = Some(row);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(row) = add_column {
+        let cells = &mut matrix.matrix[row];
+        // Rows of loaded files can be empty
+        let next = match cells.last() {
+            Some(last) => shifted_lights(last, channel_span(std::slice::from_ref(last))),
+            None => default_lights(),
+        };
+        cells.push(next);
+    }
+    if let Some(row) = remove_column {
+        matrix.matrix[row].pop();
+    }
+    let mut remove_lights = false;
+    ui.horizontal(|ui| {
+        if ui.button("Add row").clicked() {
+            let last = matrix.matrix.last().cloned().unwrap_or_default();
+            let span = channel_span(&last);
+            matrix.matrix.push(last.iter().map(|lights| shifted_lights(lights, span)).collect());
+        }
+        if matrix.matrix.len() > 1 && ui.button("Remove row").clicked() {
+            matrix.matrix.pop();
+        }
+        remove_lights = ui.button("Remove lights").clicked();
+    });
+    if remove_lights {
+        *lights = None;
+        return;
+    }
+
+    cell.0 = cell.0.min(matrix.matrix.len().saturating_sub(1));
+    cell.1 = cell.1.min(matrix.matrix.get(cell.0).map_or(0, Vec::len).saturating_sub(1));
+    if let Some(selected) = matrix.matrix.get_mut(cell.0).and_then(|cells| cells.get_mut(cell.1)) {
+        ui.separator();
+        ui.label(format!("Cell {}/{}", cell.0 + 1, cell.1 + 1));
+        ui.push_id(*cell, |ui| lights_ui(ui, selected));
+    }
+}
+
+fn lights_ranges(lights: &FixtureLights) -> Vec<DMXRange> {
+    let mut ranges = match &lights.color_mode {
+        FixtureColorMode::RGB(a, b, c) | FixtureColorMode::CMY(a, b, c) => vec![*a, *b, *c],
+        FixtureColorMode::RGBW(a, b, c, d) | FixtureColorMode::CMYW(a, b, c, d) => vec![*a, *b, *c, *d],
+        FixtureColorMode::RgbTrailingChannels(range) | FixtureColorMode::CmyTrailingChannels(range) => (0..3).map(|offset| range.shifted(offset)).collect(),
+        FixtureColorMode::RgbwTrailingChannels(range) | FixtureColorMode::CmywTrailingChannels(range) => (0..4).map(|offset| range.shifted(offset)).collect(),
+        FixtureColorMode::Presets(presets) => presets.iter().map(|(_, address)| full_range(address.channel)).collect(),
+        FixtureColorMode::Custom(_, ranges) => ranges.clone(),
+    };
+    ranges.extend(lights.dimmer);
+    ranges.extend(lights.dimmer_fine.map(full_range));
+    ranges
+}
+
+fn first_channel(lights: &FixtureLights) -> Option<Channel> {
+    lights_ranges(lights).iter().map(DMXRange::channel).min()
+}
+
+// Number of channels from the first to the last channel the cells use
+fn channel_span(cells: &[FixtureLights]) -> u16 {
+    let channels: Vec<u16> = cells.iter().flat_map(lights_ranges).map(|range| range.channel().id()).collect();
+    match (channels.iter().min(), channels.iter().max()) {
+        (Some(min), Some(max)) => max - min + 1,
+        _ => 1,
+    }
+}
+
+fn shifted_lights(lights: &FixtureLights, offset: u16) -> FixtureLights {
+    let shift = |range: &DMXRange| range.shifted(offset);
+    let color_mode = match &lights.color_mode {
+        FixtureColorMode::Presets(presets) => FixtureColorMode::Presets(presets.iter()
+            .map(|(color, address)| (color.clone(), DMXAddress::new(address.channel.offset(offset), address.value)))
+            .collect()),
+        FixtureColorMode::RGB(r, g, b) => FixtureColorMode::RGB(shift(r), shift(g), shift(b)),
+        FixtureColorMode::RGBW(r, g, b, w) => FixtureColorMode::RGBW(shift(r), shift(g), shift(b), shift(w)),
+        FixtureColorMode::CMY(c, m, y) => FixtureColorMode::CMY(shift(c), shift(m), shift(y)),
+        FixtureColorMode::CMYW(c, m, y, w) => FixtureColorMode::CMYW(shift(c), shift(m), shift(y), shift(w)),
+        FixtureColorMode::RgbTrailingChannels(range) => FixtureColorMode::RgbTrailingChannels(shift(range)),
+        FixtureColorMode::RgbwTrailingChannels(range) => FixtureColorMode::RgbwTrailingChannels(shift(range)),
+        FixtureColorMode::CmyTrailingChannels(range) => FixtureColorMode::CmyTrailingChannels(shift(range)),
+        FixtureColorMode::CmywTrailingChannels(range) => FixtureColorMode::CmywTrailingChannels(shift(range)),
+        FixtureColorMode::Custom(name, ranges) => FixtureColorMode::Custom(name.clone(), ranges.iter().map(shift).collect()),
+    };
+    let mut shifted = FixtureLights::new(color_mode, lights.dimmer.as_ref().map(shift));
+    if let Some(fine) = lights.dimmer_fine {
+        shifted.dimmer_fine(fine.offset(offset));
+    }
+    shifted
+}
+
+const COLOR_MODES: [&str; 10] = ["Presets", "RGB", "RGBW", "CMY", "CMYW", "RGB trailing", "RGBW trailing", "CMY trailing", "CMYW trailing", "Custom"];
+
+fn color_mode_index(color_mode: &FixtureColorMode) -> usize {
+    match color_mode {
+        FixtureColorMode::Presets(_) => 0,
+        FixtureColorMode::RGB(..) => 1,
+        FixtureColorMode::RGBW(..) => 2,
+        FixtureColorMode::CMY(..) => 3,
+        FixtureColorMode::CMYW(..) => 4,
+        FixtureColorMode::RgbTrailingChannels(_) => 5,
+        FixtureColorMode::RgbwTrailingChannels(_) => 6,
+        FixtureColorMode::CmyTrailingChannels(_) => 7,
+        FixtureColorMode::CmywTrailingChannels(_) => 8,
+        FixtureColorMode::Custom(..) => 9,
+    }
+}
+
+// New color modes start on the first channel of the old one and use the following channels
+fn convert_color_mode(index: usize, first: Channel) -> FixtureColorMode {
+    let range = full_range(first);
+    match index {
+        0 => FixtureColorMode::Presets(vec![(Color::White, DMXAddress::new(first, 0))]),
+        1 => FixtureColorMode::RGB(range, range.shifted(1), range.shifted(2)),
+        2 => FixtureColorMode::RGBW(range, range.shifted(1), range.shifted(2), range.shifted(3)),
+        3 => FixtureColorMode::CMY(range, range.shifted(1), range.shifted(2)),
+        4 => FixtureColorMode::CMYW(range, range.shifted(1), range.shifted(2), range.shifted(3)),
+        5 => FixtureColorMode::RgbTrailingChannels(range),
+        6 => FixtureColorMode::RgbwTrailingChannels(range),
+        7 => FixtureColorMode::CmyTrailingChannels(range),
+        8 => FixtureColorMode::CmywTrailingChannels(range),
+        _ => FixtureColorMode::Custom("Custom".into(), vec![range]),
+    }
+}
+
+fn lights_ui(ui: &mut Ui, lights: &mut FixtureLights) {
+    let index = color_mode_index(&lights.color_mode);
+    ui.horizontal(|ui| {
+        ui.label("Color mode");
+        ComboBox::from_id_source("color_mode").selected_text(COLOR_MODES[index]).show_ui(ui, |ui| {
+            for (other, label) in COLOR_MODES.iter().enumerate() {
+                if ui.selectable_label(other == index, *label).clicked() && other != index {
+                    let first = first_channel(lights).unwrap_or(channel(1));
+                    lights.color_mode = convert_color_mode(other, first);
+                }
+            }
+        });
+    });
+
+    let components = |names: &[&str], ranges: Vec<&mut DMXRange>, ui: &mut Ui| {
+        Grid::new("color_components").show(ui, |ui| {
+            for (name, range) in names.iter().zip(ranges) {
+                ui.label(*name);
+                ui.horizontal(|ui| range_ui(ui, range));
+                ui.end_row();
+            }
+        });
+    };
+    match &mut lights.color_mode {
+        FixtureColorMode::RGB(r, g, b) => components(&["Red", "Green", "Blue"], vec![r, g, b], ui),
+        FixtureColorMode::RGBW(r, g, b, w) => components(&["Red", "Green", "Blue", "White"], vec![r, g, b, w], ui),
+        FixtureColorMode::CMY(c, m, y) => components(&["Cyan", "Magenta", "Yellow"], vec![c, m, y], ui),
+        FixtureColorMode::CMYW(c, m, y, w) => components(&["Cyan", "Magenta", "Yellow", "White"], vec![c, m, y, w], ui),
+        FixtureColorMode::RgbTrailingChannels(range) | FixtureColorMode::CmyTrailingChannels(range)
+        | FixtureColorMode::RgbwTrailingChannels(range) | FixtureColorMode::CmywTrailingChannels(range) => {
+            ui.horizontal(|ui| {
+                ui.label("First channel");
+                range_ui(ui, range);
+            });
+        },
+        FixtureColorMode::Custom(name, ranges) => {
+            ui.horizontal(|ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(name);
+            });
+            let mut remove = None;
+            for (index, range) in ranges.iter_mut().enumerate() {
+                ui.push_id(index, |ui| ui.horizontal(|ui| {
+                    range_ui(ui, range);
+                    if remove_button(ui) {
+                        remove = Some(index);
+                    }
+                }));
+            }
+            if let Some(index) = remove {
+                ranges.remove(index);
+            }
+            if ui.small_button("Add channel").clicked() {
+                let next = ranges.last().map_or(full_range(channel(1)), |range| range.shifted(1));
+                ranges.push(next);
+            }
+        },
+        FixtureColorMode::Presets(presets) => presets_ui(ui, presets),
+    }
+
+    ui.horizontal(|ui| {
+        let mut dimmer = lights.dimmer.is_some();
+        if ui.checkbox(&mut dimmer, "Dimmer").changed() {
+            lights.dimmer = dimmer.then(|| full_range(channel(1)));
+            if !dimmer {
+                lights.dimmer_fine = None;
+            }
+        }
+        if let Some(range) = &mut lights.dimmer {
+            range_ui(ui, range);
+            let next = range.channel().offset(1);
+            optional_channel_ui(ui, "fine", &mut lights.dimmer_fine, next);
+        }
+    });
+}
+
+fn color_label(color: &Color) -> String {
+    match color {
+        Color::CustomRGB(name, _) => name.clone(),
+        Color::Custom(name) => name.to_string(),
+        Color::ColorChange => "Color change".into(),
+        color => format!("{:?}", color),
+    }
+}
+
+fn color_swatch(ui: &mut Ui, color: &Color) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(14.0, 14.0), Sense::hover());
+    match color.rgb() {
+        Some((r, g, b)) => ui.painter().rect_filled(rect, 2.0, Color32::from_rgb(r, g, b)),
+        None => ui.painter().rect_stroke(rect, 2.0, (1.0, Color32::GRAY)),
+    }
+}
+
+fn color_ui(ui: &mut Ui, color: &mut Color) {
+    let choices = [
+        Color::Red, Color::Green, Color::Blue, Color::Cyan, Color::Magenta, Color::Yellow, Color::White, Color::Black,
+        Color::UV, Color::ColorChange, Color::Auto, Color::All, Color::CustomRGB("Custom".into(), (255, 128, 0)),
+    ];
+    color_swatch(ui, color);
+    ComboBox::from_id_source("preset_color").selected_text(color_label(color)).show_ui(ui, |ui| {
+        for choice in choices {
+            let selected = discriminant(color) == discriminant(&choice);
+            if ui.selectable_label(selected, color_label(&choice)).clicked() && !selected {
+                *color = choice;
+            }
+        }
+    });
+    if let Color::CustomRGB(name, rgb) = color {
+        ui.add(egui::TextEdit::singleline(name).desired_width(80.0));
+        let mut srgb = [rgb.0, rgb.1, rgb.2];
+        if ui.color_edit_button_srgb(&mut srgb).changed() {
+            *rgb = (srgb[0], srgb[1], srgb[2]);
+        }
+    }
+}
+
+fn presets_ui(ui: &mut Ui, presets: &mut Vec<(Color, DMXAddress)>) {
+    let mut remove = None;
+    for (index, (color, address)) in presets.iter_mut().enumerate() {
+        ui.push_id(index, |ui| ui.horizontal(|ui| {
+            color_ui(ui, color);
+            address_ui(ui, address);
+            if remove_button(ui) {
+                remove = Some(index);
+            }
+        }));
+    }
+    if let Some(index) = remove {
+        presets.remove(index);
+    }
+    if ui.small_button("Add preset").clicked() {
+        let address = presets.last().map_or(DMXAddress::new(channel(1), 0), |(_, address)| {
+            DMXAddress::new(address.channel, address.value.saturating_add(8))
+        });
+        presets.push((Color::White, address));
+    }
+}
+
+fn movement_ui(ui: &mut Ui, movement: &mut Option<FixtureMovement>) {
+    let mut current = movement.take().unwrap_or_default();
+    for (name, axis) in [("Pan", &mut current.pan), ("Tilt", &mut current.tilt)] {
+        ui.push_id(name, |ui| axis_ui(ui, name, axis));
+    }
+    if current.pan.is_some() || current.tilt.is_some() {
+        *movement = Some(current);
+    }
+}
+
+fn axis_ui(ui: &mut Ui, name: &str, axis: &mut Option<MovementAxis>) {
+    ui.horizontal(|ui| {
+        let mut enabled = axis.is_some();
+        if ui.checkbox(&mut enabled, name).changed() {
+            *axis = enabled.then(|| MovementAxis::new(full_range(channel(1)), None));
+        }
+        let Some(axis) = axis else {
+            return;
+        };
+        range_ui(ui, &mut axis.range);
+        let next = axis.range.channel().offset(1);
+        optional_channel_ui(ui, "fine", &mut axis.fine, next);
+        let mut degrees = axis.degrees.is_some();
+        if ui.checkbox(&mut degrees, "degrees").changed() {
+            axis.degrees = degrees.then_some((0.0, if name == "Pan" { 540.0 } else { 270.0 }));
+        }
+        if let Some((start, end)) = &mut axis.degrees {
+            ui.add(DragValue::new(start).suffix("°"));
+            ui.add(DragValue::new(end).suffix("°"));
+        }
+        let channel = axis.range.channel();
+        optional_address_ui(ui, "reset", &mut axis.reset, channel);
+    });
+}
+
+fn zoom_ui(ui: &mut Ui, zoom: &mut Option<FixtureZoom>) {
+    let mut enabled = zoom.is_some();
+    if ui.checkbox(&mut enabled, "Zoom").changed() {
+        *zoom = enabled.then(|| FixtureZoom::new(full_range(channel(1)), None));
+    }
+    let Some(zoom) = zoom else {
+        return;
+    };
+    ui.horizontal(|ui| {
+        range_ui(ui, &mut zoom.range);
+        let next = zoom.range.channel().offset(1);
+        optional_channel_ui(ui, "fine", &mut zoom.fine, next);
+        let channel = zoom.range.channel();
+        optional_address_ui(ui, "reset", &mut zoom.reset, channel);
+    });
+    ui.horizontal(|ui| {
+        let mut physical = zoom.physical.is_some();
+        if ui.checkbox(&mut physical, "Beam angle").changed() {
+            zoom.physical = physical.then(|| PhysicalRange {
+                unit: Unit::Degrees,
+                points: vec![CalibrationPoint { value: 10.0, dmx: 0 }, CalibrationPoint { value: 40.0, dmx: 255 }],
+            });
+        }
+        if let Some(physical) = &mut zoom.physical {
+            physical_ui(ui, physical);
+        }
+    });
+}
@@ -0,0 +1,229 @@
+use eframe::egui::{self, Color32, Context, Ui, Window};
+use crate::windows::SubWindow;
+
+use dmxt_lib::history::History;
+use dmxt_lib::import::{ImportPlan, AddressPlacement, Remap};
+use dmxt_lib::library::FixtureLibrary;
+use dmxt_lib::patch::{sheet, Patch, PatchCommand};
+use dmxt_lib::preferences::Preferences;
+use dmxt_lib::show::{Show, ShowCommand, ShowState};
+use dmxt_lib::threads::shared::Lock;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+    Offset,
+    NextFree,
+    Manual,
+}
+
+// Imports fixtures of another show file or of a CSV patch sheet into the patch
+#[derive(Debug)]
+pub struct ImportWindow {
+    pub open: bool,
+    show: ShowState,
+    history: Lock<History<ShowCommand>>,
+    preferences: Lock<Preferences>,
+    path: String,
+    source: Option<Patch>,
+    selection: BTreeSet<usize>,
+    strategy: Strategy,
+    offset: u16,
+    placements: BTreeMap<usize, AddressPlacement>,
+    message: Option<(Color32, String)>,
+    // Rows of a patch sheet that could not be read
+    row_errors: Vec<String>,
+}
+
+impl ImportWindow {
+    pub fn new(show: ShowState, history: Lock<History<ShowCommand>>, preferences: Lock<Preferences>) -> ImportWindow {
+        ImportWindow {
+            open: false,
+            show,
+            history,
+            preferences,
+            path: String::new(),
+            source: None,
+            selection: BTreeSet::new(),
+            strategy: Strategy::NextFree,
+            offset: 0,
+            placements: BTreeMap::new(),
+            message: None,
+            row_errors: Vec::new(),
+        }
+    }
+
+    fn load(&mut self) {
+        let path = self.path.trim().to_string();
+        self.row_errors.clear();
+        let is_sheet = Path::new(&path).extension().is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+        let source = if is_sheet {
+            let library = FixtureLibrary::scan(&self.preferences.read().unwrap().fixture_library);
+            std::fs::read_to_string(&path).map_err(|error| error.to_string())
+                .and_then(|text| sheet::import(&text, &library).map_err(|error| error.to_string()))
+                .map(|(patch, errors)| {
+                    self.row_errors = errors.iter().map(ToString::to_string).collect();
+                    patch
+                })
+        } else {
+            Show::load(Path::new(&path)).map(|show| show.patch).map_err(|error| error.to_string())
+        };
+        match source {
+            Ok(patch) => {
+                self.selection = (0..patch.len()).collect();
+                self.placements.clear();
+                self.message = Some((Color32::GRAY, format!("{} has {} fixtures", path, patch.len())));
+                self.source = Some(patch);
+            },
+            Err(error) => {
+                self.source = None;
+                self.message = Some((Color32::RED, format!("Could not open {}: {}", path, error)));
+            },
+        }
+    }
+
+    fn remap(&self) -> Remap {
+        match self.strategy {
+            Strategy::Offset => Remap::Offset(self.offset),
+            Strategy::NextFree => Remap::NextFree,
+            Strategy::Manual => Remap::Manual(self.placements.clone()),
+        }
+    }
+
+    fn import(&mut self, plan: &ImportPlan) {
+        let mut history = self.history.write().unwrap();
+        history.begin_group();
+        if self.show.universes.read().unwrap().len() < plan.universes() {
+            let _ = history.execute(ShowCommand::universes(plan.universes()), &mut self.show);
+        }
+        for fixture in plan.fixtures() {
+            // The plan was made against this patch, so the fixtures fit
+            let _ = history.execute(PatchCommand::add(fixture.clone()).into(), &mut self.show);
+        }
+        history.end_group();
+        self.message = Some((Color32::GRAY, plan.report()));
+    }
+
+    fn source_ui(&mut self, ui: &mut Ui, source: &Patch, plan: &ImportPlan) {
+        let mut universes: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (index, fixture) in source.fixtures().iter().enumerate() {
+            universes.entry(fixture.universe).or_default().push(index);
+        }
+        let entries: BTreeMap<usize, _> = plan.entries.iter().map(|entry| (entry.source, entry)).collect();
+        // Fixtures can go to any universe of the show or stay in theirs, which the import adds when needed
+        let universe_count = universes.keys().last().map_or(0, |universe| universe + 1).max(self.show.universes.read().unwrap().len());
+        for (universe, fixtures) in universes {
+            let mut all = fixtures.iter().all(|index| self.selection.contains(index));
+            if ui.checkbox(&mut all, format!("Universe {}", universe + 1)).changed() {
+                for index in &fixtures {
+                    if all {
+                        self.selection.insert(*index);
+                    } else {
+                        self.selection.remove(index);
+                    }
+                }
+            }
+            egui::Grid::new(("import_fixtures", universe)).striped(true).num_columns(3).show(ui, |ui| {
+                for index in fixtures {
+                    let fixture = &source.fixtures()[index];
+                    let mut selected = self.selection.contains(&index);
+                    ui.horizontal(|ui| {
+                        ui.add_space(16.0);
+                        if ui.checkbox(&mut selected, &fixture.name).changed() {
+                            if selected {
+                                self.selection.insert(index);
+                            } else {
+                                self.selection.remove(&index);
+                            }
+                        }
+                    });
+                    let from = AddressPlacement { universe: fixture.universe, address: fixture.address.channel.id() };
+                    if self.strategy == Strategy::Manual && selected {
+                        let placement = self.placements.entry(index).or_insert(from);
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(&mut placement.universe).clamp_range(0..=universe_count.saturating_sub(1)).custom_formatter(|value, _| format!("{}", value + 1.0)));
+                            ui.add(egui::DragValue::new(&mut placement.address).clamp_range(1..=512));
+                        });
+                    } else {
+                        ui.label(from.to_string());
+                    }
+                    match entries.get(&index) {
+                        Some(entry) if entry.conflict.is_some() => {
+                            ui.colored_label(Color32::RED, entry.conflict.clone().unwrap_or_default());
+                        },
+                        Some(entry) if entry.is_moved() => {
+                            ui.colored_label(Color32::YELLOW, format!("→ {}", entry.to));
+                        },
+                        Some(_) => {
+                            ui.label("✔");
+                        },
+                        None => {
+                            ui.label("");
+                        },
+                    }
+                    ui.end_row();
+                }
+            });
+        }
+    }
+}
+
+impl Default for ImportWindow {
+    fn default() -> Self {
+        Self::new(ShowState::default(), Lock::default(), Lock::default())
+    }
+}
+
+impl SubWindow for ImportWindow {
+    fn ui(&mut self, ctx: &Context) {
+        let mut open = self.open;
+        Window::new("Import Patch")
+        .open(&mut open)
+        .default_width(520.0)
+        .vscroll(true)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Show file");
+                ui.add(egui::TextEdit::singleline(&mut self.path).hint_text("shows/other.json, patch.csv").desired_width(280.0));
+                if ui.button("Load").clicked() {
+                    self.load();
+                }
+            });
+            if let Some((color, message)) = &self.message {
+                ui.colored_label(*color, message);
+            }
+            for error in &self.row_errors {
+                ui.colored_label(Color32::RED, error);
+            }
+            let Some(source) = self.source.take() else {
+                return;
+            };
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Fixtures on used channels");
+                ui.selectable_value(&mut self.strategy, Strategy::NextFree, "Next free");
+                ui.selectable_value(&mut self.strategy, Strategy::Offset, "Offset");
+                ui.selectable_value(&mut self.strategy, Strategy::Manual, "Manual");
+                if self.strategy == Strategy::Offset {
+                    ui.add(egui::DragValue::new(&mut self.offset).clamp_range(0..=511).suffix(" channels"));
+                }
+            });
+            // Dry run against the current patch, shown next to each fixture
+            let selection: Vec<usize> = self.selection.iter().copied().collect();
+            let plan = ImportPlan::new(&self.show.patch.read().unwrap(), &source, &selection, &self.remap());
+            self.source_ui(ui, &source, &plan);
+            ui.separator();
+            let label = match plan.conflicts() {
+                0 => format!("Import {} fixtures", plan.fixtures().len()),
+                conflicts => format!("Import {} fixtures, skip {}", plan.fixtures().len(), conflicts),
+            };
+            if ui.add_enabled(!plan.fixtures().is_empty(), egui::Button::new(label)).clicked() {
+                self.import(&plan);
+            }
+            self.source = Some(source);
+        });
+        self.open = open;
+    }
+}
@@ -0,0 +1,239 @@
+use eframe::egui::{self, CollapsingHeader, Color32, ComboBox, Context, Ui, Window};
+use crate::windows::SubWindow;
+
+use dmxt_lib::history::History;
+use dmxt_lib::interfaces::{Interface, InterfaceMapping, InterfaceStatus};
+use dmxt_lib::show::{ShowCommand, ShowState};
+use dmxt_lib::threads::shared::Lock;
+
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+const MAX_UNIVERSES: usize = 64;
+
+#[derive(Debug)]
+pub struct InterfaceWindow {
+    pub open: bool,
+    show: ShowState,
+    history: Lock<History<ShowCommand>>,
+    // Universe that new mappings are added to
+    target: usize,
+    serial_port: String,
+    artnet_address: String,
+    artnet_universe: u16,
+    sacn_universe: u16,
+    error: Option<String>,
+}
+
+impl InterfaceWindow {
+    pub fn new(show: ShowState, history: Lock<History<ShowCommand>>) -> InterfaceWindow {
+        InterfaceWindow {
+            open: false,
+            show,
+            history,
+            target: 0,
+            serial_port: String::new(),
+            artnet_address: String::from("2.0.0.1"),
+            artnet_universe: 0,
+            sacn_universe: 1,
+            error: None,
+        }
+    }
+
+    fn execute(&mut self, command: ShowCommand) {
+        if let Err(error) = self.history.write().unwrap().execute(command, &mut self.show) {
+            self.error = Some(error.to_string());
+        }
+    }
+
+    // Drags become one undo step
+    fn group_edits(&self, response: &egui::Response) {
+        if response.drag_started() {
+            self.history.write().unwrap().begin_group();
+        }
+        if response.drag_released() {
+            self.history.write().unwrap().end_group();
+        }
+    }
+
+    fn map(&mut self, mapping: InterfaceMapping) {
+        let mut mappings = self.show.interfaces.read().unwrap().mappings();
+        mappings.push(mapping);
+        self.execute(ShowCommand::interfaces(mappings));
+    }
+
+    fn universes_ui(&mut self, ui: &mut Ui) {
+        let mut count = self.show.universes.read().unwrap().len();
+        ui.horizontal(|ui| {
+            ui.label("Universes");
+            let response = ui.add(egui::DragValue::new(&mut count).clamp_range(1..=MAX_UNIVERSES));
+            self.group_edits(&response);
+            if response.changed() {
+                self.execute(ShowCommand::universes(count));
+                self.target = self.target.min(count - 1);
+            }
+        });
+    }
+
+    fn mappings_ui(&mut self, ui: &mut Ui) {
+        let count = self.show.universes.read().unwrap().len();
+        let statuses = self.show.interfaces.read().unwrap().statuses();
+        if statuses.is_empty() {
+            ui.label("No interfaces mapped, the universes are not sent anywhere");
+            return;
+        }
+        let mut mappings: Vec<InterfaceMapping> = statuses.iter().map(|(mapping, _)| mapping.clone()).collect();
+        let mut remove = None;
+        let mut changed = false;
+        egui::Grid::new("interface_mappings").striped(true).num_columns(4).show(ui, |ui| {
+            for (index, (mapping, status)) in statuses.into_iter().enumerate() {
+                ui.label(mapping.interface.to_string());
+                let drag = egui::DragValue::new(&mut mappings[index].universe)
+                    .clamp_range(0..=count.saturating_sub(1))
+                    .custom_formatter(|value, _| format!("Universe {}", value + 1.0));
+                let response = ui.add(drag);
+                self.group_edits(&response);
+                changed |= response.changed();
+                let color = match status {
+                    InterfaceStatus::Connected => Color32::GREEN,
+                    InterfaceStatus::Waiting => Color32::YELLOW,
+                    InterfaceStatus::Failed(_) => Color32::RED,
+                };
+                ui.colored_label(color, status.to_string());
+                if ui.button("Remove").clicked() {
+                    remove = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = remove {
+            mappings.remove(index);
+            changed = true;
+        }
+        if changed {
+            self.execute(ShowCommand::interfaces(mappings));
+        }
+    }
+
+    fn devices_ui(&mut self, ui: &mut Ui) {
+        let count = self.show.universes.read().unwrap().len();
+        let manager = self.show.interfaces.clone();
+        let mut manager = manager.write().unwrap();
+        ui.horizontal(|ui| {
+            let mut discovering = manager.is_discovering();
+            if ui.checkbox(&mut discovering, "Discover network devices").changed() {
+                if discovering {
+                    if let Err(error) = manager.start_discovery() {
+                        self.error = Some(error.to_string());
+                    }
+                } else {
+                    manager.stop_discovery();
+                }
+            }
+            if ui.button("Refresh").clicked() {
+                manager.refresh();
+            }
+            ui.label("Map to");
+            ComboBox::from_id_source("interface_target").selected_text(format!("Universe {}", self.target + 1)).show_ui(ui, |ui| {
+                for universe in 0..count {
+                    ui.selectable_value(&mut self.target, universe, format!("Universe {}", universe + 1));
+                }
+            });
+        });
+        let devices = manager.devices();
+        if devices.is_empty() {
+            ui.label("No devices found");
+        }
+        let mapped = manager.mappings();
+        drop(manager);
+        let mut map = None;
+        egui::Grid::new("interface_devices").striped(true).num_columns(3).show(ui, |ui| {
+            for device in devices {
+                ui.label(&device.name);
+                ui.label(device.interface.to_string());
+                let mapping = InterfaceMapping {
+                    interface: device.interface,
+                    universe: self.target,
+                };
+                if ui.add_enabled(!mapped.contains(&mapping), egui::Button::new("Map")).clicked() {
+                    map = Some(mapping);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(mapping) = map {
+            self.map(mapping);
+        }
+    }
+
+    fn manual_ui(&mut self, ui: &mut Ui) {
+        let mut interface = None;
+        egui::Grid::new("interface_manual").num_columns(3).show(ui, |ui| {
+            ui.label("Serial port");
+            ui.add(egui::TextEdit::singleline(&mut self.serial_port).hint_text("COM3, /dev/ttyUSB0"));
+            if ui.button("Add").clicked() {
+                interface = Some(Interface::Serial { port: self.serial_port.trim().to_string(), serial_number: None });
+            }
+            ui.end_row();
+            ui.label("Art-Net node");
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.artnet_address).desired_width(110.0));
+                ui.add(egui::DragValue::new(&mut self.artnet_universe).clamp_range(0..=0x7fff).prefix("universe "));
+            });
+            if ui.button("Add").clicked() {
+                match self.artnet_address.trim().parse::<Ipv4Addr>() {
+                    Ok(address) => interface = Some(Interface::ArtNet { address, universe: self.artnet_universe }),
+                    Err(_) => self.error = Some(format!("{} is not an IPv4 address", self.artnet_address)),
+                }
+            }
+            ui.end_row();
+            ui.label("sACN");
+            ui.add(egui::DragValue::new(&mut self.sacn_universe).clamp_range(1..=63999).prefix("universe "));
+            if ui.button("Add").clicked() {
+                interface = Some(Interface::Sacn { universe: self.sacn_universe });
+            }
+            ui.end_row();
+        });
+        if let Some(interface) = interface {
+            self.map(InterfaceMapping { interface, universe: self.target });
+            self.error = None;
+        }
+    }
+}
+
+impl Default for InterfaceWindow {
+    fn default() -> Self {
+        Self::new(ShowState::default(), Lock::default())
+    }
+}
+
+impl SubWindow for InterfaceWindow {
+    fn ui(&mut self, ctx: &Context) {
+        let mut open = self.open;
+        Window::new("Interface Manager")
+        .open(&mut open)
+        .default_width(520.0)
+        .vscroll(true)
+        .show(ctx, |ui| {
+            if let Some(error) = &self.error {
+                ui.colored_label(Color32::RED, error);
+            }
+            self.universes_ui(ui);
+            ui.separator();
+            ui.heading("Mapped interfaces");
+            self.mappings_ui(ui);
+            ui.separator();
+            ui.heading("Devices");
+            self.devices_ui(ui);
+            CollapsingHeader::new("Add manually").id_source("interface_manual").show(ui, |ui| self.manual_ui(ui));
+        });
+        self.open = open;
+        if self.open {
+            // Statuses and discovered devices change without input
+            ctx.request_repaint_after(Duration::from_secs(1));
+        } else {
+            // Discovery holds the Art-Net and sACN ports, other listeners need them
+            self.show.interfaces.write().unwrap().stop_discovery();
+        }
+    }
+}
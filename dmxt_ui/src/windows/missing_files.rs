@@ -0,0 +1,112 @@
+use eframe::egui::{self, Color32, Context, Window};
+use crate::windows::SubWindow;
+
+use dmxt_lib::assets::{self, Asset, MissingAsset};
+use dmxt_lib::history::History;
+use dmxt_lib::patch::PatchCommand;
+use dmxt_lib::show::{ShowCommand, ShowState};
+use dmxt_lib::threads::shared::Lock;
+
+use std::path::Path;
+
+// Lists files the show refers to that don't exist and relinks them to files found in a directory
+#[derive(Debug)]
+pub struct MissingFilesWindow {
+    pub open: bool,
+    show: ShowState,
+    history: Lock<History<ShowCommand>>,
+    missing: Vec<MissingAsset>,
+    directory: String,
+    report: Option<String>,
+}
+
+impl MissingFilesWindow {
+    pub fn new(show: ShowState, history: Lock<History<ShowCommand>>) -> MissingFilesWindow {
+        MissingFilesWindow {
+            open: false,
+            show,
+            history,
+            missing: Vec::new(),
+            directory: String::new(),
+            report: None,
+        }
+    }
+
+    // Looks for missing files again, returns how many there are
+    pub fn check(&mut self) -> usize {
+        self.missing = assets::missing_assets(&self.show.patch.read().unwrap());
+        self.missing.len()
+    }
+
+    // Fixture configs first, their icons may be found next to them afterwards
+    fn relink(&mut self, directory: &Path) {
+        let before = self.missing.len();
+        let mut history = self.history.write().unwrap();
+        history.begin_group();
+        for icons in [false, true] {
+            let missing_assets = assets::missing_assets(&self.show.patch.read().unwrap());
+            for missing in missing_assets {
+                if matches!(missing.asset, Asset::Icon(_)) != icons {
+                    continue;
+                }
+                let relinked = self.show.patch.read().unwrap().get(missing.fixture).and_then(|fixture| assets::relink(fixture, &missing.asset, directory));
+                if let Some(fixture) = relinked {
+                    // Only the paths change, so the fixture can't collide
+                    let _ = history.execute(PatchCommand::update(missing.fixture, fixture).into(), &mut self.show);
+                }
+            }
+        }
+        history.end_group();
+        drop(history);
+        let remaining = self.check();
+        self.report = Some(format!("Relinked {} of {} files", before.saturating_sub(remaining), before));
+    }
+}
+
+impl Default for MissingFilesWindow {
+    fn default() -> Self {
+        Self::new(ShowState::default(), Lock::default())
+    }
+}
+
+impl SubWindow for MissingFilesWindow {
+    fn ui(&mut self, ctx: &Context) {
+        let mut open = self.open;
+        Window::new("Missing Files")
+        .open(&mut open)
+        .default_width(480.0)
+        .vscroll(true)
+        .show(ctx, |ui| {
+            if self.missing.is_empty() {
+                ui.label("All files of the show were found");
+            } else {
+                let patch = self.show.patch.read().unwrap();
+                egui::Grid::new("missing_files").striped(true).num_columns(2).show(ui, |ui| {
+                    for missing in &self.missing {
+                        ui.label(patch.get(missing.fixture).map_or("", |fixture| fixture.name.as_str()));
+                        ui.colored_label(Color32::RED, missing.asset.to_string());
+                        ui.end_row();
+                    }
+                });
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Search in");
+                ui.add(egui::TextEdit::singleline(&mut self.directory).hint_text("data/fixtures").desired_width(240.0));
+                let relink = ui.add_enabled(!self.missing.is_empty() && !self.directory.trim().is_empty(), egui::Button::new("Relink"));
+                if relink.clicked() {
+                    let directory = self.directory.trim().to_string();
+                    self.relink(Path::new(&directory));
+                }
+                if ui.button("Check again").clicked() {
+                    self.check();
+                    self.report = None;
+                }
+            });
+            if let Some(report) = &self.report {
+                ui.label(report);
+            }
+        });
+        self.open = open;
+    }
+}
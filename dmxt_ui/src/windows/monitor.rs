@@ -0,0 +1,298 @@
+use eframe::egui::{self, Align2, Color32, ComboBox, Context, FontId, Rect, Sense, Stroke, Ui, Window};
+use crate::windows::SubWindow;
+
+use dmxt_lib::dmx::{DMXUniverse, DMX_CHANNELS};
+use dmxt_lib::input::{NetworkInput, Protocol};
+use dmxt_lib::patch::{ChannelLabel, Patch};
+use dmxt_lib::threads::shared::Lock;
+
+const COLUMNS: usize = 32;
+const CELL_SIZE: [f32; 2] = [30.0, 26.0];
+const BAR_HEIGHT: f32 = 200.0;
+// Seconds a changed value stays highlighted
+const HIGHLIGHT: f64 = 1.0;
+const CHANGED: Color32 = Color32::from_rgb(255, 200, 0);
+const PATCHED: Color32 = Color32::from_rgb(60, 110, 170);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Output(usize),
+    Network(Protocol, u16),
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Output(universe) => write!(f, "Universe {}", universe + 1),
+            Source::Network(protocol, universe) => write!(f, "{} {}", protocol, universe),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    Grid,
+    Bars,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueFormat {
+    Decimal,
+    Hex,
+    Percent,
+}
+
+impl ValueFormat {
+    fn format(&self, value: u8) -> String {
+        match self {
+            ValueFormat::Decimal => value.to_string(),
+            ValueFormat::Hex => format!("{:02X}", value),
+            ValueFormat::Percent => format!("{:.0}", value as f64 / 2.55),
+        }
+    }
+}
+
+// Live view of the output universes and of universes received from the network
+#[derive(Debug)]
+pub struct MonitorWindow {
+    pub open: bool,
+    universes: Lock<Vec<DMXUniverse>>,
+    patch: Lock<Patch>,
+    network: Option<NetworkInput>,
+    sacn_universes: String,
+    source: Source,
+    view: View,
+    format: ValueFormat,
+    previous: Option<(Source, DMXUniverse)>,
+    // Time of the last change per channel
+    changed: Vec<f64>,
+    error: Option<String>,
+}
+
+impl MonitorWindow {
+    pub fn new(universes: Lock<Vec<DMXUniverse>>, patch: Lock<Patch>) -> MonitorWindow {
+        MonitorWindow {
+            open: false,
+            universes,
+            patch,
+            network: None,
+            sacn_universes: String::from("1"),
+            source: Source::Output(0),
+            view: View::Grid,
+            format: ValueFormat::Decimal,
+            previous: None,
+            changed: vec![f64::NEG_INFINITY; DMX_CHANNELS],
+            error: None,
+        }
+    }
+
+    fn current(&self) -> Option<DMXUniverse> {
+        match self.source {
+            Source::Output(universe) => self.universes.read().unwrap().get(universe).copied(),
+            Source::Network(protocol, universe) => {
+                let network = self.network.as_ref().filter(|network| network.protocol() == protocol)?;
+                let received = network.universes();
+                let universe = received.read().unwrap().get(&universe).copied();
+                universe
+            },
+        }
+    }
+
+    fn track_changes(&mut self, universe: &DMXUniverse, now: f64) {
+        match &self.previous {
+            Some((source, previous)) if *source == self.source => {
+                for (index, (value, old)) in universe.channels.iter().zip(previous.channels.iter()).enumerate() {
+                    if value != old {
+                        self.changed[index] = now;
+                    }
+                }
+            },
+            _ => self.changed = vec![f64::NEG_INFINITY; DMX_CHANNELS],
+        }
+        self.previous = Some((self.source, *universe));
+    }
+
+    fn source_ui(&mut self, ui: &mut Ui) {
+        let mut sources: Vec<Source> = (0..self.universes.read().unwrap().len()).map(Source::Output).collect();
+        if let Some(network) = &self.network {
+            sources.extend(network.universes().read().unwrap().keys().map(|universe| Source::Network(network.protocol(), *universe)));
+        }
+        ui.horizontal(|ui| {
+            ComboBox::from_id_source("monitor_source").selected_text(self.source.to_string()).show_ui(ui, |ui| {
+                for source in sources {
+                    ui.selectable_value(&mut self.source, source, source.to_string());
+                }
+            });
+            ui.separator();
+            ui.selectable_value(&mut self.view, View::Grid, "Grid");
+            ui.selectable_value(&mut self.view, View::Bars, "Bars");
+            ui.separator();
+            ui.selectable_value(&mut self.format, ValueFormat::Decimal, "Dec");
+            ui.selectable_value(&mut self.format, ValueFormat::Hex, "Hex");
+            ui.selectable_value(&mut self.format, ValueFormat::Percent, "%");
+        });
+    }
+
+    fn network_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            match &self.network {
+                Some(network) => {
+                    let count = network.universes().read().unwrap().len();
+                    ui.label(format!("Listening for {}, {} universes received", network.protocol(), count));
+                    if ui.button("Stop").clicked() {
+                        self.network = None;
+                        if let Source::Network(..) = self.source {
+                            self.source = Source::Output(0);
+                        }
+                    }
+                },
+                None => {
+                    if ui.button("Listen for Art-Net").clicked() {
+                        self.listen(NetworkInput::listen_artnet());
+                    }
+                    ui.separator();
+                    ui.label("sACN universes");
+                    ui.add(egui::TextEdit::singleline(&mut self.sacn_universes).desired_width(80.0));
+                    if ui.button("Listen for sACN").clicked() {
+                        let universes: Result<Vec<u16>, _> = self.sacn_universes.split(',').map(|universe| universe.trim().parse()).collect();
+                        match universes {
+                            Ok(universes) => self.listen(NetworkInput::listen_sacn(&universes)),
+                            Err(_) => self.error = Some(format!("Invalid universe list {}", self.sacn_universes)),
+                        }
+                    }
+                },
+            }
+        });
+    }
+
+    fn listen(&mut self, network: Result<NetworkInput, dmxt_lib::input::InputError>) {
+        match network {
+            Ok(network) => {
+                self.network = Some(network);
+                self.error = None;
+            },
+            Err(error) => self.error = Some(error.to_string()),
+        }
+    }
+
+    fn grid_ui(&self, ui: &mut Ui, universe: &DMXUniverse, labels: &[Vec<ChannelLabel>], now: f64) {
+        let rows = DMX_CHANNELS / COLUMNS;
+        let size = egui::vec2(CELL_SIZE[0] * COLUMNS as f32, CELL_SIZE[1] * rows as f32);
+        let (rect, response) = ui.allocate_exact_size(size, Sense::hover());
+        let painter = ui.painter_at(rect);
+        let text_color = ui.style().visuals.text_color();
+        for (index, value) in universe.channels.iter().enumerate() {
+            let min = rect.min + egui::vec2((index % COLUMNS) as f32 * CELL_SIZE[0], (index / COLUMNS) as f32 * CELL_SIZE[1]);
+            let cell = Rect::from_min_size(min, CELL_SIZE.into()).shrink(1.0);
+            painter.rect_filled(cell, 2.0, Color32::from_gray(*value / 2 + 20));
+            let stroke = match self.highlight(index, now) {
+                Some(alpha) => Stroke::new(2.0, CHANGED.linear_multiply(alpha)),
+                None if !labels[index].is_empty() => Stroke::new(1.0, PATCHED),
+                None => Stroke::NONE,
+            };
+            painter.rect_stroke(cell, 2.0, stroke);
+            painter.text(cell.center_top(), Align2::CENTER_TOP, (index + 1).to_string(), FontId::proportional(8.0), Color32::GRAY);
+            painter.text(cell.center_bottom(), Align2::CENTER_BOTTOM, self.format.format(*value), FontId::monospace(10.0), text_color);
+        }
+        let hovered = response.hover_pos()
+            .map(|pointer| pointer - rect.min)
+            .map(|offset| (offset.y / CELL_SIZE[1]) as usize * COLUMNS + (offset.x / CELL_SIZE[0]) as usize)
+            .filter(|index| *index < DMX_CHANNELS);
+        if let Some(index) = hovered {
+            response.on_hover_text(self.channel_text(index, universe.channels[index], &labels[index]));
+        }
+    }
+
+    fn bars_ui(&self, ui: &mut Ui, universe: &DMXUniverse, labels: &[Vec<ChannelLabel>], now: f64) {
+        let size = egui::vec2(ui.available_width().max(DMX_CHANNELS as f32), BAR_HEIGHT);
+        let (rect, response) = ui.allocate_exact_size(size, Sense::hover());
+        let painter = ui.painter_at(rect);
+        let visuals = ui.style().visuals.widgets.inactive;
+        painter.rect(rect, 0.0, visuals.bg_fill, visuals.bg_stroke);
+        let width = rect.width() / DMX_CHANNELS as f32;
+        for (index, value) in universe.channels.iter().enumerate() {
+            let height = *value as f32 / 255.0 * rect.height();
+            let bar = Rect::from_min_max(
+                egui::pos2(rect.left() + index as f32 * width, rect.bottom() - height),
+                egui::pos2(rect.left() + (index + 1) as f32 * width, rect.bottom()),
+            );
+            let color = match self.highlight(index, now) {
+                Some(alpha) => mix(Color32::GRAY, CHANGED, alpha),
+                None if !labels[index].is_empty() => PATCHED,
+                None => Color32::GRAY,
+            };
+            painter.rect_filled(bar, 0.0, color);
+        }
+        let hovered = response.hover_pos()
+            .map(|pointer| ((pointer.x - rect.left()) / width) as usize)
+            .filter(|index| *index < DMX_CHANNELS);
+        if let Some(index) = hovered {
+            response.on_hover_text(self.channel_text(index, universe.channels[index], &labels[index]));
+        }
+    }
+
+    // Highlight strength of a recently changed channel
+    fn highlight(&self, index: usize, now: f64) -> Option<f32> {
+        let age = now - self.changed[index];
+        (age < HIGHLIGHT).then(|| (1.0 - age / HIGHLIGHT) as f32)
+    }
+
+    fn channel_text(&self, index: usize, value: u8, labels: &[ChannelLabel]) -> String {
+        let mut text = format!("Channel {}: {}", index + 1, self.format.format(value));
+        if self.format == ValueFormat::Percent {
+            text.push('%');
+        }
+        // The label of the current value, a step channel has one per step
+        if let Some(label) = labels.iter().find(|label| label.contains(value)).or_else(|| labels.first()) {
+            text.push_str(&format!("\n{}", label));
+        }
+        text
+    }
+}
+
+fn mix(from: Color32, to: Color32, amount: f32) -> Color32 {
+    let channel = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * amount).round() as u8;
+    Color32::from_rgb(channel(from.r(), to.r()), channel(from.g(), to.g()), channel(from.b(), to.b()))
+}
+
+impl Default for MonitorWindow {
+    fn default() -> Self {
+        Self::new(Lock::new(vec![DMXUniverse::new()]), Lock::new(Patch::new()))
+    }
+}
+
+impl SubWindow for MonitorWindow {
+    fn ui(&mut self, ctx: &Context) {
+        let now = ctx.input().time;
+        let mut open = self.open;
+        Window::new("DMX Monitor")
+        .open(&mut open)
+        .default_width(CELL_SIZE[0] * COLUMNS as f32)
+        .hscroll(true)
+        .show(ctx, |ui| {
+            self.source_ui(ui);
+            self.network_ui(ui);
+            if let Some(error) = &self.error {
+                ui.colored_label(Color32::RED, error);
+            }
+            ui.separator();
+            let Some(universe) = self.current() else {
+                ui.label(format!("{} has no data", self.source));
+                return;
+            };
+            self.track_changes(&universe, now);
+            let labels = match self.source {
+                Source::Output(universe) => self.patch.read().unwrap().channel_labels(universe),
+                Source::Network(..) => vec![Vec::new(); DMX_CHANNELS],
+            };
+            match self.view {
+                View::Grid => self.grid_ui(ui, &universe, &labels, now),
+                View::Bars => self.bars_ui(ui, &universe, &labels, now),
+            }
+        });
+        self.open = open;
+        if self.open {
+            ctx.request_repaint();
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use eframe::egui::{self, Color32, ComboBox, Context, InputState, Key, Ui, Window};
+use crate::windows::SubWindow;
+
+use dmxt_lib::interfaces::{serial_ports, Interface, InterfaceManager};
+use dmxt_lib::preferences::{Action, KeyBinding, Preferences, Theme};
+use dmxt_lib::threads::shared::Lock;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+// Names of the keys that can be bound, as written in the preferences
+const KEYS: &[(&str, Key)] = &[
+    ("A", Key::A), ("B", Key::B), ("C", Key::C), ("D", Key::D), ("E", Key::E), ("F", Key::F), ("G", Key::G),
+    ("H", Key::H), ("I", Key::I), ("J", Key::J), ("K", Key::K), ("L", Key::L), ("M", Key::M), ("N", Key::N),
+    ("O", Key::O), ("P", Key::P), ("Q", Key::Q), ("R", Key::R), ("S", Key::S), ("T", Key::T), ("U", Key::U),
+    ("V", Key::V), ("W", Key::W), ("X", Key::X), ("Y", Key::Y), ("Z", Key::Z),
+    ("0", Key::Num0), ("1", Key::Num1), ("2", Key::Num2), ("3", Key::Num3), ("4", Key::Num4),
+    ("5", Key::Num5), ("6", Key::Num6), ("7", Key::Num7), ("8", Key::Num8), ("9", Key::Num9),
+    ("F1", Key::F1), ("F2", Key::F2), ("F3", Key::F3), ("F4", Key::F4), ("F5", Key::F5), ("F6", Key::F6),
+    ("F7", Key::F7), ("F8", Key::F8), ("F9", Key::F9), ("F10", Key::F10), ("F11", Key::F11), ("F12", Key::F12),
+    ("Space", Key::Space), ("Enter", Key::Enter), ("Tab", Key::Tab), ("Escape", Key::Escape), ("Backspace", Key::Backspace),
+    ("Insert", Key::Insert), ("Delete", Key::Delete), ("Home", Key::Home), ("End", Key::End),
+    ("PageUp", Key::PageUp), ("PageDown", Key::PageDown),
+    ("Up", Key::ArrowUp), ("Down", Key::ArrowDown), ("Left", Key::ArrowLeft), ("Right", Key::ArrowRight),
+];
+
+fn key(name: &str) -> Option<Key> {
+    KEYS.iter().find(|(key_name, _)| key_name.eq_ignore_ascii_case(name)).map(|(_, key)| *key)
+}
+
+// True in the frame the shortcut is pressed, the modifiers have to match exactly
+pub fn shortcut_pressed(input: &InputState, binding: &KeyBinding) -> bool {
+    let modifiers = &input.modifiers;
+    key(&binding.key).is_some_and(|key| {
+        modifiers.command == binding.ctrl && modifiers.shift == binding.shift && modifiers.alt == binding.alt && input.key_pressed(key)
+    })
+}
+
+// Edits a copy of the preferences, they are only applied and written when saved
+#[derive(Debug)]
+pub struct PreferencesWindow {
+    pub open: bool,
+    // Set when new preferences were saved, the app applies them and resets it
+    pub applied: bool,
+    preferences: Lock<Preferences>,
+    path: Option<PathBuf>,
+    interfaces: Lock<InterfaceManager>,
+    draft: Preferences,
+    library_path: String,
+    key_bindings: BTreeMap<Action, String>,
+    error: Option<String>,
+}
+
+impl PreferencesWindow {
+    pub fn new(preferences: Lock<Preferences>, path: Option<PathBuf>, interfaces: Lock<InterfaceManager>) -> PreferencesWindow {
+        PreferencesWindow {
+            open: false,
+            applied: false,
+            preferences,
+            path,
+            interfaces,
+            draft: Preferences::default(),
+            library_path: String::new(),
+            key_bindings: BTreeMap::new(),
+            error: None,
+        }
+    }
+
+    // Starts editing the current preferences
+    pub fn show(&mut self) {
+        let preferences = self.preferences.read().unwrap().clone();
+        self.set_draft(preferences);
+        self.error = None;
+        self.open = true;
+    }
+
+    fn set_draft(&mut self, draft: Preferences) {
+        self.key_bindings = draft.key_bindings.iter().map(|(action, binding)| (*action, binding.to_string())).collect();
+        self.draft = draft;
+    }
+
+    fn parse_key_bindings(&self) -> Result<BTreeMap<Action, KeyBinding>, String> {
+        self.key_bindings.iter().map(|(action, text)| {
+            match text.parse::<KeyBinding>() {
+                Ok(binding) if key(&binding.key).is_some() => Ok((*action, binding)),
+                Ok(binding) => Err(format!("{}: there is no key called {}", action, binding.key)),
+                Err(error) => Err(format!("{}: {}", action, error)),
+            }
+        }).collect()
+    }
+
+    fn save(&mut self) {
+        match self.parse_key_bindings() {
+            Ok(key_bindings) => self.draft.key_bindings = key_bindings,
+            Err(error) => {
+                self.error = Some(error);
+                return;
+            },
+        }
+        // Recent files may have changed while the window was open
+        let mut preferences = self.preferences.write().unwrap();
+        self.draft.recent_files = preferences.recent_files.clone();
+        *preferences = self.draft.clone();
+        self.error = match &self.path {
+            Some(path) => preferences.save(path).err().map(|error| format!("Could not save {}: {}", path.display(), error)),
+            None => Some(String::from("There is no config directory, the preferences only last until DMXT is closed")),
+        };
+        self.applied = true;
+        self.open = self.error.is_some();
+    }
+
+    fn general_ui(&mut self, ui: &mut Ui) {
+        egui::Grid::new("preferences_general").num_columns(2).show(ui, |ui| {
+            ui.label("Theme");
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.draft.theme, Theme::Dark, "Dark");
+                ui.selectable_value(&mut self.draft.theme, Theme::Light, "Light");
+            });
+            ui.end_row();
+            ui.label("Refresh rate");
+            ui.add(egui::DragValue::new(&mut self.draft.frame_rate).clamp_range(1.0..=44.0).suffix(" Hz"));
+            ui.end_row();
+            ui.label("OSC port");
+            ui.add(egui::DragValue::new(&mut self.draft.osc_port).clamp_range(1024..=u16::MAX));
+            ui.end_row();
+            ui.label("Default interface");
+            self.interface_ui(ui);
+            ui.end_row();
+        });
+    }
+
+    fn interface_ui(&mut self, ui: &mut Ui) {
+        let name = |interface: &Option<Interface>| interface.as_ref().map_or(String::from("None"), Interface::to_string);
+        ComboBox::from_id_source("preferences_interface").selected_text(name(&self.draft.default_interface)).show_ui(ui, |ui| {
+            // Only looked up while the list is open, listing serial ports is slow
+            let mut choices: Vec<Interface> = self.draft.default_interface.iter().cloned().collect();
+            {
+                let manager = self.interfaces.read().unwrap();
+                let mapped = manager.mappings().into_iter().map(|mapping| mapping.interface);
+                let found = manager.devices().into_iter().chain(serial_ports()).map(|device| device.interface);
+                for interface in mapped.chain(found).chain([Interface::Sacn { universe: 1 }]) {
+                    if !choices.contains(&interface) {
+                        choices.push(interface);
+                    }
+                }
+            }
+            ui.selectable_value(&mut self.draft.default_interface, None, "None");
+            for interface in choices.into_iter().map(Some) {
+                let text = name(&interface);
+                ui.selectable_value(&mut self.draft.default_interface, interface, text);
+            }
+        });
+    }
+
+    fn library_ui(&mut self, ui: &mut Ui) {
+        let mut remove = None;
+        for (index, path) in self.draft.fixture_library.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if !path.is_dir() {
+                    ui.colored_label(Color32::RED, "Missing").on_hover_text("The folder does not exist");
+                }
+                ui.label(path.display().to_string());
+                if ui.button("Remove").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+        if let Some(index) = remove {
+            self.draft.fixture_library.remove(index);
+        }
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.library_path).hint_text("fixtures/").desired_width(280.0));
+            if ui.button("Add").clicked() && !self.library_path.trim().is_empty() {
+                self.draft.fixture_library.push(PathBuf::from(self.library_path.trim()));
+                self.library_path.clear();
+            }
+        });
+    }
+
+    fn key_bindings_ui(&mut self, ui: &mut Ui) {
+        egui::Grid::new("preferences_keys").striped(true).num_columns(2).show(ui, |ui| {
+            for action in Action::ALL {
+                ui.label(action.to_string());
+                let text = self.key_bindings.entry(action).or_default();
+                ui.add(egui::TextEdit::singleline(text).desired_width(120.0));
+                ui.end_row();
+            }
+        });
+    }
+}
+
+impl Default for PreferencesWindow {
+    fn default() -> Self {
+        Self::new(Lock::default(), None, Lock::new(InterfaceManager::new()))
+    }
+}
+
+impl SubWindow for PreferencesWindow {
+    fn ui(&mut self, ctx: &Context) {
+        let mut open = self.open;
+        Window::new("Preferences")
+        .open(&mut open)
+        .default_width(420.0)
+        .vscroll(true)
+        .show(ctx, |ui| {
+            if let Some(error) = &self.error {
+                ui.colored_label(Color32::RED, error);
+            }
+            self.general_ui(ui);
+            ui.separator();
+            ui.heading("Fixture library");
+            self.library_ui(ui);
+            ui.separator();
+            ui.heading("Key bindings");
+            self.key_bindings_ui(ui);
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    self.save();
+                }
+                if ui.button("Reset to defaults").clicked() {
+                    let recent_files = std::mem::take(&mut self.draft.recent_files);
+                    self.set_draft(Preferences { recent_files, ..Preferences::default() });
+                }
+            });
+        });
+        self.open = open && self.open;
+    }
+}
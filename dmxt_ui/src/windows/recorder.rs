@@ -0,0 +1,221 @@
+use eframe::egui::{self, Color32, Context, Ui, Window};
+use crate::windows::SubWindow;
+
+use dmxt_lib::dmx::DMXUniverse;
+use dmxt_lib::input::NetworkInput;
+use dmxt_lib::recording::{Player, RecorderHandle, Recording};
+use dmxt_lib::threads::shared::Lock;
+
+use std::path::Path;
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Output,
+    ArtNet,
+    Sacn,
+}
+
+// Records what is sent or received to a file and plays recordings back into the output universes
+#[derive(Debug)]
+pub struct RecorderWindow {
+    pub open: bool,
+    output: RecorderHandle,
+    universes: Lock<Vec<DMXUniverse>>,
+    source: Source,
+    network: Option<NetworkInput>,
+    sacn_universes: String,
+    path: String,
+    player: Option<Player>,
+    last_tick: Option<Instant>,
+    message: Option<(Color32, String)>,
+}
+
+impl RecorderWindow {
+    pub fn new(output: RecorderHandle, universes: Lock<Vec<DMXUniverse>>) -> RecorderWindow {
+        RecorderWindow {
+            open: false,
+            output,
+            universes,
+            source: Source::Output,
+            network: None,
+            sacn_universes: String::from("1"),
+            path: String::new(),
+            player: None,
+            last_tick: None,
+            message: None,
+        }
+    }
+
+    // Called every frame, playback goes on while the window is closed
+    pub fn tick(&mut self, ctx: &Context) {
+        let now = Instant::now();
+        let elapsed = self.last_tick.replace(now).map_or(0.0, |last| (now - last).as_secs_f64());
+        let Some(player) = self.player.as_mut().filter(|player| player.playing) else {
+            return;
+        };
+        player.advance(elapsed);
+        player.apply(&mut self.universes.write().unwrap());
+        ctx.request_repaint();
+    }
+
+    fn recorder(&self) -> Option<RecorderHandle> {
+        match self.source {
+            Source::Output => Some(self.output.clone()),
+            Source::ArtNet | Source::Sacn => self.network.as_ref().map(NetworkInput::recorder),
+        }
+    }
+
+    fn is_recording(&self) -> bool {
+        self.recorder().is_some_and(|recorder| recorder.is_recording())
+    }
+
+    fn start(&mut self) {
+        let network = match self.source {
+            Source::Output => None,
+            Source::ArtNet => Some(NetworkInput::listen_artnet()),
+            Source::Sacn => {
+                let universes: Result<Vec<u16>, _> = self.sacn_universes.split(',').map(|universe| universe.trim().parse()).collect();
+                match universes {
+                    Ok(universes) => Some(NetworkInput::listen_sacn(&universes)),
+                    Err(_) => {
+                        self.message = Some((Color32::RED, format!("Invalid universe list {}", self.sacn_universes)));
+                        return;
+                    },
+                }
+            },
+        };
+        match network.transpose() {
+            Ok(network) => self.network = network,
+            Err(error) => {
+                self.message = Some((Color32::RED, format!("Could not listen: {}", error)));
+                return;
+            },
+        }
+        if let Some(recorder) = self.recorder() {
+            recorder.start();
+            self.message = None;
+        }
+    }
+
+    fn stop(&mut self) {
+        let recording = self.recorder().and_then(|recorder| recorder.stop());
+        self.network = None;
+        if let Some(recording) = recording {
+            self.message = Some((Color32::GRAY, format!("Recorded {} frames in {:.1} s", recording.len(), recording.duration())));
+            self.player = Some(Player::new(recording));
+        }
+    }
+
+    fn record_ui(&mut self, ui: &mut Ui) {
+        let recording = self.is_recording();
+        ui.add_enabled_ui(!recording, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Record");
+                ui.selectable_value(&mut self.source, Source::Output, "Output");
+                ui.selectable_value(&mut self.source, Source::ArtNet, "Art-Net in");
+                ui.selectable_value(&mut self.source, Source::Sacn, "sACN in");
+                if self.source == Source::Sacn {
+                    ui.add(egui::TextEdit::singleline(&mut self.sacn_universes).desired_width(80.0)).on_hover_text("Universes, separated by commas");
+                }
+            });
+        });
+        ui.horizontal(|ui| {
+            if recording {
+                if ui.button("⏹ Stop").clicked() {
+                    self.stop();
+                }
+                let frames = self.recorder().map_or(0, |recorder| recorder.frames());
+                ui.colored_label(Color32::RED, format!("Recording, {} frames", frames));
+                ui.ctx().request_repaint();
+            } else if ui.button("⏺ Record").clicked() {
+                self.start();
+            }
+        });
+    }
+
+    fn file_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("File");
+            ui.add(egui::TextEdit::singleline(&mut self.path).hint_text("venue.dmxr").desired_width(220.0));
+            let path = self.path.trim().to_string();
+            if ui.add_enabled(!path.is_empty(), egui::Button::new("Load")).clicked() {
+                self.message = Some(match Recording::load(Path::new(&path)) {
+                    Ok(recording) => {
+                        let message = format!("{} has {} frames, {:.1} s", path, recording.len(), recording.duration());
+                        self.player = Some(Player::new(recording));
+                        (Color32::GRAY, message)
+                    },
+                    Err(error) => (Color32::RED, format!("Could not load {}: {}", path, error)),
+                });
+            }
+            let can_save = !path.is_empty() && self.player.is_some();
+            if ui.add_enabled(can_save, egui::Button::new("Save")).clicked() {
+                let result = self.player.as_ref().map(|player| player.recording().save(Path::new(&path)));
+                self.message = match result {
+                    Some(Ok(())) => Some((Color32::GRAY, format!("Saved {}", path))),
+                    Some(Err(error)) => Some((Color32::RED, format!("Could not save {}: {}", path, error))),
+                    None => None,
+                };
+            }
+        });
+    }
+
+    fn player_ui(&mut self, ui: &mut Ui) {
+        let Some(player) = self.player.as_mut() else {
+            ui.label("Record or load a recording to play it back");
+            return;
+        };
+        ui.horizontal(|ui| {
+            if ui.button("⏹").on_hover_text("Stop").clicked() {
+                player.stop();
+            }
+            let play = if player.playing { "⏸" } else { "▶" };
+            if ui.button(play).clicked() {
+                if !player.playing && player.position() >= player.recording().duration() {
+                    player.seek(0.0);
+                }
+                player.playing = !player.playing;
+            }
+            let mut position = player.position();
+            if ui.add(egui::Slider::new(&mut position, 0.0..=player.recording().duration()).suffix(" s")).changed() {
+                player.seek(position);
+                player.apply(&mut self.universes.write().unwrap());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut player.looped, "Loop");
+            ui.label("Speed");
+            ui.add(egui::Slider::new(&mut player.speed, 0.1..=4.0).logarithmic(true).suffix("×"));
+            if ui.button("1×").clicked() {
+                player.speed = 1.0;
+            }
+        });
+    }
+}
+
+impl Default for RecorderWindow {
+    fn default() -> Self {
+        Self::new(RecorderHandle::default(), Lock::new(vec![DMXUniverse::new()]))
+    }
+}
+
+impl SubWindow for RecorderWindow {
+    fn ui(&mut self, ctx: &Context) {
+        let mut open = self.open;
+        Window::new("Recorder")
+        .open(&mut open)
+        .default_width(420.0)
+        .show(ctx, |ui| {
+            self.record_ui(ui);
+            ui.separator();
+            self.file_ui(ui);
+            if let Some((color, message)) = &self.message {
+                ui.colored_label(*color, message);
+            }
+            ui.separator();
+            self.player_ui(ui);
+        });
+        self.open = open;
+    }
+}